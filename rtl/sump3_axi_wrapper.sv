@@ -35,12 +35,22 @@
 // 0x04   | ADDR       | R/W    | Target address: {hub[23:16], pod[15:8], reg[7:0]}
 // 0x08   | WDATA      | R/W    | Write data for write operations
 // 0x0C   | CTRL       | R/W    | Control: [0]=START, [1]=IRQ_EN, [2]=ABORT
-// 0x10   | STATUS     | R      | Status: [0]=BUSY, [1]=DONE, [2]=ERROR, [3]=IRQ_PEND
+// 0x10   | STATUS     | R      | Status: [0]=BUSY, [1]=DONE, [2]=ERROR, [3]=IRQ_PEND,
+//        |            |        |         [7:4]=ERR_CODE (cause of ERROR, see below)
 // 0x14   | RDATA      | R      | Read result data from completed command
 // 0x18   | IRQ_STATUS | R/W1C  | IRQ status (write 1 to bit 0 to clear)
 // 0x1C   | HW_INFO    | R      | {ID[31:16], hub_count[15:8], revision[7:0]}
 // 0x20   | CAP_STATUS | R      | Capture status from SUMP3 core
 // 0x24   | TIMEOUT    | R/W    | Timeout value in clock cycles (16-bit)
+// 0x28   | TRIG_ROUTE | R/W    | External trigger routing (see below)
+//
+// TRIG_ROUTE: [1:0]=IN_SEL  core trigger_in source: 0=none, 1=ext_trig_in[0],
+//                           2=ext_trig_in[1], 3=reserved (none)
+//             [4]  =OUT_EN  forward core trigger_out to ext_trig_out
+//
+// ERR_CODE: 0=none, 1=unknown command, 2=local read timeout,
+//           3=serial address phase timeout (hub), 4=serial command timeout,
+//           5=serial read timeout (pod), 6=serial write timeout
 //
 // COMMAND CODES (Write to CMD Register)
 // =====================================
@@ -165,7 +175,15 @@ module sump3_axi_wrapper #(
     //=========================================================================
     input  logic                              sump_is_armed,  // ILA is armed for capture
     input  logic                              sump_is_awake,  // ILA is awake (not in sleep)
-    input  logic [7:0]                        sump_hub_count  // Number of hubs (from parameter)
+    input  logic [7:0]                        sump_hub_count, // Number of hubs (from parameter)
+
+    //=========================================================================
+    // External Trigger Routing (see TRIG_ROUTE)
+    //=========================================================================
+    input  logic [1:0]                        ext_trig_in,      // Board trigger inputs
+    output logic                              sump_trigger_in,  // To sump3_core trigger_in
+    input  logic                              sump_trigger_out, // From sump3_core trigger_out
+    output logic                              ext_trig_out      // Board trigger output
 );
 
     //=========================================================================
@@ -181,6 +199,16 @@ module sump3_axi_wrapper #(
     localparam logic [7:0] REG_HW_INFO    = 8'h1C;  // Hardware info (read-only)
     localparam logic [7:0] REG_CAP_STATUS = 8'h20;  // Capture status (read-only)
     localparam logic [7:0] REG_TIMEOUT    = 8'h24;  // Timeout value
+    localparam logic [7:0] REG_TRIG_ROUTE = 8'h28;  // External trigger routing
+
+    // STATUS ERR_CODE values
+    localparam logic [3:0] ERR_NONE         = 4'h0;
+    localparam logic [3:0] ERR_BAD_CMD      = 4'h1;
+    localparam logic [3:0] ERR_LOCAL_READ   = 4'h2;
+    localparam logic [3:0] ERR_SERIAL_ADDR  = 4'h3;
+    localparam logic [3:0] ERR_SERIAL_CMD   = 4'h4;
+    localparam logic [3:0] ERR_SERIAL_READ  = 4'h5;
+    localparam logic [3:0] ERR_SERIAL_WRITE = 4'h6;
 
     //=========================================================================
     // Wrapper Command Codes - STATE COMMANDS (0x00-0x0F)
@@ -305,11 +333,14 @@ module sump3_axi_wrapper #(
     logic [2:0]  reg_ctrl;      // Control bits
     logic [31:0] reg_rdata;     // Result data from completed read
     logic [15:0] reg_timeout;   // Timeout value in clock cycles
+    logic [4:0]  reg_trig_route;// External trigger routing
     
     // Status bits (directly visible via STATUS register)
     logic        status_busy;   // Command in progress
     logic        status_done;   // Command completed successfully
     logic        status_error;  // Command failed (timeout)
+    logic [3:0]  status_err_code; // Cause of the last ERROR
+    logic [3:0]  err_cause;     // Cause if the current state fails
     logic        irq_pending;   // IRQ is pending
 
     //=========================================================================
@@ -499,6 +530,7 @@ module sump3_axi_wrapper #(
             reg_wdata   <= 32'h0;
             reg_ctrl    <= 3'h0;
             reg_timeout <= DEFAULT_TIMEOUT[15:0];
+            reg_trig_route <= 5'h0;
             irq_pending <= 1'b0;
         end else begin
             // Auto-clear START bit once command begins
@@ -521,6 +553,7 @@ module sump3_axi_wrapper #(
                     REG_WDATA:   reg_wdata   <= axi_wr_data_reg;
                     REG_CTRL:    reg_ctrl    <= axi_wr_data_reg[2:0];
                     REG_TIMEOUT: reg_timeout <= axi_wr_data_reg[15:0];
+                    REG_TRIG_ROUTE: reg_trig_route <= axi_wr_data_reg[4:0];
                 endcase
             end
         end
@@ -544,13 +577,14 @@ module sump3_axi_wrapper #(
                     REG_ADDR:       s_axi_rdata <= reg_addr;
                     REG_WDATA:      s_axi_rdata <= reg_wdata;
                     REG_CTRL:       s_axi_rdata <= {29'h0, reg_ctrl};
-                    REG_STATUS:     s_axi_rdata <= {28'h0, irq_pending, status_error, 
-                                                    status_done, status_busy};
+                    REG_STATUS:     s_axi_rdata <= {24'h0, status_err_code, irq_pending,
+                                                    status_error, status_done, status_busy};
                     REG_RDATA:      s_axi_rdata <= reg_rdata;
                     REG_IRQ_STATUS: s_axi_rdata <= {31'h0, irq_pending};
                     REG_HW_INFO:    s_axi_rdata <= {16'h5303, sump_hub_count, 8'h01};
                     REG_CAP_STATUS: s_axi_rdata <= {30'h0, sump_is_awake, sump_is_armed};
                     REG_TIMEOUT:    s_axi_rdata <= {16'h0, reg_timeout};
+                    REG_TRIG_ROUTE: s_axi_rdata <= {27'h0, reg_trig_route};
                     default:        s_axi_rdata <= 32'hDEADCAFE;  // Unmapped address
                 endcase
             end
@@ -628,21 +662,38 @@ module sump3_axi_wrapper #(
     //=========================================================================
     assign status_busy = (state != ST_IDLE);
     
+    // Which step gave up, recorded as the state machine enters ST_ERROR
+    always_comb begin
+        case (state)
+            ST_IDLE:                                    err_cause = ERR_BAD_CMD;
+            ST_LOCAL_WAIT:                              err_cause = ERR_LOCAL_READ;
+            ST_INST_ADDR_HOLD, ST_INST_ADDR_DATA_HOLD:  err_cause = ERR_SERIAL_ADDR;
+            ST_TARGET_CMD_HOLD:                         err_cause = ERR_SERIAL_CMD;
+            ST_TARGET_SERIAL_WAIT, ST_TARGET_READ_WAIT: err_cause = ERR_SERIAL_READ;
+            ST_TARGET_WRITE_HOLD:                       err_cause = ERR_SERIAL_WRITE;
+            default:                                    err_cause = ERR_NONE;
+        endcase
+    end
+
     always_ff @(posedge s_axi_aclk) begin
         if (!s_axi_aresetn) begin
-            status_done  <= 1'b0;
-            status_error <= 1'b0;
+            status_done     <= 1'b0;
+            status_error    <= 1'b0;
+            status_err_code <= ERR_NONE;
         end else begin
             // Clear status on new command
             if (state == ST_IDLE && reg_ctrl[CTRL_START]) begin
-                status_done  <= 1'b0;
-                status_error <= 1'b0;
+                status_done     <= 1'b0;
+                status_error    <= 1'b0;
+                status_err_code <= ERR_NONE;
             end
             // Set status on completion
             if (state == ST_DONE)
                 status_done <= 1'b1;
             if (state == ST_ERROR)
                 status_error <= 1'b1;
+            if (state_next == ST_ERROR && state != ST_ERROR)
+                status_err_code <= err_cause;
         end
     end
     
@@ -1042,6 +1093,20 @@ module sump3_axi_wrapper #(
         end
     end
     
+    //=========================================================================
+    // External Trigger Routing
+    //=========================================================================
+    // Combinational: the select only changes while the ILA is idle, and the
+    // core synchronizes trigger_in to its own clock.
+    always_comb begin
+        case (reg_trig_route[1:0])
+            2'd1:    sump_trigger_in = ext_trig_in[0];
+            2'd2:    sump_trigger_in = ext_trig_in[1];
+            default: sump_trigger_in = 1'b0;
+        endcase
+    end
+    assign ext_trig_out = reg_trig_route[4] & sump_trigger_out;
+
     // Drive outputs from registers
     assign lb_cs_ctrl = lb_cs_ctrl_reg;
     assign lb_cs_data = lb_cs_data_reg;
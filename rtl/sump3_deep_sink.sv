@@ -0,0 +1,307 @@
+`timescale 1ns / 100ps
+//=============================================================================
+//
+//  sump3_deep_sink.sv - DDR-backed deep capture sink for SUMP3 probes
+//
+//=============================================================================
+//
+// Copyright (c) 2024 - MIT License
+//
+//=============================================================================
+//
+// OVERVIEW
+// ========
+// Pod BRAM limits captures to a few thousand RLE samples. This sink records
+// up to 32 probe bits into a circular buffer in DDR instead, for slow
+// failures that need seconds or minutes of history. It runs alongside the
+// SUMP3 pods: connect the same probes, and the core's trigger_out (or any
+// trigger) to `trigger`. The software side is `sump_driver::deep::DeepSink`.
+//
+// Everything runs on `aclk`; probes must be synchronous to it. Use an AXI
+// clock converter if the capture clock differs from the interconnect clock.
+//
+// RECORD FORMAT (64-bit, little-endian in DDR)
+// ============================================
+//   [31:0]   DATA       - probe value
+//   [63:32]  TIMESTAMP  - aclk cycles since ENABLE
+//
+// With RLE enabled a record is written only when the probes change (and
+// when the timestamp wraps); otherwise every `sample_en` cycle is recorded.
+//
+// REGISTER MAP (AXI4-Lite)
+// ========================
+// Offset | Name      | Access | Description
+// -------|-----------|--------|-----------------------------------------------
+// 0x00   | ID        | R      | 0x5344_0001 ("SD", revision 1)
+// 0x04   | CTRL      | R/W    | [0]=ENABLE (rising edge restarts), [1]=RLE_EN
+// 0x08   | BASE_LO   | R/W    | Buffer physical address [31:0] (8-byte aligned)
+// 0x0C   | BASE_HI   | R/W    | Buffer physical address [63:32]
+// 0x10   | LENGTH    | R/W    | Buffer length in bytes (multiple of 8)
+// 0x14   | POST_TRIG | R/W    | Records to store after the trigger record
+// 0x18   | STATUS    | R      | [0]=RUNNING [1]=TRIGGERED [2]=DONE [3]=WRAPPED
+//        |           |        | [4]=OVERFLOW (records dropped, DDR too slow)
+// 0x1C   | WR_PTR    | R      | Byte offset of the next record to be written
+// 0x20   | TRIG_PTR  | R      | Byte offset of the trigger record
+//
+//=============================================================================
+
+module sump3_deep_sink #(
+    parameter int C_M_AXI_ADDR_WIDTH = 32,
+    parameter int FIFO_DEPTH         = 64   // Records buffered ahead of DDR
+) (
+    input  logic                          aclk,
+    input  logic                          aresetn,
+
+    // Probes
+    input  logic [31:0]                   probe,
+    input  logic                          sample_en,
+    input  logic                          trigger,
+
+    // AXI4-Lite Slave (control)
+    input  logic [7:0]                    s_axi_awaddr,
+    input  logic                          s_axi_awvalid,
+    output logic                          s_axi_awready,
+    input  logic [31:0]                   s_axi_wdata,
+    input  logic                          s_axi_wvalid,
+    output logic                          s_axi_wready,
+    output logic [1:0]                    s_axi_bresp,
+    output logic                          s_axi_bvalid,
+    input  logic                          s_axi_bready,
+    input  logic [7:0]                    s_axi_araddr,
+    input  logic                          s_axi_arvalid,
+    output logic                          s_axi_arready,
+    output logic [31:0]                   s_axi_rdata,
+    output logic [1:0]                    s_axi_rresp,
+    output logic                          s_axi_rvalid,
+    input  logic                          s_axi_rready,
+
+    // AXI4 Master (write-only, single-beat 64-bit writes to DDR)
+    output logic [C_M_AXI_ADDR_WIDTH-1:0] m_axi_awaddr,
+    output logic [7:0]                    m_axi_awlen,
+    output logic [2:0]                    m_axi_awsize,
+    output logic [1:0]                    m_axi_awburst,
+    output logic                          m_axi_awvalid,
+    input  logic                          m_axi_awready,
+    output logic [63:0]                   m_axi_wdata,
+    output logic [7:0]                    m_axi_wstrb,
+    output logic                          m_axi_wlast,
+    output logic                          m_axi_wvalid,
+    input  logic                          m_axi_wready,
+    input  logic [1:0]                    m_axi_bresp,
+    input  logic                          m_axi_bvalid,
+    output logic                          m_axi_bready
+);
+
+    localparam logic [7:0] REG_ID        = 8'h00;
+    localparam logic [7:0] REG_CTRL      = 8'h04;
+    localparam logic [7:0] REG_BASE_LO   = 8'h08;
+    localparam logic [7:0] REG_BASE_HI   = 8'h0C;
+    localparam logic [7:0] REG_LENGTH    = 8'h10;
+    localparam logic [7:0] REG_POST_TRIG = 8'h14;
+    localparam logic [7:0] REG_STATUS    = 8'h18;
+    localparam logic [7:0] REG_WR_PTR    = 8'h1C;
+    localparam logic [7:0] REG_TRIG_PTR  = 8'h20;
+
+    localparam int FIFO_AW = $clog2(FIFO_DEPTH);
+
+    //=========================================================================
+    // Control Registers
+    //=========================================================================
+    logic [1:0]  reg_ctrl;
+    logic [63:0] reg_base;
+    logic [31:0] reg_length;
+    logic [31:0] reg_post_trig;
+
+    logic        running, triggered, done, wrapped, overflow;
+    logic [31:0] wr_ptr, trig_ptr;
+
+    logic        enable_q;
+    wire         start = reg_ctrl[0] && !enable_q;
+
+    // Single-cycle AXI-Lite write: accept address and data together
+    logic [7:0]  wr_addr;
+    logic        wr_pulse;
+
+    assign s_axi_awready = s_axi_awvalid && s_axi_wvalid && !s_axi_bvalid;
+    assign s_axi_wready  = s_axi_awready;
+    assign s_axi_bresp   = 2'b00;
+    assign s_axi_rresp   = 2'b00;
+
+    always_ff @(posedge aclk) begin
+        if (!aresetn) begin
+            reg_ctrl      <= 2'b00;
+            reg_base      <= 64'h0;
+            reg_length    <= 32'h0;
+            reg_post_trig <= 32'h0;
+            s_axi_bvalid  <= 1'b0;
+            enable_q      <= 1'b0;
+        end else begin
+            enable_q <= reg_ctrl[0];
+            if (s_axi_awready) begin
+                s_axi_bvalid <= 1'b1;
+                case (s_axi_awaddr)
+                    REG_CTRL:      reg_ctrl         <= s_axi_wdata[1:0];
+                    REG_BASE_LO:   reg_base[31:0]   <= s_axi_wdata;
+                    REG_BASE_HI:   reg_base[63:32]  <= s_axi_wdata;
+                    REG_LENGTH:    reg_length       <= s_axi_wdata;
+                    REG_POST_TRIG: reg_post_trig    <= s_axi_wdata;
+                    default: ;
+                endcase
+            end else if (s_axi_bvalid && s_axi_bready) begin
+                s_axi_bvalid <= 1'b0;
+            end
+            // Capture finished: drop ENABLE so the next write of 1 restarts
+            if (done)
+                reg_ctrl[0] <= 1'b0;
+        end
+    end
+
+    always_ff @(posedge aclk) begin
+        if (!aresetn) begin
+            s_axi_arready <= 1'b1;
+            s_axi_rvalid  <= 1'b0;
+            s_axi_rdata   <= 32'h0;
+        end else if (s_axi_arvalid && s_axi_arready) begin
+            s_axi_arready <= 1'b0;
+            s_axi_rvalid  <= 1'b1;
+            case (s_axi_araddr)
+                REG_ID:        s_axi_rdata <= 32'h5344_0001;
+                REG_CTRL:      s_axi_rdata <= {30'h0, reg_ctrl};
+                REG_BASE_LO:   s_axi_rdata <= reg_base[31:0];
+                REG_BASE_HI:   s_axi_rdata <= reg_base[63:32];
+                REG_LENGTH:    s_axi_rdata <= reg_length;
+                REG_POST_TRIG: s_axi_rdata <= reg_post_trig;
+                REG_STATUS:    s_axi_rdata <= {27'h0, overflow, wrapped, done, triggered, running};
+                REG_WR_PTR:    s_axi_rdata <= wr_ptr;
+                REG_TRIG_PTR:  s_axi_rdata <= trig_ptr;
+                default:       s_axi_rdata <= 32'hDEADCAFE;
+            endcase
+        end else if (s_axi_rvalid && s_axi_rready) begin
+            s_axi_rvalid  <= 1'b0;
+            s_axi_arready <= 1'b1;
+        end
+    end
+
+    //=========================================================================
+    // Record Generation
+    //=========================================================================
+    logic [31:0] timestamp, last_probe;
+    logic        have_last, trigger_q, post_active;
+    logic [31:0] post_left;
+
+    // FIFO entries: {is_trigger, timestamp, data}
+    logic [64:0] fifo [FIFO_DEPTH];
+    logic [FIFO_AW:0] fifo_wr, fifo_rd;
+    wire  fifo_empty = fifo_wr == fifo_rd;
+    wire  fifo_full  = (fifo_wr[FIFO_AW] != fifo_rd[FIFO_AW]) &&
+                       (fifo_wr[FIFO_AW-1:0] == fifo_rd[FIFO_AW-1:0]);
+
+    wire trig_edge  = running && !triggered && trigger && !trigger_q;
+    wire changed    = !have_last || probe != last_probe || &timestamp;
+    wire record     = running && sample_en && !(post_active && post_left == 0) &&
+                      (trig_edge || !reg_ctrl[1] || changed);
+
+    always_ff @(posedge aclk) begin
+        if (!aresetn || start) begin
+            timestamp   <= 32'h0;
+            have_last   <= 1'b0;
+            trigger_q   <= 1'b1;  // Ignore a trigger already high at start
+            triggered   <= 1'b0;
+            post_active <= 1'b0;
+            post_left   <= reg_post_trig;
+            overflow    <= 1'b0;
+            fifo_wr     <= '0;
+        end else if (running) begin
+            timestamp <= timestamp + 1;
+            trigger_q <= trigger;
+            if (trig_edge) begin
+                triggered   <= 1'b1;
+                post_active <= 1'b1;
+            end
+            if (record) begin
+                if (fifo_full) begin
+                    overflow <= 1'b1;
+                end else begin
+                    fifo[fifo_wr[FIFO_AW-1:0]] <= {trig_edge, timestamp, probe};
+                    fifo_wr <= fifo_wr + 1;
+                end
+                have_last  <= 1'b1;
+                last_probe <= probe;
+                if (post_active && !trig_edge)
+                    post_left <= post_left - 1;
+            end
+        end
+    end
+
+    //=========================================================================
+    // DDR Writer
+    //=========================================================================
+    typedef enum logic [1:0] { WR_IDLE, WR_ADDR_DATA, WR_RESP } wr_state_t;
+    wr_state_t wr_state;
+    logic [64:0] entry;
+    logic        aw_done, w_done;
+
+    assign m_axi_awlen   = 8'd0;
+    assign m_axi_awsize  = 3'd3;   // 8 bytes
+    assign m_axi_awburst = 2'b01;  // INCR
+    assign m_axi_wstrb   = 8'hFF;
+    assign m_axi_wlast   = 1'b1;
+    assign m_axi_bready  = wr_state == WR_RESP;
+    assign m_axi_awaddr  = reg_base[C_M_AXI_ADDR_WIDTH-1:0] + wr_ptr;
+    assign m_axi_wdata   = {entry[63:32], entry[31:0]};
+    assign m_axi_awvalid = wr_state == WR_ADDR_DATA && !aw_done;
+    assign m_axi_wvalid  = wr_state == WR_ADDR_DATA && !w_done;
+
+    always_ff @(posedge aclk) begin
+        if (!aresetn || start) begin
+            wr_state <= WR_IDLE;
+            fifo_rd  <= '0;
+            wr_ptr   <= 32'h0;
+            trig_ptr <= 32'h0;
+            wrapped  <= 1'b0;
+            done     <= 1'b0;
+            running  <= start;
+            aw_done  <= 1'b0;
+            w_done   <= 1'b0;
+        end else begin
+            case (wr_state)
+                WR_IDLE: begin
+                    if (!fifo_empty) begin
+                        entry    <= fifo[fifo_rd[FIFO_AW-1:0]];
+                        fifo_rd  <= fifo_rd + 1;
+                        aw_done  <= 1'b0;
+                        w_done   <= 1'b0;
+                        wr_state <= WR_ADDR_DATA;
+                    end else if (running && post_active && post_left == 0) begin
+                        // Post-trigger records all written
+                        running <= 1'b0;
+                        done    <= 1'b1;
+                    end else if (!reg_ctrl[0]) begin
+                        running <= 1'b0;
+                    end
+                end
+                WR_ADDR_DATA: begin
+                    if (m_axi_awvalid && m_axi_awready) aw_done <= 1'b1;
+                    if (m_axi_wvalid && m_axi_wready)   w_done  <= 1'b1;
+                    if ((aw_done || m_axi_awready) && (w_done || m_axi_wready))
+                        wr_state <= WR_RESP;
+                end
+                WR_RESP: begin
+                    if (m_axi_bvalid) begin
+                        if (entry[64])
+                            trig_ptr <= wr_ptr;
+                        if (wr_ptr + 8 >= reg_length) begin
+                            wr_ptr  <= 32'h0;
+                            wrapped <= 1'b1;
+                        end else begin
+                            wr_ptr <= wr_ptr + 8;
+                        end
+                        wr_state <= WR_IDLE;
+                    end
+                end
+                default: wr_state <= WR_IDLE;
+            endcase
+        end
+    end
+
+endmodule
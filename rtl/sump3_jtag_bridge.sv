@@ -0,0 +1,182 @@
+`timescale 1ns / 100ps
+//=============================================================================
+//
+//  sump3_jtag_bridge.sv - JTAG USER-chain to AXI4-Lite master for SUMP3
+//
+//=============================================================================
+//
+// Copyright (c) 2024 - MIT License
+//
+//=============================================================================
+//
+// OVERVIEW
+// ========
+// Lets software reach sump3_axi_wrapper over JTAG (e.g. through a Xilinx
+// Virtual Cable server) on boards without a working PS/AXI path. A BSCANE2
+// USER chain is decoded into single AXI4-Lite transactions; connect the
+// master port straight to the wrapper's slave port. The software side is
+// `sump_driver::xvc::XvcBackend`.
+//
+// DATA REGISTER (42 bits, shifted LSB first)
+// ==========================================
+// Shift in (applied on Update-DR):
+//   [0]      VALID  - 1 issues a transaction, 0 is a no-op (poll)
+//   [1]      WRITE  - 1 = write, 0 = read
+//   [9:2]    ADDR   - byte offset into the wrapper
+//   [41:10]  WDATA  - write data
+//
+// Shift out (loaded on Capture-DR):
+//   [31:0]   RDATA  - data of the last completed read
+//   [32]     DONE   - last issued transaction has completed
+//   [33]     ERROR  - last transaction got a non-OKAY response
+//
+// A read is one VALID scan followed by no-op scans until DONE is set.
+//
+//=============================================================================
+
+module sump3_jtag_bridge #(
+    parameter int JTAG_CHAIN = 1  // BSCANE2 USER chain (1-4)
+) (
+    input  logic        m_axi_aclk,
+    input  logic        m_axi_aresetn,
+
+    // Write Address / Data / Response Channels
+    output logic [7:0]  m_axi_awaddr,
+    output logic [2:0]  m_axi_awprot,
+    output logic        m_axi_awvalid,
+    input  logic        m_axi_awready,
+    output logic [31:0] m_axi_wdata,
+    output logic [3:0]  m_axi_wstrb,
+    output logic        m_axi_wvalid,
+    input  logic        m_axi_wready,
+    input  logic [1:0]  m_axi_bresp,
+    input  logic        m_axi_bvalid,
+    output logic        m_axi_bready,
+
+    // Read Address / Data Channels
+    output logic [7:0]  m_axi_araddr,
+    output logic [2:0]  m_axi_arprot,
+    output logic        m_axi_arvalid,
+    input  logic        m_axi_arready,
+    input  logic [31:0] m_axi_rdata,
+    input  logic [1:0]  m_axi_rresp,
+    input  logic        m_axi_rvalid,
+    output logic        m_axi_rready
+);
+
+    localparam int DR_LEN = 42;
+
+    //=========================================================================
+    // JTAG Domain (TCK)
+    //=========================================================================
+    logic tck, tdi, tdo, sel, capture, shift, update;
+
+    BSCANE2 #(
+        .JTAG_CHAIN (JTAG_CHAIN)
+    ) u_bscan (
+        .CAPTURE (capture),
+        .DRCK    (),
+        .RESET   (),
+        .RUNTEST (),
+        .SEL     (sel),
+        .SHIFT   (shift),
+        .TCK     (tck),
+        .TDI     (tdi),
+        .TMS     (),
+        .UPDATE  (update),
+        .TDO     (tdo)
+    );
+
+    logic [DR_LEN-1:0] dr;
+    logic [DR_LEN-1:0] req;          // Request latched on Update-DR
+    logic              req_toggle;   // Flips once per VALID request
+    logic [1:0]        done_sync;    // done_toggle synchronized to TCK
+
+    // Completion results (AXI domain, stable while the toggles match)
+    logic [31:0]       rsp_rdata;
+    logic              rsp_error;
+    logic              done_toggle;
+
+    always_ff @(posedge tck) begin
+        done_sync <= {done_sync[0], done_toggle};
+
+        if (sel && capture) begin
+            dr <= '0;
+            dr[31:0] <= rsp_rdata;
+            dr[32]   <= (done_sync[1] == req_toggle);
+            dr[33]   <= rsp_error;
+        end else if (sel && shift) begin
+            dr <= {tdi, dr[DR_LEN-1:1]};
+        end
+
+        if (sel && update && dr[0]) begin
+            req        <= dr;
+            req_toggle <= ~req_toggle;
+        end
+    end
+
+    assign tdo = dr[0];
+
+    initial begin
+        req_toggle = 1'b0;
+        done_sync  = 2'b00;
+    end
+
+    //=========================================================================
+    // AXI Domain
+    //=========================================================================
+    logic [2:0] req_sync;
+    logic       busy;
+
+    always_ff @(posedge m_axi_aclk) begin
+        if (!m_axi_aresetn) begin
+            req_sync      <= '0;
+            busy          <= 1'b0;
+            done_toggle   <= 1'b0;
+            rsp_rdata     <= '0;
+            rsp_error     <= 1'b0;
+            m_axi_awvalid <= 1'b0;
+            m_axi_wvalid  <= 1'b0;
+            m_axi_arvalid <= 1'b0;
+        end else begin
+            req_sync <= {req_sync[1:0], req_toggle};
+
+            // New request: req is stable until the next Update-DR
+            if (!busy && (req_sync[2] != done_toggle)) begin
+                busy <= 1'b1;
+                if (req[1]) begin
+                    m_axi_awaddr  <= req[9:2];
+                    m_axi_wdata   <= req[41:10];
+                    m_axi_awvalid <= 1'b1;
+                    m_axi_wvalid  <= 1'b1;
+                end else begin
+                    m_axi_araddr  <= req[9:2];
+                    m_axi_arvalid <= 1'b1;
+                end
+            end
+
+            if (m_axi_awvalid && m_axi_awready) m_axi_awvalid <= 1'b0;
+            if (m_axi_wvalid  && m_axi_wready)  m_axi_wvalid  <= 1'b0;
+            if (m_axi_arvalid && m_axi_arready) m_axi_arvalid <= 1'b0;
+
+            if (busy && m_axi_bvalid) begin
+                rsp_error   <= (m_axi_bresp != 2'b00);
+                busy        <= 1'b0;
+                done_toggle <= ~done_toggle;
+            end
+            if (busy && m_axi_rvalid) begin
+                rsp_rdata   <= m_axi_rdata;
+                rsp_error   <= (m_axi_rresp != 2'b00);
+                busy        <= 1'b0;
+                done_toggle <= ~done_toggle;
+            end
+        end
+    end
+
+    assign m_axi_awprot = 3'b000;
+    assign m_axi_arprot = 3'b000;
+    assign m_axi_wstrb  = 4'hF;
+    assign m_axi_bready = 1'b1;
+    assign m_axi_rready = 1'b1;
+
+endmodule
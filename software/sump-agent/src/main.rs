@@ -0,0 +1,92 @@
+//! SUMP3 register bridge agent
+//!
+//! Runs on the target, maps the AXI wrapper via /dev/mem and forwards 32-bit
+//! register accesses from one `sump-server` at a time (see
+//! `sump_driver::bridge` for the protocol). Point the server at it with
+//! `SUMP_BACKEND=tcp://<target>:<port>`.
+//!
+//! ## Runtime Configuration
+//! - `SUMP_AXI_ADDR`: SUMP3 AXI base address (default: 0x43C20000)
+//! - `SUMP_AGENT_PORT`: Listen port (default: 8083)
+//! - `SUMP_ILA_SIZE`: Register window to map and forward, hex or decimal
+//!   (default: 0x100); the server picks it up in the handshake
+
+use std::net::{SocketAddr, TcpListener};
+
+use sump_driver::bridge::{self, DEFAULT_BRIDGE_PORT};
+use sump_driver::devmem::DevMem;
+use sump_driver::ILA_SIZE;
+
+const DEFAULT_AXI_ADDR: usize = 0x43C2_0000;
+
+fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "sump_agent=info,sump_driver=info".into()),
+        )
+        .with_target(false)
+        .init();
+
+    let axi_addr = match std::env::var("SUMP_AXI_ADDR") {
+        Ok(s) => {
+            let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => usize::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            };
+            parsed.expect("Invalid SUMP_AXI_ADDR format")
+        }
+        Err(_) => DEFAULT_AXI_ADDR,
+    };
+    let port = std::env::var("SUMP_AGENT_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_BRIDGE_PORT);
+
+    let size = match std::env::var("SUMP_ILA_SIZE") {
+        Ok(s) => {
+            let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => usize::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            };
+            parsed.filter(|&size| size >= ILA_SIZE && size.is_multiple_of(4)).expect("Invalid SUMP_ILA_SIZE")
+        }
+        Err(_) => ILA_SIZE,
+    };
+
+    let mut mem = match DevMem::new(axi_addr, size) {
+        Ok(mem) => mem,
+        Err(e) => {
+            tracing::error!("Failed to map ILA at 0x{:08X}: {}", axi_addr, e);
+            tracing::error!("Make sure you have permission to access /dev/mem (run as root)");
+            std::process::exit(1);
+        }
+    };
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = match TcpListener::bind(addr) {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::error!("Failed to bind to {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("Bridging ILA at 0x{:08X} ({} byte window) on tcp://{}", axi_addr, size, addr);
+
+    // One client at a time: the wrapper's command handshake is not reentrant
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!("Accept failed: {}", e);
+                continue;
+            }
+        };
+        let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        tracing::info!("Client {} connected", peer);
+        match bridge::serve(stream, &mut mem, size) {
+            Ok(()) => tracing::info!("Client {} disconnected", peer),
+            Err(e) => tracing::warn!("Client {}: {}", peer, e),
+        }
+    }
+}
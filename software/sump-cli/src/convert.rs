@@ -0,0 +1,160 @@
+//! Offline capture conversion
+//!
+//! `sump-cli convert <capture> --to <format> [-o <file>] [--info <ila.json>] [--hub-hz <hz>]`
+//! writes an archived capture with one of the built-in exporters
+//! (`sump_export::EXPORTERS`), without hardware or a running server, so
+//! downloaded captures can be converted on a laptop. The input is the JSON
+//! of `/api/ila/capture/...` or a raw dump from
+//! `/api/ila/capture/:hub/:pod/raw` (or `/api/ila/archive/:id/:hub/:pod/raw`),
+//! migrated like the client does.
+//!
+//! Signal names, labels and the hub clock come from a saved `GET /api/ila`
+//! (`--info`); without it the low 32 bits are one `data` vector and times
+//! are in cycles unless `--hub-hz` is given. Plugin exporters are not loaded.
+
+use std::path::{Path, PathBuf};
+
+use sump_export::{Export, EXPORTERS};
+use sump_model::{CaptureData, IlaInfo, PodInfo, RAW_DUMP_MAGIC};
+
+const USAGE: &str =
+    "usage: sump-cli convert <capture.json|capture.bin> --to <format> [-o <file>] [--info <ila.json>] [--hub-hz <hz>]";
+
+struct Options {
+    input: PathBuf,
+    format: String,
+    output: Option<PathBuf>,
+    info: Option<PathBuf>,
+    hub_hz: Option<u64>,
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let (mut input, mut format, mut output, mut info, mut hub_hz) = (None, None, None, None, None);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--to" => format = Some(value()?),
+            "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+            "--info" => info = Some(PathBuf::from(value()?)),
+            "--hub-hz" => {
+                let hz = value()?;
+                hub_hz = Some(hz.parse().map_err(|_| format!("invalid --hub-hz '{}'", hz))?);
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            flag if flag.starts_with('-') && flag != "-" => return Err(format!("unknown option '{}'", flag)),
+            path if input.is_none() => input = Some(PathBuf::from(path)),
+            extra => return Err(format!("unexpected argument '{}'", extra)),
+        }
+    }
+    Ok(Options {
+        input: input.ok_or("no capture file given")?,
+        format: format.ok_or("--to <format> is required")?,
+        output,
+        info,
+        hub_hz,
+    })
+}
+
+/// A capture JSON or raw dump, migrated to the current schema
+fn load_capture(path: &Path) -> Result<CaptureData, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if bytes.starts_with(RAW_DUMP_MAGIC) {
+        let (header, words) = sump_client::parse_raw_dump(&bytes)
+            .ok_or_else(|| format!("{}: malformed raw dump or newer schema", path.display()))?;
+        return Ok(sump_model::capture_from_raw(&header, &words));
+    }
+    let capture: CaptureData = serde_json::from_slice(&bytes)
+        .map_err(|e| format!("{}: not a capture: {}", path.display(), e))?;
+    capture.migrate().map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The pod's enumeration, hub name and clock from `--info`, or stand-ins
+fn describe(capture: &CaptureData, info: Option<IlaInfo>) -> Result<(PodInfo, String, u64), String> {
+    let (hub, pod) = (capture.hub, capture.pod);
+    let Some(info) = info else {
+        let pod_info = PodInfo {
+            index: pod,
+            name: format!("pod{}", pod),
+            hw_rev: 0,
+            ram_depth: capture.sample_count,
+            data_bits: capture.data_bits,
+            ts_bits: capture.ts_bits,
+            triggerable: 0,
+            rle_disable: false,
+            view_rom_en: false,
+            view_mode: String::new(),
+            signals: Vec::new(),
+            readout: String::new(),
+            display_name: None,
+            description: None,
+        };
+        return Ok((pod_info, format!("hub{}", hub), 0));
+    };
+    let hub_info = info.hubs.into_iter().find(|h| h.index == hub)
+        .ok_or_else(|| format!("--info has no hub {}", hub))?;
+    let hub_name = hub_info.display_name.unwrap_or(hub_info.name);
+    let pod_info = hub_info.pods.into_iter().find(|p| p.index == pod)
+        .ok_or_else(|| format!("--info has no pod {} on hub {}", pod, hub))?;
+    Ok((pod_info, hub_name, hub_info.freq_hz))
+}
+
+fn convert(options: &Options) -> Result<PathBuf, String> {
+    let exporter = EXPORTERS.iter().find(|e| e.name() == options.format).ok_or_else(|| {
+        let names: Vec<&str> = EXPORTERS.iter().map(|e| e.name()).collect();
+        format!("unknown format '{}' (expected {})", options.format, names.join(", "))
+    })?;
+    let capture = load_capture(&options.input)?;
+    let info = match &options.info {
+        Some(path) => {
+            let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Some(serde_json::from_slice(&bytes).map_err(|e| format!("{}: not a GET /api/ila response: {}", path.display(), e))?)
+        }
+        None => None,
+    };
+    let (pod, hub_name, info_hz) = describe(&capture, info)?;
+    let export = Export {
+        capture: &capture,
+        pod: &pod,
+        hub_name: &hub_name,
+        hub_hz: options.hub_hz.unwrap_or(info_hz),
+        id: capture.sequence,
+    };
+
+    let mut body = Vec::new();
+    exporter.write(&export, &mut body).map_err(|e| format!("{}: {}", options.format, e))?;
+    let output = options.output.clone().unwrap_or_else(|| options.input.with_extension(exporter.extension()));
+    if output == Path::new("-") {
+        use std::io::Write;
+        std::io::stdout().write_all(&body).map_err(|e| e.to_string())?;
+    } else {
+        std::fs::write(&output, body).map_err(|e| format!("{}: {}", output.display(), e))?;
+    }
+    Ok(output)
+}
+
+/// Run `sump-cli convert`; returns the process exit code
+pub fn main(args: &[String]) -> i32 {
+    let options = match parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            if e != USAGE {
+                eprintln!("{}", USAGE);
+            }
+            return 2;
+        }
+    };
+    match convert(&options) {
+        Ok(output) => {
+            if output != Path::new("-") {
+                eprintln!("Wrote {}", output.display());
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("sump-cli convert: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! SUMP3 ILA command line tools
+//!
+//! For an analyst's machine or a serial console: no hardware, driver or web
+//! server is linked in.
+//!
+//! ## Subcommands
+//! - `sump-cli convert <capture> --to vcd|csv|npz`: Convert a downloaded
+//!   capture JSON or raw dump with the built-in exporters (see `convert`)
+//! - `sump-cli watch [<url>]`: Follow a running server in a terminal, with
+//!   hot keys to arm, disarm and snapshot (see `watch`)
+
+mod convert;
+mod watch;
+
+const USAGE: &str = "usage: sump-cli <convert|watch> [<args>...]";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = match args.first().map(String::as_str) {
+        Some("convert") => convert::main(&args[1..]),
+        Some("watch") => watch::main(&args[1..]).await,
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            0
+        }
+        Some(command) => {
+            eprintln!("sump-cli: unknown command '{}'\n{}", command, USAGE);
+            2
+        }
+        None => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    };
+    std::process::exit(code);
+}
@@ -0,0 +1,313 @@
+//! Terminal status view
+//!
+//! `sump-cli watch [<url>] [--hub H] [--pod P] [--interval-ms N]` follows
+//! a running server from a terminal and redraws a plain ANSI screen: the
+//! capture status, the pod's RAM fill and recent events (captures read out,
+//! from `/api/ila/digests`, alerts and hot key results). It needs no browser
+//! or terminfo, so it works over a serial console. The server has no event
+//! stream, so events are polled every interval along with the status.
+//!
+//! Keys: `a` arm, `d` disarm, `s` snapshot the pod, `q` or Ctrl-C quit.
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sump_client::Client;
+use sump_model::{CaptureStatus, RamFill};
+use tokio::sync::mpsc;
+
+const USAGE: &str = "usage: sump-cli watch [<url>] [--hub <n>] [--pod <n>] [--interval-ms <ms>]";
+
+/// Server followed when no URL is given
+const DEFAULT_URL: &str = "http://127.0.0.1:8082";
+
+/// Default `--interval-ms`
+const DEFAULT_INTERVAL_MS: u64 = 500;
+
+/// Events kept on screen
+const EVENTS: usize = 12;
+
+/// Longest wait for one request, so a wedged server shows as an error
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Width of the RAM fill bar
+const BAR_WIDTH: usize = 40;
+
+struct Options {
+    url: String,
+    hub: u8,
+    pod: u8,
+    interval: Duration,
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let mut options = Options {
+        url: DEFAULT_URL.into(),
+        hub: 0,
+        pod: 0,
+        interval: Duration::from_millis(DEFAULT_INTERVAL_MS),
+    };
+    let mut url = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+        let number = |text: String| text.parse::<u64>().map_err(|_| format!("invalid {} '{}'", arg, text));
+        match arg.as_str() {
+            "--hub" => options.hub = u8::try_from(number(value()?)?).map_err(|_| "--hub out of range")?,
+            "--pod" => options.pod = u8::try_from(number(value()?)?).map_err(|_| "--pod out of range")?,
+            "--interval-ms" => options.interval = Duration::from_millis(number(value()?)?.max(50)),
+            "-h" | "--help" => return Err(USAGE.into()),
+            flag if flag.starts_with('-') => return Err(format!("unknown option '{}'", flag)),
+            path if url.is_none() => url = Some(path.to_string()),
+            extra => return Err(format!("unexpected argument '{}'", extra)),
+        }
+    }
+    if let Some(url) = url {
+        options.url = url;
+    }
+    Ok(options)
+}
+
+/// Raw, unechoed input while alive; restores the terminal when dropped
+struct RawTerminal(Option<libc::termios>);
+
+impl RawTerminal {
+    fn enter() -> Self {
+        // SAFETY: termios is plain data, filled by tcgetattr before use
+        unsafe {
+            let mut saved: libc::termios = std::mem::zeroed();
+            if libc::isatty(libc::STDIN_FILENO) != 1 || libc::tcgetattr(libc::STDIN_FILENO, &mut saved) != 0 {
+                return Self(None);
+            }
+            let mut raw = saved;
+            libc::cfmakeraw(&mut raw);
+            // Keep output processing, so "\n" still returns the carriage
+            raw.c_oflag |= libc::OPOST;
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw);
+            Self(Some(saved))
+        }
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.0 {
+            // SAFETY: restores the settings read in `enter`
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, saved) };
+        }
+        println!("\x1b[?25h");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Key presses from stdin; closes at end of input
+fn keys() -> mpsc::UnboundedReceiver<u8> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut byte = [0u8];
+        while let Ok(1) = std::io::stdin().read(&mut byte) {
+            if sender.send(byte[0]).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// `HH:MM:SS` of now, local time
+fn now() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as libc::time_t;
+    // SAFETY: tm is plain data; localtime_r only writes it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&secs, &mut tm) };
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+fn describe_status(status: &CaptureStatus) -> &'static str {
+    if status.init_in_progress {
+        "INITIALIZING RAM"
+    } else if status.acquired {
+        "ACQUIRED"
+    } else if status.triggered {
+        "TRIGGERED, filling post-trigger"
+    } else if status.armed && status.pre_trigger {
+        "ARMED, filling pre-trigger"
+    } else if status.armed {
+        "ARMED, waiting for trigger"
+    } else {
+        "IDLE"
+    }
+}
+
+fn status_bits(status: &CaptureStatus) -> [bool; 5] {
+    [status.armed, status.pre_trigger, status.triggered, status.acquired, status.init_in_progress]
+}
+
+#[derive(Default)]
+struct View {
+    status: Option<Result<CaptureStatus, String>>,
+    fill: Option<Result<RamFill, String>>,
+    /// Status bits the fill was last read at
+    fill_status: Option<[bool; 5]>,
+    events: VecDeque<String>,
+    last_capture: Option<u64>,
+    last_alert_ms: u64,
+}
+
+impl View {
+    fn event(&mut self, text: String) {
+        if self.events.len() == EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(format!("{}  {}", now(), text));
+    }
+
+    async fn poll(&mut self, client: &Client, options: &Options) {
+        let status = client.status().await.map_err(|e| e.to_string());
+        // Reading RAM mid-capture would race the core; refresh once the status settles
+        if let Ok(s) = &status {
+            let waiting = s.armed && !s.acquired;
+            if !waiting && self.fill_status != Some(status_bits(s)) {
+                self.fill = Some(client.ram_fill(options.hub, options.pod, None).await.map_err(|e| e.to_string()));
+                self.fill_status = Some(status_bits(s));
+            }
+        }
+        self.status = Some(status);
+
+        // A server that doesn't answer already shows on the status line
+        if let Ok(digests) = client.digests(self.last_capture).await {
+            for d in digests {
+                self.last_capture = Some(d.capture_id);
+                let repeat = if d.repeat { ", same as previous" } else { "" };
+                self.event(format!(
+                    "capture {} hub {} pod {}: {} samples, active 0x{:08X}, {} gaps{}",
+                    d.capture_id, d.hub, d.pod, d.samples, d.active_bits, d.gaps, repeat
+                ));
+            }
+        }
+        if let Ok(alerts) = client.alerts().await {
+            let seen = self.last_alert_ms;
+            for alert in alerts.into_iter().filter(|a| a.fired_at_ms > seen) {
+                self.last_alert_ms = alert.fired_at_ms;
+                self.event(format!("ALERT {} (capture {}): {}", alert.rule, alert.capture_id, alert.detail));
+            }
+        }
+    }
+
+    /// Run a hot key; false to quit
+    async fn key(&mut self, client: &Client, options: &Options, key: u8) -> bool {
+        let result = match key {
+            b'q' | b'Q' | 0x03 => return false,
+            b'a' | b'A' => client.arm().await.map_err(|e| format!("arm: {}", e)),
+            b'd' | b'D' => client.disarm().await.map_err(|e| format!("disarm: {}", e)),
+            b's' | b'S' => match client.snapshot(options.hub, options.pod).await {
+                Ok(capture) => {
+                    // The snapshot rewrote the RAM
+                    self.fill_status = None;
+                    Ok(format!("snapshot {}: {} samples", capture.sequence, capture.samples.len()))
+                }
+                Err(e) => Err(format!("snapshot: {}", e)),
+            },
+            _ => return true,
+        };
+        match result {
+            Ok(message) => self.event(message),
+            Err(message) => self.event(format!("FAILED {}", message)),
+        }
+        self.poll(client, options).await;
+        true
+    }
+
+    fn render(&self, options: &Options) -> String {
+        let mut lines = vec![
+            format!("SUMP3 ILA  {}  hub {} pod {}  {}", options.url, options.hub, options.pod, now()),
+            String::new(),
+        ];
+        lines.push(match &self.status {
+            None => "Status:  ...".into(),
+            Some(Ok(status)) => format!("Status:  {}", describe_status(status)),
+            Some(Err(e)) => format!("Status:  unavailable: {}", e),
+        });
+        lines.push(match &self.fill {
+            None => "RAM:     ...".into(),
+            Some(Ok(fill)) => {
+                let filled = ((fill.fill_percent / 100.0) * BAR_WIDTH as f32).round() as usize;
+                let filled = filled.min(BAR_WIDTH);
+                format!(
+                    "RAM:     [{}{}] {:5.1}% of {}  pre {} trigger {} post {} invalid {}",
+                    "#".repeat(filled), ".".repeat(BAR_WIDTH - filled), fill.fill_percent, fill.ram_depth,
+                    fill.pre_trigger, fill.trigger, fill.post_trigger, fill.invalid
+                )
+            }
+            Some(Err(e)) => format!("RAM:     unavailable: {}", e),
+        });
+        lines.push(String::new());
+        lines.push("Recent events:".into());
+        if self.events.is_empty() {
+            lines.push("  (none yet)".into());
+        }
+        lines.extend(self.events.iter().map(|e| format!("  {}", e)));
+        lines.push(String::new());
+        lines.push("[a] arm  [d] disarm  [s] snapshot  [q] quit".into());
+
+        // Home, then overwrite each line and clear what an older frame left
+        let mut frame = String::from("\x1b[H");
+        for line in lines {
+            frame.push_str(&line);
+            frame.push_str("\x1b[K\n");
+        }
+        frame.push_str("\x1b[J");
+        frame
+    }
+}
+
+/// Run `sump-cli watch` until quit; returns the process exit code
+pub async fn main(args: &[String]) -> i32 {
+    let options = match parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            if e != USAGE {
+                eprintln!("{}", USAGE);
+            }
+            return 2;
+        }
+    };
+    let http = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(http) => http,
+        Err(e) => {
+            eprintln!("sump-cli watch: failed to create HTTP client: {}", e);
+            return 1;
+        }
+    };
+    let client = Client::with_http_client(&options.url, http);
+
+    let terminal = RawTerminal::enter();
+    let mut keys = keys();
+    let mut keys_open = true;
+    let mut view = View::default();
+    let mut ticker = tokio::time::interval(options.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut stdout = std::io::stdout();
+    // Clear once and hide the cursor; frames then redraw in place
+    let _ = write!(stdout, "\x1b[2J\x1b[?25l");
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => view.poll(&client, &options).await,
+            key = keys.recv(), if keys_open => match key {
+                Some(key) => {
+                    if !view.key(&client, &options, key).await {
+                        break;
+                    }
+                }
+                // No terminal input (stdin redirected): keep watching
+                None => keys_open = false,
+            },
+        }
+        let _ = stdout.write_all(view.render(&options).as_bytes());
+        let _ = stdout.flush();
+    }
+    drop(terminal);
+    0
+}
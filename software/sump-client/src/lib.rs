@@ -0,0 +1,463 @@
+//! SUMP3 ILA REST Client
+//!
+//! Async client for the `sump-server` REST API. Request and response bodies
+//! use the same serde types as the server ([`model`]), so the two can't drift.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), sump_client::Error> {
+//! let client = sump_client::Client::new("http://zynq:8082");
+//! client.configure_trigger(&sump_client::model::TriggerConfig {
+//!     trigger_type: "or_rising".into(),
+//!     trigger_bits: 0x1,
+//!     post_trigger: 64,
+//!     ..Default::default()
+//! }).await?;
+//! let capture = client.capture(0, 0, 2048).await?;
+//! println!("{} samples", capture.samples.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+
+pub use sump_model as model;
+
+use model::*;
+
+/// Client error
+#[derive(Debug)]
+pub enum Error {
+    /// Transport, HTTP status or decoding failure
+    Http(reqwest::Error),
+    /// The server executed the request but reported `success: false`
+    Command(String),
+    /// A capture written by a newer schema than this client knows
+    Version(UnsupportedVersion),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::Command(msg) => write!(f, "command failed: {}", msg),
+            Error::Version(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            Error::Command(_) => None,
+            Error::Version(e) => Some(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+impl From<UnsupportedVersion> for Error {
+    fn from(e: UnsupportedVersion) -> Self {
+        Error::Version(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Client for one sump-server instance
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// Create a client for a server URL such as `http://zynq:8082`
+    pub fn new(base_url: &str) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Create a client using a preconfigured `reqwest::Client` (timeouts, proxies)
+    pub fn with_http_client(base_url: &str, http: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/api/ila{}", self.base_url, path)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let resp = self.http.get(self.url(path)).send().await?.error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    async fn command(&self, req: reqwest::RequestBuilder) -> Result<String> {
+        let result: CommandResult = req.send().await?.error_for_status()?.json().await?;
+        if result.success {
+            Ok(result.message)
+        } else {
+            Err(Error::Command(result.message))
+        }
+    }
+
+    /// `GET /api/ila` - ILA info with full hub/pod enumeration
+    pub async fn info(&self) -> Result<IlaInfo> {
+        self.get("").await
+    }
+
+    /// `GET /api/ila/status` - capture status
+    pub async fn status(&self) -> Result<CaptureStatus> {
+        self.get("/status").await
+    }
+
+    /// `GET /api/ila/status/raw` - armed/awake from one register read
+    pub async fn raw_status(&self) -> Result<RawStatus> {
+        self.get("/status/raw").await
+    }
+
+    /// `POST /api/ila/reset`
+    pub async fn reset(&self) -> Result<String> {
+        self.command(self.http.post(self.url("/reset"))).await
+    }
+
+    /// `POST /api/ila/init` - initialize capture RAM
+    pub async fn init(&self) -> Result<String> {
+        self.command(self.http.post(self.url("/init"))).await
+    }
+
+    /// `POST /api/ila/arm`
+    pub async fn arm(&self) -> Result<String> {
+        self.command(self.http.post(self.url("/arm"))).await
+    }
+
+    /// `POST /api/ila/disarm` - stop waiting for the trigger, keeping pod RAM
+    pub async fn disarm(&self) -> Result<String> {
+        self.command(self.http.post(self.url("/disarm"))).await
+    }
+
+    /// `POST /api/ila/trigger` - configure trigger, INIT and ARM
+    pub async fn configure_trigger(&self, config: &TriggerConfig) -> Result<String> {
+        self.command(self.http.post(self.url("/trigger")).json(config)).await
+    }
+
+    /// `POST /api/ila/trigger?dry_run=true` - validate and list the commands
+    /// `configure_trigger` would issue, without writing them
+    pub async fn plan_trigger(&self, config: &TriggerConfig) -> Result<TriggerPlan> {
+        let resp = self.http.post(self.url("/trigger?dry_run=true"))
+            .json(config)
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `POST /api/ila/sequence` - re-arm until condition A is followed by B
+    pub async fn start_sequence(&self, config: &SequenceConfig) -> Result<SequenceStatus> {
+        let resp = self.http.post(self.url("/sequence"))
+            .json(config)
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/ila/sequence`
+    pub async fn sequence_status(&self) -> Result<SequenceStatus> {
+        self.get("/sequence").await
+    }
+
+    /// `POST /api/ila/sequence/stop`
+    pub async fn stop_sequence(&self) -> Result<String> {
+        self.command(self.http.post(self.url("/sequence/stop"))).await
+    }
+
+    /// `POST /api/ila/ext-trigger` - pulse the server's external trigger GPIO
+    /// (`None` uses the server's default width)
+    pub async fn ext_trigger(&self, pulse_us: Option<u64>) -> Result<String> {
+        let path = match pulse_us {
+            Some(us) => format!("/ext-trigger?pulse_us={}", us),
+            None => "/ext-trigger".to_string(),
+        };
+        self.command(self.http.post(self.url(&path))).await
+    }
+
+    /// `GET /api/ila/ext-trigger/routing`
+    pub async fn ext_trigger_routing(&self) -> Result<ExtTriggerRouting> {
+        self.get("/ext-trigger/routing").await
+    }
+
+    /// `PUT /api/ila/ext-trigger/routing` - returns the routing read back
+    pub async fn set_ext_trigger_routing(&self, routing: &ExtTriggerRouting) -> Result<ExtTriggerRouting> {
+        let resp = self.http.put(self.url("/ext-trigger/routing"))
+            .json(routing)
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `POST /api/ila/batch` - run operations in order as one logical operation
+    pub async fn batch(&self, ops: Vec<BatchOp>) -> Result<BatchResult> {
+        let resp = self.http.post(self.url("/batch"))
+            .json(&BatchRequest { ops })
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `POST /api/ila/selftest` - run the plumbing self-test (destroys the capture)
+    pub async fn selftest(&self, force: bool) -> Result<SelfTestReport> {
+        let resp = self.http.post(self.url(&format!("/selftest?force={}", force)))
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/ila/capture/:hub/:pod/:count`
+    pub async fn capture(&self, hub: u8, pod: u8, count: u32) -> Result<CaptureData> {
+        let capture: CaptureData = self.get(&format!("/capture/{}/{}/{}", hub, pod, count)).await?;
+        Ok(capture.migrate()?)
+    }
+
+    /// `GET /api/ila/capture/:hub/:pod/:count?window=..&span=N` - only the
+    /// samples around the trigger (see `ReadoutWindow`)
+    pub async fn capture_window(&self, hub: u8, pod: u8, window: ReadoutWindow, span: u32) -> Result<CaptureData> {
+        let window = match window {
+            ReadoutWindow::Pre => "pre",
+            ReadoutWindow::Post => "post",
+            ReadoutWindow::Around => "around",
+        };
+        let capture: CaptureData = self.get(&format!("/capture/{}/{}/1?window={}&span={}", hub, pod, window, span)).await?;
+        Ok(capture.migrate()?)
+    }
+
+    /// `POST /api/ila/snapshot?hub=H&pod=P` - trigger immediately and read
+    /// the whole capture
+    pub async fn snapshot(&self, hub: u8, pod: u8) -> Result<CaptureData> {
+        let resp = self.http.post(self.url(&format!("/snapshot?hub={}&pod={}", hub, pod)))
+            .send().await?
+            .error_for_status()?;
+        let capture: CaptureData = resp.json().await?;
+        Ok(capture.migrate()?)
+    }
+
+    /// `GET /api/ila/capture/:hub/:pod/raw` - verbatim pod RAM dump.
+    ///
+    /// Returns the header and `pages * ram_depth` words, page-major.
+    pub async fn capture_raw(&self, hub: u8, pod: u8) -> Result<(RawRamHeader, Vec<u32>)> {
+        let resp = self.http.get(self.url(&format!("/capture/{}/{}/raw", hub, pod)))
+            .send().await?
+            .error_for_status()?;
+        let bytes = resp.bytes().await?;
+        parse_raw_dump(&bytes).ok_or_else(|| Error::Command("malformed raw RAM dump".into()))
+    }
+
+    /// `GET /api/ila/capture/:hub/:pod/npz` - the pod's analog fields as a
+    /// NumPy `.npz` archive
+    pub async fn capture_npz(&self, hub: u8, pod: u8) -> Result<Vec<u8>> {
+        let resp = self.http.get(self.url(&format!("/capture/{}/{}/npz", hub, pod)))
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.bytes().await?.to_vec())
+    }
+
+    /// `GET /api/ila/fill/:hub/:pod` - pod RAM fill level (`stride: Some(1)` scans every address)
+    pub async fn ram_fill(&self, hub: u8, pod: u8, stride: Option<u32>) -> Result<RamFill> {
+        match stride {
+            Some(stride) => self.get(&format!("/fill/{}/{}?stride={}", hub, pod, stride)).await,
+            None => self.get(&format!("/fill/{}/{}", hub, pod)).await,
+        }
+    }
+
+    /// `GET /api/ila/deep` - DDR deep-capture sink state
+    pub async fn deep_status(&self) -> Result<DeepStatus> {
+        self.get("/deep").await
+    }
+
+    /// `PUT /api/ila/deep/config` - set the DDR buffer (stops recording)
+    pub async fn configure_deep(&self, config: &DeepConfig) -> Result<DeepStatus> {
+        let resp = self.http.put(self.url("/deep/config"))
+            .json(config)
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `POST /api/ila/deep/arm` - start recording into an empty buffer
+    pub async fn arm_deep(&self) -> Result<String> {
+        self.command(self.http.post(self.url("/deep/arm"))).await
+    }
+
+    /// `POST /api/ila/deep/stop`
+    pub async fn stop_deep(&self) -> Result<String> {
+        self.command(self.http.post(self.url("/deep/stop"))).await
+    }
+
+    /// `GET /api/ila/deep/data` - records oldest first as `(timestamp, data)`
+    pub async fn deep_records(&self) -> Result<Vec<(u32, u32)>> {
+        let resp = self.http.get(self.url("/deep/data")).send().await?.error_for_status()?;
+        let bytes = resp.bytes().await?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|r| {
+                let word = |i: usize| u32::from_le_bytes([r[i], r[i + 1], r[i + 2], r[i + 3]]);
+                (word(4), word(0))
+            })
+            .collect())
+    }
+
+    /// `GET /api/ila/stats` - counters since server start
+    pub async fn stats(&self) -> Result<IlaStats> {
+        self.get("/stats").await
+    }
+
+    /// `GET /api/ila/digests` - digests of captures read out, oldest first
+    /// (`after`: only captures with a larger id)
+    pub async fn digests(&self, after: Option<u64>) -> Result<Vec<CaptureDigest>> {
+        match after {
+            Some(id) => self.get(&format!("/digests?after={}", id)).await,
+            None => self.get("/digests").await,
+        }
+    }
+
+    /// `GET /api/ila/alerts` - recent alert events, oldest first
+    pub async fn alerts(&self) -> Result<Vec<AlertEvent>> {
+        self.get("/alerts").await
+    }
+
+    /// `GET /api/ila/archive` - archived captures with their raw and compressed sizes
+    pub async fn archive(&self) -> Result<ArchiveListing> {
+        self.get("/archive").await
+    }
+
+    /// `GET /api/ila/archive/:id/:hub/:pod` - an archived capture, decoded by the server
+    pub async fn archived_capture(&self, id: u64, hub: u8, pod: u8) -> Result<CaptureData> {
+        self.get(&format!("/archive/{}/{}/{}", id, hub, pod)).await
+    }
+
+    /// `GET /api/ila/health` - hardware watchdog state
+    pub async fn health(&self) -> Result<WatchdogStatus> {
+        self.get("/health").await
+    }
+
+    /// `GET /api/ila/user-ctrl` - last value written to the core user_ctrl bits
+    pub async fn user_ctrl(&self) -> Result<UserBits> {
+        self.get("/user-ctrl").await
+    }
+
+    /// `PUT /api/ila/user-ctrl` - set the core user_ctrl bits selected by `mask`
+    pub async fn set_user_ctrl(&self, value: u32, mask: u32) -> Result<UserBits> {
+        let resp = self.http.put(self.url("/user-ctrl"))
+            .json(&UserBitsWrite { value, mask })
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/ila/user-stim/:hub/:pod` - pod stimulus bits
+    pub async fn user_stim(&self, hub: u8, pod: u8) -> Result<UserBits> {
+        self.get(&format!("/user-stim/{}/{}", hub, pod)).await
+    }
+
+    /// `PUT /api/ila/user-stim/:hub/:pod` - set the pod stimulus bits selected by `mask`
+    pub async fn set_user_stim(&self, hub: u8, pod: u8, value: u32, mask: u32) -> Result<UserBits> {
+        let resp = self.http.put(self.url(&format!("/user-stim/{}/{}", hub, pod)))
+            .json(&UserBitsWrite { value, mask })
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/ila/correlation` - current capture correlation tag
+    pub async fn correlation(&self) -> Result<Option<Correlation>> {
+        self.get("/correlation").await
+    }
+
+    /// `PUT /api/ila/correlation` - tag subsequent captures; returns the tag
+    /// with `reference_ms` filled in
+    pub async fn set_correlation(&self, correlation: &Correlation) -> Result<Correlation> {
+        let resp = self.http.put(self.url("/correlation"))
+            .json(correlation)
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `DELETE /api/ila/correlation`
+    pub async fn clear_correlation(&self) -> Result<()> {
+        self.http.delete(self.url("/correlation")).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// `GET /api/boards` - fleet proxy only: status and health of every board
+    pub async fn boards(&self) -> Result<FleetStatus> {
+        let resp = self.http.get(format!("{}/api/boards", self.base_url))
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/settings` - persistent user settings
+    pub async fn settings(&self) -> Result<Settings> {
+        let resp = self.http.get(format!("{}/api/settings", self.base_url))
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `PUT /api/settings` - replace and persist settings
+    pub async fn put_settings(&self, settings: &Settings) -> Result<Settings> {
+        let resp = self.http.put(format!("{}/api/settings", self.base_url))
+            .json(settings)
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/capabilities` - features this server was built with and is configured for
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        let resp = self.http.get(format!("{}/api/capabilities", self.base_url))
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/ui-config` - embedded frontend configuration and server capabilities
+    pub async fn ui_config(&self) -> Result<UiConfig> {
+        let resp = self.http.get(format!("{}/api/ui-config", self.base_url))
+            .send().await?
+            .error_for_status()?;
+        Ok(resp.json().await?)
+    }
+
+    /// `GET /api/ila/reg/:offset` - raw wrapper register (misaligned or out-of-window offsets fail with 400/404)
+    pub async fn read_reg(&self, offset: usize) -> Result<Option<u32>> {
+        let reg: RegisterValue = self.get(&format!("/reg/{}", offset)).await?;
+        Ok(reg.value)
+    }
+}
+
+/// Split a raw RAM dump into its JSON header, migrated to the current
+/// schema, and data words; `None` if malformed or from a newer schema
+pub fn parse_raw_dump(bytes: &[u8]) -> Option<(RawRamHeader, Vec<u32>)> {
+    let rest = bytes.strip_prefix(RAW_DUMP_MAGIC.as_slice())?;
+    let header_len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let header: RawRamHeader = serde_json::from_slice(rest.get(4..4 + header_len)?).ok()?;
+    let header = header.migrate().ok()?;
+    let words = rest[4 + header_len..]
+        .chunks_exact(4)
+        .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+        .collect();
+    Some((header, words))
+}
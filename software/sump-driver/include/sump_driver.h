@@ -0,0 +1,74 @@
+/*
+ * sump_driver.h - C interface to the SUMP3 ILA driver
+ *
+ * Link against libsump_driver.so (cdylib) or libsump_driver.a (staticlib),
+ * built with `cargo build --release -p sump-driver`.
+ *
+ * Functions returning int return SUMP_OK (0) on success or a negative
+ * SUMP_ERR_* code. Declarations must match src/ffi.rs.
+ */
+
+#ifndef SUMP_DRIVER_H
+#define SUMP_DRIVER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SUMP_OK         0
+#define SUMP_ERR_NULL  -1   /* NULL handle or output pointer */
+#define SUMP_ERR_CMD   -2   /* Command error or timeout */
+#define SUMP_ERR_RANGE -3   /* Address range outside pod RAM */
+
+/* Trigger type codes for sump_configure_trigger() */
+#define SUMP_TRIG_OR_RISING   0x02
+#define SUMP_TRIG_OR_FALLING  0x03
+#define SUMP_TRIG_EXT_RISING  0x06
+
+/* Capture status bits returned by sump_read_status() */
+#define SUMP_STATUS_ARMED        0x01
+#define SUMP_STATUS_PRE_TRIGGER  0x02
+#define SUMP_STATUS_TRIGGERED    0x04
+#define SUMP_STATUS_ACQUIRED     0x08
+#define SUMP_STATUS_INIT         0x10
+
+typedef struct SumpIla SumpIla;
+
+typedef struct {
+    uint32_t address;
+    uint8_t  code;       /* 0=invalid, 1=pre-trigger, 2=trigger, 3=post-trigger */
+    uint32_t timestamp;
+    uint32_t data;
+} SumpSample;
+
+/* Map the AXI wrapper at base_addr via /dev/mem. Returns NULL on failure. */
+SumpIla *sump_open(size_t base_addr);
+void sump_close(SumpIla *ila);
+
+/* Raw wrapper command; rdata may be NULL. */
+int sump_exec_cmd(const SumpIla *ila, uint32_t cmd, uint32_t addr, uint32_t wdata, uint32_t *rdata);
+int sump_read_status(const SumpIla *ila, uint32_t *status);
+
+int sump_reset(const SumpIla *ila);
+int sump_init(const SumpIla *ila);
+int sump_arm(const SumpIla *ila);
+
+/* Reset and program the trigger on pod (0,0). Call sump_init() and sump_arm() afterwards. */
+int sump_configure_trigger(const SumpIla *ila, uint32_t trig_type, uint32_t trigger_bits, uint32_t post_trigger);
+
+/* Pod RAM geometry; output pointers may be NULL. */
+int sump_pod_config(const SumpIla *ila, uint8_t hub, uint8_t pod,
+                    uint8_t *ts_bits, uint16_t *data_bits, uint32_t *ram_depth);
+
+/* Read count samples starting at RAM address start. *read receives the number stored. */
+int sump_read_samples(const SumpIla *ila, uint8_t hub, uint8_t pod, uint32_t start, uint32_t count,
+                      SumpSample *out, uint32_t *read);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SUMP_DRIVER_H */
@@ -0,0 +1,63 @@
+//! Register access backends
+//!
+//! `Ila` issues every wrapper access through a `Backend`: `DevMem` on the
+//! target, or a transport such as `bridge::TcpBackend` when the driver runs
+//! on another machine.
+
+use crate::devmem::{merge_bits, DevMem};
+
+/// 32-bit register access to the wrapper's window (offsets relative to its base)
+pub trait Backend: Send {
+    fn read32(&mut self, offset: usize) -> Option<u32>;
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool;
+
+    /// Read-modify-write the bits under `mask`; two transactions on remote
+    /// transports, so only for registers nothing else writes meanwhile
+    fn modify32(&mut self, offset: usize, mask: u32, value: u32) -> bool {
+        match self.read32(offset) {
+            Some(word) => self.write32(offset, merge_bits(word, mask, value)),
+            None => false,
+        }
+    }
+
+    /// Transport description for logs (`/dev/mem`, `tcp://host:port`)
+    fn describe(&self) -> String;
+
+    /// Transport reconnects since the backend was opened
+    fn reconnects(&self) -> u64 {
+        0
+    }
+}
+
+impl Backend for DevMem {
+    fn read32(&mut self, offset: usize) -> Option<u32> {
+        DevMem::read32(self, offset)
+    }
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool {
+        DevMem::write32(self, offset, value)
+    }
+
+    fn describe(&self) -> String {
+        format!("/dev/mem @ 0x{:08X}", self.base_addr())
+    }
+}
+
+/// Placeholder for a backend that could not be opened: every access fails.
+/// Swapped for the real one with `Ila::set_backend` once it opens.
+pub struct Disconnected(pub String);
+
+impl Backend for Disconnected {
+    fn read32(&mut self, _offset: usize) -> Option<u32> {
+        None
+    }
+
+    fn write32(&mut self, _offset: usize, _value: u32) -> bool {
+        false
+    }
+
+    fn describe(&self) -> String {
+        format!("disconnected ({})", self.0)
+    }
+}
@@ -0,0 +1,182 @@
+//! TCP register bridge
+//!
+//! Lets the driver run off-target against `sump-agent`, a small process on
+//! the target that owns the /dev/mem mapping and forwards 32-bit accesses.
+//! All integers are little-endian:
+//!
+//! - handshake: client sends `BRIDGE_MAGIC`; agent replies `BRIDGE_MAGIC`
+//!   and the u32 window size
+//! - read: `b'R'`, u32 offset -> u8 status, u32 value
+//! - write: `b'W'`, u32 offset, u32 value -> u8 status
+//!
+//! Status 0 is success; anything else means the offset was rejected.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::backend::Backend;
+
+pub const BRIDGE_MAGIC: &[u8; 8] = b"SUMPBRG1";
+
+/// Default `sump-agent` port
+pub const DEFAULT_BRIDGE_PORT: u16 = 8083;
+
+pub const OP_READ: u8 = b'R';
+pub const OP_WRITE: u8 = b'W';
+
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_RANGE: u8 = 1;
+
+/// Per-transaction socket timeout
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `Backend` that forwards accesses to a `sump-agent` over TCP
+pub struct TcpBackend {
+    addr: String,
+    stream: Option<TcpStream>,
+    size: usize,
+    reconnects: u64,
+}
+
+fn open(addr: &str) -> io::Result<(TcpStream, usize)> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    stream.write_all(BRIDGE_MAGIC)?;
+    let mut reply = [0u8; 12];
+    stream.read_exact(&mut reply)?;
+    if &reply[..8] != BRIDGE_MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "peer is not a sump-agent"));
+    }
+    let size = u32::from_le_bytes(reply[8..].try_into().unwrap()) as usize;
+    Ok((stream, size))
+}
+
+impl TcpBackend {
+    /// Connect to an agent at `host:port` and perform the handshake
+    pub fn connect(addr: &str) -> io::Result<Self> {
+        let (stream, size) = open(addr)?;
+        tracing::info!("Connected to sump-agent at {} ({} byte window)", addr, size);
+        Ok(Self {
+            addr: addr.to_string(),
+            stream: Some(stream),
+            size,
+            reconnects: 0,
+        })
+    }
+
+    /// Size of the register window exposed by the agent
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn stream(&mut self) -> io::Result<&mut TcpStream> {
+        if self.stream.is_none() {
+            let (stream, _) = open(&self.addr)?;
+            self.reconnects += 1;
+            tracing::info!("Reconnected to sump-agent at {}", self.addr);
+            self.stream = Some(stream);
+        }
+        Ok(self.stream.as_mut().unwrap())
+    }
+
+    fn transact(&mut self, request: &[u8], reply: &mut [u8]) -> io::Result<()> {
+        let result = self.stream().and_then(|s| {
+            s.write_all(request)?;
+            s.read_exact(reply)
+        });
+        if result.is_err() {
+            // Drop the connection; the next access reconnects
+            self.stream = None;
+        }
+        result
+    }
+}
+
+impl Backend for TcpBackend {
+    fn read32(&mut self, offset: usize) -> Option<u32> {
+        let mut request = [OP_READ, 0, 0, 0, 0];
+        request[1..].copy_from_slice(&(offset as u32).to_le_bytes());
+        let mut reply = [0u8; 5];
+
+        // Reads are side-effect free, so retry once on a fresh connection
+        let result = self.transact(&request, &mut reply)
+            .or_else(|_| self.transact(&request, &mut reply));
+        if let Err(e) = result {
+            tracing::warn!("sump-agent read 0x{:02X} failed: {}", offset, e);
+            return None;
+        }
+        (reply[0] == STATUS_OK).then(|| u32::from_le_bytes(reply[1..].try_into().unwrap()))
+    }
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool {
+        let mut request = [OP_WRITE, 0, 0, 0, 0, 0, 0, 0, 0];
+        request[1..5].copy_from_slice(&(offset as u32).to_le_bytes());
+        request[5..].copy_from_slice(&value.to_le_bytes());
+        let mut reply = [0u8; 1];
+
+        // Not retried: the write may have landed before the connection dropped
+        if let Err(e) = self.transact(&request, &mut reply) {
+            tracing::warn!("sump-agent write 0x{:02X} failed: {}", offset, e);
+            return false;
+        }
+        reply[0] == STATUS_OK
+    }
+
+    fn describe(&self) -> String {
+        format!("tcp://{}", self.addr)
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+}
+
+/// Serve one bridge client from a local backend until it disconnects
+pub fn serve(mut stream: TcpStream, backend: &mut dyn Backend, size: usize) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+
+    let mut magic = [0u8; 8];
+    stream.read_exact(&mut magic)?;
+    if &magic != BRIDGE_MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bad bridge handshake"));
+    }
+    stream.write_all(BRIDGE_MAGIC)?;
+    stream.write_all(&(size as u32).to_le_bytes())?;
+
+    loop {
+        let mut op = [0u8; 1];
+        match stream.read_exact(&mut op) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            result => result?,
+        }
+        let mut word = [0u8; 4];
+        stream.read_exact(&mut word)?;
+        let offset = u32::from_le_bytes(word) as usize;
+
+        match op[0] {
+            OP_READ => {
+                let mut reply = [STATUS_RANGE, 0, 0, 0, 0];
+                if let Some(value) = backend.read32(offset) {
+                    reply[0] = STATUS_OK;
+                    reply[1..].copy_from_slice(&value.to_le_bytes());
+                }
+                stream.write_all(&reply)?;
+            }
+            OP_WRITE => {
+                stream.read_exact(&mut word)?;
+                let ok = backend.write32(offset, u32::from_le_bytes(word));
+                stream.write_all(&[if ok { STATUS_OK } else { STATUS_RANGE }])?;
+            }
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown bridge op 0x{:02X}", other),
+                ));
+            }
+        }
+    }
+}
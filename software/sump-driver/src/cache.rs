@@ -0,0 +1,77 @@
+//! Readout cache
+//!
+//! Once the capture status reports `acquired`, pod RAM no longer changes
+//! until the next ARM, RESET or INIT, so readouts are kept in memory and
+//! served again without touching the serial bus. Each invalidation bumps a
+//! generation; a readout that raced with one is not stored.
+
+use std::collections::HashMap;
+
+use sump_model::{RleSample, TriggerSource};
+
+#[derive(Default)]
+pub(crate) struct ReadoutCache {
+    generation: u64,
+    /// Decoded samples from address 0 of each pod, contiguous
+    samples: HashMap<(u8, u8), Vec<RleSample>>,
+    /// Full page-major RAM dump of each pod, with the RAM_CFG it was read with
+    raw: HashMap<(u8, u8), (u32, Vec<u32>)>,
+    /// Trigger sample address of each scanned pod, `None` if it has none
+    trigger: HashMap<(u8, u8), Option<u32>>,
+    /// Latched trigger source, once read; `Some(None)` if none was latched
+    trigger_source: Option<Option<TriggerSource>>,
+}
+
+impl ReadoutCache {
+    pub fn invalidate(&mut self) {
+        self.generation += 1;
+        self.samples.clear();
+        self.raw.clear();
+        self.trigger.clear();
+        self.trigger_source = None;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn samples(&self, pod: (u8, u8)) -> Option<&Vec<RleSample>> {
+        self.samples.get(&pod)
+    }
+
+    pub fn store_samples(&mut self, generation: u64, pod: (u8, u8), samples: Vec<RleSample>) {
+        if generation == self.generation {
+            self.samples.insert(pod, samples);
+        }
+    }
+
+    pub fn raw(&self, pod: (u8, u8), ram_cfg: u32) -> Option<&Vec<u32>> {
+        self.raw.get(&pod).filter(|(cfg, _)| *cfg == ram_cfg).map(|(_, words)| words)
+    }
+
+    pub fn store_raw(&mut self, generation: u64, pod: (u8, u8), ram_cfg: u32, words: Vec<u32>) {
+        if generation == self.generation {
+            self.raw.insert(pod, (ram_cfg, words));
+        }
+    }
+
+    pub fn trigger(&self, pod: (u8, u8)) -> Option<Option<u32>> {
+        self.trigger.get(&pod).copied()
+    }
+
+    pub fn store_trigger(&mut self, generation: u64, pod: (u8, u8), address: Option<u32>) {
+        if generation == self.generation {
+            self.trigger.insert(pod, address);
+        }
+    }
+
+    pub fn trigger_source(&self) -> Option<Option<TriggerSource>> {
+        self.trigger_source
+    }
+
+    pub fn store_trigger_source(&mut self, generation: u64, source: Option<TriggerSource>) {
+        if generation == self.generation {
+            self.trigger_source = Some(source);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! Wall-clock formatting
+//!
+//! Capture times are kept as Unix ms and also reported as RFC 3339 in the
+//! server's local time zone (`TZ`), offset included, so captures from
+//! boards in different zones or with drifting clocks can be lined up.
+
+pub use sump_model::{rfc3339_offset, rfc3339_utc};
+
+/// `unix_ms` as RFC 3339 local time with milliseconds, e.g.
+/// `2026-10-16T14:35:27.481+02:00` (`Z` when the offset is zero)
+pub fn rfc3339(unix_ms: u64) -> String {
+    let secs = (unix_ms / 1000) as libc::time_t;
+    // SAFETY: tm is plain data; localtime_r only writes it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let offset_min = if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        0
+    } else {
+        (tm.tm_gmtoff / 60) as i32
+    };
+    rfc3339_offset(unix_ms, offset_min)
+}
@@ -0,0 +1,114 @@
+//! RTL revision compatibility
+//!
+//! The wrapper reports its revision in HW_INFO[7:0] and each pod its
+//! `hw_rev` in HW_CFG[31:24]. `MATRIX` lists the first wrapper revision
+//! implementing each optional feature the driver uses; operations on a
+//! feature the wrapper lacks are refused (`Ila::require`) instead of
+//! reading whatever an older bitstream returns. Revisions outside
+//! `WRAPPER_REVISIONS`/`POD_REVISIONS` were never tested against this
+//! driver and only get a warning (`check`), as do pods whose RAM words
+//! are wider than the readout decodes.
+
+use std::ops::RangeInclusive;
+
+use sump_model::IlaInfo;
+
+/// Wrapper revisions this driver was written against
+/// (`rtl/sump3_axi_wrapper.sv` reports 1)
+pub const WRAPPER_REVISIONS: RangeInclusive<u8> = 1..=1;
+
+/// Pod `hw_rev` values of the SUMP3 RLE pods this driver was tested with
+pub const POD_REVISIONS: RangeInclusive<u8> = 0..=1;
+
+/// Widest pod RAM words the readout decodes: the data in page 0 and
+/// {code[1:0], timestamp} in page 1
+pub const MAX_DECODED_DATA_BITS: u16 = 32;
+pub const MAX_DECODED_TS_BITS: u8 = 30;
+
+/// An optional wrapper feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// STATUS[7:4] error code on a failed command
+    ErrorCodes,
+    /// TIMEOUT register bounding serial-bus commands
+    CommandTimeout,
+    /// TRIG_ROUTE register and the `external` trigger type
+    ExtTriggerRouting,
+    /// `CMD_RD_TRIG_SRC_POD` and the pods' trigger source latches
+    TriggerSource,
+}
+
+/// First wrapper revision implementing each `Feature`
+pub const MATRIX: &[(Feature, u8)] = &[
+    (Feature::ErrorCodes, 1),
+    (Feature::CommandTimeout, 1),
+    (Feature::ExtTriggerRouting, 1),
+    (Feature::TriggerSource, 1),
+];
+
+impl Feature {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ErrorCodes => "command error codes",
+            Self::CommandTimeout => "command timeout",
+            Self::ExtTriggerRouting => "external trigger routing",
+            Self::TriggerSource => "trigger source latches",
+        }
+    }
+
+    /// First wrapper revision implementing the feature
+    pub fn min_revision(self) -> u8 {
+        MATRIX.iter().find(|&&(feature, _)| feature == self).map_or(u8::MAX, |&(_, revision)| revision)
+    }
+
+    /// Whether a wrapper of `revision` implements the feature
+    pub fn supported_by(self, revision: u8) -> bool {
+        revision >= self.min_revision()
+    }
+
+    /// Why a wrapper of `revision` can't do this
+    pub fn unsupported(self, revision: u8) -> String {
+        format!(
+            "{} needs wrapper RTL revision {} or later; this wrapper reports revision {}",
+            self.name(), self.min_revision(), revision
+        )
+    }
+}
+
+/// Warnings for an enumerated ILA: untested wrapper or pod revisions, the
+/// features an old wrapper lacks and pods too wide to decode. Empty without
+/// hardware.
+pub fn check(info: &IlaInfo) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !info.connected {
+        return warnings;
+    }
+    if !WRAPPER_REVISIONS.contains(&info.revision) {
+        warnings.push(format!(
+            "Wrapper RTL revision {} is untested (supported: {}..={})",
+            info.revision, WRAPPER_REVISIONS.start(), WRAPPER_REVISIONS.end()
+        ));
+    }
+    for &(feature, _) in MATRIX {
+        if !feature.supported_by(info.revision) {
+            warnings.push(format!("{} unavailable: {}", feature.name(), feature.unsupported(info.revision)));
+        }
+    }
+    for hub in &info.hubs {
+        for pod in &hub.pods {
+            if !POD_REVISIONS.contains(&pod.hw_rev) {
+                warnings.push(format!(
+                    "Hub {} pod {}: hw_rev {} is untested (supported: {}..={})",
+                    hub.index, pod.index, pod.hw_rev, POD_REVISIONS.start(), POD_REVISIONS.end()
+                ));
+            }
+            if pod.data_bits > MAX_DECODED_DATA_BITS || pod.ts_bits > MAX_DECODED_TS_BITS {
+                warnings.push(format!(
+                    "Hub {} pod {}: {} data and {} timestamp bits are unsupported (at most {} and {}); its captures won't decode",
+                    hub.index, pod.index, pod.data_bits, pod.ts_bits, MAX_DECODED_DATA_BITS, MAX_DECODED_TS_BITS
+                ));
+            }
+        }
+    }
+    warnings
+}
@@ -0,0 +1,187 @@
+//! DDR deep-capture sink
+//!
+//! Control and readout of `rtl/sump3_deep_sink.sv`, which records probes as
+//! 64-bit `{timestamp, data}` records into a circular buffer in DDR. The
+//! buffer is read back through `/dev/mem` one window at a time, so buffers
+//! of hundreds of MB never need a single mapping.
+
+use std::io;
+
+use sump_model::{DeepConfig, DeepStatus, FieldError};
+
+use crate::devmem::DevMem;
+
+/// ID register value of a revision 1 sink
+pub const DEEP_SINK_ID: u32 = 0x5344_0001;
+
+pub const DEEP_REGS_SIZE: usize = 0x100;
+
+pub const DEEP_REG_ID: usize        = 0x00;
+pub const DEEP_REG_CTRL: usize      = 0x04;
+pub const DEEP_REG_BASE_LO: usize   = 0x08;
+pub const DEEP_REG_BASE_HI: usize   = 0x0C;
+pub const DEEP_REG_LENGTH: usize    = 0x10;
+pub const DEEP_REG_POST_TRIG: usize = 0x14;
+pub const DEEP_REG_STATUS: usize    = 0x18;
+pub const DEEP_REG_WR_PTR: usize    = 0x1C;
+pub const DEEP_REG_TRIG_PTR: usize  = 0x20;
+
+pub const DEEP_CTRL_ENABLE: u32 = 0x01;
+pub const DEEP_CTRL_RLE: u32    = 0x02;
+
+/// Bytes per record: data in the low word, timestamp in the high word
+pub const RECORD_BYTES: u32 = 8;
+
+/// Size of each `/dev/mem` window used for readout
+pub const CHUNK_BYTES: u32 = 1 << 20;
+
+/// Handle to a deep-capture sink's register window
+pub struct DeepSink {
+    regs: DevMem,
+    regs_addr: usize,
+}
+
+impl DeepSink {
+    /// Map the sink registers at `regs_addr` and check its ID
+    pub fn open(regs_addr: usize) -> io::Result<Self> {
+        let regs = DevMem::new(regs_addr, DEEP_REGS_SIZE)?;
+        match regs.read32(DEEP_REG_ID) {
+            Some(DEEP_SINK_ID) => Ok(Self { regs, regs_addr }),
+            id => Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+                "no deep-capture sink at 0x{:08X} (ID {:08X?})", regs_addr, id
+            ))),
+        }
+    }
+
+    pub fn regs_addr(&self) -> usize {
+        self.regs_addr
+    }
+
+    fn reg(&self, offset: usize) -> u32 {
+        self.regs.read32(offset).unwrap_or(0)
+    }
+
+    pub fn config(&self) -> DeepConfig {
+        DeepConfig {
+            base_addr: ((self.reg(DEEP_REG_BASE_HI) as u64) << 32) | self.reg(DEEP_REG_BASE_LO) as u64,
+            length: self.reg(DEEP_REG_LENGTH),
+            post_trigger: self.reg(DEEP_REG_POST_TRIG),
+            rle: self.reg(DEEP_REG_CTRL) & DEEP_CTRL_RLE != 0,
+        }
+    }
+
+    /// Program the buffer; stops a running capture
+    pub fn configure(&self, config: &DeepConfig) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut error = |field: &str, message: String| {
+            errors.push(FieldError { field: field.into(), message });
+        };
+        if !config.base_addr.is_multiple_of(RECORD_BYTES as u64) {
+            error("base_addr", format!("0x{:X} is not 8-byte aligned", config.base_addr));
+        }
+        if config.length == 0 || !config.length.is_multiple_of(RECORD_BYTES) {
+            error("length", format!("{} is not a non-zero multiple of 8", config.length));
+        }
+        if config.post_trigger >= config.length / RECORD_BYTES {
+            error("post_trigger", format!(
+                "{} records does not fit a {}-record buffer", config.post_trigger, config.length / RECORD_BYTES
+            ));
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        self.regs.write32(DEEP_REG_CTRL, 0);
+        self.regs.write32(DEEP_REG_BASE_LO, config.base_addr as u32);
+        self.regs.write32(DEEP_REG_BASE_HI, (config.base_addr >> 32) as u32);
+        self.regs.write32(DEEP_REG_LENGTH, config.length);
+        self.regs.write32(DEEP_REG_POST_TRIG, config.post_trigger);
+        self.regs.write32(DEEP_REG_CTRL, if config.rle { DEEP_CTRL_RLE } else { 0 });
+        Ok(())
+    }
+
+    /// Restart recording from an empty buffer
+    pub fn arm(&self) {
+        self.regs.modify32(DEEP_REG_CTRL, DEEP_CTRL_ENABLE, 0);
+        self.regs.modify32(DEEP_REG_CTRL, DEEP_CTRL_ENABLE, DEEP_CTRL_ENABLE);
+    }
+
+    /// Stop recording; the buffer is kept for readout
+    pub fn stop(&self) {
+        self.regs.modify32(DEEP_REG_CTRL, DEEP_CTRL_ENABLE, 0);
+    }
+
+    pub fn status(&self) -> DeepStatus {
+        let config = self.config();
+        let status = self.reg(DEEP_REG_STATUS);
+        let wr_ptr = self.reg(DEEP_REG_WR_PTR);
+        let wrapped = status & 0x08 != 0;
+        let triggered = status & 0x02 != 0;
+
+        // Chronological order starts at WR_PTR once the buffer has wrapped
+        let start = if wrapped { wr_ptr } else { 0 };
+        let records = if wrapped { config.length } else { wr_ptr } / RECORD_BYTES;
+        let trigger_record = triggered.then(|| {
+            let trig_ptr = self.reg(DEEP_REG_TRIG_PTR);
+            ((trig_ptr + config.length - start) % config.length.max(1) / RECORD_BYTES) as u64
+        });
+
+        DeepStatus {
+            config,
+            running: status & 0x01 != 0,
+            triggered,
+            done: status & 0x04 != 0,
+            wrapped,
+            overflow: status & 0x10 != 0,
+            records: records as u64,
+            trigger_record,
+        }
+    }
+
+    /// Read every valid record, oldest first, as raw little-endian bytes in
+    /// chunks of at most `CHUNK_BYTES`; `sink` returns false to stop early
+    pub fn read_records(&self, sink: impl FnMut(Vec<u8>) -> bool) -> io::Result<()> {
+        self.read_range(0, u64::MAX, sink)
+    }
+
+    /// Like `read_records`, for bytes `start..end` of the oldest-first
+    /// stream only (for resumed downloads)
+    pub fn read_range(&self, start: u64, end: u64, mut sink: impl FnMut(Vec<u8>) -> bool) -> io::Result<()> {
+        let config = self.config();
+        let wr_ptr = self.reg(DEEP_REG_WR_PTR);
+        let wrapped = self.reg(DEEP_REG_STATUS) & 0x08 != 0;
+
+        let mut spans = vec![(0, wr_ptr)];
+        if wrapped {
+            spans.insert(0, (wr_ptr, config.length));
+        }
+
+        // Stream offset of the current span's first byte
+        let mut stream_pos = 0u64;
+        for (span_start, span_end) in spans {
+            let span_len = (span_end - span_start) as u64;
+            let mut offset = start.max(stream_pos);
+            let stop = end.min(stream_pos + span_len);
+            while offset < stop {
+                let len = (stop - offset).min(CHUNK_BYTES as u64);
+                // Map whole words around the requested bytes
+                let addr = span_start as u64 + (offset - stream_pos);
+                let aligned = addr & !3;
+                let words_len = ((addr + len + 3) & !3) - aligned;
+                let window = DevMem::new((config.base_addr + aligned) as usize, words_len as usize)?;
+                let mut words = vec![0u32; (words_len / 4) as usize];
+                window.read_block(0, &mut words);
+                let chunk = words.iter().flat_map(|w| w.to_le_bytes())
+                    .skip((addr - aligned) as usize)
+                    .take(len as usize)
+                    .collect();
+                if !sink(chunk) {
+                    return Ok(());
+                }
+                offset += len;
+            }
+            stream_pos += span_len;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+//! Direct /dev/mem access for hardware register manipulation
+//!
+//! Provides safe(r) wrappers around mmap for accessing FPGA registers
+//! and BRAM from userspace, and the `BitField`/`merge_bits` helpers register
+//! code uses instead of open-coded shifts and masks.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+
+/// `word` with the bits under `mask` replaced by those of `value`
+#[inline]
+pub const fn merge_bits(word: u32, mask: u32, value: u32) -> u32 {
+    (word & !mask) | (value & mask)
+}
+
+/// Bits `lo..lo + width` of a 32-bit register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitField {
+    pub lo: u8,
+    pub width: u8,
+}
+
+impl BitField {
+    pub const fn new(lo: u8, width: u8) -> Self {
+        assert!(width > 0 && lo as u32 + width as u32 <= 32, "bit field outside a 32-bit register");
+        Self { lo, width }
+    }
+
+    /// The field's bits in place
+    #[inline]
+    pub const fn mask(self) -> u32 {
+        (u32::MAX >> (32 - self.width as u32)) << self.lo
+    }
+
+    /// The field's value in `word`
+    #[inline]
+    pub const fn get(self, word: u32) -> u32 {
+        (word & self.mask()) >> self.lo
+    }
+
+    /// `word` with the field set to `value`, truncated to the field width
+    #[inline]
+    pub const fn set(self, word: u32, value: u32) -> u32 {
+        merge_bits(word, self.mask(), value << self.lo)
+    }
+}
+
+/// Memory-mapped region for hardware access
+pub struct DevMem {
+    ptr: *mut u8,
+    size: usize,
+    base_addr: usize,
+}
+
+// Safety: DevMem only provides &self methods that use volatile reads/writes
+// The underlying memory is hardware registers, not shared with other threads
+unsafe impl Send for DevMem {}
+unsafe impl Sync for DevMem {}
+
+impl DevMem {
+    /// Map a physical memory region
+    ///
+    /// # Arguments
+    /// * `base_addr` - Physical base address (must be page-aligned for best results)
+    /// * `size` - Size of region to map
+    ///
+    /// # Safety
+    /// Caller must ensure the address range is valid for the hardware
+    pub fn new(base_addr: usize, size: usize) -> io::Result<Self> {
+        // O_SYNC maps uncached, so DDR written by the PL (deep capture) is
+        // read fresh instead of from stale cache lines
+        let fd = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_SYNC)
+            .open("/dev/mem")?;
+
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize };
+        let page_offset = base_addr % page_size;
+        let map_base = base_addr - page_offset;
+        let map_size = size + page_offset;
+
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                map_base as libc::off_t,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Adjust pointer to account for page alignment
+        let adjusted_ptr = unsafe { (ptr as *mut u8).add(page_offset) };
+
+        Ok(Self {
+            ptr: adjusted_ptr,
+            size,
+            base_addr,
+        })
+    }
+
+    /// Read a 32-bit word at byte offset
+    #[inline]
+    pub fn read32(&self, offset: usize) -> Option<u32> {
+        if offset + 4 > self.size {
+            return None;
+        }
+        Some(unsafe {
+            std::ptr::read_volatile(self.ptr.add(offset) as *const u32)
+        })
+    }
+
+    /// Write a 32-bit word at byte offset
+    #[inline]
+    pub fn write32(&self, offset: usize, value: u32) -> bool {
+        if offset + 4 > self.size {
+            return false;
+        }
+        unsafe {
+            std::ptr::write_volatile(self.ptr.add(offset) as *mut u32, value);
+        }
+        true
+    }
+
+    /// Read-modify-write the bits under `mask` (`false` if outside the mapping)
+    pub fn modify32(&self, offset: usize, mask: u32, value: u32) -> bool {
+        match self.read32(offset) {
+            Some(word) => self.write32(offset, merge_bits(word, mask, value)),
+            None => false,
+        }
+    }
+
+    /// Read one field of the word at byte offset
+    pub fn read_field(&self, offset: usize, field: BitField) -> Option<u32> {
+        self.read32(offset).map(|word| field.get(word))
+    }
+
+    /// Set one field of the word at byte offset, keeping the other bits
+    pub fn write_field(&self, offset: usize, field: BitField, value: u32) -> bool {
+        self.modify32(offset, field.mask(), value << field.lo)
+    }
+
+    /// Fill `words` from consecutive 32-bit words starting at byte `offset`
+    pub fn read_block(&self, offset: usize, words: &mut [u32]) -> bool {
+        if !offset.is_multiple_of(4) || offset + words.len() * 4 > self.size {
+            return false;
+        }
+        let src = unsafe { self.ptr.add(offset) as *const u32 };
+        for (i, word) in words.iter_mut().enumerate() {
+            // Word-sized volatile loads: memcpy may issue unaligned or wide
+            // accesses that device memory doesn't support
+            *word = unsafe { std::ptr::read_volatile(src.add(i)) };
+        }
+        true
+    }
+
+    /// Get the base address
+    #[allow(dead_code)]
+    pub fn base_addr(&self) -> usize {
+        self.base_addr
+    }
+
+    /// Get the mapped size
+    #[allow(dead_code)]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Drop for DevMem {
+    fn drop(&mut self) {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize };
+        let page_offset = self.base_addr % page_size;
+        let map_ptr = unsafe { self.ptr.sub(page_offset) };
+        let map_size = self.size + page_offset;
+
+        unsafe {
+            libc::munmap(map_ptr as *mut libc::c_void, map_size);
+        }
+    }
+}
+
+#[cfg(test)]
+impl DevMem {
+    /// Anonymous zeroed mapping standing in for device memory
+    fn anonymous(size: usize) -> Self {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(ptr, libc::MAP_FAILED);
+        Self { ptr: ptr as *mut u8, size, base_addr: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOW: BitField = BitField::new(0, 8);
+    const MID: BitField = BitField::new(8, 16);
+    const TOP: BitField = BitField::new(24, 8);
+    const ALL: BitField = BitField::new(0, 32);
+
+    #[test]
+    fn field_masks() {
+        assert_eq!(LOW.mask(), 0x0000_00FF);
+        assert_eq!(MID.mask(), 0x00FF_FF00);
+        assert_eq!(TOP.mask(), 0xFF00_0000);
+        assert_eq!(ALL.mask(), u32::MAX);
+        assert_eq!(BitField::new(31, 1).mask(), 0x8000_0000);
+    }
+
+    #[test]
+    fn field_get_and_set() {
+        let ram_cfg = 0x1800_200A;
+        assert_eq!((LOW.get(ram_cfg), MID.get(ram_cfg), TOP.get(ram_cfg)), (0x0A, 0x20, 0x18));
+        assert_eq!(MID.set(ram_cfg, 0x40), 0x1800_400A);
+        // Values wider than the field are truncated, not spilled into neighbours
+        assert_eq!(LOW.set(0xAABB_CC00, 0x1FF), 0xAABB_CCFF);
+        assert_eq!(ALL.set(0x1234_5678, 0xCAFE_F00D), 0xCAFE_F00D);
+    }
+
+    #[test]
+    fn merge_keeps_bits_outside_mask() {
+        assert_eq!(merge_bits(0xFFFF_0000, 0x0000_FF00, 0x1234_5678), 0xFFFF_5600);
+        assert_eq!(merge_bits(0x1234_5678, 0, u32::MAX), 0x1234_5678);
+        assert_eq!(merge_bits(0x1234_5678, u32::MAX, 0), 0);
+    }
+
+    #[test]
+    fn modify32_read_modify_writes() {
+        let mem = DevMem::anonymous(4096);
+        assert!(mem.write32(0x0C, 0x0000_0003));
+        assert!(mem.modify32(0x0C, 0x01, 0));
+        assert_eq!(mem.read32(0x0C), Some(0x0000_0002));
+        assert!(mem.modify32(0x0C, 0x11, 0x10));
+        assert_eq!(mem.read32(0x0C), Some(0x0000_0012));
+        // Neighbouring words are untouched
+        assert_eq!(mem.read32(0x08), Some(0));
+        assert_eq!(mem.read32(0x10), Some(0));
+    }
+
+    #[test]
+    fn fields_in_memory() {
+        let mem = DevMem::anonymous(4096);
+        assert!(mem.write32(0, 0xDEAD_BEEF));
+        assert!(mem.write_field(0, MID, 0x1234));
+        assert_eq!(mem.read32(0), Some(0xDE12_34EF));
+        assert_eq!(mem.read_field(0, TOP), Some(0xDE));
+        assert_eq!(mem.read_field(0, MID), Some(0x1234));
+    }
+
+    #[test]
+    fn out_of_range_accesses_fail() {
+        let mem = DevMem::anonymous(4096);
+        assert!(!mem.modify32(4096, u32::MAX, 1));
+        assert!(!mem.modify32(4094, u32::MAX, 1));
+        assert_eq!(mem.read_field(4096, LOW), None);
+        assert!(!mem.write_field(4096, LOW, 1));
+    }
+}
@@ -0,0 +1,208 @@
+//! C ABI for the driver
+//!
+//! Thin `extern "C"` wrappers around [`Ila`] for linking from C test
+//! frameworks on the target. The matching declarations live in
+//! `include/sump_driver.h`; keep both in sync.
+//!
+//! All functions returning `c_int` return `SUMP_OK` (0) on success or a
+//! negative `SUMP_ERR_*` code.
+
+use std::os::raw::c_int;
+
+use crate::ila::*;
+
+pub const SUMP_OK: c_int = 0;
+pub const SUMP_ERR_NULL: c_int = -1;
+pub const SUMP_ERR_CMD: c_int = -2;
+pub const SUMP_ERR_RANGE: c_int = -3;
+
+/// One RLE sample as laid out for C callers
+#[repr(C)]
+pub struct SumpSample {
+    pub address: u32,
+    pub code: u8,
+    pub timestamp: u32,
+    pub data: u32,
+}
+
+/// Opaque handle returned by `sump_open`
+pub struct SumpIla(Ila);
+
+/// Map the wrapper at `base_addr`. Returns NULL on failure (errno is set).
+#[no_mangle]
+pub extern "C" fn sump_open(base_addr: usize) -> *mut SumpIla {
+    match Ila::new(base_addr) {
+        Ok(ila) => Box::into_raw(Box::new(SumpIla(ila))),
+        Err(e) => {
+            tracing::error!("Failed to map ILA at 0x{:08X}: {}", base_addr, e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Unmap and free a handle returned by `sump_open`. NULL is ignored.
+///
+/// # Safety
+/// `ila` must be NULL or a handle from `sump_open` not already closed.
+#[no_mangle]
+pub unsafe extern "C" fn sump_close(ila: *mut SumpIla) {
+    if !ila.is_null() {
+        drop(Box::from_raw(ila));
+    }
+}
+
+/// Execute a raw wrapper command; `rdata` may be NULL.
+///
+/// # Safety
+/// `ila` must be a valid handle; `rdata` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn sump_exec_cmd(
+    ila: *const SumpIla,
+    cmd: u32,
+    addr: u32,
+    wdata: u32,
+    rdata: *mut u32,
+) -> c_int {
+    let Some(SumpIla(ila)) = ila.as_ref() else { return SUMP_ERR_NULL };
+    match ila.exec_cmd(cmd, addr, wdata) {
+        Some(value) => {
+            if !rdata.is_null() {
+                *rdata = value;
+            }
+            SUMP_OK
+        }
+        None => SUMP_ERR_CMD,
+    }
+}
+
+/// Read raw capture status bits (`CMD_RD_STATUS`) into `status`.
+///
+/// # Safety
+/// `ila` must be a valid handle; `status` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn sump_read_status(ila: *const SumpIla, status: *mut u32) -> c_int {
+    if status.is_null() {
+        return SUMP_ERR_NULL;
+    }
+    sump_exec_cmd(ila, CMD_RD_STATUS, 0, 0, status)
+}
+
+/// Reset the ILA.
+///
+/// # Safety
+/// `ila` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn sump_reset(ila: *const SumpIla) -> c_int {
+    sump_exec_cmd(ila, CMD_RESET, 0, 0, std::ptr::null_mut())
+}
+
+/// Initialize capture RAM (required before arming).
+///
+/// # Safety
+/// `ila` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn sump_init(ila: *const SumpIla) -> c_int {
+    sump_exec_cmd(ila, CMD_INIT, 0, 0, std::ptr::null_mut())
+}
+
+/// Arm for capture.
+///
+/// # Safety
+/// `ila` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn sump_arm(ila: *const SumpIla) -> c_int {
+    sump_exec_cmd(ila, CMD_ARM, 0, 0, std::ptr::null_mut())
+}
+
+/// Reset and program the trigger (type code, digital field, post-trigger
+/// length) on pod (0,0). Does not INIT or ARM.
+///
+/// # Safety
+/// `ila` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn sump_configure_trigger(
+    ila: *const SumpIla,
+    trig_type: u32,
+    trigger_bits: u32,
+    post_trigger: u32,
+) -> c_int {
+    let Some(SumpIla(ila)) = ila.as_ref() else { return SUMP_ERR_NULL };
+    match ila.configure_trigger(trig_type, trigger_bits, post_trigger, &[]) {
+        Ok(_) => SUMP_OK,
+        Err(msg) => {
+            tracing::warn!("sump_configure_trigger: {}", msg);
+            SUMP_ERR_CMD
+        }
+    }
+}
+
+/// Read pod RAM geometry. Any output pointer may be NULL.
+///
+/// # Safety
+/// `ila` must be a valid handle; non-NULL outputs must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn sump_pod_config(
+    ila: *const SumpIla,
+    hub: u8,
+    pod: u8,
+    ts_bits: *mut u8,
+    data_bits: *mut u16,
+    ram_depth: *mut u32,
+) -> c_int {
+    let Some(SumpIla(ila)) = ila.as_ref() else { return SUMP_ERR_NULL };
+    let (ts, data, depth) = ila.get_pod_config(hub, pod);
+    if !ts_bits.is_null() {
+        *ts_bits = ts;
+    }
+    if !data_bits.is_null() {
+        *data_bits = data;
+    }
+    if !ram_depth.is_null() {
+        *ram_depth = depth;
+    }
+    SUMP_OK
+}
+
+/// Read `count` RLE samples starting at RAM address `start` into `out`.
+/// On return `*read` holds the number of samples stored; reading stops at
+/// the first failed command.
+///
+/// # Safety
+/// `ila` must be a valid handle; `out` must have room for `count` samples;
+/// `read` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn sump_read_samples(
+    ila: *const SumpIla,
+    hub: u8,
+    pod: u8,
+    start: u32,
+    count: u32,
+    out: *mut SumpSample,
+    read: *mut u32,
+) -> c_int {
+    let Some(SumpIla(ila)) = ila.as_ref() else { return SUMP_ERR_NULL };
+    if out.is_null() || read.is_null() {
+        return SUMP_ERR_NULL;
+    }
+    *read = 0;
+
+    let (ts_bits, _, ram_depth) = ila.get_pod_config(hub, pod);
+    if start.checked_add(count).is_none_or(|end| end > ram_depth) {
+        return SUMP_ERR_RANGE;
+    }
+
+    let out = std::slice::from_raw_parts_mut(out, count as usize);
+    for (i, slot) in out.iter_mut().enumerate() {
+        let Some(sample) = ila.read_rle_sample(hub, pod, start + i as u32, ts_bits) else {
+            return SUMP_ERR_CMD;
+        };
+        *slot = SumpSample {
+            address: sample.address,
+            code: sample.code,
+            timestamp: sample.timestamp,
+            data: sample.data,
+        };
+        *read += 1;
+    }
+    SUMP_OK
+}
@@ -0,0 +1,1806 @@
+//! SUMP3 command layer
+//!
+//! Register map, command codes and the polling command/response handshake
+//! of the AXI wrapper, plus hub/pod enumeration and RLE RAM readout.
+
+use parking_lot::{Mutex, MutexGuard};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::backend::Backend;
+use crate::cache::ReadoutCache;
+use crate::clock::rfc3339;
+use crate::compat::{self, Feature};
+use crate::devmem::{merge_bits, BitField, DevMem};
+use crate::readout::MappedRam;
+use crate::stats::CommandStats;
+use sump_model::*;
+
+pub use sump_model::capture_from_raw;
+
+/// Register window of the stock wrapper; variants with registers above it
+/// use `Ila::map` / `Ila::with_window_size`
+pub const ILA_SIZE: usize = 0x100;
+
+// Register offsets (from sump3_axi_wrapper.sv)
+pub const REG_CMD: usize        = 0x00;
+pub const REG_ADDR: usize       = 0x04;
+pub const REG_WDATA: usize      = 0x08;
+pub const REG_CTRL: usize       = 0x0C;
+pub const REG_STATUS: usize     = 0x10;
+pub const REG_RDATA: usize      = 0x14;
+pub const REG_HW_INFO: usize    = 0x1C;
+pub const REG_CAP_STATUS: usize = 0x20;
+pub const REG_TIMEOUT: usize    = 0x24;
+pub const REG_TRIG_ROUTE: usize = 0x28;
+
+/// Value the wrapper returns for unmapped register offsets
+pub const REG_UNMAPPED: u32 = 0xDEAD_CAFE;
+
+// Command codes - State commands
+pub const CMD_NOP: u32          = 0x00;
+pub const CMD_ARM: u32          = 0x01;
+pub const CMD_RESET: u32        = 0x02;
+pub const CMD_INIT: u32         = 0x03;
+pub const CMD_IDLE: u32         = 0x04;
+pub const CMD_SLEEP: u32        = 0x05;
+
+// Command codes - Local reads
+pub const CMD_RD_HW_ID: u32         = 0x10;
+pub const CMD_RD_HUB_COUNT: u32     = 0x11;
+pub const CMD_RD_STATUS: u32        = 0x12;
+pub const CMD_RD_ANA_RAM_CFG: u32   = 0x13;
+pub const CMD_RD_TICK_FREQ: u32     = 0x14;
+pub const CMD_RD_ANA_FIRST_PTR: u32 = 0x15;
+pub const CMD_RD_RAM_DATA: u32      = 0x16;
+pub const CMD_RD_DIG_FIRST_PTR: u32 = 0x17;
+pub const CMD_RD_DIG_CK_FREQ: u32   = 0x18;
+pub const CMD_RD_DIG_RAM_CFG: u32   = 0x19;
+pub const CMD_RD_REC_PROFILE: u32   = 0x1A;
+pub const CMD_RD_TRIG_SRC: u32      = 0x1B;
+pub const CMD_RD_VIEW_ROM_KB: u32   = 0x1C;
+
+// Command codes - Local writes
+pub const CMD_WR_USER_CTRL: u32     = 0x20;
+pub const CMD_WR_REC_CONFIG: u32    = 0x21;
+pub const CMD_WR_TICK_DIVISOR: u32  = 0x22;
+pub const CMD_WR_TRIG_TYPE: u32     = 0x23;
+pub const CMD_WR_TRIG_DIG_FIELD: u32= 0x24;
+pub const CMD_WR_TRIG_ANA_FIELD: u32= 0x25;
+pub const CMD_WR_ANA_POST_TRIG: u32 = 0x26;
+pub const CMD_WR_TRIG_DELAY: u32    = 0x27;
+pub const CMD_WR_TRIG_NTH: u32      = 0x28;
+pub const CMD_WR_RAM_RD_PTR: u32    = 0x29;
+pub const CMD_WR_DIG_POST_TRIG: u32 = 0x2A;
+pub const CMD_WR_RAM_PAGE: u32      = 0x2B;
+
+// Command codes - Serial bus reads (external CMD codes from sump3_axi_wrapper.sv)
+pub const CMD_RD_HUB_FREQ: u32      = 0x30;
+pub const CMD_RD_POD_COUNT: u32     = 0x31;
+pub const CMD_RD_POD_REG: u32       = 0x32;
+pub const CMD_RD_TRIG_SRC_POD: u32  = 0x33;
+pub const CMD_RD_HUB_HW_CFG: u32    = 0x34;
+pub const CMD_RD_HUB_INSTANCE: u32  = 0x35;
+pub const CMD_RD_HUB_NAME_0_3: u32  = 0x36;
+pub const CMD_RD_HUB_NAME_4_7: u32  = 0x37;
+pub const CMD_RD_HUB_NAME_8_11: u32 = 0x38;
+
+// Command codes - Serial bus writes
+pub const CMD_WR_POD_REG: u32       = 0x40;
+pub const CMD_WR_TRIG_WIDTH: u32    = 0x41;
+
+// HW_INFO fields
+pub const HW_INFO_ID: BitField        = BitField::new(16, 16);
+pub const HW_INFO_HUB_COUNT: BitField = BitField::new(8, 8);
+pub const HW_INFO_REVISION: BitField  = BitField::new(0, 8);
+
+/// HW_INFO ID of a SUMP3 wrapper ("S3")
+pub const HW_ID_SUMP3: u32 = 0x5303;
+
+/// Most hubs HW_INFO is believed to report; more means a floating bus or a
+/// wrong base address, and the hubs are not enumerated
+pub const MAX_PLAUSIBLE_HUBS: u8 = 32;
+
+/// Most pods a hub is believed to report (an unanswered read gives 0xFF)
+pub const MAX_PLAUSIBLE_PODS: u8 = 64;
+
+/// Widest RAM_CFG fields the RTL can produce; wider means a floating bus or
+/// a corrupted register. RAM_PTR addresses 2^20 words and `rle_data_bits`
+/// goes up to 8192. Pods the readout can't decode are only warned about
+/// (see `compat::check`).
+pub const MAX_PLAUSIBLE_DEPTH_BITS: u32 = 20;
+pub const MAX_PLAUSIBLE_DATA_BITS: u32 = 8192;
+
+// Pod register addresses
+pub const POD_REG_HW_CFG: u8        = 0x00;
+pub const POD_REG_TRIG_CFG: u8      = 0x03;
+pub const POD_REG_TRIG_EN: u8       = 0x04;
+pub const POD_REG_RAM_PTR: u8       = 0x08;
+pub const POD_REG_RAM_DATA: u8      = 0x09;
+pub const POD_REG_RAM_CFG: u8       = 0x0A;
+pub const POD_REG_USER_CTRL: u8     = 0x0B;
+pub const POD_REG_TRIGGERABLE: u8   = 0x0E;
+pub const POD_REG_TRIG_SRC: u8      = 0x0F;
+pub const POD_REG_NAME_0_3: u8      = 0x1D;
+pub const POD_REG_NAME_4_7: u8      = 0x1E;
+pub const POD_REG_NAME_8_11: u8     = 0x1F;
+
+// Pod HW_CFG fields
+pub const HW_CFG_REVISION: BitField = BitField::new(24, 8);
+
+// Pod RAM_CFG fields
+pub const RAM_CFG_DEPTH_BITS: BitField = BitField::new(0, 8);
+pub const RAM_CFG_DATA_BITS: BitField  = BitField::new(8, 16);
+pub const RAM_CFG_TS_BITS: BitField    = BitField::new(24, 8);
+
+// CMD_RD_HUB_FREQ fields (12.20 fixed-point MHz)
+pub const HUB_FREQ_MHZ: BitField   = BitField::new(20, 12);
+pub const HUB_FREQ_FRACT: BitField = BitField::new(0, 20);
+
+// Control bits
+pub const CTRL_START: u32 = 0x01;
+pub const CTRL_ABORT: u32 = 0x04;
+
+// STATUS bits
+pub const STATUS_DONE: u32  = 0x02;
+pub const STATUS_ERROR: u32 = 0x04;
+pub const STATUS_ERR_CODE: BitField = BitField::new(4, 4);
+
+// STATUS ERR_CODE values (0 on bitstreams that predate the field)
+pub const ERR_UNKNOWN: u32      = 0x0;
+pub const ERR_BAD_CMD: u32      = 0x1;
+pub const ERR_LOCAL_READ: u32   = 0x2;
+pub const ERR_SERIAL_ADDR: u32  = 0x3;
+pub const ERR_SERIAL_CMD: u32   = 0x4;
+pub const ERR_SERIAL_READ: u32  = 0x5;
+pub const ERR_SERIAL_WRITE: u32 = 0x6;
+pub const ERR_TRANSPORT: u32    = 0xF;
+
+// TRIG_ROUTE fields
+pub const TRIG_ROUTE_IN_SEL: BitField = BitField::new(0, 2);
+pub const TRIG_ROUTE_OUT_EN: u32       = 0x10;
+
+// Trigger types
+pub const TRIG_IMMEDIATE: u32       = 0x01;
+pub const TRIG_OR_RISING: u32       = 0x02;
+pub const TRIG_OR_FALLING: u32      = 0x03;
+pub const TRIG_EXT_RISING: u32      = 0x06;
+
+/// Short name of a wrapper command code, for logs and metrics labels
+pub fn command_name(cmd: u32) -> Option<&'static str> {
+    Some(match cmd {
+        CMD_NOP => "nop",
+        CMD_ARM => "arm",
+        CMD_RESET => "reset",
+        CMD_INIT => "init",
+        CMD_IDLE => "idle",
+        CMD_SLEEP => "sleep",
+        CMD_RD_HW_ID => "rd_hw_id",
+        CMD_RD_HUB_COUNT => "rd_hub_count",
+        CMD_RD_STATUS => "rd_status",
+        CMD_RD_ANA_RAM_CFG => "rd_ana_ram_cfg",
+        CMD_RD_TICK_FREQ => "rd_tick_freq",
+        CMD_RD_ANA_FIRST_PTR => "rd_ana_first_ptr",
+        CMD_RD_RAM_DATA => "rd_ram_data",
+        CMD_RD_DIG_FIRST_PTR => "rd_dig_first_ptr",
+        CMD_RD_DIG_CK_FREQ => "rd_dig_ck_freq",
+        CMD_RD_DIG_RAM_CFG => "rd_dig_ram_cfg",
+        CMD_RD_REC_PROFILE => "rd_rec_profile",
+        CMD_RD_TRIG_SRC => "rd_trig_src",
+        CMD_RD_VIEW_ROM_KB => "rd_view_rom_kb",
+        CMD_WR_USER_CTRL => "wr_user_ctrl",
+        CMD_WR_REC_CONFIG => "wr_rec_config",
+        CMD_WR_TICK_DIVISOR => "wr_tick_divisor",
+        CMD_WR_TRIG_TYPE => "wr_trig_type",
+        CMD_WR_TRIG_DIG_FIELD => "wr_trig_dig_field",
+        CMD_WR_TRIG_ANA_FIELD => "wr_trig_ana_field",
+        CMD_WR_ANA_POST_TRIG => "wr_ana_post_trig",
+        CMD_WR_TRIG_DELAY => "wr_trig_delay",
+        CMD_WR_TRIG_NTH => "wr_trig_nth",
+        CMD_WR_RAM_RD_PTR => "wr_ram_rd_ptr",
+        CMD_WR_DIG_POST_TRIG => "wr_dig_post_trig",
+        CMD_WR_RAM_PAGE => "wr_ram_page",
+        CMD_RD_HUB_FREQ => "rd_hub_freq",
+        CMD_RD_POD_COUNT => "rd_pod_count",
+        CMD_RD_POD_REG => "rd_pod_reg",
+        CMD_RD_TRIG_SRC_POD => "rd_trig_src_pod",
+        CMD_RD_HUB_HW_CFG => "rd_hub_hw_cfg",
+        CMD_RD_HUB_INSTANCE => "rd_hub_instance",
+        CMD_RD_HUB_NAME_0_3 => "rd_hub_name_0_3",
+        CMD_RD_HUB_NAME_4_7 => "rd_hub_name_4_7",
+        CMD_RD_HUB_NAME_8_11 => "rd_hub_name_8_11",
+        CMD_WR_POD_REG => "wr_pod_reg",
+        CMD_WR_TRIG_WIDTH => "wr_trig_width",
+        _ => return None,
+    })
+}
+
+/// Describe a STATUS ERR_CODE
+pub fn error_cause(code: u32) -> &'static str {
+    match code {
+        ERR_BAD_CMD => "unknown command code",
+        ERR_LOCAL_READ => "core local bus read timed out",
+        ERR_SERIAL_ADDR => "serial bus address phase timed out (hub not responding)",
+        ERR_SERIAL_CMD => "serial bus command phase timed out",
+        ERR_SERIAL_READ => "serial bus read timed out (pod not responding)",
+        ERR_SERIAL_WRITE => "serial bus write timed out",
+        ERR_TRANSPORT => "register transport failed",
+        _ => "error (no cause reported by this bitstream)",
+    }
+}
+
+thread_local! {
+    /// Last command failure on this thread, so API handlers can attach the
+    /// cause to their error response
+    static LAST_FAILURE: RefCell<Option<CommandFailure>> = const { RefCell::new(None) };
+}
+
+/// Failure of the last command issued on this thread, if it failed
+pub fn last_failure() -> Option<CommandFailure> {
+    LAST_FAILURE.with(|f| f.borrow().clone())
+}
+
+/// `msg`, followed by the cause of the last command failure on this thread
+pub fn with_cause(msg: &str) -> String {
+    match last_failure() {
+        Some(failure) => format!("{}: {}", msg, failure),
+        None => msg.into(),
+    }
+}
+
+/// `"<what> failed"`, with the cause of the last command failure on this thread
+pub fn failure_message(what: &str) -> String {
+    with_cause(&format!("{} failed", what))
+}
+
+/// Longest INIT may take to clear pod RAM
+pub const INIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Status poll interval while INIT runs
+pub const INIT_POLL: Duration = Duration::from_millis(2);
+
+/// Error for an INIT still running after `INIT_TIMEOUT`
+pub fn init_timeout_message() -> String {
+    format!("Init still in progress after {} ms", INIT_TIMEOUT.as_millis())
+}
+
+/// Map an API trigger type name to its SUMP3 trigger type code
+pub fn trigger_type_code(name: &str) -> u32 {
+    parse_trigger_type(name).unwrap_or(TRIG_OR_RISING)
+}
+
+/// A wrapper command as reported by a dry run
+pub fn wrapper_command(cmd: u32, addr: u32, wdata: u32) -> WrapperCommand {
+    WrapperCommand { command: command_name(cmd).unwrap_or("unknown").into(), cmd, addr, wdata }
+}
+
+/// Commands `Ila::configure_trigger` issues: RESET, trigger type, digital
+/// trigger field, post-trigger length, then the trigger enable of pod (0,0)
+/// and of each further pod; and the trigger bits they program
+pub fn trigger_commands(
+    trig_type: u32,
+    trigger_bits: u32,
+    post_trigger: u32,
+    pods: &[PodTrigger],
+) -> (u32, Vec<WrapperCommand>) {
+    let trig_bits = if trigger_bits == 0 { 0x00000001 } else { trigger_bits };
+    let mut commands = vec![
+        wrapper_command(CMD_RESET, 0, 0),
+        wrapper_command(CMD_WR_TRIG_TYPE, 0, trig_type),
+        wrapper_command(CMD_WR_TRIG_DIG_FIELD, 0, trig_bits),
+        wrapper_command(CMD_WR_DIG_POST_TRIG, 0, post_trigger),
+    ];
+
+    // Pod (0,0) takes the core field unless listed, then every listed pod
+    let own = pods.iter().find(|p| (p.hub, p.pod) == (0, 0)).map_or(trig_bits, |p| p.bits);
+    let enables = std::iter::once((0, 0, own))
+        .chain(pods.iter().filter(|p| (p.hub, p.pod) != (0, 0)).map(|p| (p.hub, p.pod, p.bits)));
+    let pod_trig_cfg = (trig_type & 0x07) | 0x20;
+    for (hub, pod, bits) in enables {
+        commands.push(wrapper_command(CMD_WR_POD_REG, pod_addr(hub, pod, POD_REG_TRIG_CFG), pod_trig_cfg));
+        commands.push(wrapper_command(CMD_WR_POD_REG, pod_addr(hub, pod, POD_REG_TRIG_EN), bits));
+    }
+    (trig_bits, commands)
+}
+
+/// Commands `Ila::program_trigger` issues for `config`
+pub fn trigger_plan(config: &TriggerConfig) -> (u32, Vec<WrapperCommand>) {
+    let trig_type = trigger_type_code(&config.trigger_type);
+    let (bits, mut commands) = trigger_commands(trig_type, config.trigger_bits, config.post_trigger, &config.pods);
+    if let Some(divisor) = config.tick_divisor {
+        commands.push(wrapper_command(CMD_WR_TICK_DIVISOR, 0, divisor));
+    }
+    (bits, commands)
+}
+
+/// Error of a failed trigger programming command
+fn trigger_failure(cmd: u32) -> &'static str {
+    match cmd {
+        CMD_RESET => "Reset failed",
+        CMD_WR_TRIG_TYPE => "Failed to set trigger type",
+        CMD_WR_TRIG_DIG_FIELD => "Failed to set trigger field",
+        CMD_WR_DIG_POST_TRIG => "Failed to set post-trigger",
+        CMD_WR_TICK_DIVISOR => "Failed to set tick divisor",
+        _ => "Failed to enable pod trigger",
+    }
+}
+
+/// Trigger type code for a supported API name (empty selects `or_rising`)
+pub fn parse_trigger_type(name: &str) -> Option<u32> {
+    match name {
+        "" | "or_rising" => Some(TRIG_OR_RISING),
+        "or_falling" => Some(TRIG_OR_FALLING),
+        "external" => Some(TRIG_EXT_RISING),
+        _ => None,
+    }
+}
+
+/// Decode `CMD_RD_HUB_FREQ` (12.20 fixed-point MHz) to Hz, rounded
+pub fn hub_freq_hz(freq: u32) -> u64 {
+    let mhz = HUB_FREQ_MHZ.get(freq) as u64;
+    let fracts = HUB_FREQ_FRACT.get(freq) as u64;
+    mhz * 1_000_000 + ((fracts * 1_000_000 + (1 << 19)) >> 20)
+}
+
+/// External trigger input names, indexed by TRIG_ROUTE IN_SEL
+pub const EXT_TRIGGER_SOURCES: [&str; 3] = ["none", "in0", "in1"];
+
+/// Pack a hub/pod/register triple into the wrapper ADDR register format
+#[inline]
+fn pod_addr(hub: u8, pod: u8, reg: u8) -> u32 {
+    ((hub as u32) << 16) | ((pod as u32) << 8) | (reg as u32)
+}
+
+type PodLock = Arc<Mutex<()>>;
+
+type ArmHook = Box<dyn Fn(u64) + Send + Sync>;
+
+/// Retries of failed commands (timeouts, serial-bus and transport errors).
+///
+/// Read commands are retried freely; state commands and writes only with
+/// `retry_writes`, since the first attempt may have taken effect.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first failure
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub backoff: Duration,
+    pub retry_writes: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { retries: 2, backoff: Duration::from_micros(200), retry_writes: false }
+    }
+}
+
+/// Local and serial-bus reads have no side effects
+fn is_read_cmd(cmd: u32) -> bool {
+    cmd == CMD_NOP || matches!(cmd >> 4, 0x1 | 0x3)
+}
+
+/// Where the FPGA design identification comes from (`Ila::set_design_id`)
+#[derive(Debug, Clone)]
+pub enum DesignId {
+    /// Configured bitstream id
+    Fixed(String),
+    /// Wrapper register holding a design/version word, read each time
+    Register(usize),
+}
+
+impl DesignId {
+    /// `reg:0xOFFSET` for a register, anything else as a fixed id
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let Some(offset) = spec.strip_prefix("reg:") else {
+            return Ok(Self::Fixed(spec.to_string()));
+        };
+        usize::from_str_radix(offset.trim_start_matches("0x").trim_start_matches("0X"), 16)
+            .ok()
+            .filter(|offset| offset.is_multiple_of(4))
+            .map(Self::Register)
+            .ok_or_else(|| format!("invalid register offset '{}'", offset))
+    }
+}
+
+/// Handle to a SUMP3 AXI wrapper instance
+pub struct Ila {
+    mem: Mutex<Box<dyn Backend>>,
+    base_addr: usize,
+    /// Bytes of register window `read_reg`/`write_reg` accept
+    window_size: AtomicUsize,
+    stats: CommandStats,
+    /// Capture sequence number before this handle's first ARM
+    sequence_base: AtomicU64,
+    /// Called with the new sequence number after each successful ARM
+    arm_hook: Mutex<Option<ArmHook>>,
+    correlation: Mutex<Option<Correlation>>,
+    design_id: Mutex<Option<DesignId>>,
+    /// Last value written to the (write-only) core user_ctrl register
+    user_ctrl: Mutex<Option<u32>>,
+    /// Pods read through an AXI mirror of their RAM instead of the serial bus
+    mapped_ram: Mutex<HashMap<(u8, u8), Arc<MappedRam>>>,
+    /// Readouts of an acquired capture (see `cache`)
+    cache: Mutex<ReadoutCache>,
+    /// Last digital post-trigger sample count written
+    post_trigger: Mutex<Option<u32>>,
+    /// Per-pod locks held across multi-command sequences (RAM_PTR then
+    /// RAM_DATA), so readouts of different pods can interleave
+    pod_locks: Mutex<HashMap<(u8, u8), PodLock>>,
+    retry: Mutex<RetryPolicy>,
+}
+
+impl Ila {
+    pub fn new(base_addr: usize) -> Result<Self, std::io::Error> {
+        Self::map(base_addr, ILA_SIZE)
+    }
+
+    /// Map a `size`-byte register window through /dev/mem
+    pub fn map(base_addr: usize, size: usize) -> Result<Self, std::io::Error> {
+        let mem = DevMem::new(base_addr, size)?;
+        tracing::info!(
+            "SUMP3 ILA mapped at 0x{:08X}, size {} bytes",
+            base_addr,
+            size
+        );
+        Ok(Self::with_backend(Box::new(mem), base_addr).with_window_size(size))
+    }
+
+    /// Use an already opened register backend (e.g. `bridge::TcpBackend`);
+    /// `base_addr` is informational
+    pub fn with_backend(backend: Box<dyn Backend>, base_addr: usize) -> Self {
+        Self {
+            mem: Mutex::new(backend),
+            base_addr,
+            window_size: AtomicUsize::new(ILA_SIZE),
+            stats: CommandStats::default(),
+            sequence_base: AtomicU64::new(0),
+            arm_hook: Mutex::new(None),
+            correlation: Mutex::new(None),
+            design_id: Mutex::new(None),
+            user_ctrl: Mutex::new(None),
+            mapped_ram: Mutex::new(HashMap::new()),
+            cache: Mutex::new(ReadoutCache::default()),
+            post_trigger: Mutex::new(None),
+            pod_locks: Mutex::new(HashMap::new()),
+            retry: Mutex::new(RetryPolicy::default()),
+        }
+    }
+
+    /// Accept raw register offsets below `size` instead of `ILA_SIZE`; the
+    /// backend must reach them
+    pub fn with_window_size(self, size: usize) -> Self {
+        self.window_size.store(size, Ordering::Relaxed);
+        self
+    }
+
+    /// Bytes of register window reachable with `read_reg`/`write_reg`
+    pub fn window_size(&self) -> usize {
+        self.window_size.load(Ordering::Relaxed)
+    }
+
+    /// Replace the register backend (e.g. a `backend::Disconnected` placeholder
+    /// once the real one opens), with a `window_size`-byte window. State
+    /// cached from the old backend is dropped.
+    pub fn set_backend(&self, backend: Box<dyn Backend>, window_size: usize) {
+        let mut mem = self.mem.lock();
+        *mem = backend;
+        self.window_size.store(window_size, Ordering::Relaxed);
+        self.cache.lock().invalidate();
+        *self.user_ctrl.lock() = None;
+        *self.post_trigger.lock() = None;
+    }
+
+    /// Transport description of the register backend
+    pub fn backend(&self) -> String {
+        self.mem.lock().describe()
+    }
+
+    /// Physical base address of the wrapper
+    pub fn base_addr(&self) -> usize {
+        self.base_addr
+    }
+
+    /// Read a raw wrapper register (`None` if outside the mapped window)
+    pub fn read_reg(&self, offset: usize) -> Option<u32> {
+        if offset >= self.window_size() {
+            return None;
+        }
+        self.mem.lock().read32(offset)
+    }
+
+    /// Write a raw wrapper register (`false` if outside the mapped window)
+    pub fn write_reg(&self, offset: usize, value: u32) -> bool {
+        offset < self.window_size() && self.mem.lock().write32(offset, value)
+    }
+
+    /// Current external trigger routing, or `None` if the wrapper predates
+    /// the TRIG_ROUTE register
+    pub fn ext_trigger_routing(&self) -> Option<ExtTriggerRouting> {
+        self.require(Feature::ExtTriggerRouting).ok()?;
+        let route = self.read_reg(REG_TRIG_ROUTE).filter(|&v| v != REG_UNMAPPED)?;
+        let in_sel = TRIG_ROUTE_IN_SEL.get(route) as usize;
+        Some(ExtTriggerRouting {
+            source: EXT_TRIGGER_SOURCES.get(in_sel).unwrap_or(&"none").to_string(),
+            output_enable: route & TRIG_ROUTE_OUT_EN != 0,
+        })
+    }
+
+    /// Program TRIG_ROUTE and return the routing read back
+    pub fn set_ext_trigger_routing(&self, routing: &ExtTriggerRouting) -> Result<ExtTriggerRouting, Vec<FieldError>> {
+        let Some(in_sel) = EXT_TRIGGER_SOURCES.iter().position(|&s| s == routing.source) else {
+            return Err(vec![FieldError {
+                field: "source".into(),
+                message: format!("unknown source '{}' (expected {})", routing.source, EXT_TRIGGER_SOURCES.join(", ")),
+            }]);
+        };
+        self.require(Feature::ExtTriggerRouting)
+            .map_err(|message| vec![FieldError { field: "routing".into(), message }])?;
+        let unsupported = || vec![FieldError {
+            field: "routing".into(),
+            message: "wrapper has no TRIG_ROUTE register (older bitstream)".into(),
+        }];
+        self.ext_trigger_routing().ok_or_else(unsupported)?;
+
+        let mut route = TRIG_ROUTE_IN_SEL.set(0, in_sel as u32);
+        if routing.output_enable {
+            route |= TRIG_ROUTE_OUT_EN;
+        }
+        self.mem.lock().write32(REG_TRIG_ROUTE, route);
+        self.ext_trigger_routing().ok_or_else(unsupported)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        *self.retry.lock()
+    }
+
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry.lock() = policy;
+    }
+
+    /// Execute a command and wait for completion (polling), retrying
+    /// transient failures per the `RetryPolicy`
+    pub fn exec_cmd(&self, cmd: u32, addr: u32, wdata: u32) -> Option<u32> {
+        let policy = self.retry_policy();
+        let retryable = is_read_cmd(cmd) || policy.retry_writes;
+        let mut backoff = policy.backoff;
+        let mut attempt = 0;
+        loop {
+            let result = self.exec_once(cmd, addr, wdata);
+            // An unknown command fails the same way every time
+            let transient = last_failure().is_some_and(|f| f.code != Some(ERR_BAD_CMD as u8));
+            if result.is_some() || !transient || !retryable || attempt >= policy.retries {
+                if result.is_some() && attempt > 0 {
+                    tracing::info!("ILA command 0x{:02X} succeeded on retry {}", cmd, attempt);
+                }
+                return result;
+            }
+            attempt += 1;
+            CommandStats::inc(&self.stats.retries);
+            tracing::debug!("Retrying ILA command 0x{:02X} (attempt {}) in {:?}", cmd, attempt + 1, backoff);
+            std::thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+
+    fn exec_once(&self, cmd: u32, addr: u32, wdata: u32) -> Option<u32> {
+        LAST_FAILURE.with(|f| f.borrow_mut().take());
+        let mut mem = self.mem.lock();
+        CommandStats::inc(&self.stats.commands);
+        let start = Instant::now();
+        let result = self.run_cmd(mem.as_mut(), cmd, addr, wdata);
+        self.stats.latency.record(cmd, start.elapsed());
+        // Hand the bus to the next waiter, so a status read isn't starved
+        // by a readout loop re-acquiring it straight away
+        MutexGuard::unlock_fair(mem);
+        result
+    }
+
+    fn run_cmd(&self, mem: &mut dyn Backend, cmd: u32, addr: u32, wdata: u32) -> Option<u32> {
+        // Write command parameters
+        mem.write32(REG_CMD, cmd);
+        mem.write32(REG_ADDR, addr);
+        mem.write32(REG_WDATA, wdata);
+
+        // Set START bit to begin execution
+        mem.write32(REG_CTRL, CTRL_START);
+
+        // Poll for completion (DONE bit)
+        for _ in 0..100000 {
+            let Some(status) = mem.read32(REG_STATUS) else {
+                self.command_failed(cmd, addr, None, error_cause(ERR_TRANSPORT));
+                return None;
+            };
+            let done = (status & STATUS_DONE) != 0;
+            let error = (status & STATUS_ERROR) != 0;
+
+            if done {
+                self.stats.consecutive_timeouts.store(0, Ordering::Relaxed);
+                if error {
+                    let code = STATUS_ERR_CODE.get(status);
+                    CommandStats::inc(&self.stats.errors);
+                    CommandStats::inc(&self.stats.error_causes[code as usize]);
+                    self.command_failed(cmd, addr, Some(code as u8), error_cause(code));
+                    return None;
+                }
+                if cmd == CMD_ARM {
+                    self.stats.armed();
+                    if let Some(hook) = &*self.arm_hook.lock() {
+                        hook(self.sequence());
+                    }
+                }
+                if matches!(cmd, CMD_ARM | CMD_RESET | CMD_INIT) {
+                    self.cache.lock().invalidate();
+                }
+                if cmd == CMD_WR_DIG_POST_TRIG {
+                    *self.post_trigger.lock() = Some(wdata);
+                }
+                let rdata = mem.read32(REG_RDATA);
+                if rdata.is_none() {
+                    self.command_failed(cmd, addr, None, error_cause(ERR_TRANSPORT));
+                }
+                return rdata;
+            }
+            std::hint::spin_loop();
+        }
+        CommandStats::inc(&self.stats.timeouts);
+        CommandStats::inc(&self.stats.consecutive_timeouts);
+        self.command_failed(cmd, addr, None, "timed out waiting for DONE");
+        // Return the state machine to IDLE so a retry starts clean
+        mem.write32(REG_CTRL, CTRL_ABORT);
+        None
+    }
+
+    fn command_failed(&self, cmd: u32, addr: u32, code: Option<u8>, cause: &str) {
+        let failure = CommandFailure {
+            cmd: cmd as u8,
+            name: command_name(cmd).unwrap_or("unknown").into(),
+            addr,
+            code,
+            cause: cause.into(),
+        };
+        tracing::warn!("ILA command failed: {}", failure);
+        LAST_FAILURE.with(|f| *f.borrow_mut() = Some(failure.clone()));
+        *self.stats.last_failure.lock() = Some(failure);
+    }
+
+    /// Lock serializing multi-command sequences on one pod
+    fn pod_lock(&self, hub: u8, pod: u8) -> PodLock {
+        self.pod_locks.lock().entry((hub, pod)).or_default().clone()
+    }
+
+    /// Read a pod register
+    pub fn read_pod_reg(&self, hub: u8, pod: u8, reg: u8) -> Option<u32> {
+        self.exec_cmd(CMD_RD_POD_REG, pod_addr(hub, pod, reg), 0)
+    }
+
+    /// Write a pod register
+    pub fn write_pod_reg(&self, hub: u8, pod: u8, reg: u8, value: u32) -> bool {
+        self.exec_cmd(CMD_WR_POD_REG, pod_addr(hub, pod, reg), value).is_some()
+    }
+
+    /// Last value written to the core's `core_user_ctrl` outputs (the
+    /// register is write-only; `None` until written through this handle)
+    pub fn user_ctrl(&self) -> Option<u32> {
+        *self.user_ctrl.lock()
+    }
+
+    /// Update the core's `core_user_ctrl` outputs (mux selects etc.);
+    /// unknown bits outside `mask` are written as 0
+    pub fn write_user_ctrl(&self, value: u32, mask: u32) -> Option<u32> {
+        let mut user_ctrl = self.user_ctrl.lock();
+        let value = merge_bits(user_ctrl.unwrap_or(0), mask, value);
+        self.exec_cmd(CMD_WR_USER_CTRL, 0, value)?;
+        *user_ctrl = Some(value);
+        Some(value)
+    }
+
+    /// Read a pod's user control register (stimulus bits into the design)
+    pub fn read_user_stim(&self, hub: u8, pod: u8) -> Option<u32> {
+        self.read_pod_reg(hub, pod, POD_REG_USER_CTRL)
+    }
+
+    /// Read-modify-write a pod's user control register; returns the new value
+    pub fn write_user_stim(&self, hub: u8, pod: u8, value: u32, mask: u32) -> Option<u32> {
+        let lock = self.pod_lock(hub, pod);
+        let _pod = lock.lock();
+        let value = if mask == u32::MAX {
+            value
+        } else {
+            merge_bits(self.read_user_stim(hub, pod)?, mask, value)
+        };
+        self.write_pod_reg(hub, pod, POD_REG_USER_CTRL, value).then_some(value)
+    }
+
+    /// Read hub name (12 ASCII chars)
+    pub fn read_hub_name(&self, hub: u8) -> String {
+        let addr = (hub as u32) << 16;
+        let mut name = Vec::with_capacity(12);
+
+        for cmd in [CMD_RD_HUB_NAME_0_3, CMD_RD_HUB_NAME_4_7, CMD_RD_HUB_NAME_8_11] {
+            if let Some(data) = self.exec_cmd(cmd, addr, 0) {
+                name.extend_from_slice(&data.to_be_bytes());
+            }
+        }
+
+        String::from_utf8_lossy(&name).trim().to_string()
+    }
+
+    /// Read pod name (12 ASCII chars)
+    pub fn read_pod_name(&self, hub: u8, pod: u8) -> String {
+        let mut name = Vec::with_capacity(12);
+
+        for reg in [POD_REG_NAME_0_3, POD_REG_NAME_4_7, POD_REG_NAME_8_11] {
+            if let Some(data) = self.read_pod_reg(hub, pod, reg) {
+                name.extend_from_slice(&data.to_be_bytes());
+            }
+        }
+
+        String::from_utf8_lossy(&name).trim().to_string()
+    }
+
+    /// Read RLE sample from pod RAM (with configurable timestamp bits)
+    pub fn read_rle_sample(&self, hub: u8, pod: u8, addr: u32, ts_bits: u8) -> Option<RleSample> {
+        if let Some(ram) = self.mapped(hub, pod) {
+            return Some(decode_rle(addr, ram.read(0, addr, 1)?[0], ram.read(1, addr, 1)?[0], ts_bits));
+        }
+
+        let lock = self.pod_lock(hub, pod);
+        let _pod = lock.lock();
+
+        // Set RAM pointer to page 0, address
+        self.write_pod_reg(hub, pod, POD_REG_RAM_PTR, addr);
+
+        // Read low 32 bits (data)
+        let data = self.read_pod_reg(hub, pod, POD_REG_RAM_DATA)?;
+
+        // Read high bits from page 1
+        self.write_pod_reg(hub, pod, POD_REG_RAM_PTR, (1 << 20) | addr);
+        let hi = self.read_pod_reg(hub, pod, POD_REG_RAM_DATA)?;
+
+        Some(decode_rle(addr, data, hi, ts_bits))
+    }
+
+    /// Read `count` RLE samples from `start`.
+    ///
+    /// Stops at the first unreadable address (after retries) and returns the
+    /// samples before it along with the error.
+    pub fn read_rle_samples(&self, hub: u8, pod: u8, start: u32, count: u32, ts_bits: u8) -> (Vec<RleSample>, Option<ReadoutError>) {
+        if let Some(ram) = self.mapped(hub, pod) {
+            if let (Some(lo), Some(hi)) = (ram.read(0, start, count), ram.read(1, start, count)) {
+                let samples = lo.iter().zip(&hi).zip(start..)
+                    .map(|((&data, &hi), addr)| decode_rle(addr, data, hi, ts_bits))
+                    .collect();
+                return (samples, None);
+            }
+        }
+        let mut samples = Vec::with_capacity(count as usize);
+        for addr in start..start + count {
+            match self.read_rle_sample(hub, pod, addr, ts_bits) {
+                Some(sample) => samples.push(sample),
+                None => {
+                    let error = ReadoutError {
+                        address: addr,
+                        message: with_cause(&format!("RAM read failed at hub {} pod {} address {}", hub, pod, addr)),
+                    };
+                    tracing::warn!("{}; returning {} samples read before it", error.message, samples.len());
+                    return (samples, Some(error));
+                }
+            }
+        }
+        (samples, None)
+    }
+
+    /// First `count` samples of an acquired capture, reading only what the
+    /// cache doesn't already hold
+    fn cached_samples(&self, hub: u8, pod: u8, count: u32, ts_bits: u8) -> (Vec<RleSample>, Option<ReadoutError>) {
+        let (generation, mut samples) = {
+            let cache = self.cache.lock();
+            (cache.generation(), cache.samples((hub, pod)).cloned().unwrap_or_default())
+        };
+        if samples.len() >= count as usize {
+            CommandStats::inc(&self.stats.cache_hits);
+            samples.truncate(count as usize);
+            return (samples, None);
+        }
+
+        let start = samples.len() as u32;
+        let (more, error) = self.read_rle_samples(hub, pod, start, count - start, ts_bits);
+        samples.extend(more);
+        // A partial read is still a valid prefix for the next request to extend
+        self.cache.lock().store_samples(generation, (hub, pod), samples.clone());
+        (samples, error)
+    }
+
+    /// Address of the first sample with the trigger RLE code (2), scanning
+    /// page 1 of pod RAM
+    fn trigger_address(&self, hub: u8, pod: u8, ts_bits: u8, ram_depth: u32, acquired: bool) -> Option<u32> {
+        let generation = {
+            let cache = self.cache.lock();
+            match cache.trigger((hub, pod)) {
+                Some(address) if acquired => {
+                    CommandStats::inc(&self.stats.cache_hits);
+                    return address;
+                }
+                _ => cache.generation(),
+            }
+        };
+
+        let is_trigger = |hi: u32| rle_code(hi, ts_bits) == 2;
+        let address = match self.mapped(hub, pod).and_then(|ram| ram.read(1, 0, ram_depth)) {
+            Some(words) => words.into_iter().position(is_trigger).map(|a| a as u32),
+            None => (0..ram_depth).find(|&addr| self.read_ram_word(hub, pod, 1, addr).is_some_and(is_trigger)),
+        };
+        if acquired {
+            self.cache.lock().store_trigger(generation, (hub, pod), address);
+        }
+        address
+    }
+
+    /// Read the samples around the trigger selected by `window`.
+    ///
+    /// RAM is circular: the post-trigger setting bounds how far past the
+    /// trigger valid samples go, and the rest of the ring holds pre-trigger
+    /// history. Returns `None` if no sample carries the trigger code.
+    pub fn read_capture_window(&self, hub: u8, pod: u8, window: ReadoutWindow, span: u32) -> Option<CaptureData> {
+        let (mut capture, ram_depth) = self.capture_header(hub, pod);
+        let trigger = self.trigger_address(hub, pod, capture.ts_bits, ram_depth, capture.status.acquired)?;
+
+        let (pre, post) = self.window_span(window, span, ram_depth);
+
+        let start = (trigger + ram_depth - pre) % ram_depth;
+        let count = pre + post + 1;
+        let first = count.min(ram_depth - start);
+        let (mut samples, mut error) = self.read_rle_samples(hub, pod, start, first, capture.ts_bits);
+        if error.is_none() {
+            let (wrapped, wrap_error) = self.read_rle_samples(hub, pod, 0, count - first, capture.ts_bits);
+            samples.extend(wrapped);
+            error = wrap_error;
+        }
+
+        capture.sample_count = count;
+        capture.samples = samples;
+        capture.readout_error = error;
+        capture.gaps = find_gaps(&capture.samples, capture.ts_bits);
+        capture.trigger_address = Some(trigger);
+        Some(capture)
+    }
+
+    /// Read the whole ring oldest sample first, or in address order when no
+    /// sample carries the trigger code
+    pub fn read_capture_all(&self, hub: u8, pod: u8) -> CaptureData {
+        let (_, _, ram_depth) = self.get_pod_config(hub, pod);
+        self.read_capture_window(hub, pod, ReadoutWindow::Around, ram_depth)
+            .unwrap_or_else(|| self.read_capture(hub, pod, ram_depth))
+    }
+
+    /// `read_capture_all` from a dump of the pod's RAM (see `dump_ram`),
+    /// without reading the RAM again
+    pub fn capture_from_dump(&self, header: &RawRamHeader, words: &[u32]) -> CaptureData {
+        let (mut capture, _) = self.capture_header(header.hub, header.pod);
+        let mut samples = capture_from_raw(header, words).samples;
+        let ram_depth = samples.len() as u32;
+        match samples.iter().position(|s| s.code == 2) {
+            Some(trigger) => {
+                let (pre, post) = self.window_span(ReadoutWindow::Around, ram_depth, ram_depth);
+                samples.rotate_left((trigger + (ram_depth - pre) as usize) % ram_depth as usize);
+                samples.truncate((pre + post + 1) as usize);
+                capture.trigger_address = Some(trigger as u32);
+            }
+            // Like `read_capture`: address order, at most 2048 samples
+            None => samples.truncate(2048),
+        }
+        capture.sample_count = samples.len() as u32;
+        capture.gaps = find_gaps(&samples, capture.ts_bits);
+        capture.samples = samples;
+        capture
+    }
+
+    /// Samples read before and after the trigger for `window`, at most
+    /// `span` on each side
+    fn window_span(&self, window: ReadoutWindow, span: u32, ram_depth: u32) -> (u32, u32) {
+        let post_avail = self.post_trigger.lock().map_or(ram_depth - 1, |p| p.min(ram_depth - 1));
+        let pre_avail = ram_depth - 1 - post_avail;
+        let (pre, post) = match window {
+            ReadoutWindow::Pre => (span.min(pre_avail), 0),
+            ReadoutWindow::Post => (0, span.min(post_avail)),
+            ReadoutWindow::Around => (span.min(pre_avail), span.min(post_avail)),
+        };
+        // Without a known post-trigger setting the two sides may overlap
+        (pre.min(ram_depth - 1 - post), post)
+    }
+
+    /// Read pod RAM through the AXI mirror at `addr` from now on (see `readout`)
+    pub fn map_pod_ram(&self, hub: u8, pod: u8, addr: usize) -> std::io::Result<()> {
+        let ram_cfg = self.read_pod_reg(hub, pod, POD_REG_RAM_CFG).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("hub {} pod {} not responding", hub, pod),
+        ))?;
+        let (ram_depth, pages) = ram_geometry(ram_cfg).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            implausible_ram_cfg(hub, pod, ram_cfg),
+        ))?;
+        let ram = MappedRam::open(addr, ram_depth, pages)?;
+        self.mapped_ram.lock().insert((hub, pod), Arc::new(ram));
+        Ok(())
+    }
+
+    fn mapped(&self, hub: u8, pod: u8) -> Option<Arc<MappedRam>> {
+        self.mapped_ram.lock().get(&(hub, pod)).cloned()
+    }
+
+    /// `serial` or `mapped@0x<addr>`
+    pub fn pod_readout(&self, hub: u8, pod: u8) -> String {
+        match self.mapped(hub, pod) {
+            Some(ram) => format!("mapped@0x{:08X}", ram.addr()),
+            None => "serial".into(),
+        }
+    }
+
+    /// Get pod configuration (timestamp bits, data bits, etc.)
+    ///
+    /// A RAM depth that doesn't fit in 32 bits reads as one word, as for an
+    /// unreadable register, rather than sizing buffers from a bogus register.
+    pub fn get_pod_config(&self, hub: u8, pod: u8) -> (u8, u16, u32) {
+        let ram_cfg = self.read_pod_reg(hub, pod, POD_REG_RAM_CFG).unwrap_or(0);
+        let data_bits = RAM_CFG_DATA_BITS.get(ram_cfg) as u16;
+        let ts_bits = RAM_CFG_TS_BITS.get(ram_cfg) as u8;
+        let ram_depth = ram_depth(ram_cfg).unwrap_or(1);
+        (ts_bits, data_bits, ram_depth)
+    }
+
+    /// Read one 32-bit word of pod RAM from the given page
+    pub fn read_ram_word(&self, hub: u8, pod: u8, page: u32, addr: u32) -> Option<u32> {
+        if let Some(ram) = self.mapped(hub, pod) {
+            return ram.read(page, addr, 1).map(|w| w[0]);
+        }
+        let lock = self.pod_lock(hub, pod);
+        let _pod = lock.lock();
+        self.write_pod_reg(hub, pod, POD_REG_RAM_PTR, (page << 20) | addr);
+        self.read_pod_reg(hub, pod, POD_REG_RAM_DATA)
+    }
+
+    /// Dump every page of pod RAM verbatim, without RLE interpretation.
+    ///
+    /// Returns the header and `pages * ram_depth` words (page-major), or
+    /// why the dump failed: an unreadable or implausible RAM_CFG, or the
+    /// (page, address) of the first failed read.
+    pub fn dump_ram(&self, hub: u8, pod: u8) -> Result<(RawRamHeader, Vec<u32>), String> {
+        let ram_cfg = self.read_pod_reg(hub, pod, POD_REG_RAM_CFG)
+            .ok_or_else(|| format!("hub {} pod {} RAM_CFG read failed", hub, pod))?;
+        let (ram_depth, pages) = ram_geometry(ram_cfg).ok_or_else(|| implausible_ram_cfg(hub, pod, ram_cfg))?;
+
+        let acquired = self.capture_status().acquired;
+        let generation = {
+            let cache = self.cache.lock();
+            match cache.raw((hub, pod), ram_cfg) {
+                Some(words) if acquired => {
+                    CommandStats::inc(&self.stats.cache_hits);
+                    let words = words.clone();
+                    drop(cache);
+                    return Ok((self.raw_header(hub, pod, ram_cfg, ram_depth, pages), words));
+                }
+                _ => cache.generation(),
+            }
+        };
+
+        let mut words = Vec::with_capacity((pages * ram_depth) as usize);
+        let mapped = self.mapped(hub, pod);
+        for page in 0..pages {
+            if let Some(block) = mapped.as_ref().and_then(|ram| ram.read(page, 0, ram_depth)) {
+                words.extend(block);
+                continue;
+            }
+            for addr in 0..ram_depth {
+                let word = self.read_ram_word(hub, pod, page, addr);
+                words.push(word.ok_or_else(|| format!("RAM read failed: hub {} pod {} page {} addr {}", hub, pod, page, addr))?);
+            }
+        }
+
+        if acquired {
+            self.cache.lock().store_raw(generation, (hub, pod), ram_cfg, words.clone());
+        }
+        Ok((self.raw_header(hub, pod, ram_cfg, ram_depth, pages), words))
+    }
+
+    fn raw_header(&self, hub: u8, pod: u8, ram_cfg: u32, ram_depth: u32, pages: u32) -> RawRamHeader {
+        let (armed_at_ms, triggered_at_ms) = self.capture_times();
+        let (correlation_id, trigger_offset_ms) = self.correlate(triggered_at_ms);
+        RawRamHeader {
+            schema_version: CAPTURE_SCHEMA_VERSION,
+            hub,
+            pod,
+            ram_cfg,
+            ram_depth,
+            data_bits: RAM_CFG_DATA_BITS.get(ram_cfg) as u16,
+            ts_bits: RAM_CFG_TS_BITS.get(ram_cfg) as u8,
+            pages,
+            sequence: self.sequence(),
+            armed_at_ms,
+            triggered_at_ms,
+            armed_at: armed_at_ms.map(rfc3339),
+            triggered_at: triggered_at_ms.map(rfc3339),
+            correlation_id,
+            trigger_offset_ms,
+            design_id: self.design_id(),
+            trigger_source: self.trigger_source(),
+        }
+    }
+
+    /// Estimate pod RAM utilization by reading the RLE code of every
+    /// `stride`-th address (INIT clears RAM, so code 0 means never written).
+    ///
+    /// Without a stride, 64 evenly spaced addresses are inspected.
+    pub fn ram_fill(&self, hub: u8, pod: u8, stride: Option<u32>) -> Option<RamFill> {
+        let (ts_bits, _, ram_depth) = self.get_pod_config(hub, pod);
+        let stride = stride.unwrap_or(ram_depth / 64).clamp(1, ram_depth);
+
+        let mut fill = RamFill {
+            hub,
+            pod,
+            ram_depth,
+            sampled: 0,
+            invalid: 0,
+            pre_trigger: 0,
+            trigger: 0,
+            post_trigger: 0,
+            fill_percent: 0.0,
+        };
+
+        for addr in (0..ram_depth).step_by(stride as usize) {
+            let hi = self.read_ram_word(hub, pod, 1, addr)?;
+            match rle_code(hi, ts_bits) {
+                0 => fill.invalid += 1,
+                1 => fill.pre_trigger += 1,
+                2 => fill.trigger += 1,
+                _ => fill.post_trigger += 1,
+            }
+            fill.sampled += 1;
+        }
+
+        if fill.sampled > 0 {
+            fill.fill_percent = 100.0 * (fill.sampled - fill.invalid) as f32 / fill.sampled as f32;
+        }
+        Some(fill)
+    }
+
+    /// Read the capture status via `CMD_RD_STATUS`
+    pub fn capture_status(&self) -> CaptureStatus {
+        let status = CaptureStatus::from_bits(self.exec_cmd(CMD_RD_STATUS, 0, 0).unwrap_or(0));
+        self.stats.observe_triggered(status.triggered);
+        status
+    }
+
+    /// Which pod and bits fired the trigger of the current capture: the
+    /// first hub whose `CMD_RD_TRIG_SRC_POD` names a pod that latched bits
+    /// in `POD_REG_TRIG_SRC`. `None` before the trigger, or when no pod
+    /// latched any (RTL without trigger source latches reads 0).
+    pub fn trigger_source(&self) -> Option<TriggerSource> {
+        self.latched_trigger_source(&self.capture_status())
+    }
+
+    fn latched_trigger_source(&self, status: &CaptureStatus) -> Option<TriggerSource> {
+        if !status.triggered && !status.acquired || self.require(Feature::TriggerSource).is_err() {
+            return None;
+        }
+        let generation = {
+            let cache = self.cache.lock();
+            match cache.trigger_source() {
+                Some(source) if status.acquired => {
+                    CommandStats::inc(&self.stats.cache_hits);
+                    return source;
+                }
+                _ => cache.generation(),
+            }
+        };
+
+        let source = (0..self.hub_count()).find_map(|hub| {
+            let pod = (self.exec_cmd(CMD_RD_TRIG_SRC_POD, (hub as u32) << 16, 0)? & 0xFF) as u8;
+            if pod >= self.pod_count(hub)? {
+                return None;
+            }
+            let bits = self.read_pod_reg(hub, pod, POD_REG_TRIG_SRC)?;
+            (bits != 0).then_some(TriggerSource { hub, pod, bits })
+        });
+        if status.acquired {
+            self.cache.lock().store_trigger_source(generation, source);
+        }
+        source
+    }
+
+    /// Poll the status until INIT has finished clearing pod RAM, for up to
+    /// `INIT_TIMEOUT`
+    pub fn wait_init(&self) -> Result<(), String> {
+        let deadline = Instant::now() + INIT_TIMEOUT;
+        while self.capture_status().init_in_progress {
+            if Instant::now() >= deadline {
+                return Err(init_timeout_message());
+            }
+            std::thread::sleep(INIT_POLL);
+        }
+        Ok(())
+    }
+
+    /// Server wall-clock time (Unix ms) of the last ARM and of the first
+    /// status read that saw it triggered, for correlating with other logs
+    pub fn capture_times(&self) -> (Option<u64>, Option<u64>) {
+        self.stats.capture_times()
+    }
+
+    /// Tag subsequent captures with a shared correlation id (`None` clears it)
+    pub fn set_correlation(&self, correlation: Option<Correlation>) {
+        *self.correlation.lock() = correlation;
+    }
+
+    pub fn correlation(&self) -> Option<Correlation> {
+        self.correlation.lock().clone()
+    }
+
+    /// Identify the FPGA design in `info` and capture metadata
+    pub fn set_design_id(&self, design_id: Option<DesignId>) {
+        *self.design_id.lock() = design_id;
+    }
+
+    /// The FPGA design identification, if configured and readable
+    pub fn design_id(&self) -> Option<String> {
+        let design_id = self.design_id.lock().clone();
+        match design_id? {
+            DesignId::Fixed(id) => Some(id),
+            DesignId::Register(offset) => self.read_reg(offset).map(|word| format!("0x{:08X}", word)),
+        }
+    }
+
+    /// Correlation id and trigger offset for a capture triggered at `triggered_at_ms`
+    fn correlate(&self, triggered_at_ms: Option<u64>) -> (Option<String>, Option<i64>) {
+        match &*self.correlation.lock() {
+            Some(c) => (Some(c.id.clone()), c.offset_ms(triggered_at_ms)),
+            None => (None, None),
+        }
+    }
+
+    /// Command counters and backend reconnects since this handle was opened
+    /// (`uptime_s` is left for the caller to fill in)
+    pub fn stats(&self) -> IlaStats {
+        IlaStats {
+            reconnects: self.mem.lock().reconnects(),
+            ..self.stats.snapshot()
+        }
+    }
+
+    /// Command timeouts since the last command that signalled DONE; doesn't
+    /// wait for the bus, unlike `stats`
+    pub fn consecutive_timeouts(&self) -> u64 {
+        self.stats.consecutive_timeouts.load(Ordering::Relaxed)
+    }
+
+    /// Last failed command, without waiting for the bus
+    pub fn last_command_failure(&self) -> Option<CommandFailure> {
+        self.stats.last_failure.lock().clone()
+    }
+
+    /// Successful ARMs through this handle
+    pub fn arm_count(&self) -> u64 {
+        self.stats.arms.load(Ordering::Relaxed)
+    }
+
+    /// Sequence number of the capture in pod RAM: `base` plus the ARMs
+    /// through this handle, so numbering can continue across restarts
+    pub fn sequence(&self) -> u64 {
+        self.sequence_base.load(Ordering::Relaxed) + self.arm_count()
+    }
+
+    /// Continue numbering from `base`: the first ARM through this handle
+    /// starts capture `base + 1`
+    pub fn set_sequence_base(&self, base: u64) {
+        self.sequence_base.store(base, Ordering::Relaxed);
+    }
+
+    /// Call `hook` with the new `sequence` after each successful ARM. It runs
+    /// with the register bus held, so it must not use this handle.
+    pub fn on_arm(&self, hook: impl Fn(u64) + Send + Sync + 'static) {
+        *self.arm_hook.lock() = Some(Box::new(hook));
+    }
+
+    /// Number of hubs reported by HW_INFO (0 if no SUMP3 wrapper answers,
+    /// or if it reports more than `MAX_PLAUSIBLE_HUBS`)
+    pub fn hub_count(&self) -> u8 {
+        let hw_info = self.read_reg(REG_HW_INFO).unwrap_or(0);
+        if HW_INFO_ID.get(hw_info) != HW_ID_SUMP3 {
+            return 0;
+        }
+        let hub_count = HW_INFO_HUB_COUNT.get(hw_info) as u8;
+        if hub_count > MAX_PLAUSIBLE_HUBS { 0 } else { hub_count }
+    }
+
+    /// Wrapper RTL revision from HW_INFO (`None` if no SUMP3 wrapper answers)
+    pub fn revision(&self) -> Option<u8> {
+        let hw_info = self.read_reg(REG_HW_INFO)?;
+        (HW_INFO_ID.get(hw_info) == HW_ID_SUMP3).then(|| HW_INFO_REVISION.get(hw_info) as u8)
+    }
+
+    /// Refuse `feature` if the wrapper's RTL revision predates it (see
+    /// `compat::MATRIX`); without a wrapper, the operation fails on its own
+    pub fn require(&self, feature: Feature) -> Result<(), String> {
+        match self.revision() {
+            Some(revision) if !feature.supported_by(revision) => Err(feature.unsupported(revision)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Number of pods on a hub (serial-bus read; `None` if it fails or
+    /// reports more than `MAX_PLAUSIBLE_PODS`)
+    pub fn pod_count(&self, hub: u8) -> Option<u8> {
+        self.read_pod_count(hub).filter(|&count| count <= MAX_PLAUSIBLE_PODS)
+    }
+
+    fn read_pod_count(&self, hub: u8) -> Option<u8> {
+        self.exec_cmd(CMD_RD_POD_COUNT, (hub as u32) << 16, 0)
+            .map(|v| (v & 0xFF) as u8)
+    }
+
+    /// Read HW_INFO/CAP_STATUS and enumerate all hubs and pods
+    pub fn info(&self) -> IlaInfo {
+        let hw_info = self.read_reg(REG_HW_INFO).unwrap_or(0);
+
+        let id = HW_INFO_ID.get(hw_info);
+        let hub_count = HW_INFO_HUB_COUNT.get(hw_info) as u8;
+        let revision = HW_INFO_REVISION.get(hw_info) as u8;
+
+        let connected = id == HW_ID_SUMP3;
+        let hw_id = format!("{}{}",
+            char::from_u32((id >> 8) & 0xFF).unwrap_or('?'),
+            char::from_u32(id & 0xFF).unwrap_or('?')
+        );
+
+        let cap_status = self.read_reg(REG_CAP_STATUS).unwrap_or(0);
+
+        let is_armed = (cap_status & 0x01) != 0;
+        let is_awake = (cap_status & 0x02) != 0;
+
+        // Enumerate hubs and pods, stopping at the first implausible count or
+        // silent hub rather than timing out command by command on the rest
+        let mut hubs = Vec::new();
+        let mut unreliable = None;
+        if connected && hub_count == 0 {
+            unreliable = Some(format!("HW_INFO 0x{:08X} reports no hubs", hw_info));
+        } else if connected && hub_count > MAX_PLAUSIBLE_HUBS {
+            unreliable = Some(format!(
+                "HW_INFO 0x{:08X} reports {} hubs (at most {} expected); check the wrapper base address",
+                hw_info, hub_count, MAX_PLAUSIBLE_HUBS
+            ));
+        } else if connected {
+            for hub_idx in 0..hub_count {
+                match self.enumerate_hub(hub_idx) {
+                    Ok(hub) => hubs.push(hub),
+                    Err(reason) => {
+                        unreliable = Some(reason);
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut info = IlaInfo {
+            connected,
+            hw_id,
+            revision,
+            hub_count: if hub_count > MAX_PLAUSIBLE_HUBS { 0 } else { hub_count },
+            is_armed,
+            is_awake,
+            base_addr: format!("0x{:08X}", self.base_addr),
+            hubs,
+            ext_trigger: if connected { self.ext_trigger_routing() } else { None },
+            groups: Vec::new(),
+            design_id: self.design_id(),
+            error: None,
+            rtl_warnings: Vec::new(),
+            unreliable,
+        };
+        info.rtl_warnings = compat::check(&info);
+        info
+    }
+
+    /// Hub clock in Hz (0 if unreadable)
+    pub fn hub_clock_hz(&self, hub: u8) -> u64 {
+        hub_freq_hz(self.exec_cmd(CMD_RD_HUB_FREQ, (hub as u32) << 16, 0).unwrap_or(0))
+    }
+
+    /// A hub and its pods, or why its pod count can't be trusted
+    fn enumerate_hub(&self, hub_idx: u8) -> Result<HubInfo, String> {
+        let addr = (hub_idx as u32) << 16;
+
+        let pod_count = match self.read_pod_count(hub_idx) {
+            None => {
+                return Err(failure_message(&format!("Pod count read of hub {}", hub_idx))
+                    + "; hub enumeration stopped");
+            }
+            Some(count) if count > MAX_PLAUSIBLE_PODS => {
+                return Err(format!(
+                    "Hub {} reports {} pods (at most {} expected); hub enumeration stopped",
+                    hub_idx, count, MAX_PLAUSIBLE_PODS
+                ));
+            }
+            Some(count) => count,
+        };
+        let name = self.read_hub_name(hub_idx);
+        let freq = self.exec_cmd(CMD_RD_HUB_FREQ, addr, 0).unwrap_or(0);
+        let freq_hz = hub_freq_hz(freq);
+        let instance = self.exec_cmd(CMD_RD_HUB_INSTANCE, addr, 0).unwrap_or(0);
+
+        let mut pods = Vec::with_capacity(pod_count as usize);
+        for pod_idx in 0..pod_count {
+            if let Some(ram_cfg) = self.read_pod_reg(hub_idx, pod_idx, POD_REG_RAM_CFG) {
+                check_ram_cfg(ram_cfg).map_err(|problem| format!(
+                    "Hub {} pod {} RAM_CFG 0x{:08X} {}; hub enumeration stopped",
+                    hub_idx, pod_idx, ram_cfg, problem
+                ))?;
+            }
+            pods.push(self.enumerate_pod(hub_idx, pod_idx));
+        }
+
+        Ok(HubInfo {
+            index: hub_idx,
+            name,
+            instance,
+            freq_mhz: HUB_FREQ_MHZ.get(freq),
+            freq_hz,
+            pod_count,
+            pods,
+            display_name: None,
+            description: None,
+        })
+    }
+
+    /// Name, geometry and signal list of one pod
+    pub fn enumerate_pod(&self, hub_idx: u8, pod_idx: u8) -> PodInfo {
+        let pod_name = self.read_pod_name(hub_idx, pod_idx);
+
+        let hw_cfg = self.read_pod_reg(hub_idx, pod_idx, POD_REG_HW_CFG).unwrap_or(0);
+        let hw_rev = HW_CFG_REVISION.get(hw_cfg) as u8;
+
+        let norom_view_dwords = (hw_cfg & 0x0800) != 0;
+        let norom_view_words = (hw_cfg & 0x0400) != 0;
+        let norom_view_bytes = (hw_cfg & 0x0200) != 0;
+        let norom_view_bits = (hw_cfg & 0x0100) != 0;
+        let rle_disable = (hw_cfg & 0x04) != 0;
+        let view_rom_en = (hw_cfg & 0x02) != 0;
+
+        let (ts_bits, data_bits, ram_depth) = self.get_pod_config(hub_idx, pod_idx);
+
+        let triggerable = self.read_pod_reg(hub_idx, pod_idx, POD_REG_TRIGGERABLE).unwrap_or(0);
+
+        let (view_mode, mut signals) = if view_rom_en {
+            ("custom".to_string(), Vec::new())
+        } else {
+            generate_norom_signals(&pod_name, data_bits,
+                norom_view_dwords, norom_view_words, norom_view_bytes, norom_view_bits,
+                rle_disable)
+        };
+        for signal in &mut signals {
+            signal.triggerable = signal.mask() & triggerable != 0;
+        }
+
+        PodInfo {
+            index: pod_idx,
+            name: pod_name,
+            hw_rev,
+            ram_depth,
+            data_bits,
+            ts_bits,
+            triggerable,
+            rle_disable,
+            view_rom_en,
+            view_mode,
+            signals,
+            readout: self.pod_readout(hub_idx, pod_idx),
+            display_name: None,
+            description: None,
+        }
+    }
+
+    /// Reset, then program trigger type, digital trigger field and post-trigger
+    /// length, and enable the trigger on pod (0,0) and on any further `pods`.
+    ///
+    /// Does not INIT or ARM; returns the trigger bits actually programmed.
+    pub fn configure_trigger(
+        &self,
+        trig_type: u32,
+        trigger_bits: u32,
+        post_trigger: u32,
+        pods: &[PodTrigger],
+    ) -> Result<u32, &'static str> {
+        let (trig_bits, commands) = trigger_commands(trig_type, trigger_bits, post_trigger, pods);
+        self.issue(&commands)?;
+        Ok(trig_bits)
+    }
+
+    /// `configure_trigger` with the settings of `config` (after
+    /// `resolve_trigger_signals`), then the tick divisor
+    pub fn program_trigger(&self, config: &TriggerConfig) -> Result<u32, &'static str> {
+        let (bits, commands) = trigger_plan(config);
+        self.issue(&commands)?;
+        Ok(bits)
+    }
+
+    /// Issue `commands` in order, stopping at the first that fails
+    fn issue(&self, commands: &[WrapperCommand]) -> Result<(), &'static str> {
+        for c in commands {
+            if self.exec_cmd(c.cmd, c.addr, c.wdata).is_none() {
+                if c.cmd == CMD_WR_POD_REG {
+                    let (hub, pod, reg) = ((c.addr >> 16) & 0xFF, (c.addr >> 8) & 0xFF, c.addr & 0xFF);
+                    tracing::warn!("Failed to write 0x{:08X} to register 0x{:02X} of hub {} pod {}", c.wdata, reg, hub, pod);
+                }
+                return Err(trigger_failure(c.cmd));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve `trigger_signals` against pod (0,0)'s signal list and OR
+    /// their bits into `trigger_bits`
+    pub fn resolve_trigger_signals(&self, config: &TriggerConfig) -> Result<TriggerConfig, Vec<FieldError>> {
+        let mut resolved = config.clone();
+        resolved.trigger_bits |= self.signal_bits(&config.trigger_signals, "trigger_signals")?;
+        Ok(resolved)
+    }
+
+    /// Mask of pod (0,0) bits covered by the named signals; errors are
+    /// reported against `field`
+    pub fn signal_bits(&self, names: &[String], field: &str) -> Result<u32, Vec<FieldError>> {
+        if names.is_empty() {
+            return Ok(0);
+        }
+        let error = |message: String| FieldError { field: field.into(), message };
+
+        let pod = self.enumerate_pod(0, 0);
+        if pod.view_rom_en {
+            return Err(vec![error("hub 0 pod 0 describes its signals in a view ROM, which the server doesn't decode; use bit masks".into())]);
+        }
+        let mut bits = 0;
+        let mut errors = Vec::new();
+        for name in names {
+            match signal_mask(&pod.signals, name) {
+                Ok(mask) => bits |= mask,
+                Err(message) => errors.push(error(message)),
+            }
+        }
+        if errors.is_empty() { Ok(bits) } else { Err(errors) }
+    }
+
+    /// Check a trigger configuration against pod (0,0), the pod
+    /// `configure_trigger` programs, and any further `pods`: known type, bits
+    /// within `data_bits` and the `triggerable` mask, post-trigger length
+    /// within RAM depth
+    pub fn validate_trigger(&self, config: &TriggerConfig) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut error = |field: &str, message: String| {
+            errors.push(FieldError { field: field.into(), message });
+        };
+
+        let trig_type = parse_trigger_type(&config.trigger_type);
+        if trig_type.is_none() {
+            error("trigger_type", format!(
+                "unsupported trigger type '{}' (expected or_rising, or_falling or external)",
+                config.trigger_type
+            ));
+        }
+
+        let (Some(ram_cfg), Some(triggerable)) = (
+            self.read_pod_reg(0, 0, POD_REG_RAM_CFG),
+            self.read_pod_reg(0, 0, POD_REG_TRIGGERABLE),
+        ) else {
+            error("pod", "hub 0 pod 0 not responding".into());
+            return Err(errors);
+        };
+        let Some(ram_depth) = ram_depth(ram_cfg) else {
+            error("pod", implausible_ram_cfg(0, 0, ram_cfg));
+            return Err(errors);
+        };
+
+        // External triggers don't use the digital trigger field
+        let digital = trig_type != Some(TRIG_EXT_RISING);
+        if !digital {
+            if let Err(message) = self.require(Feature::ExtTriggerRouting) {
+                error("trigger_type", message);
+            }
+        } else {
+            // configure_trigger substitutes bit 0 for an empty field
+            let bits = if config.trigger_bits == 0 { 1 } else { config.trigger_bits };
+            if let Err(message) = check_trigger_enable(0, 0, bits, ram_cfg, triggerable) {
+                error("trigger_bits", message);
+            }
+        }
+
+        let hub_count = self.hub_count();
+        for (i, entry) in config.pods.iter().enumerate() {
+            let field = format!("pods[{}]", i);
+            let (hub, pod) = (entry.hub, entry.pod);
+            if config.pods[..i].iter().any(|p| (p.hub, p.pod) == (hub, pod)) {
+                error(&field, format!("hub {} pod {} is listed twice", hub, pod));
+                continue;
+            }
+            if hub >= hub_count {
+                error(&field, format!("hub {} does not exist ({} hubs)", hub, hub_count));
+                continue;
+            }
+            let pod_count = self.pod_count(hub).unwrap_or(0);
+            if pod >= pod_count {
+                error(&field, format!("hub {} has no pod {} ({} pods)", hub, pod, pod_count));
+                continue;
+            }
+            if !digital || entry.bits == 0 {
+                continue;
+            }
+            let (Some(ram_cfg), Some(triggerable)) = (
+                self.read_pod_reg(hub, pod, POD_REG_RAM_CFG),
+                self.read_pod_reg(hub, pod, POD_REG_TRIGGERABLE),
+            ) else {
+                error(&field, format!("hub {} pod {} not responding", hub, pod));
+                continue;
+            };
+            if let Err(message) = check_trigger_enable(hub, pod, entry.bits, ram_cfg, triggerable) {
+                error(&format!("{}.bits", field), message);
+            }
+        }
+
+        if config.post_trigger > ram_depth {
+            error("post_trigger", format!(
+                "{} exceeds the pod's RAM depth of {}", config.post_trigger, ram_depth
+            ));
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Read up to `count` RLE samples (capped at RAM depth and 2048) from a pod
+    pub fn read_capture(&self, hub: u8, pod: u8, count: u32) -> CaptureData {
+        let (mut capture, ram_depth) = self.capture_header(hub, pod);
+        let sample_count = count.min(ram_depth).min(2048);
+        (capture.samples, capture.readout_error) = if capture.status.acquired {
+            self.cached_samples(hub, pod, sample_count, capture.ts_bits)
+        } else {
+            self.read_rle_samples(hub, pod, 0, sample_count, capture.ts_bits)
+        };
+        capture.sample_count = sample_count;
+        capture.gaps = find_gaps(&capture.samples, capture.ts_bits);
+        capture
+    }
+
+    /// Capture metadata without samples, and the pod's RAM depth
+    fn capture_header(&self, hub: u8, pod: u8) -> (CaptureData, u32) {
+        let status = self.capture_status();
+        let (armed_at_ms, triggered_at_ms) = self.capture_times();
+        let (correlation_id, trigger_offset_ms) = self.correlate(triggered_at_ms);
+        let trigger_source = self.latched_trigger_source(&status);
+
+        let (ts_bits, data_bits, ram_depth) = self.get_pod_config(hub, pod);
+
+        let capture = CaptureData {
+            schema_version: CAPTURE_SCHEMA_VERSION,
+            hub,
+            pod,
+            ts_bits,
+            data_bits,
+            status,
+            samples: Vec::new(),
+            sample_count: 0,
+            sequence: self.sequence(),
+            armed_at_ms,
+            triggered_at_ms,
+            armed_at: armed_at_ms.map(rfc3339),
+            triggered_at: triggered_at_ms.map(rfc3339),
+            correlation_id,
+            trigger_offset_ms,
+            design_id: self.design_id(),
+            trigger_address: None,
+            trigger_source,
+            readout_error: None,
+            gaps: Vec::new(),
+        };
+        (capture, ram_depth)
+    }
+}
+
+/// RAM depth from a pod's RAM_CFG register; None when the depth bits don't
+/// fit in 32 bits (a floating or corrupted register)
+fn ram_depth(ram_cfg: u32) -> Option<u32> {
+    1u32.checked_shl(RAM_CFG_DEPTH_BITS.get(ram_cfg))
+}
+
+fn implausible_ram_cfg(hub: u8, pod: u8, ram_cfg: u32) -> String {
+    format!(
+        "hub {} pod {} RAM_CFG 0x{:08X} claims a depth of 2^{} words",
+        hub, pod, ram_cfg, RAM_CFG_DEPTH_BITS.get(ram_cfg)
+    )
+}
+
+/// Why a RAM_CFG register's fields can't be trusted, if they can't
+fn check_ram_cfg(ram_cfg: u32) -> Result<(), String> {
+    let fields = [
+        ("address bits", RAM_CFG_DEPTH_BITS.get(ram_cfg), MAX_PLAUSIBLE_DEPTH_BITS),
+        ("data bits", RAM_CFG_DATA_BITS.get(ram_cfg), MAX_PLAUSIBLE_DATA_BITS),
+    ];
+    match fields.into_iter().find(|&(_, bits, max)| bits > max) {
+        Some((what, bits, max)) => Err(format!("reports {} {} (at most {} expected)", bits, what, max)),
+        None => Ok(()),
+    }
+}
+
+/// RAM depth and 32-bit pages per word from a pod's RAM_CFG register.
+///
+/// A RAM word is {code[1:0], timestamp, data}; at least the two pages the
+/// RLE decoder reads.
+fn ram_geometry(ram_cfg: u32) -> Option<(u32, u32)> {
+    let ram_width = 2 + RAM_CFG_TS_BITS.get(ram_cfg) + RAM_CFG_DATA_BITS.get(ram_cfg);
+    Some((ram_depth(ram_cfg)?, ram_width.div_ceil(32).max(2)))
+}
+
+// ============================================================================
+// Signal generation helpers
+// ============================================================================
+
+/// Check trigger enable bits against a pod's `data_bits` and triggerable mask
+fn check_trigger_enable(hub: u8, pod: u8, bits: u32, ram_cfg: u32, triggerable: u32) -> Result<(), String> {
+    let data_bits = RAM_CFG_DATA_BITS.get(ram_cfg);
+    if data_bits < 32 && bits >> data_bits != 0 {
+        return Err(format!("0x{:08X} exceeds hub {} pod {}'s {} data bits", bits, hub, pod, data_bits));
+    }
+    if triggerable == 0 {
+        return Err(format!("hub {} pod {} is not triggerable", hub, pod));
+    }
+    if bits & !triggerable != 0 {
+        return Err(format!(
+            "bits 0x{:08X} are not triggerable (mask 0x{:08X})", bits & !triggerable, triggerable
+        ));
+    }
+    Ok(())
+}
+
+/// Trigger field mask of a signal: a full name, its name without the
+/// `[hi:lo]` suffix, or one bit of it as `name[n]`
+fn signal_mask(signals: &[SignalInfo], name: &str) -> Result<u32, String> {
+    let base = |s: &str| s.split_once('[').map_or(s, |(base, _)| base).to_string();
+    let single_bit = || {
+        let (sig, bit) = name.strip_suffix(']')?.rsplit_once('[')?;
+        let bit: u16 = bit.parse().ok()?;
+        signals.iter()
+            .any(|s| base(&s.name) == sig && (s.bit_low..=s.bit_high).contains(&bit))
+            .then_some((bit, bit))
+    };
+    let (lo, hi) = match signals.iter().find(|s| s.name == name || base(&s.name) == name) {
+        Some(signal) => (signal.bit_low, signal.bit_high),
+        None => single_bit().ok_or_else(|| {
+            let known: Vec<&str> = signals.iter().take(8).map(|s| s.name.as_str()).collect();
+            let more = if signals.len() > known.len() { ", ..." } else { "" };
+            format!("unknown signal '{}' (hub 0 pod 0 has {}{})", name, known.join(", "), more)
+        })?,
+    };
+    if hi > 31 {
+        return Err(format!("signal '{}' (bits {}..{}) lies beyond the 32-bit trigger field", name, lo, hi));
+    }
+    Ok(((1u64 << (hi + 1)) - (1u64 << lo)) as u32)
+}
+
+/// Generate signal list based on norom_view_* flags
+fn generate_norom_signals(
+    pod_name: &str,
+    data_bits: u16,
+    view_dwords: bool,
+    view_words: bool,
+    view_bytes: bool,
+    view_bits: bool,
+    rle_disable: bool,
+) -> (String, Vec<SignalInfo>) {
+    let mut signals = Vec::new();
+    let pod_name_trimmed = pod_name.trim();
+
+    // Determine view mode based on flags
+    let view_mode = if view_dwords {
+        "dwords"
+    } else if view_words {
+        "words"
+    } else if view_bytes {
+        "bytes"
+    } else if view_bits {
+        "bits"
+    } else {
+        "dwords" // default
+    };
+
+    let signal_type = if rle_disable { "analog" } else { "vector" };
+
+    // Special case: ADC I/Q pod with known layout
+    if pod_name_trimmed.contains("adc") && pod_name_trimmed.contains("iq") && data_bits >= 25 {
+        signals.push(SignalInfo {
+            name: "adc_i[11:0]".to_string(),
+            bit_high: 11,
+            bit_low: 0,
+            signal_type: "analog".to_string(),
+            triggerable: false,
+        });
+        signals.push(SignalInfo {
+            name: "adc_q[11:0]".to_string(),
+            bit_high: 23,
+            bit_low: 12,
+            signal_type: "analog".to_string(),
+            triggerable: false,
+        });
+        signals.push(SignalInfo {
+            name: "adc_valid".to_string(),
+            bit_high: 24,
+            bit_low: 24,
+            signal_type: "bit".to_string(),
+            triggerable: false,
+        });
+        return ("iq".to_string(), signals);
+    }
+
+    match view_mode {
+        "dwords" => {
+            let num_dwords = data_bits.div_ceil(32);
+            for i in 0..num_dwords {
+                let bit_low = i * 32;
+                let bit_high = std::cmp::min((i + 1) * 32 - 1, data_bits - 1);
+                signals.push(SignalInfo {
+                    name: if num_dwords == 1 {
+                        format!("{}[{}:0]", pod_name_trimmed, bit_high)
+                    } else {
+                        format!("{}_d{}[{}:{}]", pod_name_trimmed, i, bit_high, bit_low)
+                    },
+                    bit_high,
+                    bit_low,
+                    signal_type: signal_type.to_string(),
+                    triggerable: false,
+                });
+            }
+        }
+        "words" => {
+            let num_words = data_bits.div_ceil(16);
+            for i in 0..num_words {
+                let bit_low = i * 16;
+                let bit_high = std::cmp::min((i + 1) * 16 - 1, data_bits - 1);
+                signals.push(SignalInfo {
+                    name: if num_words == 1 {
+                        format!("{}[{}:0]", pod_name_trimmed, bit_high)
+                    } else {
+                        format!("{}_w{}[{}:{}]", pod_name_trimmed, i, bit_high, bit_low)
+                    },
+                    bit_high,
+                    bit_low,
+                    signal_type: signal_type.to_string(),
+                    triggerable: false,
+                });
+            }
+        }
+        "bytes" => {
+            let num_bytes = data_bits.div_ceil(8);
+            for i in 0..num_bytes {
+                let bit_low = i * 8;
+                let bit_high = std::cmp::min((i + 1) * 8 - 1, data_bits - 1);
+                signals.push(SignalInfo {
+                    name: format!("{}_b{}[{}:{}]", pod_name_trimmed, i, bit_high, bit_low),
+                    bit_high,
+                    bit_low,
+                    signal_type: "vector".to_string(),
+                    triggerable: false,
+                });
+            }
+        }
+        "bits" => {
+            for i in 0..data_bits {
+                signals.push(SignalInfo {
+                    name: format!("{}[{}]", pod_name_trimmed, i),
+                    bit_high: i,
+                    bit_low: i,
+                    signal_type: "bit".to_string(),
+                    triggerable: false,
+                });
+            }
+        }
+        _ => {}
+    }
+
+    (view_mode.to_string(), signals)
+}
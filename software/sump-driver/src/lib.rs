@@ -0,0 +1,31 @@
+//! SUMP3 ILA Driver
+//!
+//! Register-level driver for the SUMP3 AXI wrapper (`rtl/sump3_axi_wrapper.sv`).
+//! Uses polling-based register access via /dev/mem (no IRQ/kernel driver needed),
+//! or any other [`Backend`] such as the TCP bridge to a remote `sump-agent`,
+//! JTAG over XVC (`rtl/sump3_jtag_bridge.sv`) or MesaBus over UART.
+//!
+//! Used by `sump-server` for the REST API, and built as a `cdylib`/`staticlib`
+//! with a C header (`include/sump_driver.h`) for test frameworks that need to
+//! drive the ILA without the HTTP hop.
+
+pub mod backend;
+pub mod bridge;
+mod cache;
+pub mod clock;
+pub mod compat;
+pub mod deep;
+pub mod devmem;
+pub mod ffi;
+mod ila;
+pub mod local_bus;
+pub mod readout;
+pub mod record;
+pub mod sim;
+mod stats;
+pub mod uart;
+pub mod xvc;
+
+pub use backend::Backend;
+pub use ila::*;
+pub use sump_model as model;
@@ -0,0 +1,232 @@
+//! SUMP3 core local bus
+//!
+//! Core control/data register command codes (technical reference section 5.1)
+//! and `LocalBusBackend`, which performs the wrapper's command sequences in
+//! software for transports that reach the core's local bus directly, without
+//! `sump3_axi_wrapper.sv` in between (e.g. MesaBus over UART).
+
+use std::io;
+use std::time::Duration;
+
+use crate::backend::Backend;
+use crate::ila::*;
+
+/// Default local bus address of the core control register (data is +4)
+pub const DEFAULT_CTRL_ADDR: u32 = 0x98;
+
+// Core control register commands
+pub const CORE_IDLE: u32             = 0x00;
+pub const CORE_ARM: u32              = 0x01;
+pub const CORE_RESET: u32            = 0x02;
+pub const CORE_INIT: u32             = 0x03;
+pub const CORE_SLEEP: u32            = 0x04;
+pub const CORE_RD_HW_ID: u32         = 0x0B;
+pub const CORE_RD_ANA_RAM_CFG: u32   = 0x0C;
+pub const CORE_RD_TICK_FREQ: u32     = 0x0D;
+pub const CORE_RD_ANA_FIRST_PTR: u32 = 0x0E;
+pub const CORE_RD_RAM_DATA: u32      = 0x0F;
+pub const CORE_RD_DIG_FIRST_PTR: u32 = 0x10;
+pub const CORE_RD_DIG_CK_FREQ: u32   = 0x11;
+pub const CORE_RD_DIG_RAM_CFG: u32   = 0x12;
+pub const CORE_RD_REC_PROFILE: u32   = 0x13;
+pub const CORE_RD_TRIG_SRC: u32      = 0x14;
+pub const CORE_RD_VIEW_ROM_KB: u32   = 0x15;
+pub const CORE_RD_HUB_COUNT: u32     = 0x30;
+pub const CORE_RD_POD_COUNT: u32     = 0x31;
+pub const CORE_WR_INST_ADDR: u32     = 0x32;
+pub const CORE_RW_POD_DATA: u32      = 0x33;
+pub const CORE_RD_TRIG_SRC_POD: u32  = 0x34;
+pub const CORE_WR_TRIG_WIDTH: u32    = 0x35;
+pub const CORE_RD_HUB_FREQ: u32      = 0x36;
+pub const CORE_RD_HUB_HW_CFG: u32    = 0x3A;
+pub const CORE_RD_HUB_INSTANCE: u32  = 0x3C;
+pub const CORE_RD_HUB_NAME_0_3: u32  = 0x3D;
+pub const CORE_RD_HUB_NAME_4_7: u32  = 0x3E;
+pub const CORE_RD_HUB_NAME_8_11: u32 = 0x3F;
+
+/// SUMP3 identifier in the top byte of the core HW ID
+const CORE_SUMP_ID: u32 = 0x53;
+
+/// Settle time after triggering a hub/pod serial-bus read
+const SERIAL_READ_DELAY: Duration = Duration::from_millis(1);
+
+/// Core control code for a wrapper command (mirrors `get_sump_cmd` in the RTL)
+pub fn core_cmd(cmd: u32) -> Option<u32> {
+    let core = match cmd {
+        CMD_ARM => CORE_ARM,
+        CMD_RESET => CORE_RESET,
+        CMD_INIT => CORE_INIT,
+        CMD_IDLE | CMD_RD_STATUS => CORE_IDLE,
+        CMD_SLEEP => CORE_SLEEP,
+        CMD_RD_HW_ID => CORE_RD_HW_ID,
+        CMD_RD_HUB_COUNT => CORE_RD_HUB_COUNT,
+        CMD_RD_ANA_RAM_CFG => CORE_RD_ANA_RAM_CFG,
+        CMD_RD_TICK_FREQ => CORE_RD_TICK_FREQ,
+        CMD_RD_ANA_FIRST_PTR => CORE_RD_ANA_FIRST_PTR,
+        CMD_RD_RAM_DATA => CORE_RD_RAM_DATA,
+        CMD_RD_DIG_FIRST_PTR => CORE_RD_DIG_FIRST_PTR,
+        CMD_RD_DIG_CK_FREQ => CORE_RD_DIG_CK_FREQ,
+        CMD_RD_DIG_RAM_CFG => CORE_RD_DIG_RAM_CFG,
+        CMD_RD_REC_PROFILE => CORE_RD_REC_PROFILE,
+        CMD_RD_TRIG_SRC => CORE_RD_TRIG_SRC,
+        CMD_RD_VIEW_ROM_KB => CORE_RD_VIEW_ROM_KB,
+        // Local writes share their codes with the wrapper
+        CMD_WR_USER_CTRL..=CMD_WR_RAM_PAGE => cmd,
+        CMD_RD_HUB_FREQ => CORE_RD_HUB_FREQ,
+        CMD_RD_POD_COUNT => CORE_RD_POD_COUNT,
+        CMD_RD_POD_REG | CMD_WR_POD_REG => CORE_RW_POD_DATA,
+        CMD_RD_TRIG_SRC_POD => CORE_RD_TRIG_SRC_POD,
+        CMD_RD_HUB_HW_CFG => CORE_RD_HUB_HW_CFG,
+        CMD_RD_HUB_INSTANCE => CORE_RD_HUB_INSTANCE,
+        CMD_RD_HUB_NAME_0_3 => CORE_RD_HUB_NAME_0_3,
+        CMD_RD_HUB_NAME_4_7 => CORE_RD_HUB_NAME_4_7,
+        CMD_RD_HUB_NAME_8_11 => CORE_RD_HUB_NAME_8_11,
+        CMD_WR_TRIG_WIDTH => CORE_WR_TRIG_WIDTH,
+        _ => return None,
+    };
+    Some(core)
+}
+
+/// DWORD access to the core's local bus
+pub trait LocalBus: Send {
+    fn write(&mut self, addr: u32, data: u32) -> io::Result<()>;
+
+    fn read(&mut self, addr: u32) -> io::Result<u32>;
+
+    /// Transport description for logs
+    fn describe(&self) -> String;
+
+    /// Transport reconnects since the bus was opened
+    fn reconnects(&self) -> u64 {
+        0
+    }
+}
+
+/// `Backend` presenting the wrapper register map on top of a core local bus
+pub struct LocalBusBackend<B> {
+    bus: B,
+    ctrl_addr: u32,
+    cmd: u32,
+    addr: u32,
+    wdata: u32,
+    status: u32,
+    rdata: u32,
+}
+
+impl<B: LocalBus> LocalBusBackend<B> {
+    /// `ctrl_addr` is the core control register's local bus address
+    pub fn new(bus: B, ctrl_addr: u32) -> Self {
+        Self {
+            bus,
+            ctrl_addr,
+            cmd: 0,
+            addr: 0,
+            wdata: 0,
+            status: 0,
+            rdata: 0,
+        }
+    }
+
+    fn core_read(&mut self, core: u32) -> io::Result<u32> {
+        self.bus.write(self.ctrl_addr, core)?;
+        self.bus.read(self.ctrl_addr + 4)
+    }
+
+    /// Run the current CMD/ADDR/WDATA the way the wrapper state machine does
+    fn execute(&mut self) -> io::Result<u32> {
+        let cmd = self.cmd & 0xFF;
+        if cmd == CMD_NOP {
+            return Ok(0);
+        }
+        let core = core_cmd(cmd).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("unknown command 0x{:02X}", cmd))
+        })?;
+        let (ctrl, data) = (self.ctrl_addr, self.ctrl_addr + 4);
+
+        match cmd >> 4 {
+            0x0 => self.bus.write(ctrl, core).map(|_| 0),
+            0x1 => self.core_read(core),
+            0x2 => {
+                self.bus.write(ctrl, core)?;
+                self.bus.write(data, self.wdata).map(|_| 0)
+            }
+            _ => {
+                // Serial bus: select hub/pod/register, then the target command
+                self.bus.write(ctrl, CORE_WR_INST_ADDR)?;
+                self.bus.write(data, self.addr)?;
+                self.bus.write(ctrl, core)?;
+                if cmd >> 4 == 0x4 {
+                    return self.bus.write(data, self.wdata).map(|_| 0);
+                }
+                // First read starts the serial request, second returns the result
+                self.bus.read(data)?;
+                std::thread::sleep(SERIAL_READ_DELAY);
+                self.bus.read(data)
+            }
+        }
+    }
+
+    /// HW_INFO as the wrapper builds it: {0x5303, hub_count, revision}
+    fn hw_info(&mut self) -> io::Result<u32> {
+        let hw_id = self.core_read(CORE_RD_HW_ID)?;
+        if hw_id >> 24 != CORE_SUMP_ID {
+            return Ok(0);
+        }
+        let hubs = self.core_read(CORE_RD_HUB_COUNT)? & 0xFF;
+        let revision = (hw_id >> 16) & 0xFF;
+        Ok((0x5303 << 16) | (hubs << 8) | revision)
+    }
+}
+
+impl<B: LocalBus> Backend for LocalBusBackend<B> {
+    fn read32(&mut self, offset: usize) -> Option<u32> {
+        let value = match offset {
+            REG_CMD => self.cmd,
+            REG_ADDR => self.addr,
+            REG_WDATA => self.wdata,
+            REG_STATUS => self.status,
+            REG_RDATA => self.rdata,
+            REG_HW_INFO => self.hw_info().ok()?,
+            // No sleep state is visible on the local bus; report awake
+            REG_CAP_STATUS => 0x02 | (self.core_read(CORE_IDLE).ok()? & 0x01),
+            // Trigger routing is wrapper logic; not present here
+            REG_TRIG_ROUTE => REG_UNMAPPED,
+            _ if offset < ILA_SIZE => 0,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool {
+        match offset {
+            REG_CMD => self.cmd = value,
+            REG_ADDR => self.addr = value,
+            REG_WDATA => self.wdata = value,
+            REG_CTRL if value & CTRL_START != 0 => match self.execute() {
+                Ok(rdata) => {
+                    self.rdata = rdata;
+                    self.status = STATUS_DONE;
+                }
+                Err(e) => {
+                    tracing::warn!("{}: command 0x{:02X} failed: {}", self.bus.describe(), self.cmd, e);
+                    let code = match e.kind() {
+                        io::ErrorKind::InvalidInput => ERR_BAD_CMD,
+                        _ => ERR_TRANSPORT,
+                    };
+                    self.status = STATUS_DONE | STATUS_ERROR | (code << 4);
+                }
+            },
+            _ if offset < ILA_SIZE => {}
+            _ => return false,
+        }
+        true
+    }
+
+    fn describe(&self) -> String {
+        self.bus.describe()
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.bus.reconnects()
+    }
+}
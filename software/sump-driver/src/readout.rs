@@ -0,0 +1,57 @@
+//! Pod RAM readout paths
+//!
+//! Pod RAM is normally read over the serial bus: a RAM_PTR write and a
+//! RAM_DATA read per word, hundreds of cycles each. A pod whose RAM is also
+//! mirrored into the AXI address space (e.g. the second port of its BRAM
+//! behind an AXI BRAM controller) can instead be copied with block reads of
+//! a `/dev/mem` mapping. The path is chosen per pod.
+
+use std::io;
+
+use crate::devmem::DevMem;
+
+/// Pod RAM mirrored at a physical address, page-major: word `addr` of page
+/// `page` is at byte offset `(page * ram_depth + addr) * 4`
+pub(crate) struct MappedRam {
+    mem: DevMem,
+    addr: usize,
+    ram_depth: u32,
+    pages: u32,
+}
+
+impl MappedRam {
+    pub fn open(addr: usize, ram_depth: u32, pages: u32) -> io::Result<Self> {
+        let mem = DevMem::new(addr, (ram_depth * pages * 4) as usize)?;
+        Ok(Self { mem, addr, ram_depth, pages })
+    }
+
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    /// Copy `count` words of `page` starting at `start`
+    pub fn read(&self, page: u32, start: u32, count: u32) -> Option<Vec<u32>> {
+        if page >= self.pages || start.checked_add(count)? > self.ram_depth {
+            return None;
+        }
+        let mut words = vec![0; count as usize];
+        let offset = ((page * self.ram_depth + start) * 4) as usize;
+        self.mem.read_block(offset, &mut words).then_some(words)
+    }
+}
+
+/// Parse `hub.pod=0xADDR,...` (as in `SUMP_POD_READOUT`)
+pub fn parse_readout_map(spec: &str) -> Result<Vec<(u8, u8, usize)>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(pod, addr)| {
+                let (hub, pod) = pod.split_once('.')?;
+                let addr = addr.trim_start_matches("0x").trim_start_matches("0X");
+                Some((hub.parse().ok()?, pod.parse().ok()?, usize::from_str_radix(addr, 16).ok()?))
+            });
+            parsed.ok_or_else(|| format!("expected hub.pod=0xADDR, got '{}'", entry))
+        })
+        .collect()
+}
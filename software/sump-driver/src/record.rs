@@ -0,0 +1,229 @@
+//! Register transcripts
+//!
+//! `RecordingBackend` wraps any `Backend` and writes every access, with its
+//! time since recording started, to a text transcript. `ReplayBackend`
+//! serves the responses of a transcript back in order, so the exchange with
+//! a failing board can be rerun on a developer machine without it. One
+//! access per line, times in microseconds:
+//!
+//! ```text
+//! # sump-driver transcript 1: /dev/mem @ 0x43C20000
+//! 12 R 0x10 0x00000002
+//! 15 R 0x1C -
+//! 40 W 0x0C 0x00000001 ok
+//! ```
+//!
+//! `-` is a failed read, `ok`/`err` whether a write was accepted. Replay
+//! expects exactly the recorded accesses; at the first that differs it logs
+//! the transcript line and fails every later access, like a lost connection.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use crate::backend::Backend;
+
+/// First line of every transcript
+pub const TRANSCRIPT_HEADER: &str = "# sump-driver transcript 1";
+
+/// Longest time recorded accesses stay buffered before reaching the file
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// One recorded register access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read { offset: usize, value: Option<u32> },
+    Write { offset: usize, value: u32, ok: bool },
+}
+
+impl Access {
+    fn line(&self, time_us: u64) -> String {
+        match *self {
+            Self::Read { offset, value: Some(value) } => format!("{} R 0x{:X} 0x{:08X}", time_us, offset, value),
+            Self::Read { offset, value: None } => format!("{} R 0x{:X} -", time_us, offset),
+            Self::Write { offset, value, ok } => {
+                format!("{} W 0x{:X} 0x{:08X} {}", time_us, offset, value, if ok { "ok" } else { "err" })
+            }
+        }
+    }
+
+    /// The same access, ignoring its outcome
+    fn matches(&self, other: &Access) -> bool {
+        match (*self, *other) {
+            (Self::Read { offset: a, .. }, Self::Read { offset: b, .. }) => a == b,
+            (Self::Write { offset: a, value: x, .. }, Self::Write { offset: b, value: y, .. }) => a == b && x == y,
+            _ => false,
+        }
+    }
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text.strip_prefix("0x")?, 16).ok()
+}
+
+/// `(time_us, access)` of each access line; `#` lines and blank lines are skipped
+pub fn parse_transcript(text: &str) -> Result<Vec<(u64, Access)>, String> {
+    let mut accesses = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let access = match fields[..] {
+            [time, "R", offset, value] => time.parse().ok().zip(parse_hex(offset)).and_then(|(time, offset)| {
+                let value = if value == "-" { None } else { Some(parse_hex(value)?) };
+                Some((time, Access::Read { offset: offset as usize, value }))
+            }),
+            [time, "W", offset, value, ok @ ("ok" | "err")] => {
+                time.parse().ok().zip(parse_hex(offset)).zip(parse_hex(value)).map(|((time, offset), value)| {
+                    (time, Access::Write { offset: offset as usize, value, ok: ok == "ok" })
+                })
+            }
+            _ => None,
+        };
+        accesses.push(access.ok_or_else(|| format!("line {}: cannot parse '{}'", number + 1, line))?);
+    }
+    Ok(accesses)
+}
+
+/// `Backend` writing a transcript of every access to the one it wraps
+pub struct RecordingBackend {
+    inner: Box<dyn Backend>,
+    out: BufWriter<File>,
+    path: String,
+    started: Instant,
+    flushed: Instant,
+}
+
+impl RecordingBackend {
+    /// Record to `path`, replacing an earlier transcript there
+    pub fn create(inner: Box<dyn Backend>, path: &str) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}: {}", TRANSCRIPT_HEADER, inner.describe())?;
+        let now = Instant::now();
+        Ok(Self { inner, out, path: path.into(), started: now, flushed: now })
+    }
+
+    fn record(&mut self, access: Access) {
+        let line = access.line(self.started.elapsed().as_micros() as u64);
+        let result = writeln!(self.out, "{}", line).and_then(|()| {
+            if self.flushed.elapsed() < FLUSH_INTERVAL {
+                return Ok(());
+            }
+            self.flushed = Instant::now();
+            self.out.flush()
+        });
+        if let Err(e) = result {
+            tracing::warn!("Register transcript {}: {}", self.path, e);
+        }
+    }
+}
+
+impl Backend for RecordingBackend {
+    fn read32(&mut self, offset: usize) -> Option<u32> {
+        let value = self.inner.read32(offset);
+        self.record(Access::Read { offset, value });
+        value
+    }
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool {
+        let ok = self.inner.write32(offset, value);
+        self.record(Access::Write { offset, value, ok });
+        ok
+    }
+
+    fn describe(&self) -> String {
+        format!("{} (recording to {})", self.inner.describe(), self.path)
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.inner.reconnects()
+    }
+}
+
+impl Drop for RecordingBackend {
+    fn drop(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+/// `Backend` answering from a recorded transcript
+pub struct ReplayBackend {
+    path: String,
+    accesses: Vec<(u64, Access)>,
+    next: usize,
+    diverged: bool,
+    /// Keep the recorded pace instead of answering at once
+    realtime: bool,
+    started: Instant,
+}
+
+impl ReplayBackend {
+    pub fn open(path: &str, realtime: bool) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        if !text.starts_with(TRANSCRIPT_HEADER) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a register transcript", path)));
+        }
+        let accesses = parse_transcript(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Self { path: path.into(), accesses, next: 0, diverged: false, realtime, started: Instant::now() })
+    }
+
+    /// Accesses of the transcript not replayed yet
+    pub fn remaining(&self) -> usize {
+        if self.diverged { 0 } else { self.accesses.len() - self.next }
+    }
+
+    /// Whether an access differed from the transcript
+    pub fn diverged(&self) -> bool {
+        self.diverged
+    }
+
+    /// The recorded outcome of `access`, if it is the next one in the transcript
+    fn replay(&mut self, access: Access) -> Option<Access> {
+        if self.diverged {
+            return None;
+        }
+        let Some(&(time_us, recorded)) = self.accesses.get(self.next) else {
+            tracing::warn!("Replay of {}: transcript ended, {:?} has no answer", self.path, access);
+            self.diverged = true;
+            return None;
+        };
+        if !recorded.matches(&access) {
+            tracing::warn!(
+                "Replay of {} diverged at access {}: expected {:?}, got {:?}",
+                self.path, self.next + 1, recorded, access
+            );
+            self.diverged = true;
+            return None;
+        }
+        self.next += 1;
+        if self.realtime {
+            let due = Duration::from_micros(time_us);
+            if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+                std::thread::sleep(wait);
+            }
+        }
+        Some(recorded)
+    }
+}
+
+impl Backend for ReplayBackend {
+    fn read32(&mut self, offset: usize) -> Option<u32> {
+        match self.replay(Access::Read { offset, value: None })? {
+            Access::Read { value, .. } => value,
+            Access::Write { .. } => unreachable!("matched a read"),
+        }
+    }
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool {
+        match self.replay(Access::Write { offset, value, ok: true }) {
+            Some(Access::Write { ok, .. }) => ok,
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("replay of {} ({} accesses left)", self.path, self.remaining())
+    }
+}
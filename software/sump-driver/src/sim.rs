@@ -0,0 +1,312 @@
+//! Simulated wrapper for tests
+//!
+//! `SimBackend` answers the register map of `sump3_axi_wrapper.sv` from an
+//! in-memory model: the CMD/ADDR/WDATA/CTRL/STATUS handshake (BUSY for a
+//! configurable number of STATUS polls, then DONE or ERROR with an
+//! ERR_CODE), HW_INFO, and hubs and pods behind the serial bus with their
+//! registers and RAM. Clones share the model, so a test keeps one to script
+//! faults and inspect the commands an `Ila` issued through another.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::backend::Backend;
+use crate::devmem::BitField;
+use crate::ila::*;
+
+/// STATUS bit set while a command runs
+pub const STATUS_BUSY: u32 = 0x01;
+
+/// CMD_RD_STATUS capture status bits
+pub const CAP_ARMED: u32     = 0x01;
+pub const CAP_PRE_TRIG: u32  = 0x02;
+pub const CAP_TRIGGERED: u32 = 0x04;
+pub const CAP_ACQUIRED: u32  = 0x08;
+
+// Serial-bus ADDR fields
+const ADDR_HUB: BitField = BitField::new(16, 8);
+const ADDR_POD: BitField = BitField::new(8, 8);
+const ADDR_REG: BitField = BitField::new(0, 8);
+
+// Pod RAM_PTR fields
+const RAM_PTR_PAGE: BitField = BitField::new(20, 4);
+const RAM_PTR_ADDR: BitField = BitField::new(0, 20);
+
+/// Name registers hold 4 ASCII characters each, first one in the top byte
+fn name_word(name: &str, index: usize) -> u32 {
+    let mut bytes = [b' '; 12];
+    for (dst, src) in bytes.iter_mut().zip(name.bytes()) {
+        *dst = src;
+    }
+    u32::from_be_bytes(bytes[index * 4..index * 4 + 4].try_into().unwrap())
+}
+
+#[derive(Debug, Clone)]
+pub struct SimPod {
+    pub name: String,
+    pub hw_cfg: u32,
+    pub ram_cfg: u32,
+    pub triggerable: u32,
+    /// Registers written over the serial bus, by register address
+    pub regs: HashMap<u8, u32>,
+    /// RAM pages (page 0 data, page 1 `{code, timestamp}`), `ram_depth` words each
+    pub ram: Vec<Vec<u32>>,
+}
+
+impl SimPod {
+    /// A pod with `1 << depth_bits` words of RAM, all unwritten
+    pub fn new(name: &str, depth_bits: u8, data_bits: u16, ts_bits: u8) -> Self {
+        let ram_cfg = RAM_CFG_TS_BITS.set(RAM_CFG_DATA_BITS.set(depth_bits as u32, data_bits as u32), ts_bits as u32);
+        let pages = (2 + ts_bits as u32 + data_bits as u32).div_ceil(32).max(2);
+        Self {
+            name: name.into(),
+            hw_cfg: 0,
+            ram_cfg,
+            triggerable: u32::MAX,
+            regs: HashMap::new(),
+            ram: vec![vec![0; 1 << depth_bits]; pages as usize],
+        }
+    }
+
+    /// Store an RLE sample at `addr`
+    pub fn set_sample(&mut self, addr: u32, code: u8, timestamp: u32, data: u32) {
+        let ts_bits = RAM_CFG_TS_BITS.get(self.ram_cfg);
+        self.ram[0][addr as usize] = data;
+        self.ram[1][addr as usize] = (code as u32) << ts_bits | timestamp;
+    }
+
+    fn read(&self, reg: u8) -> u32 {
+        match reg {
+            POD_REG_HW_CFG => self.hw_cfg,
+            POD_REG_RAM_CFG => self.ram_cfg,
+            POD_REG_TRIGGERABLE => self.triggerable,
+            POD_REG_NAME_0_3 => name_word(&self.name, 0),
+            POD_REG_NAME_4_7 => name_word(&self.name, 1),
+            POD_REG_NAME_8_11 => name_word(&self.name, 2),
+            POD_REG_RAM_DATA => {
+                let ptr = self.regs.get(&POD_REG_RAM_PTR).copied().unwrap_or(0);
+                let page = self.ram.get(RAM_PTR_PAGE.get(ptr) as usize);
+                page.and_then(|p| p.get(RAM_PTR_ADDR.get(ptr) as usize)).copied().unwrap_or(0)
+            }
+            reg => self.regs.get(&reg).copied().unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimHub {
+    pub name: String,
+    pub instance: u32,
+    /// CMD_RD_HUB_FREQ word (12.20 fixed-point MHz)
+    pub freq: u32,
+    /// CMD_RD_TRIG_SRC_POD word: the pod that fired the trigger
+    pub trig_src_pod: u32,
+    pub pods: Vec<SimPod>,
+}
+
+impl SimHub {
+    pub fn new(name: &str, freq_mhz: u32, pods: Vec<SimPod>) -> Self {
+        Self { name: name.into(), instance: 0, freq: HUB_FREQ_MHZ.set(0, freq_mhz), trig_src_pod: 0, pods }
+    }
+}
+
+/// Scripted outcome of a future command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    /// DONE with ERROR and this ERR_CODE
+    Error(u32),
+    /// BUSY until aborted
+    Hang,
+}
+
+#[derive(Debug)]
+pub struct Sim {
+    pub hubs: Vec<SimHub>,
+    /// HW_INFO revision byte
+    pub revision: u8,
+    /// STATUS polls a command stays BUSY for
+    pub busy_polls: u32,
+    /// Capture status returned by CMD_RD_STATUS
+    pub capture: u32,
+    /// Last value of each local write command, by command code
+    pub local: HashMap<u32, u32>,
+    /// Every command started, as `(cmd, addr, wdata)`
+    pub log: Vec<(u32, u32, u32)>,
+    /// Faults for upcoming commands with a given code, applied in order
+    pub faults: Vec<(u32, Fault)>,
+    regs: [u32; ILA_SIZE / 4],
+    status: u32,
+    polls_left: u32,
+    hung: bool,
+}
+
+impl Sim {
+    fn hub(&self, addr: u32) -> Result<&SimHub, u32> {
+        self.hubs.get(ADDR_HUB.get(addr) as usize).ok_or(ERR_SERIAL_ADDR)
+    }
+
+    fn pod_mut(&mut self, addr: u32) -> Result<&mut SimPod, u32> {
+        let hub = self.hubs.get_mut(ADDR_HUB.get(addr) as usize).ok_or(ERR_SERIAL_ADDR)?;
+        hub.pods.get_mut(ADDR_POD.get(addr) as usize).ok_or(ERR_SERIAL_ADDR)
+    }
+
+    /// Run a command the way the wrapper state machine does; `Err` is the ERR_CODE
+    fn execute(&mut self, cmd: u32, addr: u32, wdata: u32) -> Result<u32, u32> {
+        match cmd {
+            CMD_NOP => Ok(0),
+            CMD_ARM => {
+                self.capture = CAP_ARMED | CAP_PRE_TRIG;
+                Ok(0)
+            }
+            CMD_RESET | CMD_INIT | CMD_IDLE | CMD_SLEEP => {
+                self.capture = 0;
+                Ok(0)
+            }
+            CMD_RD_STATUS => Ok(self.capture),
+            CMD_RD_HUB_COUNT => Ok(self.hubs.len() as u32),
+            CMD_RD_HW_ID => Ok(0x5300_0000 | (self.revision as u32) << 16),
+            cmd if (CMD_RD_HW_ID..=CMD_RD_VIEW_ROM_KB).contains(&cmd) => Ok(self.local.get(&cmd).copied().unwrap_or(0)),
+            cmd if (CMD_WR_USER_CTRL..=CMD_WR_RAM_PAGE).contains(&cmd) => {
+                self.local.insert(cmd, wdata);
+                Ok(0)
+            }
+            CMD_RD_HUB_FREQ => Ok(self.hub(addr)?.freq),
+            CMD_RD_POD_COUNT => Ok(self.hub(addr)?.pods.len() as u32),
+            CMD_RD_HUB_INSTANCE => Ok(self.hub(addr)?.instance),
+            CMD_RD_HUB_NAME_0_3 => Ok(name_word(&self.hub(addr)?.name, 0)),
+            CMD_RD_HUB_NAME_4_7 => Ok(name_word(&self.hub(addr)?.name, 1)),
+            CMD_RD_HUB_NAME_8_11 => Ok(name_word(&self.hub(addr)?.name, 2)),
+            CMD_RD_POD_REG => Ok(self.pod_mut(addr)?.read(ADDR_REG.get(addr) as u8)),
+            CMD_WR_POD_REG => {
+                self.pod_mut(addr)?.regs.insert(ADDR_REG.get(addr) as u8, wdata);
+                Ok(0)
+            }
+            CMD_RD_TRIG_SRC_POD => Ok(self.hub(addr)?.trig_src_pod),
+            CMD_RD_HUB_HW_CFG => self.hub(addr).map(|_| 0),
+            CMD_WR_TRIG_WIDTH => Ok(0),
+            _ => Err(ERR_BAD_CMD),
+        }
+    }
+
+    fn start(&mut self) {
+        let (cmd, addr, wdata) = (self.regs[REG_CMD / 4], self.regs[REG_ADDR / 4], self.regs[REG_WDATA / 4]);
+        self.log.push((cmd, addr, wdata));
+        let fault = self.faults.iter().position(|&(c, _)| c == cmd).map(|i| self.faults.remove(i).1);
+        self.polls_left = self.busy_polls;
+        self.status = STATUS_BUSY;
+        let result = match fault {
+            Some(Fault::Hang) => {
+                self.hung = true;
+                return;
+            }
+            Some(Fault::Error(code)) => Err(code),
+            None => self.execute(cmd, addr, wdata),
+        };
+        match result {
+            Ok(rdata) => self.regs[REG_RDATA / 4] = rdata,
+            Err(code) => self.status |= STATUS_ERROR | STATUS_ERR_CODE.set(0, code),
+        }
+    }
+
+    fn read_status(&mut self) -> u32 {
+        if self.status & STATUS_BUSY == 0 {
+            return self.status;
+        }
+        if self.hung || self.polls_left > 0 {
+            self.polls_left = self.polls_left.saturating_sub(1);
+            return STATUS_BUSY;
+        }
+        self.status = (self.status & !STATUS_BUSY) | STATUS_DONE;
+        self.status
+    }
+}
+
+/// `Backend` over a shared `Sim`
+#[derive(Clone)]
+pub struct SimBackend(Arc<Mutex<Sim>>);
+
+impl SimBackend {
+    pub fn new(hubs: Vec<SimHub>) -> Self {
+        Self(Arc::new(Mutex::new(Sim {
+            hubs,
+            revision: 1,
+            busy_polls: 2,
+            capture: 0,
+            local: HashMap::new(),
+            log: Vec::new(),
+            faults: Vec::new(),
+            regs: [0; ILA_SIZE / 4],
+            status: 0,
+            polls_left: 0,
+            hung: false,
+        })))
+    }
+
+    /// One hub ("core", 100 MHz) with one 256-word, 32-bit pod ("pod0")
+    pub fn single_pod() -> Self {
+        Self::new(vec![SimHub::new("core", 100, vec![SimPod::new("pod0", 8, 32, 16)])])
+    }
+
+    /// The model, for scripting and inspection
+    pub fn sim(&self) -> parking_lot::MutexGuard<'_, Sim> {
+        self.0.lock()
+    }
+
+    /// Make the next command `cmd` fail with `fault`
+    pub fn fault(&self, cmd: u32, fault: Fault) {
+        self.0.lock().faults.push((cmd, fault));
+    }
+
+    /// Mark the capture triggered and acquired
+    pub fn trigger(&self) {
+        self.0.lock().capture = CAP_ARMED | CAP_TRIGGERED | CAP_ACQUIRED;
+    }
+
+    /// Take the command log
+    pub fn take_log(&self) -> Vec<(u32, u32, u32)> {
+        std::mem::take(&mut self.0.lock().log)
+    }
+}
+
+impl Backend for SimBackend {
+    fn read32(&mut self, offset: usize) -> Option<u32> {
+        if offset >= ILA_SIZE || !offset.is_multiple_of(4) {
+            return None;
+        }
+        let mut sim = self.0.lock();
+        Some(match offset {
+            REG_STATUS => sim.read_status(),
+            REG_HW_INFO => {
+                let hw_info = HW_INFO_ID.set(0, HW_ID_SUMP3);
+                HW_INFO_REVISION.set(HW_INFO_HUB_COUNT.set(hw_info, sim.hubs.len() as u32), sim.revision as u32)
+            }
+            REG_CAP_STATUS => 0x02 | (sim.capture & CAP_ARMED),
+            REG_TRIG_ROUTE | REG_CMD | REG_ADDR | REG_WDATA | REG_RDATA | REG_TIMEOUT => sim.regs[offset / 4],
+            _ => REG_UNMAPPED,
+        })
+    }
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool {
+        if offset >= ILA_SIZE || !offset.is_multiple_of(4) {
+            return false;
+        }
+        let mut sim = self.0.lock();
+        match offset {
+            REG_CTRL if value & CTRL_ABORT != 0 => {
+                sim.status = 0;
+                sim.hung = false;
+            }
+            REG_CTRL if value & CTRL_START != 0 => sim.start(),
+            REG_CTRL => {}
+            // Only the defined IN_SEL and OUT_EN bits are kept
+            REG_TRIG_ROUTE => sim.regs[offset / 4] = value & (TRIG_ROUTE_IN_SEL.mask() | TRIG_ROUTE_OUT_EN),
+            offset => sim.regs[offset / 4] = value,
+        }
+        true
+    }
+
+    fn describe(&self) -> String {
+        "sim".into()
+    }
+}
@@ -0,0 +1,151 @@
+//! Command counters
+//!
+//! Lock-free counters updated by the command layer, so every user of the
+//! driver (server, FFI, Python) gets the same longitudinal data.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use sump_model::{CommandFailure, CommandLatency, IlaStats, LatencyBucket};
+
+use crate::ila::{command_name, error_cause};
+
+/// Histogram bucket upper bounds in microseconds; local register commands
+/// land in the low buckets, serial-bus round trips further up
+pub const LATENCY_BUCKETS_US: [u64; 12] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000, 5000, 10000];
+
+/// Command codes are 7 bits wide
+const COMMAND_CODES: usize = 0x80;
+
+#[derive(Default)]
+struct Histogram {
+    /// Per-bucket (non-cumulative) counts; the last one is +Inf
+    buckets: [AtomicU64; LATENCY_BUCKETS_US.len() + 1],
+    count: AtomicU64,
+    sum_ns: AtomicU64,
+}
+
+/// Execution time histogram per command code
+pub(crate) struct CommandLatencies {
+    codes: Vec<Histogram>,
+}
+
+impl Default for CommandLatencies {
+    fn default() -> Self {
+        Self { codes: (0..COMMAND_CODES).map(|_| Histogram::default()).collect() }
+    }
+}
+
+impl CommandLatencies {
+    pub fn record(&self, cmd: u32, elapsed: Duration) {
+        let Some(h) = self.codes.get(cmd as usize) else {
+            return;
+        };
+        let us = elapsed.as_micros() as u64;
+        let bucket = LATENCY_BUCKETS_US.iter().position(|&le| us <= le).unwrap_or(LATENCY_BUCKETS_US.len());
+        h.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        h.count.fetch_add(1, Ordering::Relaxed);
+        h.sum_ns.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Cumulative histograms of the command codes seen so far
+    fn snapshot(&self) -> Vec<CommandLatency> {
+        self.codes.iter().enumerate()
+            .filter(|(_, h)| h.count.load(Ordering::Relaxed) > 0)
+            .map(|(cmd, h)| {
+                let mut cumulative = 0;
+                let buckets = LATENCY_BUCKETS_US.iter().zip(&h.buckets)
+                    .map(|(&le_us, n)| {
+                        cumulative += n.load(Ordering::Relaxed);
+                        LatencyBucket { le_us, count: cumulative }
+                    })
+                    .collect();
+                CommandLatency {
+                    cmd: cmd as u8,
+                    name: command_name(cmd as u32).unwrap_or("unknown").into(),
+                    count: h.count.load(Ordering::Relaxed),
+                    sum_us: h.sum_ns.load(Ordering::Relaxed) as f64 / 1000.0,
+                    buckets,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct CommandStats {
+    pub commands: AtomicU64,
+    pub errors: AtomicU64,
+    pub timeouts: AtomicU64,
+    /// Timeouts since the last command that signalled DONE
+    pub consecutive_timeouts: AtomicU64,
+    pub arms: AtomicU64,
+    pub triggers: AtomicU64,
+    pub cache_hits: AtomicU64,
+    pub retries: AtomicU64,
+    pub latency: CommandLatencies,
+    /// Errors by STATUS ERR_CODE
+    pub error_causes: [AtomicU64; 16],
+    pub last_failure: Mutex<Option<CommandFailure>>,
+    /// Last observed triggered bit, for edge detection
+    pub triggered: AtomicBool,
+    /// Wall-clock time (Unix ms) of the last ARM, 0 if none
+    pub armed_at_ms: AtomicU64,
+    /// Wall-clock time (Unix ms) the trigger was first seen after that ARM, 0 if none
+    pub triggered_at_ms: AtomicU64,
+}
+
+impl CommandStats {
+    #[inline]
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a successful ARM; the next triggered bit counts as a new edge
+    pub fn armed(&self) {
+        Self::inc(&self.arms);
+        self.armed_at_ms.store(unix_ms(), Ordering::Relaxed);
+        self.triggered_at_ms.store(0, Ordering::Relaxed);
+        self.triggered.store(false, Ordering::Relaxed);
+    }
+
+    /// Record a triggered status bit; counts rising edges only
+    pub fn observe_triggered(&self, triggered: bool) {
+        if self.triggered.swap(triggered, Ordering::Relaxed) != triggered && triggered {
+            Self::inc(&self.triggers);
+            self.triggered_at_ms.store(unix_ms(), Ordering::Relaxed);
+        }
+    }
+
+    /// Wall-clock times of the last ARM and of its trigger
+    pub fn capture_times(&self) -> (Option<u64>, Option<u64>) {
+        let load = |t: &AtomicU64| Some(t.load(Ordering::Relaxed)).filter(|&ms| ms != 0);
+        (load(&self.armed_at_ms), load(&self.triggered_at_ms))
+    }
+
+    pub fn snapshot(&self) -> IlaStats {
+        IlaStats {
+            commands: self.commands.load(Ordering::Relaxed),
+            command_errors: self.errors.load(Ordering::Relaxed),
+            command_timeouts: self.timeouts.load(Ordering::Relaxed),
+            arms: self.arms.load(Ordering::Relaxed),
+            triggers: self.triggers.load(Ordering::Relaxed),
+            readout_cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            command_retries: self.retries.load(Ordering::Relaxed),
+            command_latency: self.latency.snapshot(),
+            command_error_causes: self.error_causes.iter().enumerate()
+                .map(|(code, n)| (error_cause(code as u32).to_string(), n.load(Ordering::Relaxed)))
+                .filter(|&(_, n)| n > 0)
+                .collect(),
+            last_command_error: self.last_failure.lock().clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
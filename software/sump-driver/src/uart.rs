@@ -0,0 +1,192 @@
+//! MesaBus over UART
+//!
+//! Reaches the SUMP3 core through BlackMesaLabs' MesaBus UART bridge, for
+//! FPGAs that expose the ILA only over a USB-UART with no AXI master. Packets
+//! are ASCII hex (technical reference section 8):
+//!
+//! - write: `FFF0 00 00 LL <addr> <data>`
+//! - read: `FFF0 00 01 08 <addr> <n>` -> `F0FE 00 LL <data>...`
+//!
+//! Combined with `LocalBusBackend`, the wrapper's command sequences run on
+//! the host instead of in `sump3_axi_wrapper.sv`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+
+use crate::local_bus::{LocalBus, LocalBusBackend};
+
+/// Default baud rate of the MesaBus UART
+pub const DEFAULT_BAUD: u32 = 921_600;
+
+/// Reply timeout per read
+const READ_TIMEOUT_MS: i32 = 1000;
+
+/// Wrapper `Backend` over a MesaBus UART
+pub type UartBackend = LocalBusBackend<MesaUart>;
+
+/// termios speed constant for a baud rate
+fn speed(baud: u32) -> Option<libc::speed_t> {
+    Some(match baud {
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        460800 => libc::B460800,
+        921600 => libc::B921600,
+        1_000_000 => libc::B1000000,
+        2_000_000 => libc::B2000000,
+        3_000_000 => libc::B3000000,
+        _ => return None,
+    })
+}
+
+fn open_tty(path: &str, baud: u32) -> io::Result<File> {
+    let speed = speed(baud).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("unsupported baud rate {}", baud))
+    })?;
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(path)?;
+
+    let fd = file.as_raw_fd();
+    // SAFETY: fd is an open descriptor owned by `file`; tio is fully
+    // initialized by tcgetattr before use.
+    unsafe {
+        let mut tio: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut tio) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut tio);
+        tio.c_cflag |= libc::CLOCAL | libc::CREAD;
+        libc::cfsetispeed(&mut tio, speed);
+        libc::cfsetospeed(&mut tio, speed);
+        if libc::tcsetattr(fd, libc::TCSANOW, &tio) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::tcflush(fd, libc::TCIOFLUSH);
+    }
+    Ok(file)
+}
+
+/// MesaBus local bus link on a tty device
+pub struct MesaUart {
+    path: String,
+    baud: u32,
+    tty: Option<File>,
+    reconnects: u64,
+}
+
+impl MesaUart {
+    pub fn open(path: &str, baud: u32) -> io::Result<Self> {
+        let tty = open_tty(path, baud)?;
+        tracing::info!("Opened MesaBus UART {} at {} baud", path, baud);
+        Ok(Self {
+            path: path.to_string(),
+            baud,
+            tty: Some(tty),
+            reconnects: 0,
+        })
+    }
+
+    fn tty(&mut self) -> io::Result<&mut File> {
+        if self.tty.is_none() {
+            self.tty = Some(open_tty(&self.path, self.baud)?);
+            self.reconnects += 1;
+            tracing::info!("Reopened MesaBus UART {}", self.path);
+        }
+        Ok(self.tty.as_mut().unwrap())
+    }
+
+    fn send(&mut self, subslot_cmd: u8, addr: u32, data: u32) -> io::Result<()> {
+        let packet = format!("\nFFF000{:02X}08{:08X}{:08X}\n", subslot_cmd, addr, data);
+        let result = self.tty().and_then(|tty| tty.write_all(packet.as_bytes()));
+        if result.is_err() {
+            // USB-UARTs disappear on replug; reopen on the next access
+            self.tty = None;
+        }
+        result
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let tty = self.tty()?;
+        let mut pfd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: pfd points to one valid pollfd for the duration of the call
+        let ready = unsafe { libc::poll(&mut pfd, 1, READ_TIMEOUT_MS) };
+        if ready < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if ready == 0 {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no MesaBus reply"));
+        }
+        let mut byte = [0u8; 1];
+        tty.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    /// Collect hex digits until a complete `F0FE` readback packet arrives
+    fn read_reply(&mut self) -> io::Result<u32> {
+        let mut hex = String::new();
+        loop {
+            let byte = self.read_byte()?;
+            if !byte.is_ascii_hexdigit() {
+                continue;
+            }
+            hex.push(byte.to_ascii_uppercase() as char);
+            let Some(start) = hex.find("F0FE") else {
+                continue;
+            };
+            // F0 FE <subslot> <len> <data>
+            let body = &hex[start + 4..];
+            if body.len() < 4 {
+                continue;
+            }
+            let len = usize::from_str_radix(&body[2..4], 16).unwrap_or(0);
+            if len < 4 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "short MesaBus readback"));
+            }
+            if body.len() >= 4 + 2 * len {
+                return u32::from_str_radix(&body[4..12], 16)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+        }
+    }
+}
+
+impl LocalBus for MesaUart {
+    fn write(&mut self, addr: u32, data: u32) -> io::Result<()> {
+        self.send(0x00, addr, data)
+    }
+
+    fn read(&mut self, addr: u32) -> io::Result<u32> {
+        // Drop anything left over from a timed-out reply
+        let fd = self.tty()?.as_raw_fd();
+        // SAFETY: fd belongs to the open tty
+        unsafe { libc::tcflush(fd, libc::TCIFLUSH) };
+        self.send(0x01, addr, 1)?;
+        let result = self.read_reply();
+        if let Err(e) = &result {
+            if e.kind() != io::ErrorKind::TimedOut && e.kind() != io::ErrorKind::InvalidData {
+                self.tty = None;
+            }
+        }
+        result
+    }
+
+    fn describe(&self) -> String {
+        format!("uart://{}@{}", self.path, self.baud)
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+}
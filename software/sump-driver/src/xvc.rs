@@ -0,0 +1,278 @@
+//! JTAG register backend over Xilinx Virtual Cable
+//!
+//! Drives the `rtl/sump3_jtag_bridge.sv` USER chain through an XVC server
+//! (`xvcServer_v1.0`: `getinfo:`, `settck:`, `shift:`), for boards that
+//! have JTAG before they have a working Linux AXI path. Each register access
+//! is one request scan followed by poll scans until the bridge reports DONE.
+//!
+//! The FPGA must be the only TAP on the cable. Vivado's hw_server is a
+//! client of XVC, not a server, so it can't be used as the transport.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::backend::Backend;
+
+/// Default XVC server port
+pub const DEFAULT_XVC_PORT: u16 = 2542;
+
+/// Bridge data register length (see `sump3_jtag_bridge.sv`)
+const DR_LEN: usize = 42;
+
+const DR_VALID: u64 = 1 << 0;
+const DR_WRITE: u64 = 1 << 1;
+const DR_DONE: u64  = 1 << 32;
+const DR_ERROR: u64 = 1 << 33;
+
+/// Poll scans before an access is reported as failed
+const MAX_POLLS: usize = 16;
+
+/// Per-transaction socket timeout
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// TAP and cable settings
+#[derive(Debug, Clone)]
+pub struct XvcConfig {
+    /// Instruction register length of the FPGA TAP (6 on 7-series)
+    pub ir_len: u32,
+    /// Instruction selecting the bridge's USER chain (USER1 = 0x02 on 7-series)
+    pub user_ir: u32,
+    /// Requested TCK period in ns
+    pub tck_period_ns: u32,
+}
+
+impl Default for XvcConfig {
+    fn default() -> Self {
+        Self {
+            ir_len: 6,
+            user_ir: 0x02,
+            tck_period_ns: 100,
+        }
+    }
+}
+
+impl XvcConfig {
+    /// Parse `ir=0x22&ir_len=6&tck_ns=100` style options over the defaults
+    pub fn parse_query(query: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(|| format!("missing value for '{}'", pair))?;
+            let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => value.parse(),
+            }
+            .map_err(|_| format!("invalid value for '{}': {}", key, value))?;
+            match key {
+                "ir" => config.user_ir = parsed,
+                "ir_len" if (1..=32).contains(&parsed) => config.ir_len = parsed,
+                "tck_ns" => config.tck_period_ns = parsed,
+                _ => return Err(format!("unknown or out-of-range option '{}'", pair)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// TMS/TDI bit vectors for one `shift:` command
+#[derive(Default)]
+struct Scan {
+    tms: Vec<bool>,
+    tdi: Vec<bool>,
+}
+
+impl Scan {
+    fn clock(&mut self, tms: bool, tdi: bool) {
+        self.tms.push(tms);
+        self.tdi.push(tdi);
+    }
+
+    fn tms(&mut self, bits: &[bool]) {
+        for &tms in bits {
+            self.clock(tms, false);
+        }
+    }
+
+    /// Shift `len` bits of `value` from Shift-xR, leaving the TAP in Exit1-xR.
+    /// Returns the clock index of the first shifted bit.
+    fn shift(&mut self, value: u64, len: usize) -> usize {
+        let start = self.tms.len();
+        for i in 0..len {
+            self.clock(i == len - 1, (value >> i) & 1 != 0);
+        }
+        start
+    }
+
+    /// Test-Logic-Reset, then Run-Test/Idle
+    fn reset(&mut self) {
+        self.tms(&[true, true, true, true, true, false]);
+    }
+
+    /// Run-Test/Idle -> IR scan -> Run-Test/Idle
+    fn ir(&mut self, value: u32, len: u32) {
+        self.tms(&[true, true, false, false]);
+        self.shift(value as u64, len as usize);
+        self.tms(&[true, false]);
+    }
+
+    /// Run-Test/Idle -> DR scan -> Run-Test/Idle; returns the TDO offset
+    fn dr(&mut self, value: u64, len: usize) -> usize {
+        self.tms(&[true, false, false]);
+        let start = self.shift(value, len);
+        self.tms(&[true, false]);
+        start
+    }
+
+    fn pack(bits: &[bool]) -> Vec<u8> {
+        let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+        for (i, _) in bits.iter().enumerate().filter(|(_, &b)| b) {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+        bytes
+    }
+}
+
+/// `Backend` that reaches the wrapper through the JTAG bridge over XVC
+pub struct XvcBackend {
+    addr: String,
+    config: XvcConfig,
+    stream: Option<TcpStream>,
+    reconnects: u64,
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl XvcBackend {
+    /// Connect to an XVC server at `host:port`, reset the TAP and select
+    /// the bridge's USER chain
+    pub fn connect(addr: &str, config: XvcConfig) -> io::Result<Self> {
+        let mut backend = Self {
+            addr: addr.to_string(),
+            config,
+            stream: None,
+            reconnects: 0,
+        };
+        backend.open()?;
+        tracing::info!("Connected to XVC server at {} (IR 0x{:X}/{})", addr, backend.config.user_ir, backend.config.ir_len);
+        Ok(backend)
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        let mut stream = TcpStream::connect(&self.addr)?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        stream.write_all(b"getinfo:")?;
+        let mut info = Vec::new();
+        let mut byte = [0u8; 1];
+        while byte[0] != b'\n' && info.len() < 64 {
+            stream.read_exact(&mut byte)?;
+            info.push(byte[0]);
+        }
+        let info = String::from_utf8_lossy(&info);
+        if !info.starts_with("xvcServer_v1") {
+            return Err(invalid(format!("unexpected XVC getinfo reply '{}'", info.trim())));
+        }
+
+        stream.write_all(b"settck:")?;
+        stream.write_all(&self.config.tck_period_ns.to_le_bytes())?;
+        let mut period = [0u8; 4];
+        stream.read_exact(&mut period)?;
+
+        self.stream = Some(stream);
+        let mut scan = Scan::default();
+        scan.reset();
+        scan.ir(self.config.user_ir, self.config.ir_len);
+        if let Err(e) = self.run(&scan) {
+            self.stream = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Send one `shift:` command and return the TDO bits
+    fn run(&mut self, scan: &Scan) -> io::Result<Vec<bool>> {
+        let stream = self.stream.as_mut().ok_or_else(|| invalid("not connected".into()))?;
+        let bits = scan.tms.len();
+        stream.write_all(b"shift:")?;
+        stream.write_all(&(bits as u32).to_le_bytes())?;
+        stream.write_all(&Scan::pack(&scan.tms))?;
+        stream.write_all(&Scan::pack(&scan.tdi))?;
+
+        let mut tdo = vec![0u8; bits.div_ceil(8)];
+        stream.read_exact(&mut tdo)?;
+        Ok((0..bits).map(|i| tdo[i / 8] & (1 << (i % 8)) != 0).collect())
+    }
+
+    /// Shift one bridge DR value and return the captured one
+    fn scan_dr(&mut self, value: u64) -> io::Result<u64> {
+        if self.stream.is_none() {
+            self.open()?;
+            self.reconnects += 1;
+            tracing::info!("Reconnected to XVC server at {}", self.addr);
+        }
+        let mut scan = Scan::default();
+        let start = scan.dr(value, DR_LEN);
+        let result = self.run(&scan).map(|tdo| {
+            tdo[start..start + DR_LEN]
+                .iter()
+                .enumerate()
+                .fold(0u64, |acc, (i, &b)| acc | ((b as u64) << i))
+        });
+        if result.is_err() {
+            // Drop the connection; the next access reconnects
+            self.stream = None;
+        }
+        result
+    }
+
+    /// Issue a request and poll until the bridge reports completion
+    fn access(&mut self, request: u64) -> io::Result<u32> {
+        self.scan_dr(request)?;
+        for _ in 0..MAX_POLLS {
+            let captured = self.scan_dr(0)?;
+            if captured & DR_DONE != 0 {
+                if captured & DR_ERROR != 0 {
+                    return Err(invalid("AXI error response".into()));
+                }
+                return Ok(captured as u32);
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::TimedOut, "JTAG bridge did not complete"))
+    }
+}
+
+impl Backend for XvcBackend {
+    fn read32(&mut self, offset: usize) -> Option<u32> {
+        if offset > 0xFF {
+            return None;
+        }
+        let request = DR_VALID | ((offset as u64) << 2);
+        // Reads are side-effect free, so retry once on a fresh connection
+        self.access(request)
+            .or_else(|_| self.access(request))
+            .map_err(|e| tracing::warn!("XVC read 0x{:02X} failed: {}", offset, e))
+            .ok()
+    }
+
+    fn write32(&mut self, offset: usize, value: u32) -> bool {
+        if offset > 0xFF {
+            return false;
+        }
+        let request = DR_VALID | DR_WRITE | ((offset as u64) << 2) | ((value as u64) << 10);
+        self.access(request)
+            .map_err(|e| tracing::warn!("XVC write 0x{:02X} failed: {}", offset, e))
+            .is_ok()
+    }
+
+    fn describe(&self) -> String {
+        format!("xvc://{}", self.addr)
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+}
@@ -0,0 +1,77 @@
+//! Register transcripts recorded from the simulated wrapper (`sump_driver::record`)
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sump_driver::record::{parse_transcript, Access, RecordingBackend, ReplayBackend};
+use sump_driver::sim::SimBackend;
+use sump_driver::*;
+
+fn transcript(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("sump-transcript-{}-{}.txt", std::process::id(), name))
+}
+
+fn ila(backend: Box<dyn Backend>) -> Ila {
+    let ila = Ila::with_backend(backend, 0x43C2_0000);
+    ila.set_retry_policy(RetryPolicy { backoff: Duration::ZERO, ..RetryPolicy::default() });
+    ila
+}
+
+/// Enumerate, arm and read a capture from a one-pod sim, recording to `path`
+fn record_session(path: &Path) -> (String, Vec<(u8, u32, u32)>) {
+    let sim = SimBackend::single_pod();
+    sim.sim().hubs[0].pods[0].set_sample(0, 1, 7, 0x5A);
+    let recording = RecordingBackend::create(Box::new(sim.clone()), path.to_str().unwrap()).unwrap();
+    let ila = ila(Box::new(recording));
+
+    let info = format!("{:?}", ila.info().hubs);
+    ila.exec_cmd(CMD_ARM, 0, 0);
+    sim.trigger();
+    let capture = ila.read_capture(0, 0, 2);
+    (info, capture.samples.iter().map(|s| (s.code, s.timestamp, s.data)).collect())
+}
+
+#[test]
+fn replay_answers_like_the_recorded_wrapper() {
+    let path = transcript("replay");
+    let (info, samples) = record_session(&path);
+    assert_eq!(samples[0], (1, 7, 0x5A));
+
+    let replay = ReplayBackend::open(path.to_str().unwrap(), false).unwrap();
+    let ila = ila(Box::new(replay));
+    assert_eq!(format!("{:?}", ila.info().hubs), info);
+    ila.exec_cmd(CMD_ARM, 0, 0);
+    let capture = ila.read_capture(0, 0, 2);
+    let replayed: Vec<_> = capture.samples.iter().map(|s| (s.code, s.timestamp, s.data)).collect();
+    assert_eq!(replayed, samples);
+    assert!(ila.backend().contains("0 accesses left"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn replay_fails_from_the_first_divergence() {
+    let path = transcript("diverge");
+    record_session(&path);
+
+    // The session starts with a command, not a write of 0xFFFF to WDATA
+    let mut replay = ReplayBackend::open(path.to_str().unwrap(), false).unwrap();
+    assert!(!replay.write32(REG_WDATA, 0xFFFF));
+    assert!(replay.diverged());
+    assert_eq!(replay.remaining(), 0);
+    assert_eq!(replay.read32(REG_STATUS), None);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn transcripts_parse_line_by_line() {
+    let text = "# sump-driver transcript 1: test\n12 R 0x10 0x00000002\n\n15 R 0x1C -\n40 W 0xC 0x00000001 err\n";
+    assert_eq!(
+        parse_transcript(text),
+        Ok(vec![
+            (12, Access::Read { offset: 0x10, value: Some(2) }),
+            (15, Access::Read { offset: 0x1C, value: None }),
+            (40, Access::Write { offset: 0xC, value: 1, ok: false }),
+        ])
+    );
+    assert_eq!(parse_transcript("1 R 0x10\n").unwrap_err(), "line 1: cannot parse '1 R 0x10'");
+}
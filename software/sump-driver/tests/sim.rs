@@ -0,0 +1,383 @@
+//! Command layer against the simulated wrapper (`sump_driver::sim`)
+
+use std::time::Duration;
+
+use sump_driver::model::{ExtTriggerRouting, PodTrigger, TriggerConfig, TriggerSource};
+use sump_driver::sim::{Fault, SimBackend, SimHub, SimPod, CAP_ARMED};
+use sump_driver::*;
+
+fn ila(sim: &SimBackend) -> Ila {
+    let ila = Ila::with_backend(Box::new(sim.clone()), 0x43C2_0000);
+    ila.set_retry_policy(RetryPolicy { backoff: Duration::ZERO, ..RetryPolicy::default() });
+    ila
+}
+
+fn two_hubs() -> SimBackend {
+    let mut adc = SimPod::new("adc_iq", 10, 26, 20);
+    adc.triggerable = 0x0300_0000;
+    SimBackend::new(vec![
+        SimHub::new("core", 100, vec![SimPod::new("pod0", 8, 32, 16), SimPod::new("gpio", 6, 8, 12)]),
+        SimHub::new("adc", 250, vec![adc]),
+    ])
+}
+
+#[test]
+fn exec_cmd_waits_for_done() {
+    let sim = SimBackend::single_pod();
+    sim.sim().busy_polls = 50;
+    sim.sim().local.insert(CMD_RD_TICK_FREQ, 0x1234);
+    let ila = ila(&sim);
+
+    assert_eq!(ila.exec_cmd(CMD_RD_TICK_FREQ, 0, 0), Some(0x1234));
+    assert_eq!(ila.exec_cmd(CMD_WR_TICK_DIVISOR, 0, 7), Some(0));
+    assert_eq!(sim.sim().local.get(&CMD_WR_TICK_DIVISOR), Some(&7));
+    assert_eq!(sim.take_log(), vec![(CMD_RD_TICK_FREQ, 0, 0), (CMD_WR_TICK_DIVISOR, 0, 7)]);
+    assert_eq!(ila.stats().commands, 2);
+}
+
+#[test]
+fn failed_reads_are_retried() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    sim.fault(CMD_RD_POD_COUNT, Fault::Error(ERR_SERIAL_READ));
+
+    assert_eq!(ila.pod_count(0), Some(1));
+    assert_eq!(sim.take_log().len(), 2);
+    let stats = ila.stats();
+    assert_eq!((stats.command_errors, stats.command_retries), (1, 1));
+    assert_eq!(stats.last_command_error.and_then(|f| f.code), Some(ERR_SERIAL_READ as u8));
+}
+
+#[test]
+fn retries_give_up_after_the_policy() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    for _ in 0..3 {
+        sim.fault(CMD_RD_POD_COUNT, Fault::Error(ERR_SERIAL_READ));
+    }
+
+    assert_eq!(ila.pod_count(0), None);
+    assert_eq!(sim.take_log().len(), 3);
+}
+
+#[test]
+fn writes_and_unknown_commands_are_not_retried() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    sim.fault(CMD_WR_POD_REG, Fault::Error(ERR_SERIAL_WRITE));
+
+    assert!(!ila.write_pod_reg(0, 0, POD_REG_USER_CTRL, 1));
+    assert_eq!(ila.exec_cmd(0x1F, 0, 0), None);
+    assert_eq!(sim.take_log().len(), 2);
+    assert_eq!(ila.stats().command_retries, 0);
+}
+
+#[test]
+fn hung_command_times_out_and_aborts() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    ila.set_retry_policy(RetryPolicy { retries: 0, ..ila.retry_policy() });
+    sim.fault(CMD_RD_HUB_COUNT, Fault::Hang);
+
+    assert_eq!(ila.exec_cmd(CMD_RD_HUB_COUNT, 0, 0), None);
+    assert_eq!(ila.stats().command_timeouts, 1);
+    assert_eq!(ila.consecutive_timeouts(), 1);
+    // The abort leaves the state machine ready for the next command
+    assert_eq!(ila.exec_cmd(CMD_RD_HUB_COUNT, 0, 0), Some(1));
+    assert_eq!(ila.consecutive_timeouts(), 0);
+}
+
+#[test]
+fn enumerates_hubs_and_pods() {
+    let sim = two_hubs();
+    let info = ila(&sim).info();
+
+    assert!(info.connected);
+    assert_eq!((info.hw_id.as_str(), info.revision, info.hub_count), ("S\u{3}", 1, 2));
+    assert_eq!(info.hubs.len(), 2);
+
+    let core = &info.hubs[0];
+    assert_eq!((core.name.as_str(), core.freq_mhz, core.freq_hz, core.pod_count), ("core", 100, 100_000_000, 2));
+    let gpio = &core.pods[1];
+    assert_eq!((gpio.name.as_str(), gpio.ram_depth, gpio.data_bits, gpio.ts_bits), ("gpio", 64, 8, 12));
+
+    let adc = &info.hubs[1];
+    assert_eq!((adc.name.as_str(), adc.freq_hz), ("adc", 250_000_000));
+    let pod = &adc.pods[0];
+    assert_eq!((pod.ram_depth, pod.data_bits, pod.triggerable), (1024, 26, 0x0300_0000));
+    assert!(!pod.signals.is_empty());
+}
+
+#[test]
+fn no_sump3_wrapper_means_no_hubs() {
+    let sim = SimBackend::new(Vec::new());
+    let ila = ila(&sim);
+    assert_eq!(ila.hub_count(), 0);
+    assert!(ila.info().hubs.is_empty());
+}
+
+#[test]
+fn implausible_counts_stop_enumeration() {
+    let floating = SimBackend::new((0..255).map(|_| SimHub::new("hub", 100, Vec::new())).collect());
+    let info = ila(&floating).info();
+    assert!(info.connected);
+    assert_eq!((info.hub_count, info.hubs.len(), ila(&floating).hub_count()), (0, 0, 0));
+    assert!(info.unreliable.unwrap().contains("reports 255 hubs"));
+    assert!(floating.take_log().is_empty());
+
+    let pods = (0..100).map(|i| SimPod::new(&format!("pod{}", i), 4, 8, 8)).collect();
+    let sim = SimBackend::new(vec![
+        SimHub::new("core", 100, vec![SimPod::new("pod0", 8, 32, 16)]),
+        SimHub::new("bad", 100, pods),
+        SimHub::new("late", 100, vec![SimPod::new("pod0", 8, 32, 16)]),
+    ]);
+    let bad = ila(&sim);
+    let info = bad.info();
+    assert_eq!(info.hubs.len(), 1);
+    assert!(info.unreliable.unwrap().starts_with("Hub 1 reports 100 pods"));
+    assert_eq!(bad.pod_count(1), None);
+    assert!(!sim.take_log().iter().any(|&(_, addr, _)| addr >> 16 == 2));
+
+    // A pod whose RAM_CFG floats high claims 2^255 words of 65535 bits
+    let mut floating_pod = SimPod::new("pod0", 8, 32, 16);
+    floating_pod.ram_cfg = 0xFFFF_FFFF;
+    let sim = SimBackend::new(vec![
+        SimHub::new("core", 100, vec![SimPod::new("pod0", 8, 32, 16)]),
+        SimHub::new("bad", 100, vec![SimPod::new("pod0", 8, 32, 16), floating_pod]),
+        SimHub::new("late", 100, vec![SimPod::new("pod0", 8, 32, 16)]),
+    ]);
+    let info = ila(&sim).info();
+    assert_eq!(info.hubs.len(), 1);
+    assert!(info.unreliable.unwrap().starts_with("Hub 1 pod 1 RAM_CFG 0xFFFFFFFF reports 255 address bits"));
+    assert!(!sim.take_log().iter().any(|&(_, addr, _)| addr >> 16 == 2));
+}
+
+#[test]
+fn pods_too_wide_to_decode_are_warned_about() {
+    // The reference defaults: 38 timestamp bits; data goes up to 8192 bits
+    let sim = SimBackend::new(vec![
+        SimHub::new("core", 100, vec![SimPod::new("wide_ts", 8, 32, 38), SimPod::new("wide_data", 8, 8192, 16)]),
+        SimHub::new("late", 100, vec![SimPod::new("pod0", 8, 32, 16)]),
+    ]);
+    let info = ila(&sim).info();
+    assert_eq!(info.unreliable, None);
+    assert_eq!(info.hubs.len(), 2);
+    assert_eq!(info.hubs[0].pods.len(), 2);
+    let wide: Vec<&String> = info.rtl_warnings.iter().filter(|w| w.ends_with("its captures won't decode")).collect();
+    assert_eq!(wide.len(), 2);
+    assert!(wide[0].starts_with("Hub 0 pod 0: 32 data and 38 timestamp bits are unsupported"));
+    assert!(wide[1].starts_with("Hub 0 pod 1: 8192 data and 16 timestamp bits"));
+}
+
+#[test]
+fn serial_bus_errors_for_missing_hubs() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    assert_eq!(ila.read_pod_reg(3, 0, POD_REG_RAM_CFG), None);
+    assert_eq!(ila.stats().last_command_error.and_then(|f| f.code), Some(ERR_SERIAL_ADDR as u8));
+}
+
+#[test]
+fn trigger_programming_issues_the_plan() {
+    let sim = two_hubs();
+    let ila = ila(&sim);
+    let config = TriggerConfig {
+        trigger_type: "or_falling".into(),
+        trigger_bits: 0x10,
+        post_trigger: 100,
+        pods: vec![PodTrigger { hub: 0, pod: 1, bits: 0x03 }],
+        tick_divisor: Some(4),
+        ..TriggerConfig::default()
+    };
+
+    assert!(ila.validate_trigger(&config).is_ok());
+    sim.take_log();
+    assert_eq!(ila.program_trigger(&config), Ok(0x10));
+
+    let (_, plan) = trigger_plan(&config);
+    let issued: Vec<_> = plan.iter().map(|c| (c.cmd, c.addr, c.wdata)).collect();
+    assert_eq!(sim.take_log(), issued);
+    let model = sim.sim();
+    assert_eq!(model.local.get(&CMD_WR_TRIG_TYPE), Some(&TRIG_OR_FALLING));
+    assert_eq!(model.local.get(&CMD_WR_DIG_POST_TRIG), Some(&100));
+    assert_eq!(model.local.get(&CMD_WR_TICK_DIVISOR), Some(&4));
+    assert_eq!(model.hubs[0].pods[0].regs.get(&POD_REG_TRIG_EN), Some(&0x10));
+    assert_eq!(model.hubs[0].pods[1].regs.get(&POD_REG_TRIG_EN), Some(&0x03));
+}
+
+#[test]
+fn trigger_programming_stops_at_a_failed_command() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    sim.fault(CMD_WR_TRIG_DIG_FIELD, Fault::Error(ERR_LOCAL_READ));
+
+    assert_eq!(ila.program_trigger(&TriggerConfig::default()), Err("Failed to set trigger field"));
+    assert_eq!(sim.take_log().last().map(|c| c.0), Some(CMD_WR_TRIG_DIG_FIELD));
+}
+
+#[test]
+fn trigger_validation_checks_the_pods() {
+    let sim = two_hubs();
+    let ila = ila(&sim);
+    let config = TriggerConfig {
+        trigger_type: "pulse".into(),
+        post_trigger: 1000,
+        pods: vec![PodTrigger { hub: 1, pod: 0, bits: 0x1 }, PodTrigger { hub: 2, pod: 0, bits: 0x1 }],
+        ..TriggerConfig::default()
+    };
+
+    let errors = ila.validate_trigger(&config).unwrap_err();
+    let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(fields, ["trigger_type", "pods[0].bits", "pods[1]", "post_trigger"]);
+}
+
+#[test]
+fn arm_and_read_capture() {
+    let sim = SimBackend::single_pod();
+    {
+        let mut model = sim.sim();
+        let pod = &mut model.hubs[0].pods[0];
+        pod.set_sample(0, 1, 0, 0xA);
+        pod.set_sample(1, 2, 5, 0xB);
+        pod.set_sample(2, 1, 3, 0xC);
+    }
+    let ila = ila(&sim);
+
+    assert_eq!(ila.exec_cmd(CMD_ARM, 0, 0), Some(0));
+    assert_eq!(sim.sim().capture & CAP_ARMED, CAP_ARMED);
+    assert!(ila.capture_status().armed);
+    sim.trigger();
+
+    let capture = ila.read_capture(0, 0, 4);
+    assert!(capture.status.acquired);
+    let samples: Vec<_> = capture.samples.iter().map(|s| (s.code, s.timestamp, s.data)).collect();
+    assert_eq!(samples, [(1, 0, 0xA), (2, 5, 0xB), (1, 3, 0xC), (0, 0, 0)]);
+    assert_eq!(ila.sequence(), 1);
+}
+
+#[test]
+fn raw_dumps_decode_like_a_readout() {
+    let sim = SimBackend::single_pod();
+    {
+        let mut model = sim.sim();
+        let pod = &mut model.hubs[0].pods[0];
+        pod.set_sample(0, 1, 0, 0xA);
+        pod.set_sample(1, 2, 5, 0xB);
+    }
+    let ila = ila(&sim);
+    let (header, words) = ila.dump_ram(0, 0).unwrap();
+    let offline = capture_from_raw(&header, &words);
+    let read = ila.read_capture(0, 0, header.ram_depth);
+
+    let samples = |c: &model::CaptureData| c.samples.iter().map(|s| (s.address, s.code, s.timestamp, s.data)).collect::<Vec<_>>();
+    assert_eq!(samples(&offline), samples(&read));
+    assert_eq!((offline.hub, offline.pod, offline.data_bits, offline.ts_bits), (0, 0, read.data_bits, read.ts_bits));
+}
+
+#[test]
+fn dumps_read_like_the_whole_ring() {
+    let sim = SimBackend::single_pod();
+    {
+        let mut model = sim.sim();
+        let pod = &mut model.hubs[0].pods[0];
+        for addr in 0..256 {
+            pod.set_sample(addr, if addr == 200 { 2 } else { 1 }, addr, addr ^ 0x5A);
+        }
+    }
+    let ila = ila(&sim);
+    let (header, words) = ila.dump_ram(0, 0).unwrap();
+    let dumped = ila.capture_from_dump(&header, &words);
+    let read = ila.read_capture_all(0, 0);
+
+    let samples = |c: &model::CaptureData| c.samples.iter().map(|s| (s.address, s.code, s.timestamp, s.data)).collect::<Vec<_>>();
+    assert_eq!(samples(&dumped), samples(&read));
+    // No post-trigger setting is known, so the ring is read from the trigger
+    assert_eq!(dumped.samples[0].address, 200);
+    assert_eq!((dumped.trigger_address, dumped.sample_count), (read.trigger_address, read.sample_count));
+}
+
+#[test]
+fn implausible_ram_depth_is_rejected() {
+    let sim = SimBackend::single_pod();
+    sim.sim().hubs[0].pods[0].ram_cfg = 0xFFFF_FFFF;
+    let ila = ila(&sim);
+    assert!(ila.dump_ram(0, 0).unwrap_err().contains("depth of 2^255"));
+    assert_eq!(ila.get_pod_config(0, 0).2, 1);
+    assert!(ila.map_pod_ram(0, 0, 0x4000_0000).is_err());
+}
+
+#[test]
+fn implausible_timestamp_width_reads_without_overflow() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    {
+        let mut model = sim.sim();
+        let pod = &mut model.hubs[0].pods[0];
+        pod.set_sample(0, 1, 5, 0xA);
+        pod.ram_cfg = RAM_CFG_TS_BITS.set(pod.ram_cfg, 40);
+    }
+    let capture = ila.read_capture(0, 0, 4);
+    assert_eq!(capture.ts_bits, 40);
+    // The timestamp swallows the code bits, so every word reads as unwritten
+    assert!(capture.samples.iter().all(|s| s.code == 0));
+    assert!(capture.gaps.is_empty());
+    assert_eq!(ila.ram_fill(0, 0, Some(1)).unwrap().invalid, ila.get_pod_config(0, 0).2);
+}
+
+#[test]
+fn trigger_source_is_reported_with_the_capture() {
+    let sim = two_hubs();
+    let ila = ila(&sim);
+    assert_eq!(ila.exec_cmd(CMD_ARM, 0, 0), Some(0));
+    assert_eq!(ila.trigger_source(), None);
+
+    // Hub 0 names a pod with nothing latched; hub 1 its ADC pod
+    sim.sim().hubs[1].pods[0].regs.insert(POD_REG_TRIG_SRC, 0x0100_0000);
+    sim.trigger();
+    let source = Some(TriggerSource { hub: 1, pod: 0, bits: 0x0100_0000 });
+    assert_eq!(ila.trigger_source(), source);
+    assert_eq!(ila.read_capture_all(0, 1).trigger_source, source);
+
+    // Re-arming forgets it
+    sim.sim().hubs[1].pods[0].regs.clear();
+    assert_eq!(ila.exec_cmd(CMD_ARM, 0, 0), Some(0));
+    sim.trigger();
+    assert_eq!(ila.trigger_source(), None);
+}
+
+#[test]
+fn old_wrapper_revisions_refuse_missing_features() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    assert!(ila.info().rtl_warnings.is_empty());
+    assert!(ila.require(compat::Feature::ExtTriggerRouting).is_ok());
+
+    sim.sim().revision = 0;
+    let info = ila.info();
+    assert!(info.ext_trigger.is_none());
+    assert!(info.rtl_warnings[0].contains("revision 0 is untested"));
+    let message = ila.require(compat::Feature::ExtTriggerRouting).unwrap_err();
+    assert!(message.contains("needs wrapper RTL revision 1"), "{}", message);
+
+    let routing = ExtTriggerRouting { source: "in0".into(), output_enable: false };
+    let errors = ila.set_ext_trigger_routing(&routing).unwrap_err();
+    assert_eq!(errors[0].message, message);
+    let config = TriggerConfig { trigger_type: "external".into(), ..TriggerConfig::default() };
+    let fields: Vec<_> = ila.validate_trigger(&config).unwrap_err().into_iter().map(|e| e.field).collect();
+    assert_eq!(fields, ["trigger_type"]);
+
+    // No trigger source read from a wrapper without the latches
+    sim.sim().hubs[0].pods[0].regs.insert(POD_REG_TRIG_SRC, 0x1);
+    assert_eq!(ila.exec_cmd(CMD_ARM, 0, 0), Some(0));
+    sim.trigger();
+    assert_eq!(ila.trigger_source(), None);
+}
+
+#[test]
+fn user_stim_read_modify_writes() {
+    let sim = SimBackend::single_pod();
+    let ila = ila(&sim);
+    assert_eq!(ila.write_user_stim(0, 0, 0xFF00, u32::MAX), Some(0xFF00));
+    assert_eq!(ila.write_user_stim(0, 0, 0x0011, 0x00FF), Some(0xFF11));
+    assert_eq!(ila.read_user_stim(0, 0), Some(0xFF11));
+}
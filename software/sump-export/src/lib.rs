@@ -0,0 +1,117 @@
+//! SUMP3 capture exporters
+//!
+//! The file formats `sump-server` serves from
+//! `/api/ila/capture/:hub/:pod/export/:format` and `sump-cli convert` writes
+//! offline. Each implements `Exporter` and is listed in `EXPORTERS`; a new
+//! format is one more `Exporter` in the list.
+//!
+//! Like `sump-model` this crate touches neither the OS nor the hardware.
+
+use std::io::{self, Write};
+
+use sump_model::{CaptureData, PodInfo, SignalInfo};
+
+pub mod npy;
+pub mod vcd;
+
+/// A capture read out in chronological order, with what the formats
+/// need to label and time it
+pub struct Export<'a> {
+    pub capture: &'a CaptureData,
+    /// Enumeration with the configured label
+    pub pod: &'a PodInfo,
+    pub hub_name: &'a str,
+    /// 0 if the hub doesn't report its clock
+    pub hub_hz: u64,
+    /// Sequence number of the capture (see `CaptureData::sequence`)
+    pub id: u64,
+}
+
+pub trait Exporter: Sync {
+    /// Path segment and listing name
+    fn name(&self) -> &str;
+    fn mime_type(&self) -> &str;
+    fn extension(&self) -> &str;
+    fn description(&self) -> &str;
+    /// Write the capture; `InvalidInput` if it can't be represented
+    fn write(&self, export: &Export, out: &mut dyn Write) -> io::Result<()>;
+}
+
+struct Vcd;
+
+impl Exporter for Vcd {
+    fn name(&self) -> &'static str { "vcd" }
+    fn mime_type(&self) -> &'static str { "text/x-vcd" }
+    fn extension(&self) -> &'static str { "vcd" }
+    fn description(&self) -> &'static str { "Value change dump of the pod's signals in the low 32 bits" }
+
+    fn write(&self, e: &Export, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(vcd::render_vcd(e.capture, e.pod, e.hub_name, e.hub_hz, e.id).as_bytes())
+    }
+}
+
+struct Csv;
+
+impl Exporter for Csv {
+    fn name(&self) -> &'static str { "csv" }
+    fn mime_type(&self) -> &'static str { "text/csv" }
+    fn extension(&self) -> &'static str { "csv" }
+    fn description(&self) -> &'static str { "One row per stored sample: cycles, time and each signal in decimal" }
+
+    fn write(&self, e: &Export, out: &mut dyn Write) -> io::Result<()> {
+        let data = [SignalInfo {
+            name: "data".into(),
+            bit_high: e.capture.data_bits.clamp(1, 32) - 1,
+            bit_low: 0,
+            signal_type: "vector".into(),
+            triggerable: false,
+        }];
+        let signals: Vec<&SignalInfo> = match e.pod.signals.iter().filter(|s| s.bit_high < 32).collect::<Vec<_>>() {
+            signals if signals.is_empty() => data.iter().collect(),
+            signals => signals,
+        };
+
+        let names: Vec<String> = signals.iter().map(|s| csv_field(&s.name)).collect();
+        writeln!(out, "cycles,time_ns,{}", names.join(","))?;
+        let written = e.capture.samples.iter().filter(|s| s.code != 0);
+        for (sample, cycles) in written.zip(e.capture.elapsed_cycles()) {
+            let time = if e.hub_hz == 0 { String::new() } else { format!("{:.3}", cycles as f64 * 1e9 / e.hub_hz as f64) };
+            let values: Vec<String> = signals.iter().map(|s| {
+                let width = s.bit_high - s.bit_low + 1;
+                let mask = if width >= 32 { u32::MAX } else { (1u32 << width) - 1 };
+                ((sample.data >> s.bit_low) & mask).to_string()
+            }).collect();
+            writeln!(out, "{},{},{}", cycles, time, values.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Quote a header field containing a separator or quote
+fn csv_field(name: &str) -> String {
+    if name.contains([',', '"', '\n']) {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        name.into()
+    }
+}
+
+struct Npz;
+
+impl Exporter for Npz {
+    fn name(&self) -> &'static str { "npz" }
+    fn mime_type(&self) -> &'static str { "application/zip" }
+    fn extension(&self) -> &'static str { "npz" }
+    fn description(&self) -> &'static str { "NumPy arrays of the pod's analog fields (see /capture/:hub/:pod/npz)" }
+
+    fn write(&self, e: &Export, out: &mut dyn Write) -> io::Result<()> {
+        let analog = npy::analog_signals(e.pod);
+        if analog.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the pod has no analog fields in its low 32 bits"));
+        }
+        out.write_all(&npy::npz(e.capture, &analog, e.hub_hz))
+    }
+}
+
+/// Built-in formats, in listing order
+pub static EXPORTERS: &[&dyn Exporter] = &[&Vcd, &Csv, &Npz];
@@ -0,0 +1,134 @@
+//! NumPy encoding of analog fields
+//!
+//! Analog signals as `.npy` arrays, or bundled with the sample times in an
+//! uncompressed `.npz` that `numpy.load` reads.
+
+use sump_model::{CaptureData, PodInfo, SignalInfo};
+
+/// One `.npy` file: little-endian values of a NumPy dtype
+struct Array {
+    descr: &'static str,
+    shape: Option<usize>,
+    data: Vec<u8>,
+}
+
+impl Array {
+    fn vector<T, const N: usize>(descr: &'static str, values: &[T], to_le: fn(&T) -> [u8; N]) -> Self {
+        Self { descr, shape: Some(values.len()), data: values.iter().flat_map(to_le).collect() }
+    }
+
+    fn scalar_f64(value: f64) -> Self {
+        Self { descr: "<f8", shape: None, data: value.to_le_bytes().to_vec() }
+    }
+
+    /// NPY format 1.0: magic, header length, then a dict padded so the data
+    /// starts on a 64-byte boundary
+    fn encode(&self) -> Vec<u8> {
+        let shape = self.shape.map_or("()".to_string(), |n| format!("({},)", n));
+        let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", self.descr, shape);
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+        header.push('\n');
+
+        let mut out = b"\x93NUMPY\x01\x00".to_vec();
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(&self.data);
+        out
+    }
+}
+
+/// Decode an analog field; fields of 12 bits or more are two's complement,
+/// as in the web UI
+fn analog_array(capture: &CaptureData, signal: &SignalInfo) -> Array {
+    let width = (signal.bit_high - signal.bit_low + 1) as u32;
+    let mask = if width >= 32 { u32::MAX } else { (1u32 << width) - 1 };
+    let raw = capture.samples.iter()
+        .filter(|s| s.code != 0)
+        .map(move |s| (s.data >> signal.bit_low) & mask);
+    if width >= 12 {
+        let shift = 32 - width;
+        let values: Vec<i32> = raw.map(|v| ((v << shift) as i32) >> shift).collect();
+        if width <= 16 {
+            let values: Vec<i16> = values.iter().map(|&v| v as i16).collect();
+            Array::vector("<i2", &values, |v| v.to_le_bytes())
+        } else {
+            Array::vector("<i4", &values, |v| v.to_le_bytes())
+        }
+    } else {
+        let values: Vec<u16> = raw.map(|v| v as u16).collect();
+        Array::vector("<u2", &values, |v| v.to_le_bytes())
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |c, _| if c & 1 != 0 { (c >> 1) ^ 0xEDB8_8320 } else { c >> 1 })
+    })
+}
+
+/// Uncompressed (stored) zip archive, which `numpy.load` reads as `.npz`
+fn zip_stored(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in files {
+        let offset = out.len() as u32;
+        let crc = crc32(data);
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        common.extend_from_slice(&0u16.to_le_bytes()); // flags
+        common.extend_from_slice(&0u16.to_le_bytes()); // stored
+        common.extend_from_slice(&0u32.to_le_bytes()); // DOS time/date
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // extra length
+
+        out.extend_from_slice(&0x0403_4B50u32.to_le_bytes());
+        out.extend_from_slice(&common);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        central.extend_from_slice(&0x0201_4B50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central.extend_from_slice(&common);
+        central.extend_from_slice(&[0; 6]); // comment length, disk, internal attributes
+        central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4B50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]); // disk numbers
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    out
+}
+
+/// Analog fields in the low 32 bits, which the sample data covers
+pub fn analog_signals(pod: &PodInfo) -> Vec<SignalInfo> {
+    pod.signals.iter()
+        .filter(|sig| sig.signal_type == "analog" && sig.bit_high < 32)
+        .cloned()
+        .collect()
+}
+
+/// `.npz` with `cycles`, `sample_rate_hz` and one array per analog field
+pub fn npz(capture: &CaptureData, analog: &[SignalInfo], sample_rate_hz: u64) -> Vec<u8> {
+    let mut files = vec![
+        ("cycles.npy".to_string(), Array::vector("<u8", &capture.elapsed_cycles(), |v| v.to_le_bytes()).encode()),
+        ("sample_rate_hz.npy".to_string(), Array::scalar_f64(sample_rate_hz as f64).encode()),
+    ];
+    files.extend(analog.iter().map(|sig| (format!("{}.npy", sig.name), analog_array(capture, sig).encode())));
+    zip_stored(&files)
+}
+
+/// One analog field as `.npy`
+pub fn npy(capture: &CaptureData, signal: &SignalInfo) -> Vec<u8> {
+    analog_array(capture, signal).encode()
+}
@@ -0,0 +1,323 @@
+//! VCD rendering
+//!
+//! Captures as value change dumps, timed in picoseconds of the hub clock:
+//! one pod (`render_vcd`), several pods on one timebase
+//! (`render_merged_vcd`), or sample by sample with `VcdWriter` for
+//! captures too long to hold at once.
+
+use std::fmt::Write;
+
+use sump_model::{CaptureData, PodInfo, SignalInfo};
+
+/// Hub clock assumed when the hub doesn't report one
+const FALLBACK_HZ: u64 = 100_000_000;
+
+/// VCD identifier code for the `n`th variable
+fn var_id(mut n: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (n % 94) as u8) as char);
+        n /= 94;
+        if n == 0 {
+            return id;
+        }
+        n -= 1;
+    }
+}
+
+/// VCD references end at whitespace
+fn vcd_name(name: &str, fallback: &str) -> String {
+    let name = name.trim();
+    if name.is_empty() {
+        return fallback.into();
+    }
+    name.chars().map(|c| if c.is_ascii_graphic() { c } else { '_' }).collect()
+}
+
+fn vcd_value(value: u32, width: u16, id: &str) -> String {
+    if width == 1 {
+        format!("{}{}\n", value, id)
+    } else {
+        format!("b{:b} {}\n", value, id)
+    }
+}
+
+fn extract(s: &SignalInfo, data: u32) -> u32 {
+    let width = s.bit_high - s.bit_low + 1;
+    let mask = if width >= 32 { u32::MAX } else { (1u32 << width) - 1 };
+    (data >> s.bit_low) & mask
+}
+
+/// Clock period of a hub in picoseconds
+fn period_ps(hub_hz: u64) -> f64 {
+    1e12 / if hub_hz == 0 { FALLBACK_HZ } else { hub_hz } as f64
+}
+
+/// Incremental VCD writer for one pod's signals in its low 32 bits: the
+/// variable definitions, then the values that changed at each sample.
+/// Holds only the last value of each signal, so a capture of any length can
+/// be written in chunks (see `deep`).
+pub struct VcdWriter {
+    signals: Vec<SignalInfo>,
+    ids: Vec<String>,
+    /// Signals above bit 31, left out
+    omitted: usize,
+    period_ps: f64,
+    prev: Option<Vec<u32>>,
+}
+
+impl VcdWriter {
+    pub fn new(pod: &PodInfo, data_bits: u16, hub_hz: u64) -> Self {
+        let signals: Vec<SignalInfo> = if pod.signals.is_empty() {
+            // Sample data is the pod's low 32 bits
+            let data_bits = data_bits.clamp(1, 32);
+            vec![SignalInfo {
+                name: format!("data[{}:0]", data_bits - 1),
+                bit_high: data_bits - 1,
+                bit_low: 0,
+                signal_type: "vector".into(),
+                triggerable: false,
+            }]
+        } else {
+            pod.signals.iter().filter(|s| s.bit_high < 32).cloned().collect()
+        };
+        Self {
+            ids: (0..signals.len()).map(var_id).collect(),
+            omitted: pod.signals.len().saturating_sub(signals.len()),
+            period_ps: period_ps(hub_hz),
+            signals,
+            prev: None,
+        }
+    }
+
+    /// Number the variables from `first`, for several writers in one VCD
+    pub fn ids_from(mut self, first: usize) -> Self {
+        self.ids = (first..first + self.signals.len()).map(var_id).collect();
+        self
+    }
+
+    pub fn var_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Header comments shared by every VCD, then `$timescale` through
+    /// `$enddefinitions`
+    pub fn definitions(&self, vcd: &mut String, pod: &PodInfo, hub: u8) {
+        if let Some(description) = &pod.description {
+            let _ = writeln!(vcd, "$comment\n   {}\n$end", description);
+        }
+        if self.omitted > 0 {
+            let _ = writeln!(vcd, "$comment\n   {} signals above bit 31 omitted\n$end", self.omitted);
+        }
+        vcd.push_str("$timescale 1ps $end\n");
+        self.scope(vcd, pod, hub);
+        vcd.push_str("$enddefinitions $end\n");
+    }
+
+    /// The pod's `$scope` with its variables
+    pub fn scope(&self, vcd: &mut String, pod: &PodInfo, hub: u8) {
+        let scope = pod.display_name.as_deref().unwrap_or(&pod.name);
+        let _ = writeln!(vcd, "$scope module {} $end", vcd_name(scope, &format!("hub{}_pod{}", hub, pod.index)));
+        for (s, id) in self.signals.iter().zip(&self.ids) {
+            let _ = writeln!(vcd, "$var wire {} {} {} $end", s.bit_high - s.bit_low + 1, id, vcd_name(&s.name, "data"));
+        }
+        vcd.push_str("$upscope $end\n");
+    }
+
+    /// Every variable as unknown (`x`)
+    pub fn undefined(&self, vcd: &mut String) {
+        for (s, id) in self.signals.iter().zip(&self.ids) {
+            if s.bit_high == s.bit_low {
+                let _ = writeln!(vcd, "x{}", id);
+            } else {
+                let _ = writeln!(vcd, "bx {}", id);
+            }
+        }
+    }
+
+    /// Picoseconds `cycles` hub clocks after the first sample
+    pub fn time_ps(&self, cycles: u64) -> u64 {
+        (cycles as f64 * self.period_ps).round() as u64
+    }
+
+    /// Values of the signals that changed since the last sample, all of
+    /// them at the first
+    pub fn changes(&mut self, data: u32) -> String {
+        let values: Vec<u32> = self.signals.iter().map(|s| extract(s, data)).collect();
+        let mut changes = String::new();
+        for (i, ((s, id), value)) in self.signals.iter().zip(&self.ids).zip(&values).enumerate() {
+            if self.prev.as_ref().is_none_or(|prev| prev[i] != *value) {
+                changes.push_str(&vcd_value(*value, s.bit_high - s.bit_low + 1, id));
+            }
+        }
+        self.prev = Some(values);
+        changes
+    }
+
+    /// Whether no sample was written yet
+    pub fn is_first(&self) -> bool {
+        self.prev.is_none()
+    }
+
+    /// A sample `cycles` hub clocks after the first: the initial values at
+    /// time 0, then only the signals that changed
+    pub fn sample(&mut self, vcd: &mut String, cycles: u64, data: u32) {
+        let first = self.is_first();
+        let changes = self.changes(data);
+        if first {
+            vcd.push_str("#0\n$dumpvars\n");
+            vcd.push_str(&changes);
+            vcd.push_str("$end\n");
+        } else if !changes.is_empty() {
+            let _ = writeln!(vcd, "#{}", self.time_ps(cycles));
+            vcd.push_str(&changes);
+        }
+    }
+}
+
+/// Render a capture read in chronological order. Timestamps are unwrapped
+/// across counter rollovers; times are in picoseconds like the web UI export.
+pub fn render_vcd(capture: &CaptureData, pod: &PodInfo, hub_name: &str, hub_hz: u64, id: u64) -> String {
+    let mut writer = VcdWriter::new(pod, capture.data_bits, hub_hz);
+
+    let mut vcd = String::new();
+    if let Some(date) = capture.triggered_at.as_ref().or(capture.armed_at.as_ref()) {
+        let _ = writeln!(vcd, "$date\n   {}\n$end", date);
+    }
+    let _ = writeln!(vcd, "$version\n   SUMP3 ILA capture {} - {} hub {} pod {}\n$end", id, hub_name, capture.hub, capture.pod);
+    if let Some(ms) = capture.triggered_at_ms {
+        let _ = writeln!(vcd, "$comment\n   triggered at Unix ms {}\n$end", ms);
+    }
+    if let Some(design_id) = &capture.design_id {
+        let _ = writeln!(vcd, "$comment\n   design {}\n$end", design_id);
+    }
+    if let Some(t) = &capture.trigger_source {
+        let _ = writeln!(vcd, "$comment\n   triggered by hub {} pod {} bits 0x{:08X}\n$end", t.hub, t.pod, t.bits);
+    }
+    writer.definitions(&mut vcd, pod, capture.hub);
+
+    let written = capture.samples.iter().enumerate().filter(|(_, s)| s.code != 0);
+    for ((index, sample), cycles) in written.zip(capture.elapsed_cycles()) {
+        if !writer.is_first() {
+            if let Some(gap) = capture.gaps.iter().find(|g| g.index as usize == index) {
+                let _ = writeln!(vcd, "$comment\n   lost RLE data before address {} ({})\n$end", gap.address, gap.reason.as_str());
+            }
+            if sample.code == 2 {
+                let _ = writeln!(vcd, "$comment\n   trigger at address {}\n$end", sample.address);
+            }
+        }
+        writer.sample(&mut vcd, cycles, sample.data);
+    }
+    vcd
+}
+
+/// One pod of a merged VCD
+pub struct MergedPod<'a> {
+    pub capture: &'a CaptureData,
+    /// Enumeration with the configured label
+    pub pod: &'a PodInfo,
+    pub hub_name: &'a str,
+    /// 0 if the hub doesn't report its clock
+    pub hub_hz: u64,
+}
+
+/// Render the captures of several pods on one timebase: each pod's samples
+/// in picoseconds of its hub clock, shifted so the trigger samples (the
+/// first sample of a pod without one) coincide. Variables are unknown
+/// until their pod's first sample. `pods` are in hub order.
+pub fn render_merged_vcd(pods: &[MergedPod], id: u64) -> String {
+    let mut writers = Vec::with_capacity(pods.len());
+    let mut first_id = 0;
+    for p in pods {
+        let writer = VcdWriter::new(p.pod, p.capture.data_bits, p.hub_hz).ids_from(first_id);
+        first_id += writer.var_count();
+        writers.push(writer);
+    }
+
+    // (time, pod, sample index, data), in time order
+    let mut events = Vec::new();
+    let cycles: Vec<Vec<u64>> = pods.iter().map(|p| p.capture.elapsed_cycles()).collect();
+    let trigger_ps: Vec<u64> = pods.iter().zip(&cycles).zip(&writers).map(|((p, cycles), writer)| {
+        let written = p.capture.samples.iter().filter(|s| s.code != 0);
+        let trigger = written.zip(cycles).find(|(s, _)| s.code == 2).map_or(0, |(_, &c)| c);
+        writer.time_ps(trigger)
+    }).collect();
+    let shift = trigger_ps.iter().copied().max().unwrap_or(0);
+    for (i, (p, cycles)) in pods.iter().zip(&cycles).enumerate() {
+        let written = p.capture.samples.iter().enumerate().filter(|(_, s)| s.code != 0);
+        for ((index, sample), &c) in written.zip(cycles) {
+            events.push((shift - trigger_ps[i] + writers[i].time_ps(c), i, index, sample.data));
+        }
+    }
+    events.sort_by_key(|&(time, i, _, _)| (time, i));
+
+    let mut vcd = String::new();
+    let first = pods.first().map(|p| p.capture);
+    if let Some(date) = first.and_then(|c| c.triggered_at.as_ref().or(c.armed_at.as_ref())) {
+        let _ = writeln!(vcd, "$date\n   {}\n$end", date);
+    }
+    let _ = writeln!(vcd, "$version\n   SUMP3 ILA capture {} - {} pods merged\n$end", id, pods.len());
+    if let Some(design_id) = first.and_then(|c| c.design_id.as_ref()) {
+        let _ = writeln!(vcd, "$comment\n   design {}\n$end", design_id);
+    }
+    if let Some(t) = first.and_then(|c| c.trigger_source.as_ref()) {
+        let _ = writeln!(vcd, "$comment\n   triggered by hub {} pod {} bits 0x{:08X}\n$end", t.hub, t.pod, t.bits);
+    }
+    let mut hubs: Vec<&MergedPod> = pods.iter().collect();
+    hubs.dedup_by_key(|p| p.capture.hub);
+    for p in hubs {
+        let clock = match p.hub_hz {
+            0 => format!("clock not reported, {} Hz assumed", FALLBACK_HZ),
+            hz => format!("{} Hz", hz),
+        };
+        let _ = writeln!(vcd, "$comment\n   hub {} ({}): {}, {:.1} ps per cycle\n$end", p.capture.hub, p.hub_name, clock, period_ps(p.hub_hz));
+    }
+    vcd.push_str("$comment\n   pods aligned on their trigger samples\n$end\n");
+    vcd.push_str("$timescale 1ps $end\n");
+    let mut hub = None;
+    for (p, writer) in pods.iter().zip(&writers) {
+        if hub != Some(p.capture.hub) {
+            if hub.is_some() {
+                vcd.push_str("$upscope $end\n");
+            }
+            let _ = writeln!(vcd, "$scope module {} $end", vcd_name(p.hub_name, &format!("hub{}", p.capture.hub)));
+            hub = Some(p.capture.hub);
+        }
+        writer.scope(&mut vcd, p.pod, p.capture.hub);
+    }
+    if hub.is_some() {
+        vcd.push_str("$upscope $end\n");
+    }
+    vcd.push_str("$enddefinitions $end\n#0\n$dumpvars\n");
+    for writer in &writers {
+        writer.undefined(&mut vcd);
+    }
+    vcd.push_str("$end\n");
+
+    let mut at = 0;
+    for (time, i, index, data) in events {
+        let capture = pods[i].capture;
+        let mut notes = String::new();
+        if !writers[i].is_first() {
+            if let Some(gap) = capture.gaps.iter().find(|g| g.index as usize == index) {
+                let _ = writeln!(notes, "$comment\n   hub {} pod {}: lost RLE data before address {} ({})\n$end",
+                    capture.hub, capture.pod, gap.address, gap.reason.as_str());
+            }
+        }
+        if capture.samples[index].code == 2 {
+            let _ = writeln!(notes, "$comment\n   hub {} pod {}: trigger at address {}\n$end",
+                capture.hub, capture.pod, capture.samples[index].address);
+        }
+        let changes = writers[i].changes(data);
+        if notes.is_empty() && changes.is_empty() {
+            continue;
+        }
+        if time != at {
+            let _ = writeln!(vcd, "#{}", time);
+            at = time;
+        }
+        vcd.push_str(&notes);
+        vcd.push_str(&changes);
+    }
+    vcd
+}
@@ -0,0 +1,1436 @@
+//! SUMP3 ILA API data structures
+//!
+//! Serde types returned by the driver, served as JSON by `sump-server` and
+//! parsed back by REST clients and the embedded Surfer frontend.
+//!
+//! This crate only depends on serde (plus schemars with the `schema`
+//! feature) and builds for `wasm32-unknown-unknown`, so the Surfer fork can
+//! depend on it directly (path or git dependency on `software/sump-model`)
+//! instead of mirroring these structs by hand.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+mod manifest;
+mod raw;
+#[cfg(feature = "schema")]
+pub mod schema;
+
+pub use manifest::{rfc3339_offset, rfc3339_utc, UnsupportedVersion, CAPTURE_SCHEMA_VERSION};
+pub use raw::{capture_from_raw, decode_rle, find_gaps, rle_code};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IlaInfo {
+    pub connected: bool,
+    pub hw_id: String,
+    pub revision: u8,
+    pub hub_count: u8,
+    pub is_armed: bool,
+    pub is_awake: bool,
+    pub base_addr: String,
+    pub hubs: Vec<HubInfo>,
+    /// `None` if the wrapper has no external trigger routing
+    #[serde(default)]
+    pub ext_trigger: Option<ExtTriggerRouting>,
+    /// Design-level hierarchy from `Settings.groups`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<PodGroup>,
+    /// FPGA design identification (`SUMP_DESIGN_ID`), if configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub design_id: Option<String>,
+    /// Why the server runs without hardware (degraded startup), while it
+    /// keeps trying to reconnect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Untested wrapper or pod RTL revisions, features the wrapper revision
+    /// lacks and pods too wide to decode (see `sump_driver::compat`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rtl_warnings: Vec<String>,
+    /// Why the enumeration is incomplete: HW_INFO or a hub reported an
+    /// implausible count, a pod RAM_CFG widths the RTL can't produce, or a
+    /// hub stopped answering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unreliable: Option<String>,
+}
+
+/// Wrapper external trigger routing (`GET/PUT /api/ila/ext-trigger/routing`);
+/// select the `external` trigger type to trigger from the chosen input
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExtTriggerRouting {
+    /// Input driving the core's trigger_in: `none`, `in0` or `in1`
+    pub source: String,
+    /// Forward the core's trigger_out to the `ext_trig_out` pin
+    #[serde(default)]
+    pub output_enable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HubInfo {
+    pub index: u8,
+    pub name: String,
+    /// Hub instance number (`CMD_RD_HUB_INSTANCE`); tells apart identical
+    /// hubs from a generate loop, unlike `index`, which is their position
+    #[serde(default)]
+    pub instance: u32,
+    /// Whole MHz only; use `freq_hz` for timing
+    pub freq_mhz: u32,
+    /// Hub clock including the fractional MHz field
+    #[serde(default)]
+    pub freq_hz: u64,
+    /// Pods on the hub, including any in `Settings.hidden_pods`
+    pub pod_count: u8,
+    pub pods: Vec<PodInfo>,
+    /// From `Settings.hub_labels`; `name` stays the RTL name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PodInfo {
+    pub index: u8,
+    pub name: String,
+    pub hw_rev: u8,
+    pub ram_depth: u32,
+    pub data_bits: u16,
+    pub ts_bits: u8,
+    pub triggerable: u32,
+    pub rle_disable: bool,
+    pub view_rom_en: bool,
+    pub view_mode: String,
+    pub signals: Vec<SignalInfo>,
+    /// RAM readout path: `serial` or `mapped@0x<addr>`
+    #[serde(default)]
+    pub readout: String,
+    /// From `Settings.pod_labels`; `name` stays the RTL name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SignalInfo {
+    pub name: String,
+    pub bit_high: u16,
+    pub bit_low: u16,
+    pub signal_type: String,
+    /// Some bit of the signal is in the pod's `triggerable` mask
+    #[serde(default)]
+    pub triggerable: bool,
+}
+
+impl SignalInfo {
+    /// The signal's bits within the low 32 (the trigger and sample width)
+    pub fn mask(&self) -> u32 {
+        (self.bit_low..=self.bit_high.min(31)).fold(0, |mask, bit| mask | 1 << bit)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RleSample {
+    pub address: u32,
+    pub code: u8,
+    pub timestamp: u32,
+    pub data: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CaptureStatus {
+    pub armed: bool,
+    pub pre_trigger: bool,
+    pub triggered: bool,
+    pub acquired: bool,
+    pub init_in_progress: bool,
+}
+
+impl CaptureStatus {
+    /// Decode the capture status bits returned by `CMD_RD_STATUS`
+    pub fn from_bits(status: u32) -> Self {
+        Self {
+            armed: (status & 0x01) != 0,
+            pre_trigger: (status & 0x02) != 0,
+            triggered: (status & 0x04) != 0,
+            acquired: (status & 0x08) != 0,
+            init_in_progress: (status & 0x10) != 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CaptureData {
+    /// Manifest version (see `CAPTURE_SCHEMA_VERSION`); 0 when absent
+    #[serde(default)]
+    pub schema_version: u32,
+    pub hub: u8,
+    pub pod: u8,
+    pub ts_bits: u8,
+    pub data_bits: u16,
+    pub status: CaptureStatus,
+    pub samples: Vec<RleSample>,
+    pub sample_count: u32,
+    /// Capture sequence number: increases with every ARM and continues
+    /// across server restarts
+    #[serde(default)]
+    pub sequence: u64,
+    /// Server wall-clock time (Unix ms) of the ARM that produced this capture
+    #[serde(default)]
+    pub armed_at_ms: Option<u64>,
+    /// Server wall-clock time (Unix ms) the trigger was detected; only as
+    /// precise as the status polling that noticed it
+    #[serde(default)]
+    pub triggered_at_ms: Option<u64>,
+    /// `armed_at_ms` as RFC 3339 in the server's time zone
+    #[serde(default)]
+    pub armed_at: Option<String>,
+    /// `triggered_at_ms` as RFC 3339 in the server's time zone
+    #[serde(default)]
+    pub triggered_at: Option<String>,
+    /// Correlation id set when this capture was read (see `Correlation`)
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+    /// `triggered_at_ms` minus the correlation `reference_ms`
+    #[serde(default)]
+    pub trigger_offset_ms: Option<i64>,
+    /// FPGA design the capture came from (see `IlaInfo::design_id`)
+    #[serde(default)]
+    pub design_id: Option<String>,
+    /// RAM address of the trigger sample, when a readout window located it
+    #[serde(default)]
+    pub trigger_address: Option<u32>,
+    /// Pod and bits that caused the trigger, where the hardware latched them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_source: Option<TriggerSource>,
+    /// Set when the readout stopped early; `samples` holds what was read
+    /// before the failing address
+    #[serde(default)]
+    pub readout_error: Option<ReadoutError>,
+    /// Places where RLE data was lost; the time or samples before
+    /// `samples[index]` can't be trusted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<SampleGap>,
+}
+
+/// What fired the trigger: the pod its hub reports (`CMD_RD_TRIG_SRC_POD`)
+/// and the bits that pod latched (`POD_REG_TRIG_SRC`)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TriggerSource {
+    pub hub: u8,
+    pub pod: u8,
+    /// Data bits whose condition matched
+    pub bits: u32,
+}
+
+/// Lost RLE data ahead of a sample
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SampleGap {
+    /// Index in `samples` of the first sample after the gap
+    pub index: u32,
+    pub address: u32,
+    pub reason: GapReason,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GapReason {
+    /// The timestamp counter hit its maximum, so the time since the
+    /// previous sample is unknown
+    TimestampSaturated,
+    /// Unwritten RAM words between written samples
+    Unwritten,
+}
+
+impl GapReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TimestampSaturated => "timestamp_saturated",
+            Self::Unwritten => "unwritten",
+        }
+    }
+}
+
+impl CaptureData {
+    /// Hub clock cycles from the first written sample to each written one,
+    /// unwrapping timestamp rollovers between consecutive samples
+    pub fn elapsed_cycles(&self) -> Vec<u64> {
+        let ts_mask = (1u64 << self.ts_bits) - 1;
+        let mut last = None;
+        let mut cycles = 0;
+        self.samples.iter()
+            .filter(|s| s.code != 0)
+            .map(|s| {
+                if let Some(last) = last {
+                    cycles += (s.timestamp as u64).wrapping_sub(last) & ts_mask;
+                }
+                last = Some(s.timestamp as u64);
+                cycles
+            })
+            .collect()
+    }
+}
+
+/// Summary of one completed capture as read out (`GET /api/ila/digests`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CaptureDigest {
+    /// Capture sequence number (see `CaptureData::sequence`); the same
+    /// capture read again keeps its id
+    pub capture_id: u64,
+    pub hub: u8,
+    pub pod: u8,
+    #[serde(default)]
+    pub triggered_at_ms: Option<u64>,
+    /// `triggered_at_ms` as RFC 3339
+    #[serde(default)]
+    pub triggered_at: Option<String>,
+    /// Written samples summarized
+    pub samples: u32,
+    /// Changes per data bit (bits 0..31)
+    pub toggles: Vec<u32>,
+    /// Data bits that changed at least once
+    pub active_bits: u32,
+    /// Bits whose activity (changed or not) differs from the previous
+    /// capture of the same pod
+    pub activity_changed: u32,
+    /// Data of the trigger sample
+    #[serde(default)]
+    pub trigger_data: Option<u32>,
+    /// FNV-1a hash of the samples' timestamps and data, hex
+    pub hash: String,
+    /// Same hash as the previous capture of the same pod
+    pub repeat: bool,
+    /// Lost-data gaps found in the readout
+    pub gaps: u32,
+}
+
+/// Where and why a pod RAM readout stopped
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ReadoutError {
+    pub address: u32,
+    pub message: String,
+}
+
+/// Part of pod RAM to read relative to the trigger sample, which is always
+/// included (`?window=pre|post|around&span=N` on the capture endpoints)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ReadoutWindow {
+    /// Up to `span` samples before the trigger
+    Pre,
+    /// Up to `span` samples after the trigger
+    Post,
+    /// Up to `span` samples on each side of the trigger
+    Around,
+}
+
+/// Tag shared by captures taken on several ILAs or servers around one event
+/// (`GET/PUT/DELETE /api/ila/correlation`, `PUT /api/boards/correlation`)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Correlation {
+    pub id: String,
+    /// Common reference time (Unix ms) that trigger offsets are measured
+    /// from; the server fills in its current time if omitted
+    #[serde(default)]
+    pub reference_ms: Option<u64>,
+}
+
+impl Correlation {
+    /// Offset of a trigger time from the reference, in ms
+    pub fn offset_ms(&self, triggered_at_ms: Option<u64>) -> Option<i64> {
+        Some(triggered_at_ms? as i64 - self.reference_ms? as i64)
+    }
+}
+
+/// `GET /api/ila/status` response: a capture status and how long ago the
+/// hardware was read (0 for a fresh read)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StatusSnapshot {
+    #[serde(flatten)]
+    pub status: CaptureStatus,
+    pub age_ms: u64,
+}
+
+/// `GET /api/ila/status/raw` response: the wrapper's `CAP_STATUS` register,
+/// a single AXI read with no serial-bus command
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RawStatus {
+    pub raw: u32,
+    pub armed: bool,
+    pub awake: bool,
+}
+
+impl RawStatus {
+    /// Decode the `CAP_STATUS` register
+    pub fn from_bits(raw: u32) -> Self {
+        Self {
+            raw,
+            armed: (raw & 0x01) != 0,
+            awake: (raw & 0x02) != 0,
+        }
+    }
+}
+
+/// Pod RAM utilization, from the RLE code bits of a (strided) address scan
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RamFill {
+    pub hub: u8,
+    pub pod: u8,
+    pub ram_depth: u32,
+    /// Number of addresses inspected (`ram_depth / stride`)
+    pub sampled: u32,
+    pub invalid: u32,
+    pub pre_trigger: u32,
+    pub trigger: u32,
+    pub post_trigger: u32,
+    /// Percentage of sampled addresses holding a valid entry
+    pub fill_percent: f32,
+}
+
+/// JSON header of a raw pod RAM dump (`GET /api/ila/capture/:hub/:pod/raw`)
+///
+/// The dump is `RAW_DUMP_MAGIC`, a little-endian u32 header length, this
+/// header as JSON, then `pages * ram_depth` little-endian u32 words:
+/// all addresses of page 0, then page 1, and so on.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RawRamHeader {
+    /// See `CaptureData::schema_version`
+    #[serde(default)]
+    pub schema_version: u32,
+    pub hub: u8,
+    pub pod: u8,
+    pub ram_cfg: u32,
+    pub ram_depth: u32,
+    pub data_bits: u16,
+    pub ts_bits: u8,
+    pub pages: u32,
+    /// See `CaptureData::sequence`
+    #[serde(default)]
+    pub sequence: u64,
+    /// See `CaptureData::armed_at_ms`
+    #[serde(default)]
+    pub armed_at_ms: Option<u64>,
+    /// See `CaptureData::triggered_at_ms`
+    #[serde(default)]
+    pub triggered_at_ms: Option<u64>,
+    #[serde(default)]
+    pub armed_at: Option<String>,
+    #[serde(default)]
+    pub triggered_at: Option<String>,
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+    #[serde(default)]
+    pub trigger_offset_ms: Option<i64>,
+    /// See `CaptureData::design_id`
+    #[serde(default)]
+    pub design_id: Option<String>,
+    /// See `CaptureData::trigger_source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_source: Option<TriggerSource>,
+}
+
+pub const RAW_DUMP_MAGIC: &[u8; 8] = b"SUMPRAW1";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TriggerConfig {
+    #[serde(default)]
+    pub trigger_type: String,
+    #[serde(default)]
+    pub trigger_bits: u32,
+    #[serde(default = "default_post_trigger")]
+    pub post_trigger: u32,
+    /// Signal names of pod (0,0) (`PodInfo.signals`, e.g. `adc_valid` or
+    /// `probe[7:0]`, or one bit of a vector as `probe[3]`) whose bits are
+    /// ORed into `trigger_bits`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trigger_signals: Vec<String>,
+    /// Further pods contributing to the trigger (the hub ORs them); an
+    /// entry for pod (0,0) replaces `trigger_bits` as its enable mask
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pods: Vec<PodTrigger>,
+    /// `CMD_WR_TICK_DIVISOR`: decimates the core's low-speed (`ck_tick`)
+    /// sampling. RLE pods have no sample divider; they store only changes,
+    /// so slow signals already cost little RAM.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_divisor: Option<u32>,
+    /// Seconds to wait for the trigger after arming; `on_timeout` runs if
+    /// the capture hasn't triggered by then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_s: Option<f64>,
+    /// What `timeout_s` does; `disarm` when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_timeout: Option<TimeoutAction>,
+}
+
+/// One wrapper command: written to the CMD, ADDR and WDATA registers, then
+/// started with CTRL.START
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WrapperCommand {
+    /// Command name, e.g. `wr_trig_type`
+    pub command: String,
+    pub cmd: u32,
+    /// Serial-bus address: hub in bits 23:16, pod in 15:8, pod register in 7:0
+    pub addr: u32,
+    pub wdata: u32,
+}
+
+/// Commands a trigger configuration would issue
+/// (`POST /api/ila/trigger?dry_run=true`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TriggerPlan {
+    /// Trigger field of pod (0,0) after resolving `trigger_signals`
+    pub trigger_bits: u32,
+    /// In issue order, ending with INIT and ARM
+    pub commands: Vec<WrapperCommand>,
+}
+
+/// Action when an armed capture hasn't triggered within its timeout
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutAction {
+    /// Return the core to idle; pod RAM keeps the pre-trigger samples
+    #[default]
+    Disarm,
+    /// Re-arm with an immediate trigger, capturing whatever the bus shows
+    ForceTrigger,
+    /// INIT and ARM again with the same trigger and timeout
+    Rearm,
+}
+
+/// Pending arm timeout (`GET /api/ila/arm-timeout`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ArmTimeout {
+    pub timeout_s: f64,
+    pub on_timeout: TimeoutAction,
+    /// Sequence number of the capture being waited for (see `/waveforms`)
+    pub capture_id: u64,
+    pub remaining_ms: u64,
+    /// Times `rearm` has re-armed so far
+    pub rearms: u32,
+}
+
+/// Trigger enable of one pod (`TriggerConfig.pods`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PodTrigger {
+    pub hub: u8,
+    pub pod: u8,
+    /// `POD_REG_TRIG_EN` mask; 0 disables the pod's contribution
+    pub bits: u32,
+}
+
+fn default_post_trigger() -> u32 { 64 }
+
+impl Default for TriggerConfig {
+    fn default() -> Self {
+        Self {
+            trigger_type: String::new(),
+            trigger_bits: 0,
+            post_trigger: default_post_trigger(),
+            trigger_signals: Vec::new(),
+            pods: Vec::new(),
+            tick_divisor: None,
+            timeout_s: None,
+            on_timeout: None,
+        }
+    }
+}
+
+/// Sequenced trigger (`POST /api/ila/sequence`): keep a capture only when
+/// condition A (the hardware trigger) is followed by condition B within
+/// `within` pod clock cycles. The server re-arms until a capture matches.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SequenceConfig {
+    /// Condition A; its `post_trigger` must cover `within`
+    pub trigger: TriggerConfig,
+    /// Condition B: any of these pod (0,0) bits high
+    #[serde(default)]
+    pub b_bits: u32,
+    /// Signal names ORed into `b_bits`, as for `trigger_signals`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub b_signals: Vec<String>,
+    /// Cycles after the trigger sample in which B must be seen
+    pub within: u32,
+    /// Match when B is *not* seen in time (e.g. a request without grant)
+    #[serde(default)]
+    pub b_absent: bool,
+    /// Captures to try before giving up; 0 retries until stopped
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_max_attempts() -> u32 { 100 }
+
+/// Sequenced trigger progress (`GET /api/ila/sequence`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SequenceStatus {
+    pub config: Option<SequenceConfig>,
+    pub running: bool,
+    /// Captures evaluated so far
+    pub attempts: u32,
+    /// The capture now in pod RAM satisfies the sequence
+    pub matched: bool,
+    pub message: String,
+}
+
+/// Rolling history mode (`POST /api/ila/history`): the ILA stays armed
+/// with a minimal post-trigger count, so pod RAM always holds the latest
+/// samples; a snapshot freezes and reads it, then re-arms
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HistoryConfig {
+    /// Pod read out on each snapshot
+    #[serde(default)]
+    pub hub: u8,
+    #[serde(default)]
+    pub pod: u8,
+    /// Also snapshot on a rising edge of the routed external trigger input
+    /// (see `ExtTriggerRouting`)
+    #[serde(default)]
+    pub external: bool,
+    /// Snapshots kept for `GET /api/ila/history/:id`
+    #[serde(default = "default_history_keep")]
+    pub keep: u32,
+    /// Analysis run on each snapshot, reported in its `HistorySnapshot`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<AnalysisConfig>,
+}
+
+fn default_history_keep() -> u32 { 4 }
+
+/// Automatic analysis of kept captures
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AnalysisConfig {
+    /// Include the capture's `CaptureDigest` (per-bit activity)
+    #[serde(default)]
+    pub stats: bool,
+    /// Report pulses of a data bit shorter than this many hub cycles (0 = no scan)
+    #[serde(default)]
+    pub glitch_cycles: u32,
+    /// Decoders run over the capture
+    #[serde(default)]
+    pub decoders: Vec<AnalysisDecoder>,
+}
+
+/// A decoder of an `AnalysisConfig`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AnalysisDecoder {
+    /// Name from `GET /api/ila/decoders`
+    pub decoder: String,
+    /// Parameter values in their text form, as in `DecoderParam::default`
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+/// A pulse shorter than `AnalysisConfig::glitch_cycles`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Glitch {
+    pub bit: u8,
+    /// Hub cycles from the first sample to the pulse's leading edge
+    pub cycle: u64,
+    pub width: u64,
+    /// Level of the pulse: 1 for a high pulse on a low line
+    pub level: u8,
+}
+
+/// Outcome of one decoder of an analysis
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DecodeSummary {
+    pub decoder: String,
+    pub annotations: u32,
+    /// Annotations marked `error` (framing, parity, missing ACK, ...)
+    pub errors: u32,
+    /// Why the decoder failed to run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed: Option<String>,
+}
+
+/// Result of an `AnalysisConfig` on one capture
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AnalysisReport {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<CaptureDigest>,
+    /// Glitches found, the first `MAX_GLITCHES` listed in `glitches`
+    #[serde(default)]
+    pub glitch_count: u32,
+    #[serde(default)]
+    pub glitches: Vec<Glitch>,
+    #[serde(default)]
+    pub decodes: Vec<DecodeSummary>,
+    /// Glitches, decode errors and failed decoders: nonzero marks a
+    /// capture worth a look
+    pub findings: u32,
+}
+
+/// A kept rolling-history snapshot
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HistorySnapshot {
+    /// Sequence number of the frozen capture (see `/waveforms`)
+    pub id: u64,
+    /// Server wall-clock time (Unix ms) of the freeze
+    pub taken_at_ms: u64,
+    /// `taken_at_ms` as RFC 3339 in the server's time zone
+    #[serde(default)]
+    pub taken_at: String,
+    /// `request` or `external`
+    pub source: String,
+    pub samples: u32,
+    /// Result of `HistoryConfig::analysis`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<AnalysisReport>,
+}
+
+/// Rolling history progress (`GET /api/ila/history`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HistoryStatus {
+    pub config: Option<HistoryConfig>,
+    pub running: bool,
+    /// Oldest first
+    pub snapshots: Vec<HistorySnapshot>,
+    pub message: String,
+}
+
+/// Capture file format (`GET /api/ila/export-formats`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExportFormat {
+    /// Path segment of `/api/ila/capture/:hub/:pod/export/:format`
+    pub name: String,
+    pub mime_type: String,
+    pub extension: String,
+    pub description: String,
+}
+
+/// Kind of a decoder parameter, for rendering its form field
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DecoderParamKind {
+    /// Name of a signal of the decoded pod, or one bit of it as `name[n]`
+    Signal,
+    Integer,
+    Boolean,
+    /// One of `DecoderParam.choices`
+    Choice,
+}
+
+/// Parameter of a protocol decoder
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DecoderParam {
+    pub name: String,
+    pub kind: DecoderParamKind,
+    pub description: String,
+    pub required: bool,
+    /// Value used when omitted, as text (`"8"`, `"true"`, `"none"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+}
+
+/// Protocol decoder (`GET /api/ila/decoders`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DecoderInfo {
+    /// Path segment of `/api/ila/capture/:hub/:pod/decode/:decoder`
+    pub name: String,
+    pub description: String,
+    pub params: Vec<DecoderParam>,
+}
+
+/// One decoded item, in hub clock cycles since the first sample
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Annotation {
+    pub start_cycle: u64,
+    pub end_cycle: u64,
+    pub label: String,
+    /// Decoded value (a byte, word or address), if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<u32>,
+    /// Malformed item (framing or parity error, missing ACK, ...)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub error: bool,
+}
+
+/// Decoder output for one pod's capture
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DecodeResult {
+    pub decoder: String,
+    pub hub: u8,
+    pub pod: u8,
+    /// Hub clock of the cycle counts (0 if the hub doesn't report it)
+    pub hub_clock_hz: u64,
+    pub annotations: Vec<Annotation>,
+}
+
+/// User control bits (`GET /api/ila/user-ctrl`, `/api/ila/user-stim/:hub/:pod`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UserBits {
+    /// `None` for the write-only core register until it has been written
+    pub value: Option<u32>,
+}
+
+/// Update of user control bits; only bits set in `mask` change
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UserBitsWrite {
+    pub value: u32,
+    #[serde(default = "all_bits")]
+    pub mask: u32,
+}
+
+fn all_bits() -> u32 { 0xFFFF_FFFF }
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RegisterValue {
+    pub offset: usize,
+    pub value: Option<u32>,
+}
+
+/// DDR deep-capture buffer (`PUT /api/ila/deep/config`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DeepConfig {
+    /// Physical address of the reserved DDR buffer (8-byte aligned)
+    pub base_addr: u64,
+    /// Buffer length in bytes (multiple of 8)
+    pub length: u32,
+    /// Records stored after the trigger record
+    pub post_trigger: u32,
+    /// Record only when the probes change
+    #[serde(default)]
+    pub rle: bool,
+}
+
+/// Deep-capture sink state (`GET /api/ila/deep`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DeepStatus {
+    pub config: DeepConfig,
+    pub running: bool,
+    pub triggered: bool,
+    /// Post-trigger records written; the capture is complete
+    pub done: bool,
+    /// The buffer wrapped, so the oldest records were overwritten
+    pub wrapped: bool,
+    /// Records were dropped because DDR writes fell behind
+    pub overflow: bool,
+    /// Valid records in the buffer
+    pub records: u64,
+    /// Index of the trigger record in chronological order
+    pub trigger_record: Option<u64>,
+}
+
+/// Persistent user settings (`GET/PUT /api/settings`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Settings {
+    /// Named trigger configurations
+    #[serde(default)]
+    pub trigger_presets: BTreeMap<String, TriggerConfig>,
+    /// Signal name -> display alias
+    #[serde(default)]
+    pub signal_aliases: BTreeMap<String, String>,
+    /// Hub index (`"0"`) -> display name and description
+    #[serde(default)]
+    pub hub_labels: BTreeMap<String, Label>,
+    /// `"<hub>.<pod>"` (`"0.1"`) -> display name and description
+    #[serde(default)]
+    pub pod_labels: BTreeMap<String, Label>,
+    /// Logical grouping of pods and signals, e.g. by subsystem
+    #[serde(default)]
+    pub groups: Vec<PodGroup>,
+    /// Pods (`"<hub>.<pod>"`) left out of enumeration, readout and exports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_pods: Vec<String>,
+    /// Signals blanked or redacted for clients of the read-only listener
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signal_masks: Vec<SignalMask>,
+    /// Continuous (re-arm after readout) capture enabled
+    #[serde(default)]
+    pub continuous: bool,
+    /// Free-form frontend preferences; values are opaque to the server
+    #[serde(default)]
+    pub ui: BTreeMap<String, String>,
+}
+
+/// Friendly name for a hub or pod, shown instead of its RTL name
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Label {
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+/// Signals of one pod withheld from read-only clients (`Settings::signal_masks`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SignalMask {
+    /// `"<hub>.<pod>"`, as in `pod_labels`
+    pub pod: String,
+    /// Signal names; empty masks every bit of the pod
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signals: Vec<String>,
+    /// Also leave the signals out of the enumeration; otherwise they are
+    /// listed but read as 0
+    #[serde(default)]
+    pub redact: bool,
+}
+
+/// Named group of pods and signals; groups nest to form a design tree
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PodGroup {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Member pods as `"<hub>.<pod>"`, as in `pod_labels`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pods: Vec<String>,
+    /// Member signals as `"<hub>.<pod>/<signal>"`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signals: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<PodGroup>,
+}
+
+/// Embedded frontend configuration (`GET /api/ui-config`), read from the
+/// `SUMP_UI_CONFIG` file; the frontend fetches it at startup
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct UiConfig {
+    /// Surfer theme name, e.g. `dark+` or `light+`
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Pod selected at startup, `"<hub>.<pod>"`
+    #[serde(default)]
+    pub default_pod: Option<String>,
+    /// Waveform opened at startup, e.g. `/waveforms/latest.vcd`
+    #[serde(default)]
+    pub load_url: Option<String>,
+    /// Signal groups added to the viewer, as in `Settings::groups`
+    #[serde(default)]
+    pub signal_groups: Vec<PodGroup>,
+    /// Filled in by the server; ignored in the file
+    #[serde(default)]
+    pub capabilities: Capabilities,
+}
+
+/// Features of this server (`GET /api/capabilities`), so clients can adapt
+/// to its build and configuration without probing for 404s
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Capabilities {
+    /// sump-server version
+    pub version: String,
+    /// Cargo features the server was built with (`grpc`, `plugins`)
+    pub features: Vec<String>,
+    /// `SUMP_BACKEND` schemes this build accepts
+    pub backends: Vec<String>,
+    /// Register backend in use
+    pub backend: String,
+    /// Client authentication: always `none`; restrict access with
+    /// `SUMP_BIND` and the read-only listener
+    pub auth: String,
+    /// Answered on the read-only listener: state-changing requests get 403
+    pub read_only: bool,
+    /// Names accepted by `/api/ila/capture/:hub/:pod/export/:format`
+    pub export_formats: Vec<String>,
+    /// Names accepted by `/api/ila/capture/:hub/:pod/decode/:decoder`
+    pub decoders: Vec<String>,
+    /// A DDR deep-capture sink is configured under `/api/ila/deep`
+    pub deep_capture: bool,
+    /// `POST /api/ila/ext-trigger` has a GPIO to pulse
+    pub ext_trigger: bool,
+    /// The `/api/ila/console` WebSocket is enabled (`SUMP_CONSOLE`)
+    #[serde(default)]
+    pub console: bool,
+    /// Largest sample count a capture request accepts (the deepest pod
+    /// RAM); `None` without hardware
+    #[serde(default)]
+    pub max_sample_count: Option<u32>,
+}
+
+/// Counters since server start (`GET /api/ila/stats`, `/metrics`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IlaStats {
+    pub uptime_s: u64,
+    /// Wrapper commands issued
+    pub commands: u64,
+    /// Commands that completed with the STATUS error bit set
+    pub command_errors: u64,
+    /// Commands that never signalled DONE
+    pub command_timeouts: u64,
+    /// Failed commands attempted again under the retry policy
+    #[serde(default)]
+    pub command_retries: u64,
+    /// Successful ARM commands
+    pub arms: u64,
+    /// Rising edges of the triggered status bit seen by status reads
+    pub triggers: u64,
+    /// Capture readouts served from memory instead of pod RAM
+    #[serde(default)]
+    pub readout_cache_hits: u64,
+    /// Hardware reconnects after a lost connection
+    pub reconnects: u64,
+    /// Successful watchdog recoveries (`CMD_RESET` + re-enumeration)
+    pub recoveries: u64,
+    /// Times the circuit breaker opened on consecutive command timeouts
+    #[serde(default)]
+    pub circuit_trips: u64,
+    /// Execution time histograms of the command codes issued so far
+    #[serde(default)]
+    pub command_latency: Vec<CommandLatency>,
+    /// `command_errors` broken down by the wrapper's error cause
+    #[serde(default)]
+    pub command_error_causes: BTreeMap<String, u64>,
+    /// Most recent failed command
+    #[serde(default)]
+    pub last_command_error: Option<CommandFailure>,
+}
+
+/// A command that completed with the error bit set or never completed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CommandFailure {
+    pub cmd: u8,
+    pub name: String,
+    pub addr: u32,
+    /// Wrapper STATUS error code; `None` when software gave up (timeout,
+    /// register transport failure)
+    pub code: Option<u8>,
+    pub cause: String,
+}
+
+impl fmt::Display for CommandFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (command 0x{:02X} {}, addr 0x{:06X})", self.cause, self.cmd, self.name, self.addr)
+    }
+}
+
+/// Execution time histogram of one wrapper command code, measured from
+/// writing CMD to reading RDATA (excluding time queued behind other commands)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CommandLatency {
+    pub cmd: u8,
+    pub name: String,
+    pub count: u64,
+    pub sum_us: f64,
+    /// Cumulative counts per upper bound; `count` is the +Inf bucket
+    pub buckets: Vec<LatencyBucket>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LatencyBucket {
+    pub le_us: u64,
+    pub count: u64,
+}
+
+/// Hardware watchdog state (`GET /api/ila/health`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WatchdogStatus {
+    /// `off`, `report` or `recover`
+    pub mode: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub recoveries: u64,
+}
+
+/// Circuit breaker state (`GET /api/ila/circuit`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CircuitStatus {
+    /// Consecutive command timeouts that open the circuit (0 = disabled)
+    pub threshold: u64,
+    /// Hardware endpoints answer 503 until a background probe completes
+    pub open: bool,
+    /// Command timeouts since the last command that completed
+    pub consecutive_timeouts: u64,
+    /// Why the circuit opened (the last failed command)
+    pub reason: Option<String>,
+    /// RFC 3339 time the circuit opened
+    pub opened_at: Option<String>,
+    /// Times the circuit opened since server start
+    pub trips: u64,
+}
+
+/// Startup INIT progress (`SUMP_INIT_ON_BOOT`)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BootInit {
+    /// Not requested; captures need an explicit INIT
+    #[default]
+    Disabled,
+    Pending,
+    Done,
+    /// Hardware missing or INIT failed; a successful `POST /api/ila/init` clears it
+    Failed,
+}
+
+/// Readiness probe (`GET /readyz`); 503 unless `ready`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Readiness {
+    pub ready: bool,
+    pub boot_init: BootInit,
+    /// From the watchdog (see `WatchdogStatus`)
+    pub hardware_healthy: bool,
+    pub message: String,
+}
+
+/// Register watched over the `/api/ila/watch` WebSocket
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchTarget {
+    /// Wrapper register read directly over AXI
+    Reg { offset: usize },
+    /// Pod register read over the serial bus
+    PodReg { hub: u8, pod: u8, reg: u8 },
+    /// Capture status via `CMD_RD_STATUS`
+    Status,
+}
+
+/// Subscription message sent by the client; each one replaces the previous
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WatchRequest {
+    pub targets: Vec<WatchTarget>,
+    #[serde(default = "default_watch_interval_ms")]
+    pub interval_ms: u64,
+    /// Only send values that changed since the previous update
+    #[serde(default)]
+    pub on_change: bool,
+}
+
+fn default_watch_interval_ms() -> u64 { 100 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WatchValue {
+    pub target: WatchTarget,
+    pub value: Option<u32>,
+}
+
+/// Periodic update sent by the server
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WatchUpdate {
+    /// Milliseconds since the subscription was made
+    pub elapsed_ms: u64,
+    pub values: Vec<WatchValue>,
+}
+
+/// Rejected request field (422 responses)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Body of a 422 response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+/// One step of a `POST /api/ila/batch` request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOp {
+    Reset,
+    /// `CMD_INIT` followed by the RAM clear delay
+    Init,
+    Arm,
+    /// Program the trigger on pod (0,0) without INIT/ARM
+    Trigger(TriggerConfig),
+    /// Raw wrapper command
+    Cmd {
+        cmd: u32,
+        #[serde(default)]
+        addr: u32,
+        #[serde(default)]
+        wdata: u32,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BatchRequest {
+    pub ops: Vec<BatchOp>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BatchStepResult {
+    pub success: bool,
+    pub message: String,
+    /// RDATA of a raw command, or the trigger bits written
+    pub value: Option<u32>,
+}
+
+/// Per-step results; execution stops at the first failed step
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BatchResult {
+    pub success: bool,
+    pub steps: Vec<BatchStepResult>,
+}
+
+/// Body of a 409 response: the request conflicts with an operation in progress
+/// or with the current capture state
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct OperationConflict {
+    pub message: String,
+    /// Logical operation holding the ILA, if any (`readout`, `trigger`, ...)
+    pub operation: Option<String>,
+    pub status: CaptureStatus,
+}
+
+/// One step of `POST /api/ila/selftest`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SelfTestStep {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub duration_us: u64,
+}
+
+/// `POST /api/ila/selftest` report; steps after the first failure are not run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub steps: Vec<SelfTestStep>,
+}
+
+/// Latency summary of one benchmarked access, in nanoseconds
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LatencyStats {
+    pub count: u32,
+    pub failures: u32,
+    pub min_ns: u64,
+    pub mean_ns: u64,
+    pub p99_ns: u64,
+    pub max_ns: u64,
+}
+
+/// `POST /api/ila/benchmark` results
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BenchmarkReport {
+    /// Register transport (`/dev/mem`, `tcp://host:port`, ...)
+    pub backend: String,
+    /// Raw wrapper register read (HW_INFO)
+    pub read32: LatencyStats,
+    /// Raw wrapper register write (WDATA, which is only latched on START)
+    pub write32: LatencyStats,
+    /// Local command round trip (`CMD_RD_STATUS`)
+    pub local_cmd: LatencyStats,
+    /// Serial-bus command round trip to hub 0 (`CMD_RD_POD_COUNT`)
+    pub serial_cmd: LatencyStats,
+    /// Uncached RLE samples read from hub 0 pod 0
+    pub readout_samples: u32,
+    pub readout_us: u64,
+    pub samples_per_sec: f64,
+}
+
+/// One board behind a fleet proxy (`GET /api/boards`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BoardStatus {
+    pub name: String,
+    pub url: String,
+    pub reachable: bool,
+    /// Why the board could not be polled
+    pub error: Option<String>,
+    pub status: Option<CaptureStatus>,
+    pub health: Option<WatchdogStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FleetStatus {
+    pub boards: Vec<BoardStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CommandResult {
+    pub success: bool,
+    pub message: String,
+}
+
+/// Server log filter and outputs (`GET /api/log`); `PUT /api/log` changes
+/// the filter at runtime
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LogConfig {
+    /// `RUST_LOG`-style directives, e.g. `sump_server=debug,tower_http=info`
+    pub filter: String,
+    /// Where log lines go, e.g. `stdout`, `file:/var/log/sump.log`, `syslog`;
+    /// filled in by the server, ignored by `PUT`
+    #[serde(default)]
+    pub sinks: Vec<String>,
+}
+
+/// A matched alert rule (`GET /api/ila/alerts`), also sent to the
+/// configured webhooks and MQTT topic
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AlertEvent {
+    pub rule: String,
+    /// Capture sequence number (see `CaptureData::sequence`)
+    pub capture_id: u64,
+    pub hub: u8,
+    pub pod: u8,
+    /// Server wall-clock time (Unix ms) of the evaluation
+    pub fired_at_ms: u64,
+    /// `fired_at_ms` as RFC 3339
+    pub fired_at: String,
+    /// What matched, e.g. `fifo_overflow == 0x1 at cycle 1234`
+    pub detail: String,
+}
+
+/// A pod's capture kept in the on-disk archive (`GET /api/ila/archive`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ArchiveEntry {
+    /// Capture sequence number (see `CaptureData::sequence`)
+    pub id: u64,
+    pub hub: u8,
+    pub pod: u8,
+    /// Server wall-clock time (Unix ms) it was archived
+    pub archived_at_ms: u64,
+    /// `archived_at_ms` as RFC 3339
+    pub archived_at: String,
+    /// Size of the raw dump (see `RawRamHeader`)
+    pub raw_bytes: u64,
+    /// Size of the zstd-compressed file on disk
+    pub compressed_bytes: u64,
+    /// Result of the pod's analysis, if `SUMP_ARCHIVE_ANALYSIS` configures one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<AnalysisReport>,
+}
+
+/// Archived captures, oldest first, and their total sizes
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ArchiveListing {
+    pub entries: Vec<ArchiveEntry>,
+    pub raw_bytes: u64,
+    pub compressed_bytes: u64,
+    /// Entries kept before the oldest are deleted
+    pub keep: u32,
+}
+
+/// Command of the `/api/ila/console` WebSocket
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ConsoleCommand {
+    /// Raw wrapper command; replies with RDATA
+    ExecCmd {
+        cmd: u32,
+        #[serde(default)]
+        addr: u32,
+        #[serde(default)]
+        wdata: u32,
+    },
+    /// Wrapper register read directly over AXI
+    ReadReg { offset: usize },
+    ReadPodReg { hub: u8, pod: u8, reg: u8 },
+    WritePodReg { hub: u8, pod: u8, reg: u8, value: u32 },
+    /// `count` raw words of pod RAM `page` from address `start`, streamed
+    /// in several replies
+    DumpRam {
+        hub: u8,
+        pod: u8,
+        #[serde(default)]
+        page: u32,
+        #[serde(default)]
+        start: u32,
+        count: u32,
+    },
+}
+
+/// Console message from the client; `id` is echoed in its replies
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConsoleRequest {
+    #[serde(default)]
+    pub id: u64,
+    #[serde(flatten)]
+    pub command: ConsoleCommand,
+}
+
+/// Console reply; a request gets one or more, the last with `done`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConsoleReply {
+    pub id: u64,
+    pub ok: bool,
+    pub message: String,
+    /// Register value or RDATA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<u32>,
+    /// RAM address of `words[0]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<u32>,
+    pub done: bool,
+}
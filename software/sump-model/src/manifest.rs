@@ -0,0 +1,101 @@
+//! Capture manifest versioning
+//!
+//! `CaptureData` and the `RawRamHeader` of a raw dump are the manifests of
+//! archived captures. Both carry `schema_version`; files written before it
+//! existed read as version 0. Fields are only ever added with serde
+//! defaults (or renamed with `alias`), so any older manifest still parses,
+//! and `migrate` then fills in what an older writer left out. A manifest
+//! from a newer writer is rejected rather than misread.
+//!
+//! Versions:
+//! - 0: no `schema_version`, `sequence`, `armed_at` or `triggered_at`
+//! - 1: capture sequence number and RFC 3339 times
+
+use std::fmt;
+
+use crate::{CaptureData, RawRamHeader};
+
+/// Manifest version written by this build
+pub const CAPTURE_SCHEMA_VERSION: u32 = 1;
+
+/// A manifest written by a newer, unknown schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedVersion(pub u32);
+
+impl fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "capture schema version {} is newer than the supported {}",
+            self.0, CAPTURE_SCHEMA_VERSION
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedVersion {}
+
+/// `unix_ms` as RFC 3339 with milliseconds in the zone `offset_min`
+/// minutes east of UTC, e.g. `2026-10-16T14:35:27.481+02:00` (`Z` when the
+/// offset is zero). The driver's `clock::rfc3339` supplies the local offset.
+pub fn rfc3339_offset(unix_ms: u64, offset_min: i32) -> String {
+    let local_ms = unix_ms as i64 + offset_min as i64 * 60_000;
+    let (secs, millis) = (local_ms.div_euclid(1000), local_ms.rem_euclid(1000));
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil date from days since 1970-01-01 (H. Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let zone = match offset_min {
+        0 => "Z".to_string(),
+        m => format!("{}{:02}:{:02}", if m < 0 { '-' } else { '+' }, m.abs() / 60, m.abs() % 60),
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}{}",
+        year, month, day, rem / 3600, rem / 60 % 60, rem % 60, millis, zone
+    )
+}
+
+/// `unix_ms` as RFC 3339 UTC, e.g. `2026-10-16T12:35:27.481Z`, for times
+/// whose writer's zone is unknown
+pub fn rfc3339_utc(unix_ms: u64) -> String {
+    rfc3339_offset(unix_ms, 0)
+}
+
+/// Check `version` and whether it predates RFC 3339 times
+fn check(version: u32) -> Result<bool, UnsupportedVersion> {
+    if version > CAPTURE_SCHEMA_VERSION {
+        return Err(UnsupportedVersion(version));
+    }
+    Ok(version < 1)
+}
+
+impl CaptureData {
+    /// Bring a capture loaded from an older manifest up to
+    /// `CAPTURE_SCHEMA_VERSION`
+    pub fn migrate(mut self) -> Result<Self, UnsupportedVersion> {
+        if check(self.schema_version)? {
+            self.armed_at = self.armed_at_ms.map(rfc3339_utc);
+            self.triggered_at = self.triggered_at_ms.map(rfc3339_utc);
+        }
+        self.schema_version = CAPTURE_SCHEMA_VERSION;
+        Ok(self)
+    }
+}
+
+impl RawRamHeader {
+    /// Bring a raw dump header up to `CAPTURE_SCHEMA_VERSION`
+    pub fn migrate(mut self) -> Result<Self, UnsupportedVersion> {
+        if check(self.schema_version)? {
+            self.armed_at = self.armed_at_ms.map(rfc3339_utc);
+            self.triggered_at = self.triggered_at_ms.map(rfc3339_utc);
+        }
+        self.schema_version = CAPTURE_SCHEMA_VERSION;
+        Ok(self)
+    }
+}
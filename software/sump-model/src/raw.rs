@@ -0,0 +1,91 @@
+//! RLE sample decoding
+//!
+//! How the words of a pod's RAM read as samples: page 0 holds the data,
+//! page 1 the RLE code above the timestamp. The driver decodes readouts
+//! with these, and raw dumps decode the same way without it.
+
+use crate::{CaptureData, CaptureStatus, GapReason, RawRamHeader, RleSample, SampleGap};
+
+/// Decode a raw RAM dump as a capture in address order, the way the
+/// driver reads a pod; for archived dumps decoded without the hardware
+pub fn capture_from_raw(header: &RawRamHeader, words: &[u32]) -> CaptureData {
+    let depth = header.ram_depth as usize;
+    let samples: Vec<RleSample> = if header.pages >= 2 && words.len() >= 2 * depth {
+        let (data, hi) = words.split_at(depth);
+        data.iter().zip(hi).zip(0..).map(|((&data, &hi), addr)| decode_rle(addr, data, hi, header.ts_bits)).collect()
+    } else {
+        Vec::new()
+    };
+    CaptureData {
+        schema_version: header.schema_version,
+        hub: header.hub,
+        pod: header.pod,
+        ts_bits: header.ts_bits,
+        data_bits: header.data_bits,
+        // A dump doesn't record the status it was taken in
+        status: CaptureStatus::from_bits(0),
+        sample_count: samples.len() as u32,
+        gaps: find_gaps(&samples, header.ts_bits),
+        samples,
+        sequence: header.sequence,
+        armed_at_ms: header.armed_at_ms,
+        triggered_at_ms: header.triggered_at_ms,
+        armed_at: header.armed_at.clone(),
+        triggered_at: header.triggered_at.clone(),
+        correlation_id: header.correlation_id.clone(),
+        trigger_offset_ms: header.trigger_offset_ms,
+        design_id: header.design_id.clone(),
+        trigger_address: None,
+        trigger_source: header.trigger_source,
+        readout_error: None,
+    }
+}
+
+/// Largest timestamp of a `ts_bits` wide field; a RAM_CFG claiming 32 or
+/// more bits leaves the whole page 1 word to the timestamp
+fn ts_max(ts_bits: u8) -> u32 {
+    1u32.checked_shl(ts_bits as u32).map_or(u32::MAX, |v| v - 1)
+}
+
+/// RLE code above the timestamp of a page 1 word; 0 (unwritten) when the
+/// timestamp takes the whole word
+pub fn rle_code(hi: u32, ts_bits: u8) -> u8 {
+    hi.checked_shr(ts_bits as u32).map_or(0, |v| v & 0x3) as u8
+}
+
+/// Decode the page 0 (data) and page 1 ({code, timestamp}) words of an RLE sample
+pub fn decode_rle(address: u32, data: u32, hi: u32, ts_bits: u8) -> RleSample {
+    RleSample {
+        address,
+        code: rle_code(hi, ts_bits),
+        timestamp: hi & ts_max(ts_bits),
+        data,
+    }
+}
+
+/// Lost data in a run of samples. The pods have no overflow flag, so this
+/// looks for its traces: a saturated timestamp, or unwritten words between
+/// written ones.
+pub fn find_gaps(samples: &[RleSample], ts_bits: u8) -> Vec<SampleGap> {
+    let ts_max = ts_max(ts_bits);
+    let mut gaps = Vec::new();
+    let (mut written, mut unwritten) = (false, false);
+    for (index, sample) in samples.iter().enumerate() {
+        if sample.code == 0 {
+            unwritten |= written;
+            continue;
+        }
+        let reason = if unwritten {
+            Some(GapReason::Unwritten)
+        } else if ts_bits > 0 && sample.timestamp == ts_max {
+            Some(GapReason::TimestampSaturated)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            gaps.push(SampleGap { index: index as u32, address: sample.address, reason });
+        }
+        (written, unwritten) = (true, false);
+    }
+    gaps
+}
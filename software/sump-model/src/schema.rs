@@ -0,0 +1,39 @@
+//! JSON Schemas of the API types (feature `schema`)
+//!
+//! Derived from the serde attributes, so the schemas follow the JSON the
+//! server actually emits and accepts. `sump-server` serves them at
+//! `/api/schema/:type`.
+
+pub use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::*;
+
+macro_rules! schema_types {
+    ($($name:ident),* $(,)?) => {
+        /// Type names accepted by [`schema`]
+        pub const TYPES: &[&str] = &[$(stringify!($name)),*];
+
+        /// JSON Schema (draft 7) of an API type by its Rust name
+        pub fn schema(name: &str) -> Option<RootSchema> {
+            match name {
+                $(stringify!($name) => Some(schema_for!($name)),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+schema_types!(
+    IlaInfo, ExtTriggerRouting, HubInfo, PodInfo, SignalInfo, RleSample, CaptureStatus, CaptureData, SampleGap, TriggerSource,
+    GapReason, CaptureDigest, ReadoutError, ReadoutWindow, Correlation, StatusSnapshot, RawStatus, RamFill,
+    RawRamHeader, TriggerConfig, PodTrigger, WrapperCommand, TriggerPlan, TimeoutAction, ArmTimeout, SequenceConfig, SequenceStatus,
+    HistoryConfig, AnalysisConfig, AnalysisDecoder, Glitch, DecodeSummary, AnalysisReport, HistorySnapshot,
+    HistoryStatus, ExportFormat, DecoderParamKind, DecoderParam, DecoderInfo,
+    Annotation, DecodeResult, UserBits, UserBitsWrite, RegisterValue, DeepConfig, DeepStatus, Settings, Label,
+    SignalMask, PodGroup, UiConfig, Capabilities, IlaStats, CommandFailure, CommandLatency, LatencyBucket, WatchdogStatus, CircuitStatus,
+    BootInit, Readiness, WatchTarget, WatchRequest, WatchValue, WatchUpdate, FieldError, ValidationErrors, BatchOp,
+    BatchRequest, BatchStepResult, BatchResult, OperationConflict, SelfTestStep, SelfTestReport, LatencyStats,
+    BenchmarkReport, BoardStatus, FleetStatus, CommandResult, LogConfig, AlertEvent, ArchiveEntry,
+    ArchiveListing, ConsoleCommand, ConsoleRequest, ConsoleReply,
+);
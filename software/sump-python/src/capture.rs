@@ -0,0 +1,193 @@
+//! Typed capture objects shared by the driver and REST bindings
+
+use numpy::PyArray1;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use sump_model::{CaptureData, CaptureStatus, RleSample};
+
+/// Capture status flags
+#[pyclass(name = "CaptureStatus", module = "sump_surfer", frozen)]
+#[derive(Clone)]
+pub struct PyCaptureStatus {
+    #[pyo3(get)]
+    armed: bool,
+    #[pyo3(get)]
+    pre_trigger: bool,
+    #[pyo3(get)]
+    triggered: bool,
+    #[pyo3(get)]
+    acquired: bool,
+    #[pyo3(get)]
+    init_in_progress: bool,
+}
+
+impl From<CaptureStatus> for PyCaptureStatus {
+    fn from(s: CaptureStatus) -> Self {
+        Self {
+            armed: s.armed,
+            pre_trigger: s.pre_trigger,
+            triggered: s.triggered,
+            acquired: s.acquired,
+            init_in_progress: s.init_in_progress,
+        }
+    }
+}
+
+#[pymethods]
+impl PyCaptureStatus {
+    fn __repr__(&self) -> String {
+        format!(
+            "CaptureStatus(armed={}, pre_trigger={}, triggered={}, acquired={}, init_in_progress={})",
+            py_bool(self.armed), py_bool(self.pre_trigger), py_bool(self.triggered),
+            py_bool(self.acquired), py_bool(self.init_in_progress)
+        )
+    }
+}
+
+/// One RLE sample from pod RAM
+#[pyclass(name = "Sample", module = "sump_surfer", frozen)]
+#[derive(Clone)]
+pub struct PySample {
+    #[pyo3(get)]
+    address: u32,
+    #[pyo3(get)]
+    code: u8,
+    #[pyo3(get)]
+    timestamp: u32,
+    #[pyo3(get)]
+    data: u32,
+}
+
+impl From<&RleSample> for PySample {
+    fn from(s: &RleSample) -> Self {
+        Self {
+            address: s.address,
+            code: s.code,
+            timestamp: s.timestamp,
+            data: s.data,
+        }
+    }
+}
+
+#[pymethods]
+impl PySample {
+    fn __repr__(&self) -> String {
+        format!(
+            "Sample(address={}, code={}, timestamp={}, data=0x{:08X})",
+            self.address, self.code, self.timestamp, self.data
+        )
+    }
+}
+
+/// Samples read from one hub/pod
+#[pyclass(name = "Capture", module = "sump_surfer", frozen)]
+pub struct PyCapture {
+    #[pyo3(get)]
+    hub: u8,
+    #[pyo3(get)]
+    pod: u8,
+    #[pyo3(get)]
+    ts_bits: u8,
+    #[pyo3(get)]
+    data_bits: u16,
+    #[pyo3(get)]
+    status: PyCaptureStatus,
+    /// Capture sequence number; increases with every ARM, across server restarts
+    #[pyo3(get)]
+    sequence: u64,
+    /// Server wall-clock time (Unix ms) of the ARM, if known
+    #[pyo3(get)]
+    armed_at_ms: Option<u64>,
+    /// Server wall-clock time (Unix ms) the trigger was detected, if known
+    #[pyo3(get)]
+    triggered_at_ms: Option<u64>,
+    /// `armed_at_ms` as an RFC 3339 string in the server's time zone
+    #[pyo3(get)]
+    armed_at: Option<String>,
+    /// `triggered_at_ms` as an RFC 3339 string in the server's time zone
+    #[pyo3(get)]
+    triggered_at: Option<String>,
+    /// Shared correlation id set on the server, if any
+    #[pyo3(get)]
+    correlation_id: Option<String>,
+    /// Trigger time relative to the correlation reference, in ms
+    #[pyo3(get)]
+    trigger_offset_ms: Option<i64>,
+    /// FPGA design the capture came from, if the server is configured with one
+    #[pyo3(get)]
+    design_id: Option<String>,
+    /// `(hub, pod, bits)` that fired the trigger, where the hardware latched it
+    #[pyo3(get)]
+    trigger_source: Option<(u8, u8, u32)>,
+    /// RAM address the readout stopped at, if it failed partway
+    #[pyo3(get)]
+    readout_error_address: Option<u32>,
+    /// Why the readout stopped early; `samples` holds what was read before it
+    #[pyo3(get)]
+    readout_error: Option<String>,
+    /// Lost RLE data as `(sample index, reason)`; the time or samples
+    /// before that sample can't be trusted
+    #[pyo3(get)]
+    gaps: Vec<(u32, &'static str)>,
+    samples: Vec<RleSample>,
+}
+
+impl From<CaptureData> for PyCapture {
+    fn from(c: CaptureData) -> Self {
+        Self {
+            hub: c.hub,
+            pod: c.pod,
+            ts_bits: c.ts_bits,
+            data_bits: c.data_bits,
+            status: c.status.into(),
+            sequence: c.sequence,
+            armed_at_ms: c.armed_at_ms,
+            triggered_at_ms: c.triggered_at_ms,
+            armed_at: c.armed_at,
+            triggered_at: c.triggered_at,
+            correlation_id: c.correlation_id,
+            trigger_offset_ms: c.trigger_offset_ms,
+            design_id: c.design_id,
+            trigger_source: c.trigger_source.map(|t| (t.hub, t.pod, t.bits)),
+            readout_error_address: c.readout_error.as_ref().map(|e| e.address),
+            readout_error: c.readout_error.map(|e| e.message),
+            gaps: c.gaps.iter().map(|g| (g.index, g.reason.as_str())).collect(),
+            samples: c.samples,
+        }
+    }
+}
+
+#[pymethods]
+impl PyCapture {
+    /// Samples as a list of `Sample` objects
+    #[getter]
+    fn samples(&self) -> Vec<PySample> {
+        self.samples.iter().map(PySample::from).collect()
+    }
+
+    /// Columns as numpy arrays: `address`, `code`, `timestamp`, `data`
+    fn to_numpy<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("address", PyArray1::from_iter_bound(py, self.samples.iter().map(|s| s.address)))?;
+        dict.set_item("code", PyArray1::from_iter_bound(py, self.samples.iter().map(|s| s.code)))?;
+        dict.set_item("timestamp", PyArray1::from_iter_bound(py, self.samples.iter().map(|s| s.timestamp)))?;
+        dict.set_item("data", PyArray1::from_iter_bound(py, self.samples.iter().map(|s| s.data)))?;
+        Ok(dict)
+    }
+
+    fn __len__(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Capture(hub={}, pod={}, samples={}, ts_bits={}, data_bits={})",
+            self.hub, self.pod, self.samples.len(), self.ts_bits, self.data_bits
+        )
+    }
+}
+
+fn py_bool(b: bool) -> &'static str {
+    if b { "True" } else { "False" }
+}
@@ -0,0 +1,145 @@
+//! Off-target REST client bindings
+
+use std::time::Duration;
+
+use pyo3::exceptions::{PyConnectionError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+
+use sump_model::*;
+
+use crate::capture::{PyCapture, PyCaptureStatus};
+
+/// Blocking client for a remote sump-server (`http://host:8082`)
+#[pyclass(name = "Client", module = "sump_surfer", frozen)]
+pub struct PyClient {
+    url: String,
+    agent: ureq::Agent,
+}
+
+fn map_err(e: ureq::Error) -> PyErr {
+    match e {
+        ureq::Error::Status(code, resp) => {
+            PyRuntimeError::new_err(format!("HTTP {} from {}", code, resp.get_url()))
+        }
+        ureq::Error::Transport(t) => PyConnectionError::new_err(t.to_string()),
+    }
+}
+
+impl PyClient {
+    fn get<T: DeserializeOwned + Send>(&self, py: Python<'_>, path: &str) -> PyResult<T> {
+        let url = format!("{}/api/ila{}", self.url, path);
+        py.allow_threads(|| {
+            self.agent.get(&url).call().map_err(map_err)?
+                .into_json()
+                .map_err(|e| PyConnectionError::new_err(e.to_string()))
+        })
+    }
+
+    fn post(&self, py: Python<'_>, path: &str, body: Option<&TriggerConfig>) -> PyResult<String> {
+        let url = format!("{}/api/ila{}", self.url, path);
+        let result: CommandResult = py.allow_threads(|| {
+            let req = self.agent.post(&url);
+            let resp = match body {
+                Some(body) => req.send_json(body),
+                None => req.call(),
+            };
+            resp.map_err(map_err)?
+                .into_json()
+                .map_err(|e| PyConnectionError::new_err(e.to_string()))
+        })?;
+        if result.success {
+            Ok(result.message)
+        } else {
+            Err(PyRuntimeError::new_err(result.message))
+        }
+    }
+}
+
+#[pymethods]
+impl PyClient {
+    #[new]
+    #[pyo3(signature = (url, timeout = 30.0))]
+    fn new(url: &str, timeout: f64) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs_f64(timeout))
+                .build(),
+        }
+    }
+
+    #[getter]
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Full hub/pod enumeration (`GET /api/ila`)
+    fn info(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let info: IlaInfo = self.get(py, "")?;
+        crate::to_py_object(py, &info)
+    }
+
+    fn status(&self, py: Python<'_>) -> PyResult<PyCaptureStatus> {
+        let status: CaptureStatus = self.get(py, "/status")?;
+        Ok(status.into())
+    }
+
+    fn reset(&self, py: Python<'_>) -> PyResult<()> {
+        self.post(py, "/reset", None).map(|_| ())
+    }
+
+    fn init(&self, py: Python<'_>) -> PyResult<()> {
+        self.post(py, "/init", None).map(|_| ())
+    }
+
+    fn arm(&self, py: Python<'_>) -> PyResult<()> {
+        self.post(py, "/arm", None).map(|_| ())
+    }
+
+    /// Configure the trigger, INIT and ARM. Returns the server's message.
+    #[pyo3(signature = (
+        trigger_type = "or_rising",
+        trigger_bits = 0,
+        post_trigger = 64,
+        trigger_signals = Vec::new(),
+        tick_divisor = None,
+    ))]
+    fn configure_trigger(
+        &self,
+        py: Python<'_>,
+        trigger_type: &str,
+        trigger_bits: u32,
+        post_trigger: u32,
+        trigger_signals: Vec<String>,
+        tick_divisor: Option<u32>,
+    ) -> PyResult<String> {
+        let config = TriggerConfig {
+            trigger_type: trigger_type.to_string(),
+            trigger_bits,
+            post_trigger,
+            trigger_signals,
+            tick_divisor,
+            ..Default::default()
+        };
+        self.post(py, "/trigger", Some(&config))
+    }
+
+    /// Read up to `count` samples from a hub/pod
+    #[pyo3(signature = (hub = 0, pod = 0, count = 2048))]
+    fn capture(&self, py: Python<'_>, hub: u8, pod: u8, count: u32) -> PyResult<PyCapture> {
+        let data: CaptureData = self.get(py, &format!("/capture/{}/{}/{}", hub, pod, count))?;
+        let data = data.migrate().map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(data.into())
+    }
+
+    /// Read a raw wrapper register (`None` if the read failed; bad offsets raise)
+    fn read_reg(&self, py: Python<'_>, offset: usize) -> PyResult<Option<u32>> {
+        let reg: RegisterValue = self.get(py, &format!("/reg/{}", offset))?;
+        Ok(reg.value)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Client(url={:?})", self.url)
+    }
+}
@@ -0,0 +1,134 @@
+//! On-target driver bindings
+
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use sump_driver::{Ila, CMD_ARM, CMD_INIT, CMD_RESET};
+use sump_model::TriggerConfig;
+
+use crate::capture::{PyCapture, PyCaptureStatus};
+
+/// Default SUMP3 AXI base address (matches the sump-server build default)
+const DEFAULT_AXI_ADDR: usize = 0x43C2_0000;
+
+/// Direct /dev/mem access to a SUMP3 AXI wrapper (requires root)
+#[pyclass(name = "Ila", module = "sump_surfer", frozen)]
+pub struct PyIla {
+    ila: Ila,
+}
+
+impl PyIla {
+    fn command(&self, py: Python<'_>, cmd: u32, what: &str) -> PyResult<()> {
+        py.allow_threads(|| self.ila.exec_cmd(cmd, 0, 0))
+            .map(|_| ())
+            .ok_or_else(|| PyRuntimeError::new_err(format!("{} failed", what)))
+    }
+}
+
+#[pymethods]
+impl PyIla {
+    #[new]
+    #[pyo3(signature = (base_addr = DEFAULT_AXI_ADDR))]
+    fn new(base_addr: usize) -> PyResult<Self> {
+        Ila::new(base_addr)
+            .map(|ila| Self { ila })
+            .map_err(|e| PyOSError::new_err(format!("Failed to map ILA at 0x{:08X}: {}", base_addr, e)))
+    }
+
+    /// Physical base address of the wrapper
+    #[getter]
+    fn base_addr(&self) -> usize {
+        self.ila.base_addr()
+    }
+
+    /// Full hub/pod enumeration (same structure as `GET /api/ila`)
+    fn info(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let info = py.allow_threads(|| self.ila.info());
+        crate::to_py_object(py, &info)
+    }
+
+    fn status(&self, py: Python<'_>) -> PyCaptureStatus {
+        py.allow_threads(|| self.ila.capture_status()).into()
+    }
+
+    fn reset(&self, py: Python<'_>) -> PyResult<()> {
+        self.command(py, CMD_RESET, "Reset")
+    }
+
+    fn init(&self, py: Python<'_>) -> PyResult<()> {
+        self.command(py, CMD_INIT, "Init")
+    }
+
+    fn arm(&self, py: Python<'_>) -> PyResult<()> {
+        self.command(py, CMD_ARM, "Arm")
+    }
+
+    /// Configure the trigger, INIT and ARM (same sequence as `POST /api/ila/trigger`).
+    /// Returns the trigger bits actually programmed; raises `ValueError` if the
+    /// configuration doesn't fit pod (0,0). `trigger_signals` names are ORed
+    /// into `trigger_bits`; `tick_divisor` decimates low-speed tick sampling.
+    #[pyo3(signature = (
+        trigger_type = "or_rising",
+        trigger_bits = 0,
+        post_trigger = 64,
+        trigger_signals = Vec::new(),
+        tick_divisor = None,
+    ))]
+    fn configure_trigger(
+        &self,
+        py: Python<'_>,
+        trigger_type: &str,
+        trigger_bits: u32,
+        post_trigger: u32,
+        trigger_signals: Vec<String>,
+        tick_divisor: Option<u32>,
+    ) -> PyResult<u32> {
+        let config = TriggerConfig {
+            trigger_type: trigger_type.into(),
+            trigger_bits,
+            post_trigger,
+            trigger_signals,
+            tick_divisor,
+            ..Default::default()
+        };
+        let checked = py.allow_threads(|| {
+            let resolved = self.ila.resolve_trigger_signals(&config)?;
+            self.ila.validate_trigger(&resolved).map(|()| resolved)
+        });
+        let config = checked.map_err(|errors| {
+            let messages: Vec<String> = errors.iter()
+                .map(|e| format!("{}: {}", e.field, e.message))
+                .collect();
+            PyValueError::new_err(messages.join("; "))
+        })?;
+        let bits = py
+            .allow_threads(|| self.ila.program_trigger(&config))
+            .map_err(PyRuntimeError::new_err)?;
+        self.command(py, CMD_INIT, "Init")?;
+        py.allow_threads(|| std::thread::sleep(std::time::Duration::from_millis(10)));
+        self.command(py, CMD_ARM, "Arm")?;
+        Ok(bits)
+    }
+
+    /// Read up to `count` samples from a hub/pod
+    #[pyo3(signature = (hub = 0, pod = 0, count = 2048))]
+    fn capture(&self, py: Python<'_>, hub: u8, pod: u8, count: u32) -> PyCapture {
+        py.allow_threads(|| self.ila.read_capture(hub, pod, count)).into()
+    }
+
+    /// Execute a raw wrapper command, returning RDATA
+    #[pyo3(signature = (cmd, addr = 0, wdata = 0))]
+    fn exec_cmd(&self, py: Python<'_>, cmd: u32, addr: u32, wdata: u32) -> PyResult<u32> {
+        py.allow_threads(|| self.ila.exec_cmd(cmd, addr, wdata))
+            .ok_or_else(|| PyRuntimeError::new_err(format!("Command 0x{:02X} failed", cmd)))
+    }
+
+    /// Read a raw wrapper register (`None` if out of range)
+    fn read_reg(&self, offset: usize) -> Option<u32> {
+        self.ila.read_reg(offset)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Ila(base_addr=0x{:08X})", self.ila.base_addr())
+    }
+}
@@ -0,0 +1,34 @@
+//! `sump_surfer` Python module
+//!
+//! - [`Ila`](driver::PyIla): direct driver access on the target (/dev/mem)
+//! - [`Client`](client::PyClient): REST client for a remote `sump-server`
+//!
+//! Both return the same typed capture objects (`Capture`, `Sample`,
+//! `CaptureStatus`), so tests can switch between on-target and off-target
+//! execution without changes. Build with `maturin develop` / `maturin build`.
+
+// pyo3 0.22's #[pymethods] expansion trips this lint on every PyResult return
+#![allow(clippy::useless_conversion)]
+
+use pyo3::prelude::*;
+
+mod capture;
+mod client;
+mod driver;
+
+/// Serialize a model value to a plain Python object (dicts/lists) via JSON
+fn to_py_object<T: serde::Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    Ok(py.import_bound("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+#[pymodule]
+fn sump_surfer(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<driver::PyIla>()?;
+    m.add_class::<client::PyClient>()?;
+    m.add_class::<capture::PyCapture>()?;
+    m.add_class::<capture::PySample>()?;
+    m.add_class::<capture::PyCaptureStatus>()?;
+    Ok(())
+}
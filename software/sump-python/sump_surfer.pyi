@@ -0,0 +1,59 @@
+from typing import Any, Dict, List, Optional, Sequence, Tuple
+
+import numpy as np
+import numpy.typing as npt
+
+class CaptureStatus:
+    armed: bool
+    pre_trigger: bool
+    triggered: bool
+    acquired: bool
+    init_in_progress: bool
+
+class Sample:
+    address: int
+    code: int
+    timestamp: int
+    data: int
+
+class Capture:
+    hub: int
+    pod: int
+    ts_bits: int
+    data_bits: int
+    status: CaptureStatus
+    armed_at_ms: Optional[int]
+    triggered_at_ms: Optional[int]
+    correlation_id: Optional[str]
+    trigger_offset_ms: Optional[int]
+    readout_error_address: Optional[int]
+    readout_error: Optional[str]
+    gaps: List[Tuple[int, str]]
+    samples: List[Sample]
+    def to_numpy(self) -> Dict[str, npt.NDArray[np.unsignedinteger]]: ...
+    def __len__(self) -> int: ...
+
+class Ila:
+    base_addr: int
+    def __init__(self, base_addr: int = 0x43C20000) -> None: ...
+    def info(self) -> Dict[str, Any]: ...
+    def status(self) -> CaptureStatus: ...
+    def reset(self) -> None: ...
+    def init(self) -> None: ...
+    def arm(self) -> None: ...
+    def configure_trigger(self, trigger_type: str = "or_rising", trigger_bits: int = 0, post_trigger: int = 64, trigger_signals: Sequence[str] = (), tick_divisor: Optional[int] = None) -> int: ...
+    def capture(self, hub: int = 0, pod: int = 0, count: int = 2048) -> Capture: ...
+    def exec_cmd(self, cmd: int, addr: int = 0, wdata: int = 0) -> int: ...
+    def read_reg(self, offset: int) -> Optional[int]: ...
+
+class Client:
+    url: str
+    def __init__(self, url: str, timeout: float = 30.0) -> None: ...
+    def info(self) -> Dict[str, Any]: ...
+    def status(self) -> CaptureStatus: ...
+    def reset(self) -> None: ...
+    def init(self) -> None: ...
+    def arm(self) -> None: ...
+    def configure_trigger(self, trigger_type: str = "or_rising", trigger_bits: int = 0, post_trigger: int = 64, trigger_signals: Sequence[str] = (), tick_divisor: Optional[int] = None) -> str: ...
+    def capture(self, hub: int = 0, pod: int = 0, count: int = 2048) -> Capture: ...
+    def read_reg(self, offset: int) -> Optional[int]: ...
@@ -4,8 +4,21 @@
 //!    - SUMP_PORT: HTTP server port (default: 8082)
 //!    - SUMP_AXI_ADDR: SUMP3 AXI base address (default: 0x43C20000)
 //!
-//! 2. Builds the Surfer WASM frontend using trunk (if not already built)
-//!    - Set SKIP_SURFER_BUILD=1 to skip this step
+//! 2. With feature `embed-frontend` (default), builds the Surfer WASM
+//!    frontend using trunk (if not already built)
+//!    - Set SUMP_SURFER_DIST=/path/to/dist to embed a prebuilt bundle
+//!      instead (e.g. built once by CI and reused for every target); trunk
+//!      is not run (relative paths are from the sump-server directory)
+//!    - Set SKIP_SURFER_BUILD=1 to skip this step; an existing dist is
+//!      still embedded, a missing one leaves the built-in status page
+//!    - The frontend is target independent: cross builds (e.g. the static
+//!      musl targets in `.cargo/config.toml`) only need trunk on the host
+//!      if no dist exists yet
+//!    - The embedded folder is passed to `Assets` as SUMP_SURFER_DIST_DIR
+//!
+//! 3. With feature `grpc`, generates the tonic service for `proto/sump.proto`
+//!    (messages are hand-written prost structs in `src/grpc.rs`, so no
+//!    protoc is needed)
 
 use std::path::Path;
 use std::process::Command;
@@ -33,18 +46,42 @@ fn main() {
     println!("cargo:rerun-if-env-changed=SUMP_PORT");
     println!("cargo:rerun-if-env-changed=SUMP_AXI_ADDR");
 
+    #[cfg(feature = "grpc")]
+    grpc_service();
+
     // ============================================
     // Part 2: Build Surfer WASM frontend
     // ============================================
-    
-    // Allow skipping surfer build (useful for CI or quick rebuilds)
-    if std::env::var("SKIP_SURFER_BUILD").is_ok() {
-        println!("cargo:warning=Skipping Surfer WASM build (SKIP_SURFER_BUILD set)");
+
+    if std::env::var_os("CARGO_FEATURE_EMBED_FRONTEND").is_none() {
+        return;
+    }
+
+    // Prebuilt bundle: embed it as is
+    println!("cargo:rerun-if-env-changed=SUMP_SURFER_DIST");
+    if let Some(dist) = std::env::var_os("SUMP_SURFER_DIST") {
+        let dist = Path::new(&dist);
+        let dist = dist
+            .canonicalize()
+            .unwrap_or_else(|e| panic!("SUMP_SURFER_DIST {}: {}", dist.display(), e));
+        if !dist.join("index.html").is_file() {
+            panic!("SUMP_SURFER_DIST {} has no index.html (expected a trunk dist folder)", dist.display());
+        }
+        println!("cargo:rerun-if-changed={}", dist.display());
+        println!("cargo:rustc-env=SUMP_SURFER_DIST_DIR={}", dist.display());
         return;
     }
 
     let surfer_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../surfer/surfer");
     let surfer_dist = surfer_dir.join("dist");
+    println!("cargo:rustc-env=SUMP_SURFER_DIST_DIR={}", surfer_dist.display());
+
+    // Allow skipping surfer build (useful for CI or quick rebuilds)
+    println!("cargo:rerun-if-env-changed=SKIP_SURFER_BUILD");
+    if std::env::var("SKIP_SURFER_BUILD").is_ok() {
+        println!("cargo:warning=Skipping Surfer WASM build (SKIP_SURFER_BUILD set)");
+        return;
+    }
 
     // Track surfer source changes for rebuild
     println!("cargo:rerun-if-changed=../../surfer/surfer/src");
@@ -84,10 +121,36 @@ fn main() {
             }
             Err(e) => {
                 panic!(
-                    "Failed to run 'trunk build'. Make sure trunk is installed (cargo install trunk). Error: {}",
+                    "Failed to run 'trunk build'. Make sure trunk is installed (cargo install trunk), \
+                     or set SKIP_SURFER_BUILD=1 or build with --no-default-features. Error: {}",
                     e
                 );
             }
         }
     }
 }
+
+/// Generate the `sump.v1.Ila` server; keep in sync with `proto/sump.proto`
+#[cfg(feature = "grpc")]
+fn grpc_service() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::grpc::proto::{}", input))
+            .output_type(format!("crate::grpc::proto::{}", output))
+            .codec_path("tonic::codec::ProstCodec")
+    };
+    let service = Service::builder()
+        .name("Ila")
+        .package("sump.v1")
+        .method(method("get_info", "GetInfo", "Empty", "IlaInfo").build())
+        .method(method("get_status", "GetStatus", "Empty", "CaptureStatus").build())
+        .method(method("arm", "Arm", "Empty", "CommandResult").build())
+        .method(method("configure_trigger", "ConfigureTrigger", "TriggerConfig", "CommandResult").build())
+        .method(method("stream_capture", "StreamCapture", "CaptureRequest", "SampleBatch").server_streaming().build())
+        .build();
+    Builder::new().build_client(false).compile(&[service]);
+}
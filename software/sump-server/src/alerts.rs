@@ -0,0 +1,361 @@
+//! Alert rules
+//!
+//! `SUMP_ALERTS` names a JSON file of rules evaluated on every capture the
+//! server reads out, for soak runs where the ILA acts as a monitor:
+//!
+//! ```json
+//! {"rules": [
+//!    {"name": "overflow", "pod": 1, "signal": "fifo_overflow", "ever": 1},
+//!    {"name": "uart 0xFF", "decoder": "uart", "params": {"rx": "uart_rx"}, "contains": 255},
+//!    {"name": "i2c nack", "decoder": "i2c", "params": {"scl": "scl", "sda": "sda"}, "decode_errors": true}],
+//!  "webhooks": ["http://monitor.lab:8080/sump"],
+//!  "mqtt": {"broker": "monitor.lab:1883", "topic": "sump/alerts"}}
+//! ```
+//!
+//! A rule watches one pod (`hub`, `pod`, default 0.0) and matches when a
+//! `signal` takes the value `ever` in any sample, or when a `decoder` run
+//! with `params` (text values, as in `/api/ila/decoders`) produces an
+//! annotation of value `contains` or, with `decode_errors`, any malformed
+//! item. A match logs a warning, is kept for `GET /api/ila/alerts`, and is
+//! POSTed as an `AlertEvent` to each webhook and published (QoS 0) to the
+//! MQTT topic.
+//!
+//! Captures nobody reads are read out for the rules' pods once the ILA is
+//! idle, as `hooks` detect completion, so an armed soak run is checked
+//! without a client. Each capture is evaluated once per pod.
+
+use axum::{extract::State, http::Extensions, response::Json};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use sump_driver::clock::rfc3339;
+use sump_model::{AlertEvent, CaptureData, PodInfo};
+
+use crate::decode::{self, Params, Trace};
+use crate::digest;
+use crate::ila::IlaState;
+use crate::masks;
+
+/// Events kept for `GET /api/ila/alerts`
+const KEPT_EVENTS: usize = 100;
+
+/// Capture status poll interval while rules are configured
+const POLL: Duration = Duration::from_millis(500);
+
+/// Deadline of each webhook POST and MQTT exchange
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_client_id() -> String {
+    "sump-server".into()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    name: String,
+    #[serde(default)]
+    hub: u8,
+    #[serde(default)]
+    pod: u8,
+    /// Signal condition: `signal` equals `ever` in some sample
+    #[serde(default)]
+    signal: Option<String>,
+    #[serde(default)]
+    ever: Option<u32>,
+    /// Decoder condition: an annotation of value `contains`, or any error
+    #[serde(default)]
+    decoder: Option<String>,
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+    #[serde(default)]
+    contains: Option<u32>,
+    #[serde(default)]
+    decode_errors: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    /// `host:port` of the broker
+    broker: String,
+    topic: String,
+    #[serde(default = "default_client_id")]
+    client_id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertsConfig {
+    rules: Vec<Rule>,
+    #[serde(default)]
+    webhooks: Vec<String>,
+    #[serde(default)]
+    mqtt: Option<MqttConfig>,
+}
+
+impl AlertsConfig {
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+}
+
+/// Read and check the alerts file
+pub fn load(path: &Path) -> Result<AlertsConfig, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let config: AlertsConfig = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    for rule in &config.rules {
+        match (&rule.signal, &rule.decoder) {
+            (Some(_), None) if rule.ever.is_some() => {}
+            (Some(_), None) => return Err(format!("rule '{}': a signal rule needs 'ever'", rule.name)),
+            (None, Some(_)) if rule.contains.is_some() || rule.decode_errors => {}
+            (None, Some(_)) => {
+                return Err(format!("rule '{}': a decoder rule needs 'contains' or 'decode_errors'", rule.name))
+            }
+            _ => return Err(format!("rule '{}': give exactly one of 'signal' and 'decoder'", rule.name)),
+        }
+    }
+    if let Some(url) = config.webhooks.iter().find(|url| reqwest::Url::parse(url).is_err()) {
+        return Err(format!("invalid webhook URL '{}'", url));
+    }
+    Ok(config)
+}
+
+/// Configured rules and the events they raised
+#[derive(Default)]
+pub struct Alerts {
+    rules: Vec<Rule>,
+    events: Mutex<VecDeque<AlertEvent>>,
+    /// Last capture evaluated per pod
+    evaluated: Mutex<HashMap<(u8, u8), u64>>,
+    deliveries: Option<mpsc::UnboundedSender<AlertEvent>>,
+}
+
+impl Alerts {
+    /// Rules of `config`, and the receiver of their events for `deliver`
+    pub fn new(config: &AlertsConfig) -> (Self, mpsc::UnboundedReceiver<AlertEvent>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let alerts = Self { rules: config.rules.clone(), deliveries: Some(sender), ..Self::default() };
+        (alerts, receiver)
+    }
+
+    /// Pods some rule watches
+    fn pods(&self) -> Vec<(u8, u8)> {
+        let mut pods: Vec<_> = self.rules.iter().map(|r| (r.hub, r.pod)).collect();
+        pods.sort_unstable();
+        pods.dedup();
+        pods
+    }
+
+    fn was_evaluated(&self, capture_id: u64, hub: u8, pod: u8) -> bool {
+        self.evaluated.lock().unwrap().get(&(hub, pod)) == Some(&capture_id)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// What a rule matched in the capture, if anything
+fn matches(state: &IlaState, rule: &Rule, capture: &CaptureData, pod: &PodInfo) -> Result<Option<String>, String> {
+    if let (Some(name), Some(value)) = (&rule.signal, rule.ever) {
+        let field = decode::resolve_signal(pod, name)?;
+        let cycles = capture.elapsed_cycles();
+        let samples = capture.samples.iter().filter(|s| s.code != 0);
+        let hit = cycles.iter().zip(samples).find(|(_, s)| field.extract(s.data) == value);
+        return Ok(hit.map(|(cycle, _)| format!("{} == 0x{:X} at cycle {}", name, value, cycle)));
+    }
+    let name = rule.decoder.as_deref().unwrap_or_default();
+    let decoder = decode::find(state, name).ok_or_else(|| format!("unknown decoder '{}'", name))?;
+    let given: Map<String, Value> =
+        rule.params.iter().map(|(name, value)| (name.clone(), Value::String(value.clone()))).collect();
+    let params = Params::parse(&decoder.params(), &given, pod).map_err(|errors| {
+        errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect::<Vec<_>>().join(", ")
+    })?;
+    let annotations = decoder.decode(&Trace::new(capture, state.ila.hub_clock_hz(capture.hub)), &params)?;
+    let hit = annotations.iter().find(|a| {
+        (rule.contains.is_some() && a.value == rule.contains) || (rule.decode_errors && a.error)
+    });
+    Ok(hit.map(|a| format!("{} '{}' at cycle {}", name, a.label, a.start_cycle)))
+}
+
+/// Evaluate the rules of the capture's pod, once per capture; called for
+/// every capture recorded by `digest`
+pub fn evaluate(state: &IlaState, capture: &CaptureData) {
+    let alerts = &state.alerts;
+    let (hub, pod) = (capture.hub, capture.pod);
+    let mut rules = alerts.rules.iter().filter(|r| (r.hub, r.pod) == (hub, pod)).peekable();
+    if rules.peek().is_none() {
+        return;
+    }
+    if alerts.evaluated.lock().unwrap().insert((hub, pod), capture.sequence) == Some(capture.sequence) {
+        return;
+    }
+    let info = state.ila.enumerate_pod(hub, pod);
+    for rule in rules {
+        let detail = match matches(state, rule, capture, &info) {
+            Ok(Some(detail)) => detail,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("Alert rule '{}' on capture {}: {}", rule.name, capture.sequence, e);
+                continue;
+            }
+        };
+        tracing::warn!("Alert '{}' on capture {} of hub {} pod {}: {}", rule.name, capture.sequence, hub, pod, detail);
+        let fired_at_ms = now_ms();
+        let event = AlertEvent {
+            rule: rule.name.clone(),
+            capture_id: capture.sequence,
+            hub,
+            pod,
+            fired_at_ms,
+            fired_at: rfc3339(fired_at_ms),
+            detail,
+        };
+        if let Some(deliveries) = &alerts.deliveries {
+            let _ = deliveries.send(event.clone());
+        }
+        let mut events = alerts.events.lock().unwrap();
+        events.push_back(event);
+        while events.len() > KEPT_EVENTS {
+            events.pop_front();
+        }
+    }
+}
+
+/// Read out completed captures no one has read for the rules' pods, until shutdown
+pub async fn watch(state: Arc<IlaState>) {
+    let pods = state.alerts.pods();
+    let mut ticker = tokio::time::interval(POLL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut shutdown = state.shutdown_signal();
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+        if state.operation.lock().unwrap().is_some() {
+            continue;
+        }
+        let (id, status) = state.run(|s| (s.ila.sequence(), s.ila.capture_status())).await;
+        if !status.acquired {
+            continue;
+        }
+        for &(hub, pod) in &pods {
+            if state.alerts.was_evaluated(id, hub, pod) {
+                continue;
+            }
+            // A busy ILA is retried on the next poll
+            let _ = state.run_op("readout", move |s| {
+                let capture = s.ila.read_capture_all(hub, pod);
+                match &capture.readout_error {
+                    Some(e) => {
+                        tracing::warn!("Alerts: readout of hub {} pod {} failed: {}", hub, pod, e.message);
+                        // Not retried: the capture is skipped like one already evaluated
+                        s.alerts.evaluated.lock().unwrap().insert((hub, pod), id);
+                    }
+                    None => digest::record(s, &capture),
+                }
+            }).await;
+        }
+    }
+}
+
+/// MQTT remaining length
+fn mqtt_length(packet: &mut Vec<u8>, mut length: usize) {
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+        packet.push(if length > 0 { byte | 0x80 } else { byte });
+        if length == 0 {
+            return;
+        }
+    }
+}
+
+/// MQTT packet of a fixed header byte and a body
+fn mqtt_packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    mqtt_length(&mut packet, body.len());
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn mqtt_string(body: &mut Vec<u8>, text: &str) {
+    body.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    body.extend_from_slice(text.as_bytes());
+}
+
+/// Publish one message with QoS 0 on a fresh MQTT 3.1.1 connection
+async fn mqtt_publish(config: &MqttConfig, payload: &[u8]) -> std::io::Result<()> {
+    let mut stream = tokio::net::TcpStream::connect(&config.broker).await?;
+    let mut connect = Vec::new();
+    mqtt_string(&mut connect, "MQTT");
+    // Protocol level 4, clean session, 60 s keep-alive
+    connect.extend_from_slice(&[4, 0x02, 0, 60]);
+    mqtt_string(&mut connect, &config.client_id);
+    stream.write_all(&mqtt_packet(0x10, &connect)).await?;
+
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack).await?;
+    if connack[0] != 0x20 || connack[3] != 0 {
+        let message = format!("broker refused the connection (return code {})", connack[3]);
+        return Err(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, message));
+    }
+
+    let mut publish = Vec::new();
+    mqtt_string(&mut publish, &config.topic);
+    publish.extend_from_slice(payload);
+    stream.write_all(&mqtt_packet(0x30, &publish)).await?;
+    stream.write_all(&[0xE0, 0]).await?;
+    stream.shutdown().await
+}
+
+/// Send events to the webhooks and MQTT topic of `config`, until the state goes
+pub async fn deliver(config: AlertsConfig, mut events: mpsc::UnboundedReceiver<AlertEvent>) {
+    let http = reqwest::Client::builder().timeout(DELIVERY_TIMEOUT).build();
+    let http = match http {
+        Ok(http) => http,
+        Err(e) => {
+            tracing::error!("Alerts: failed to create HTTP client: {}", e);
+            return;
+        }
+    };
+    while let Some(event) = events.recv().await {
+        let payload = serde_json::to_vec(&event).expect("AlertEvent serializes");
+        for url in &config.webhooks {
+            let sent = http.post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(payload.clone())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = sent {
+                tracing::warn!("Alert '{}': webhook {} failed: {}", event.rule, url, e);
+            }
+        }
+        if let Some(mqtt) = &config.mqtt {
+            match tokio::time::timeout(DELIVERY_TIMEOUT, mqtt_publish(mqtt, &payload)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::warn!("Alert '{}': MQTT broker {} failed: {}", event.rule, mqtt.broker, e),
+                Err(_) => tracing::warn!("Alert '{}': MQTT broker {} timed out", event.rule, mqtt.broker),
+            }
+        }
+    }
+}
+
+/// GET /api/ila/alerts - Recent alert events, oldest first
+pub async fn get_alerts(State(state): State<Arc<IlaState>>, extensions: Extensions) -> Json<Vec<AlertEvent>> {
+    // A rule's detail quotes signal values, so partners don't see masked pods' events
+    let partner = masks::partner(&extensions);
+    let settings = state.settings.lock().unwrap().clone();
+    Json(state.alerts.events.lock().unwrap().iter()
+        .filter(|event| !partner || !masks::masked(&settings, event.hub, event.pod))
+        .cloned()
+        .collect())
+}
@@ -0,0 +1,123 @@
+//! Automatic capture analysis
+//!
+//! `HistoryConfig::analysis` runs a pipeline over every kept snapshot and
+//! attaches the `AnalysisReport` to its `HistorySnapshot`, and
+//! `SUMP_ARCHIVE_ANALYSIS` to each archived capture's `ArchiveEntry` (see
+//! `archive`), so the listings of `GET /api/ila/history` and
+//! `GET /api/ila/archive` already say which captures of an unattended run
+//! need a look: the capture digest, pulses shorter than `glitch_cycles` on
+//! any data bit, and a summary of each configured decoder. Decoder
+//! parameters are checked against the pod when a history run starts, and
+//! on each archived capture.
+
+use serde_json::{Map, Value};
+
+use sump_model::{AnalysisConfig, AnalysisReport, CaptureData, DecodeSummary, FieldError, Glitch, PodInfo};
+
+use crate::decode::{self, Params, Trace};
+use crate::ila::IlaState;
+
+/// Glitches listed in a report; the rest are only counted
+pub const MAX_GLITCHES: usize = 100;
+
+/// An `AnalysisConfig` with its decoders resolved against the pod
+pub struct Pipeline {
+    stats: bool,
+    glitch_cycles: u64,
+    data_bits: u16,
+    decoders: Vec<(String, Params)>,
+}
+
+impl Pipeline {
+    /// Check the decoders and their parameters; errors name fields under `analysis`
+    pub fn new(state: &IlaState, config: &AnalysisConfig, pod: &PodInfo) -> Result<Self, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut decoders = Vec::new();
+        for (i, step) in config.decoders.iter().enumerate() {
+            let field = format!("analysis.decoders[{}]", i);
+            let Some(decoder) = decode::find(state, &step.decoder) else {
+                errors.push(FieldError { field, message: format!("unknown decoder '{}'", step.decoder) });
+                continue;
+            };
+            let given: Map<String, Value> =
+                step.params.iter().map(|(name, value)| (name.clone(), Value::String(value.clone()))).collect();
+            match Params::parse(&decoder.params(), &given, pod) {
+                Ok(params) => decoders.push((step.decoder.clone(), params)),
+                Err(param_errors) => errors.extend(param_errors.into_iter().map(|e| FieldError {
+                    field: format!("{}.params.{}", field, e.field),
+                    message: e.message,
+                })),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Self {
+            stats: config.stats,
+            glitch_cycles: config.glitch_cycles as u64,
+            data_bits: pod.data_bits.min(32),
+            decoders,
+        })
+    }
+
+    /// Analyze a capture already recorded by `digest::record`
+    pub fn run(&self, state: &IlaState, capture: &CaptureData, hub_hz: u64) -> AnalysisReport {
+        let mut report = AnalysisReport::default();
+        if self.stats {
+            let digests = state.digests.lock().unwrap();
+            report.stats = digests.iter().rev()
+                .find(|d| d.capture_id == capture.sequence && (d.hub, d.pod) == (capture.hub, capture.pod))
+                .cloned();
+        }
+        if self.glitch_cycles > 0 {
+            let glitches = glitches(capture, self.data_bits, self.glitch_cycles);
+            report.glitch_count = glitches.len() as u32;
+            report.glitches = glitches.into_iter().take(MAX_GLITCHES).collect();
+        }
+        if !self.decoders.is_empty() {
+            let trace = Trace::new(capture, hub_hz);
+            for (name, params) in &self.decoders {
+                let decoder = decode::find(state, name).expect("checked by Pipeline::new");
+                report.decodes.push(match decoder.decode(&trace, params) {
+                    Ok(annotations) => DecodeSummary {
+                        decoder: name.clone(),
+                        annotations: annotations.len() as u32,
+                        errors: annotations.iter().filter(|a| a.error).count() as u32,
+                        failed: None,
+                    },
+                    Err(message) => {
+                        DecodeSummary { decoder: name.clone(), annotations: 0, errors: 0, failed: Some(message) }
+                    }
+                });
+            }
+        }
+        report.findings = report.glitch_count
+            + report.decodes.iter().map(|d| d.errors + d.failed.is_some() as u32).sum::<u32>();
+        report
+    }
+}
+
+/// Pulses of each data bit narrower than `max_cycles`, in bit then time order
+fn glitches(capture: &CaptureData, data_bits: u16, max_cycles: u64) -> Vec<Glitch> {
+    let cycles = capture.elapsed_cycles();
+    let data: Vec<u32> = capture.samples.iter().filter(|s| s.code != 0).map(|s| s.data).collect();
+    let mut found = Vec::new();
+    for bit in 0..data_bits as u8 {
+        let mut level = None;
+        let mut last_edge: Option<u64> = None;
+        for (&t, &d) in cycles.iter().zip(&data) {
+            let value = (d >> bit) & 1;
+            match level {
+                Some(old) if old != value => {
+                    if let Some(start) = last_edge.filter(|&start| t - start < max_cycles) {
+                        found.push(Glitch { bit, cycle: start, width: t - start, level: old as u8 });
+                    }
+                    last_edge = Some(t);
+                }
+                _ => {}
+            }
+            level = Some(value);
+        }
+    }
+    found
+}
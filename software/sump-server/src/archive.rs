@@ -0,0 +1,392 @@
+//! On-disk capture archive
+//!
+//! `SUMP_ARCHIVE_DIR` keeps every completed capture on disk: once the ILA
+//! is idle, each visible pod's RAM is dumped (the format of
+//! `GET /api/ila/capture/:hub/:pod/raw`) and stored zstd-compressed as
+//! `<id>-<hub>.<pod>.sumpraw.zst`, since eMMC space is what limits
+//! retention on the target. Beyond `SUMP_ARCHIVE_KEEP` entries (default
+//! 256) the oldest are deleted. `GET /api/ila/archive` lists the entries
+//! with their raw and compressed sizes; reads decompress transparently.
+//!
+//! `SUMP_ARCHIVE_ANALYSIS` names a JSON file of per-pod analysis pipelines
+//! (see `analysis`), each an `AnalysisConfig` with the pod it applies to:
+//!
+//! ```json
+//! [{"hub": 0, "pod": 1, "stats": true, "glitch_cycles": 2,
+//!   "decoders": [{"decoder": "uart", "params": {"rx": "uart_rx"}}]}]
+//! ```
+//!
+//! Each archived capture of such a pod is analyzed and the `AnalysisReport`
+//! stored with its entry, so the listing says which captures need a look.
+//!
+//! With `SUMP_ARCHIVE_HDF5=1` (feature `hdf5`) the decoded pods of each
+//! capture are also written to `<id>.h5` (see `hdf5`), deleted with the
+//! capture's last entry.
+//!
+//! Completion is detected by polling, as `hooks` do, so captures armed by
+//! any client or a SIGUSR1 snapshot are archived alike, once per capture.
+//! Signal masks apply to archive reads on the read-only listener.
+
+use axum::{
+    extract::{Path, State},
+    http::{Extensions, StatusCode},
+    response::{Json, Response},
+};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path as FilePath, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+use sump_driver::clock::rfc3339;
+use sump_model::{AnalysisConfig, AnalysisReport, ArchiveEntry, ArchiveListing, CaptureData, RawRamHeader};
+#[cfg(feature = "hdf5")]
+use sump_model::TriggerConfig;
+
+use crate::analysis::Pipeline;
+use crate::digest;
+use crate::ila::{raw_dump, raw_dump_response, IlaState};
+use crate::masks;
+use crate::persist;
+use crate::settings;
+use crate::validate;
+
+/// Default `SUMP_ARCHIVE_KEEP`
+pub const DEFAULT_KEEP: usize = 256;
+
+/// Entries of the archive, in its directory
+const INDEX_FILE: &str = "index.json";
+
+/// Capture status poll interval while archiving
+const POLL: Duration = Duration::from_millis(500);
+
+/// Stored entries a slow `subscribe` receiver may fall behind by
+const NOTIFY_BACKLOG: usize = 64;
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+fn file_name(id: u64, hub: u8, pod: u8) -> String {
+    format!("{}-{}.{}.sumpraw.zst", id, hub, pod)
+}
+
+fn hdf5_name(id: u64) -> String {
+    format!("{}.h5", id)
+}
+
+/// Analysis of one pod's archived captures
+#[derive(Debug, Deserialize)]
+pub struct PodAnalysis {
+    #[serde(default)]
+    hub: u8,
+    #[serde(default)]
+    pod: u8,
+    #[serde(flatten)]
+    config: AnalysisConfig,
+}
+
+/// Read the `SUMP_ARCHIVE_ANALYSIS` file; decoders are checked against the
+/// pod on each capture, since the pod may not be enumerated yet
+pub fn load_analysis(path: &FilePath) -> Result<Vec<PodAnalysis>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let analyses: Vec<PodAnalysis> = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    for (i, a) in analyses.iter().enumerate() {
+        if analyses[..i].iter().any(|b| (b.hub, b.pod) == (a.hub, a.pod)) {
+            return Err(format!("hub {} pod {} is listed twice", a.hub, a.pod));
+        }
+    }
+    Ok(analyses)
+}
+
+/// The archive directory and its entries, oldest first
+pub struct Archive {
+    dir: PathBuf,
+    keep: usize,
+    entries: Mutex<VecDeque<ArchiveEntry>>,
+    analyses: Vec<PodAnalysis>,
+    hdf5: bool,
+    stored: broadcast::Sender<ArchiveEntry>,
+}
+
+impl Archive {
+    /// Open `dir`, creating it if needed, with what earlier runs archived
+    pub fn open(dir: PathBuf, keep: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let mut entries: VecDeque<ArchiveEntry> = persist::load_json(&dir, INDEX_FILE).unwrap_or_default();
+        entries.retain(|e| dir.join(file_name(e.id, e.hub, e.pod)).is_file());
+        Ok(Self {
+            dir,
+            keep: keep.max(1),
+            entries: Mutex::new(entries),
+            analyses: Vec::new(),
+            hdf5: false,
+            stored: broadcast::channel(NOTIFY_BACKLOG).0,
+        })
+    }
+
+    pub fn with_analyses(mut self, analyses: Vec<PodAnalysis>) -> Self {
+        self.analyses = analyses;
+        self
+    }
+
+    /// Also write each capture as `<id>.h5`
+    pub fn with_hdf5(mut self, hdf5: bool) -> Self {
+        self.hdf5 = hdf5;
+        self
+    }
+
+    fn analysis(&self, hub: u8, pod: u8) -> Option<&AnalysisConfig> {
+        self.analyses.iter().find(|a| (a.hub, a.pod) == (hub, pod)).map(|a| &a.config)
+    }
+
+    pub fn describe(&self) -> String {
+        let hdf5 = if self.hdf5 { ", with HDF5 copies" } else { "" };
+        format!("{} (keeping {}{})", self.dir.display(), self.keep, hdf5)
+    }
+
+    /// Each entry as it's stored (see `hooks`)
+    pub fn subscribe(&self) -> broadcast::Receiver<ArchiveEntry> {
+        self.stored.subscribe()
+    }
+
+    /// The compressed file of an entry
+    pub fn path(&self, entry: &ArchiveEntry) -> PathBuf {
+        self.dir.join(file_name(entry.id, entry.hub, entry.pod))
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        self.entries.lock().unwrap().iter().any(|e| e.id == id)
+    }
+
+    /// Compress and store a raw dump with its analysis, then delete the
+    /// oldest beyond `keep`
+    fn store(&self, header: &RawRamHeader, words: &[u32], report: Option<AnalysisReport>) -> io::Result<ArchiveEntry> {
+        let raw = raw_dump(header, words);
+        let compressed = zstd::encode_all(raw.as_slice(), zstd::DEFAULT_COMPRESSION_LEVEL)?;
+        let (id, hub, pod) = (header.sequence, header.hub, header.pod);
+        let name = file_name(id, hub, pod);
+        let tmp = self.dir.join(format!(".{}.tmp", name));
+        std::fs::write(&tmp, &compressed)?;
+        std::fs::rename(&tmp, self.dir.join(&name))?;
+
+        let archived_at_ms = now_ms();
+        let entry = ArchiveEntry {
+            id,
+            hub,
+            pod,
+            archived_at_ms,
+            archived_at: rfc3339(archived_at_ms),
+            raw_bytes: raw.len() as u64,
+            compressed_bytes: compressed.len() as u64,
+            report,
+        };
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|e| (e.id, e.hub, e.pod) != (id, hub, pod));
+        entries.push_back(entry.clone());
+        while entries.len() > self.keep {
+            let Some(old) = entries.pop_front() else { break };
+            let path = self.path(&old);
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("Archive: cannot delete {}: {}", path.display(), e);
+            }
+            if self.hdf5 && !entries.iter().any(|e| e.id == old.id) {
+                let path = self.dir.join(hdf5_name(old.id));
+                if let Err(e) = std::fs::remove_file(&path) {
+                    tracing::warn!("Archive: cannot delete {}: {}", path.display(), e);
+                }
+            }
+        }
+        persist::save_json(&self.dir, INDEX_FILE, &*entries)?;
+        // No receiver without archive hooks
+        let _ = self.stored.send(entry.clone());
+        Ok(entry)
+    }
+
+    /// Write the decoded pods of capture `id` to `<id>.h5`
+    #[cfg(feature = "hdf5")]
+    fn store_hdf5(&self, s: &IlaState, id: u64, pods: &[crate::hdf5::PodCapture]) -> io::Result<PathBuf> {
+        let trigger = persist::load_json::<TriggerConfig>(&s.state_dir, persist::LAST_TRIGGER_FILE);
+        let name = hdf5_name(id);
+        let tmp = self.dir.join(format!(".{}.tmp", name));
+        crate::hdf5::write(&tmp, id, trigger.as_ref(), pods)?;
+        let path = self.dir.join(name);
+        std::fs::rename(&tmp, &path)?;
+        Ok(path)
+    }
+
+    /// Decompress an archived dump
+    fn load(&self, id: u64, hub: u8, pod: u8) -> Result<(RawRamHeader, Vec<u32>), validate::Invalid> {
+        let not_archived = || (StatusCode::NOT_FOUND, format!("Capture {} of hub {} pod {} is not archived", id, hub, pod));
+        if !self.entries.lock().unwrap().iter().any(|e| (e.id, e.hub, e.pod) == (id, hub, pod)) {
+            return Err(not_archived());
+        }
+        let path = self.dir.join(file_name(id, hub, pod));
+        let compressed = std::fs::read(&path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => not_archived(),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("{}: {}", path.display(), e)),
+        })?;
+        let raw = zstd::decode_all(compressed.as_slice())
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{}: {}", path.display(), e)))?;
+        sump_client::parse_raw_dump(&raw).ok_or_else(|| {
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("{}: not a raw dump of a supported schema", path.display()))
+        })
+    }
+}
+
+/// Run the pod's analysis on its decoded dump, if one is configured
+fn analyze(s: &IlaState, archive: &Archive, capture: &CaptureData) -> Option<AnalysisReport> {
+    let (hub, pod) = (capture.hub, capture.pod);
+    let config = archive.analysis(hub, pod)?;
+    let pipeline = match Pipeline::new(s, config, &s.ila.enumerate_pod(hub, pod)) {
+        Ok(pipeline) => pipeline,
+        Err(errors) => {
+            let errors: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+            tracing::error!("Archive analysis of hub {} pod {}: {}", hub, pod, errors.join("; "));
+            return None;
+        }
+    };
+    digest::record(s, capture);
+    let report = pipeline.run(s, capture, s.ila.hub_clock_hz(hub));
+    if report.findings > 0 {
+        tracing::warn!("Archive: capture {} of hub {} pod {} analysis has {} findings", capture.sequence, hub, pod, report.findings);
+    }
+    Some(report)
+}
+
+/// Dump, analyze and store every visible pod of capture `id`
+fn archive_capture(s: &IlaState, id: u64) {
+    let Some(archive) = &s.archive else { return };
+    #[cfg(feature = "hdf5")]
+    let mut decoded = Vec::new();
+    for hub in s.info().hubs {
+        for pod in hub.pods {
+            let stored = s.ila.dump_ram(hub.index, pod.index).and_then(|(header, words)| {
+                // Decoded once, for the analysis and the HDF5 copy
+                let capture = (archive.hdf5 || archive.analysis(hub.index, pod.index).is_some())
+                    .then(|| s.ila.capture_from_dump(&header, &words));
+                let report = capture.as_ref().and_then(|capture| analyze(s, archive, capture));
+                let entry = archive.store(&header, &words, report).map_err(|e| e.to_string())?;
+                #[cfg(feature = "hdf5")]
+                if let Some(capture) = capture.filter(|_| archive.hdf5) {
+                    let (name, signals) = (pod.name.clone(), pod.signals.clone());
+                    decoded.push(crate::hdf5::PodCapture { capture, name, signals, hub_hz: hub.freq_hz });
+                }
+                Ok(entry)
+            });
+            match stored {
+                Ok(entry) => tracing::info!(
+                    "Archived capture {} of hub {} pod {}: {} bytes, {} compressed",
+                    id, hub.index, pod.index, entry.raw_bytes, entry.compressed_bytes
+                ),
+                Err(e) => tracing::error!("Archive: capture {} of hub {} pod {}: {}", id, hub.index, pod.index, e),
+            }
+        }
+    }
+    #[cfg(feature = "hdf5")]
+    if !decoded.is_empty() {
+        match archive.store_hdf5(s, id, &decoded) {
+            Ok(path) => tracing::info!("Archived capture {} as {}", id, path.display()),
+            Err(e) => tracing::error!("Archive: capture {} as HDF5: {}", id, e),
+        }
+    }
+}
+
+/// Archive each completed capture until shutdown
+pub async fn run(state: Arc<IlaState>) {
+    let Some(archive) = state.archive.as_ref() else { return };
+    let mut ticker = tokio::time::interval(POLL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut shutdown = state.shutdown_signal();
+    // Failed pods are logged, not retried
+    let mut last = None;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+        if state.operation.lock().unwrap().is_some() {
+            continue;
+        }
+        let (id, status) = state.run(|s| (s.ila.sequence(), s.ila.capture_status())).await;
+        if !status.acquired || last == Some(id) || archive.contains(id) {
+            continue;
+        }
+        // A busy ILA is retried on the next poll
+        if state.run_op("archive", move |s| archive_capture(s, id)).await.is_ok() {
+            last = Some(id);
+        }
+    }
+}
+
+/// The archive, or 404 when `SUMP_ARCHIVE_DIR` isn't set
+fn configured(state: &IlaState) -> Result<&Archive, validate::Invalid> {
+    state.archive.as_ref().ok_or((StatusCode::NOT_FOUND, "No capture archive (SUMP_ARCHIVE_DIR is not set)".into()))
+}
+
+/// A visible pod's archived dump, decompressed on the blocking pool, with
+/// the request's signal mask applied
+async fn masked_dump(
+    state: &Arc<IlaState>,
+    (id, hub, pod): (u64, u8, u8),
+    extensions: &Extensions,
+) -> Result<(RawRamHeader, Vec<u32>), validate::Invalid> {
+    let partner = masks::partner(extensions);
+    state.run(move |s| {
+        if settings::is_hidden(&s.settings.lock().unwrap(), hub, pod) {
+            return Err((StatusCode::NOT_FOUND, format!("Pod {} not found on hub {}", pod, hub)));
+        }
+        let (header, mut words) = configured(s)?.load(id, hub, pod)?;
+        if let Some(mask) = masks::for_request(s, partner, hub, pod) {
+            mask.blank_dump(&header, &mut words);
+        }
+        Ok((header, words))
+    }).await
+}
+
+/// GET /api/ila/archive - Archived captures with their raw and compressed
+/// sizes and analysis reports
+pub async fn get_archive(
+    State(state): State<Arc<IlaState>>,
+    extensions: Extensions,
+) -> Result<Json<ArchiveListing>, validate::Invalid> {
+    let archive = configured(&state)?;
+    let settings = state.settings.lock().unwrap().clone();
+    let partner = masks::partner(&extensions);
+    let mut entries: Vec<ArchiveEntry> = archive.entries.lock().unwrap().iter()
+        .filter(|e| !settings::is_hidden(&settings, e.hub, e.pod))
+        .cloned()
+        .collect();
+    // Analysis reports cover every signal of the pod
+    for entry in entries.iter_mut().filter(|e| partner && masks::masked(&settings, e.hub, e.pod)) {
+        entry.report = None;
+    }
+    Ok(Json(ArchiveListing {
+        raw_bytes: entries.iter().map(|e| e.raw_bytes).sum(),
+        compressed_bytes: entries.iter().map(|e| e.compressed_bytes).sum(),
+        keep: archive.keep as u32,
+        entries,
+    }))
+}
+
+/// GET /api/ila/archive/:id/:hub/:pod - An archived capture, decoded
+pub async fn get_archived_capture(
+    State(state): State<Arc<IlaState>>,
+    Path(key): Path<(u64, u8, u8)>,
+    extensions: Extensions,
+) -> Result<Json<CaptureData>, validate::Invalid> {
+    let (header, words) = masked_dump(&state, key, &extensions).await?;
+    Ok(Json(sump_driver::capture_from_raw(&header, &words)))
+}
+
+/// GET /api/ila/archive/:id/:hub/:pod/raw - An archived raw dump, decompressed
+pub async fn get_archived_raw(
+    State(state): State<Arc<IlaState>>,
+    Path(key): Path<(u64, u8, u8)>,
+    extensions: Extensions,
+) -> Result<Response, validate::Invalid> {
+    let (header, words) = masked_dump(&state, key, &extensions).await?;
+    Ok(raw_dump_response(&header, &words))
+}
@@ -0,0 +1,192 @@
+//! Arm timeouts
+//!
+//! `POST /api/ila/arm?timeout_s=N` and trigger requests with `timeout_s`
+//! start a timer task. If the capture hasn't triggered when it expires, the
+//! `on_timeout` action runs as operation `timeout` (waiting while another
+//! operation holds the ILA):
+//!
+//! - `disarm` - IDLE; pod RAM keeps the pre-trigger samples
+//! - `force_trigger` - re-program an immediate trigger and ARM, so the
+//!   capture completes with whatever the bus shows; the requested trigger
+//!   stays replaced until the next trigger request
+//! - `rearm` - INIT and ARM again with the same trigger and timeout
+//!
+//! Any other ARM (a new request, a sequence, a batch) supersedes the timer.
+
+use axum::{extract::State, response::Json};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sump_driver::*;
+use sump_model::{ArmTimeout, FieldError, TimeoutAction, TriggerConfig};
+
+use crate::ila::IlaState;
+
+/// Longest accepted timeout (one week)
+const MAX_TIMEOUT_S: f64 = 7.0 * 24.0 * 3600.0;
+
+/// Retry interval while another operation holds the ILA at expiry
+const BUSY_RETRY: Duration = Duration::from_millis(100);
+
+/// The timeout of the current capture
+pub struct Pending {
+    status: ArmTimeout,
+    deadline: Instant,
+    /// Trigger to re-apply for `rearm`; a plain ARM when absent
+    trigger: Option<TriggerConfig>,
+}
+
+/// Pending timeout, if any (see `IlaState::arm_timeout`)
+pub type PendingTimeout = Option<Pending>;
+
+/// Validate `timeout_s` and `on_timeout`
+pub fn check(timeout_s: Option<f64>, on_timeout: Option<TimeoutAction>) -> Result<(), Vec<FieldError>> {
+    let message = match timeout_s {
+        Some(t) if !t.is_finite() || t <= 0.0 => format!("must be a positive number of seconds, got {}", t),
+        Some(t) if t > MAX_TIMEOUT_S => format!("must be at most {} s", MAX_TIMEOUT_S),
+        None if on_timeout.is_some() => "on_timeout needs timeout_s".into(),
+        _ => return Ok(()),
+    };
+    Err(vec![FieldError { field: "timeout_s".into(), message }])
+}
+
+/// Start the timer for the capture just armed; no-op without `timeout_s`
+pub fn start(
+    state: &Arc<IlaState>,
+    timeout_s: Option<f64>,
+    on_timeout: Option<TimeoutAction>,
+    trigger: Option<TriggerConfig>,
+) {
+    let Some(timeout_s) = timeout_s else {
+        return;
+    };
+    let capture_id = state.ila.sequence();
+    let on_timeout = on_timeout.unwrap_or_default();
+    let status = ArmTimeout { timeout_s, on_timeout, capture_id, remaining_ms: 0, rearms: 0 };
+    let deadline = Instant::now() + Duration::from_secs_f64(timeout_s);
+    *state.arm_timeout.lock().unwrap() = Some(Pending { status, deadline, trigger });
+    tracing::info!("Capture {}: {:?} after {} s without a trigger", capture_id, on_timeout, timeout_s);
+    tokio::spawn(wait(state.clone(), capture_id));
+}
+
+/// Deadline of the timeout for `capture_id`, unless superseded
+fn deadline(state: &IlaState, capture_id: u64) -> Option<Instant> {
+    state.arm_timeout.lock().unwrap().as_ref()
+        .filter(|p| p.status.capture_id == capture_id)
+        .map(|p| p.deadline)
+}
+
+fn clear(state: &IlaState, capture_id: u64) {
+    let mut pending = state.arm_timeout.lock().unwrap();
+    if pending.as_ref().is_some_and(|p| p.status.capture_id == capture_id) {
+        *pending = None;
+    }
+}
+
+async fn wait(state: Arc<IlaState>, mut capture_id: u64) {
+    let mut shutdown = state.shutdown_signal();
+    loop {
+        let Some(deadline) = deadline(&state, capture_id) else {
+            return;
+        };
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline.into()) => {}
+            _ = shutdown.changed() => return,
+        }
+        let guard = loop {
+            if state.ila.sequence() != capture_id {
+                clear(&state, capture_id);
+                return;
+            }
+            match state.begin_op("timeout") {
+                Ok(guard) => break guard,
+                Err(_) => tokio::select! {
+                    _ = tokio::time::sleep(BUSY_RETRY) => {}
+                    _ = shutdown.changed() => return,
+                },
+            }
+        };
+        let rearmed = state.run(move |s| {
+            let _guard = guard;
+            expire(s, capture_id)
+        }).await;
+        match rearmed {
+            Some(id) => capture_id = id,
+            None => return,
+        }
+    }
+}
+
+/// Run the action if the capture is still waiting; the new capture id if re-armed
+fn expire(state: &IlaState, capture_id: u64) -> Option<u64> {
+    let status = state.ila.capture_status();
+    let (action, trigger) = {
+        let pending = state.arm_timeout.lock().unwrap();
+        let p = pending.as_ref().filter(|p| p.status.capture_id == capture_id)?;
+        (p.status.on_timeout, p.trigger.clone())
+    };
+    if state.ila.sequence() != capture_id || status.triggered || !status.armed {
+        clear(state, capture_id);
+        return None;
+    }
+
+    match action {
+        TimeoutAction::Disarm => {
+            match state.ila.exec_cmd(CMD_IDLE, 0, 0) {
+                Some(_) => tracing::warn!("Capture {} timed out without a trigger; disarmed", capture_id),
+                None => tracing::error!("Capture {} timed out; disarm failed", capture_id),
+            }
+        }
+        TimeoutAction::ForceTrigger => {
+            let (_, _, ram_depth) = state.ila.get_pod_config(0, 0);
+            let post = trigger.map_or(ram_depth / 2, |t| t.post_trigger);
+            match state.arm_immediate(post) {
+                Ok(_) => tracing::warn!(
+                    "Capture {} timed out without a trigger; forced capture {}",
+                    capture_id, state.ila.sequence()
+                ),
+                Err(e) => tracing::error!("Capture {} timed out; force trigger failed: {}", capture_id, e),
+            }
+        }
+        TimeoutAction::Rearm => {
+            let rearmed = match &trigger {
+                Some(t) => state.ila.resolve_trigger_signals(t)
+                    .map_err(|errors| errors.into_iter().map(|e| e.message).collect::<Vec<_>>().join("; "))
+                    .and_then(|resolved| state.apply_trigger(&resolved)),
+                None => state.ila.exec_cmd(CMD_INIT, 0, 0)
+                    .ok_or_else(|| failure_message("Init"))
+                    .and_then(|_| state.ila.wait_init())
+                    .and_then(|()| state.ila.exec_cmd(CMD_ARM, 0, 0).ok_or_else(|| failure_message("Arm"))),
+            };
+            if let Err(e) = rearmed {
+                tracing::error!("Capture {} timed out; re-arm failed: {}", capture_id, e);
+            } else {
+                let id = state.ila.sequence();
+                let mut pending = state.arm_timeout.lock().unwrap();
+                if let Some(p) = pending.as_mut().filter(|p| p.status.capture_id == capture_id) {
+                    p.status.capture_id = id;
+                    p.status.rearms += 1;
+                    p.deadline = Instant::now() + Duration::from_secs_f64(p.status.timeout_s);
+                    tracing::warn!(
+                        "Capture {} timed out without a trigger; re-armed as {} ({} re-arms)",
+                        capture_id, id, p.status.rearms
+                    );
+                    return Some(id);
+                }
+                return None;
+            }
+        }
+    }
+    clear(state, capture_id);
+    None
+}
+
+/// GET /api/ila/arm-timeout - Timeout of the current capture, if any
+pub async fn get_arm_timeout(State(state): State<Arc<IlaState>>) -> Json<Option<ArmTimeout>> {
+    let current = state.ila.sequence();
+    let pending = state.arm_timeout.lock().unwrap();
+    Json(pending.as_ref().filter(|p| p.status.capture_id == current).map(|p| ArmTimeout {
+        remaining_ms: p.deadline.saturating_duration_since(Instant::now()).as_millis() as u64,
+        ..p.status.clone()
+    }))
+}
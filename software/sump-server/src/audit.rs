@@ -0,0 +1,20 @@
+//! Audit log
+//!
+//! Requests that drive the wrapper directly (raw commands, register and
+//! stimulus writes) are logged under the `sump_server::audit` target with
+//! the client address, whether they came over REST or the console. Send
+//! that target to a file (see `logging`) to keep a record of who changed
+//! what during bring-up.
+
+use std::fmt::Display;
+use std::net::SocketAddr;
+
+/// Log `action` by `client` through `via` (`rest`, `console`)
+pub fn record(client: SocketAddr, via: &str, action: impl Display) {
+    tracing::info!(target: "sump_server::audit", "{} via {}: {}", client, via, action);
+}
+
+/// Audit entry of a raw wrapper command
+pub fn command(cmd: u32, addr: u32, wdata: u32) -> String {
+    format!("exec_cmd 0x{:02X} addr=0x{:08X} wdata=0x{:08X}", cmd, addr, wdata)
+}
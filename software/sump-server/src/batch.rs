@@ -0,0 +1,105 @@
+//! Batch command API
+//!
+//! `POST /api/ila/batch` runs an ordered list of operations as one logical
+//! operation, so scripted setups don't pay an HTTP round trip per step and
+//! can't be interleaved with other clients.
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use sump_driver::*;
+use sump_model::*;
+
+use crate::audit;
+use crate::ila::IlaState;
+
+/// Maximum number of operations per batch
+const MAX_OPS: usize = 256;
+
+fn step(success: bool, message: impl Into<String>, value: Option<u32>) -> BatchStepResult {
+    BatchStepResult { success, message: message.into(), value }
+}
+
+fn run_step(ila: &Ila, op: &BatchOp) -> BatchStepResult {
+    match op {
+        BatchOp::Reset => match ila.exec_cmd(CMD_RESET, 0, 0) {
+            Some(_) => step(true, "Reset complete", None),
+            None => step(false, failure_message("Reset"), None),
+        },
+        BatchOp::Init => match ila.exec_cmd(CMD_INIT, 0, 0).map(|_| ila.wait_init()) {
+            Some(Ok(())) => step(true, "Init complete", None),
+            Some(Err(message)) => step(false, message, None),
+            None => step(false, failure_message("Init"), None),
+        },
+        BatchOp::Arm => match ila.exec_cmd(CMD_ARM, 0, 0) {
+            Some(_) => step(true, "Armed", None),
+            None => step(false, failure_message("Arm"), None),
+        },
+        BatchOp::Trigger(config) => {
+            let checked = ila.resolve_trigger_signals(config)
+                .and_then(|resolved| ila.validate_trigger(&resolved).map(|()| resolved));
+            let config = match checked {
+                Ok(resolved) => resolved,
+                Err(errors) => {
+                    let messages: Vec<String> = errors.iter()
+                        .map(|e| format!("{}: {}", e.field, e.message))
+                        .collect();
+                    return step(false, format!("Invalid trigger: {}", messages.join("; ")), None);
+                }
+            };
+            match ila.program_trigger(&config) {
+                Ok(bits) => step(true, format!("Configured: type={}", config.trigger_type), Some(bits)),
+                Err(msg) => step(false, with_cause(msg), None),
+            }
+        }
+        BatchOp::Cmd { cmd, addr, wdata } => match ila.exec_cmd(*cmd, *addr, *wdata) {
+            Some(rdata) => step(true, format!("Command 0x{:02X} complete", cmd), Some(rdata)),
+            None => step(false, failure_message(&format!("Command 0x{:02X}", cmd)), None),
+        },
+    }
+}
+
+/// POST /api/ila/batch - Execute operations in order under one claim of the ILA
+pub async fn post_batch(
+    State(state): State<Arc<IlaState>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Json(request): Json<BatchRequest>,
+) -> Response {
+    if request.ops.len() > MAX_OPS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Too many operations ({} > {})", request.ops.len(), MAX_OPS),
+        ).into_response();
+    }
+    for op in &request.ops {
+        if let BatchOp::Cmd { cmd, addr, wdata } = *op {
+            audit::record(client, "rest", audit::command(cmd, addr, wdata));
+        }
+    }
+
+    let result = state.run_op("batch", move |s| {
+        let mut steps = Vec::with_capacity(request.ops.len());
+        for op in &request.ops {
+            let result = run_step(&s.ila, op);
+            let failed = !result.success;
+            steps.push(result);
+            if failed {
+                break;
+            }
+        }
+        BatchResult {
+            success: steps.len() == request.ops.len() && steps.iter().all(|r| r.success),
+            steps,
+        }
+    }).await;
+
+    match result {
+        Ok(result) => Json(result).into_response(),
+        Err(conflict) => conflict.into_response(),
+    }
+}
@@ -0,0 +1,236 @@
+//! bd_server-compatible TCP listener
+//!
+//! Speaks the BlackMesaLabs "backdoor" socket protocol of `bd_server.py`, so
+//! the upstream sump3.py GUI can connect to this server directly. Each packet
+//! is an 8-digit hex payload length followed by an ASCII command:
+//!
+//! - `w <addr> <d0> <d1> ...` - write DWORDs at incrementing addresses
+//! - `W <addr> <d0> <d1> ...` - write DWORDs to the same address
+//! - `r <addr> <n-1>` - read n DWORDs at incrementing addresses
+//! - `k <addr> <n-1>` - read n DWORDs from the same address
+//!
+//! Reads reply with space-separated 8-digit hex DWORDs, writes with `ok`.
+//! sump3.py drives the core through its control/data register pair at
+//! `SUMP_BD_CTRL_ADDR` (default 0x98) and the following DWORD. Accesses there
+//! are translated to wrapper commands; other addresses read as zero.
+//!
+//! A client's first packet claims the ILA like a REST request (409 there, an
+//! `error:` reply here), and the claim is held until the client has sent
+//! nothing for `IDLE_RELEASE`, so REST operations can't run between the
+//! packets of a sump3.py readout. Pod accesses are checked against the
+//! topology and `hidden_pods`.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use sump_driver::local_bus::*;
+use sump_driver::*;
+
+use crate::ila::IlaState;
+use crate::ops::OpGuard;
+use crate::tcp;
+use crate::validate;
+
+/// Largest accepted packet payload
+const MAX_PAYLOAD: usize = 1 << 20;
+
+/// Largest burst read per packet
+const MAX_READ_DWORDS: u32 = 1 << 16;
+
+/// Quiet time after which a client's claim on the ILA is released
+const IDLE_RELEASE: Duration = Duration::from_secs(2);
+
+/// Wrapper command for a core state command (executed on the CTRL write)
+fn state_cmd(core: u32) -> Option<u32> {
+    match core {
+        CORE_IDLE => Some(CMD_IDLE),
+        CORE_ARM => Some(CMD_ARM),
+        CORE_RESET => Some(CMD_RESET),
+        CORE_INIT => Some(CMD_INIT),
+        CORE_SLEEP => Some(CMD_SLEEP),
+        _ => None,
+    }
+}
+
+/// Wrapper read command for a core CTRL code, and whether it takes the
+/// hub/pod instance address
+fn read_cmd(core: u32) -> Option<(u32, bool)> {
+    let cmd = match core {
+        CORE_IDLE | CORE_ARM => CMD_RD_STATUS,
+        CORE_RD_HW_ID => CMD_RD_HW_ID,
+        CORE_RD_ANA_RAM_CFG => CMD_RD_ANA_RAM_CFG,
+        CORE_RD_TICK_FREQ => CMD_RD_TICK_FREQ,
+        CORE_RD_ANA_FIRST_PTR => CMD_RD_ANA_FIRST_PTR,
+        CORE_RD_RAM_DATA => CMD_RD_RAM_DATA,
+        CORE_RD_DIG_FIRST_PTR => CMD_RD_DIG_FIRST_PTR,
+        CORE_RD_DIG_CK_FREQ => CMD_RD_DIG_CK_FREQ,
+        CORE_RD_DIG_RAM_CFG => CMD_RD_DIG_RAM_CFG,
+        CORE_RD_REC_PROFILE => CMD_RD_REC_PROFILE,
+        CORE_RD_TRIG_SRC => CMD_RD_TRIG_SRC,
+        CORE_RD_VIEW_ROM_KB => CMD_RD_VIEW_ROM_KB,
+        CORE_RD_HUB_COUNT => CMD_RD_HUB_COUNT,
+        CORE_RD_POD_COUNT => return Some((CMD_RD_POD_COUNT, true)),
+        CORE_RW_POD_DATA => return Some((CMD_RD_POD_REG, true)),
+        CORE_RD_TRIG_SRC_POD => return Some((CMD_RD_TRIG_SRC_POD, true)),
+        CORE_RD_HUB_FREQ => return Some((CMD_RD_HUB_FREQ, true)),
+        CORE_RD_HUB_HW_CFG => return Some((CMD_RD_HUB_HW_CFG, true)),
+        CORE_RD_HUB_INSTANCE => return Some((CMD_RD_HUB_INSTANCE, true)),
+        CORE_RD_HUB_NAME_0_3 => return Some((CMD_RD_HUB_NAME_0_3, true)),
+        CORE_RD_HUB_NAME_4_7 => return Some((CMD_RD_HUB_NAME_4_7, true)),
+        CORE_RD_HUB_NAME_8_11 => return Some((CMD_RD_HUB_NAME_8_11, true)),
+        _ => return None,
+    };
+    Some((cmd, false))
+}
+
+/// Per-connection view of the core's control/data register pair
+#[derive(Default)]
+struct CoreRegs {
+    ctrl: u32,
+    /// Hub/pod/register selected with CTRL 0x32
+    inst_addr: u32,
+}
+
+impl CoreRegs {
+    /// The selected pod must exist and not be hidden
+    fn check_pod(&self, s: &IlaState) -> Result<(), String> {
+        let [_, hub, pod, _] = self.inst_addr.to_be_bytes();
+        validate::visible_pod(s, hub, pod).map_err(|(_, message)| message)
+    }
+
+    fn write(&mut self, s: &IlaState, ctrl_addr: u32, addr: u32, data: u32) -> Result<(), String> {
+        let ila = &s.ila;
+        if addr == ctrl_addr {
+            self.ctrl = data & 0x3F;
+            if let Some(cmd) = state_cmd(self.ctrl) {
+                ila.exec_cmd(cmd, 0, 0);
+            }
+        } else if addr == ctrl_addr + 4 {
+            match self.ctrl {
+                CORE_WR_INST_ADDR => self.inst_addr = data,
+                CORE_RW_POD_DATA => {
+                    self.check_pod(s)?;
+                    ila.exec_cmd(CMD_WR_POD_REG, self.inst_addr, data);
+                }
+                CORE_WR_TRIG_WIDTH => {
+                    ila.exec_cmd(CMD_WR_TRIG_WIDTH, self.inst_addr, data);
+                }
+                // Local writes share their codes with the wrapper
+                CMD_WR_USER_CTRL..=CMD_WR_RAM_PAGE => {
+                    ila.exec_cmd(self.ctrl, 0, data);
+                }
+                ctrl => tracing::debug!("bd_server: ignoring data write for CTRL 0x{:02X}", ctrl),
+            }
+        } else {
+            tracing::debug!("bd_server: ignoring write to 0x{:08X}", addr);
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, s: &IlaState, ctrl_addr: u32, addr: u32) -> Result<u32, String> {
+        if addr == ctrl_addr {
+            return Ok(self.ctrl);
+        }
+        if addr != ctrl_addr + 4 {
+            return Ok(0);
+        }
+        Ok(match read_cmd(self.ctrl) {
+            Some((cmd, true)) => {
+                if matches!(self.ctrl, CORE_RW_POD_DATA | CORE_RD_TRIG_SRC_POD) {
+                    self.check_pod(s)?;
+                }
+                s.ila.exec_cmd(cmd, self.inst_addr, 0).unwrap_or(0)
+            }
+            Some((cmd, false)) => s.ila.exec_cmd(cmd, 0, 0).unwrap_or(0),
+            None => {
+                tracing::debug!("bd_server: unsupported data read for CTRL 0x{:02X}", self.ctrl);
+                0
+            }
+        })
+    }
+
+    /// Execute one packet and return the reply payload
+    fn execute(&mut self, s: &IlaState, ctrl_addr: u32, packet: &str) -> String {
+        let mut tokens = packet.split_whitespace();
+        let cmd = tokens.next().unwrap_or("");
+        let args: Result<Vec<u32>, _> = tokens.map(|t| u32::from_str_radix(t, 16)).collect();
+        let Ok(args) = args else {
+            return format!("error: malformed arguments in '{}'", packet.trim());
+        };
+        let Some((&addr, rest)) = args.split_first() else {
+            return format!("error: missing address in '{}'", packet.trim());
+        };
+
+        let reply = match cmd {
+            "w" | "W" => rest.iter().enumerate()
+                .try_for_each(|(i, &data)| {
+                    let addr = if cmd == "w" { addr.wrapping_add(4 * i as u32) } else { addr };
+                    self.write(s, ctrl_addr, addr, data)
+                })
+                .map(|()| "ok".to_string()),
+            "r" | "k" => {
+                let count = rest.first().copied().unwrap_or(0).saturating_add(1).min(MAX_READ_DWORDS);
+                (0..count)
+                    .map(|i| {
+                        let addr = if cmd == "r" { addr.wrapping_add(4 * i) } else { addr };
+                        self.read(s, ctrl_addr, addr).map(|value| format!("{:08x}", value))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|words| words.join(" "))
+            }
+            _ => Err(format!("unknown command '{}'", cmd)),
+        };
+        reply.unwrap_or_else(|message| format!("error: {}", message))
+    }
+}
+
+async fn serve_connection(mut stream: TcpStream, state: Arc<IlaState>, ctrl_addr: u32) -> io::Result<()> {
+    let mut regs = CoreRegs::default();
+    let mut claim: Option<OpGuard> = None;
+    loop {
+        // Times a peek, not the read, which could drop a partial header
+        if claim.is_some() && tokio::time::timeout(IDLE_RELEASE, stream.peek(&mut [0u8])).await.is_err() {
+            claim = None;
+        }
+        let mut header = [0u8; 8];
+        match stream.read_exact(&mut header).await {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            result => result?,
+        };
+        let len = std::str::from_utf8(&header)
+            .ok()
+            .and_then(|h| usize::from_str_radix(h, 16).ok())
+            .filter(|&len| len <= MAX_PAYLOAD)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad packet header"))?;
+
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await?;
+        let packet = String::from_utf8_lossy(&payload).into_owned();
+
+        let claimed = match claim.take() {
+            Some(guard) => Ok(guard),
+            None => state.claim_op("bd_server").await,
+        };
+        let reply = match claimed {
+            Ok(guard) => {
+                let (reply, returned) = state.run(move |s| (regs.execute(s, ctrl_addr, &packet), regs)).await;
+                regs = returned;
+                claim = Some(guard);
+                reply
+            }
+            Err(conflict) => format!("error: {}", conflict.0.message),
+        };
+
+        stream.write_all(format!("{:08x}{}", reply.len(), reply).as_bytes()).await?;
+    }
+}
+
+/// Accept bd_server connections until shutdown
+pub async fn run(state: Arc<IlaState>, addr: SocketAddr, ctrl_addr: u32) {
+    tracing::info!("bd_server: CTRL at 0x{:08X}", ctrl_addr);
+    tcp::listen(state, addr, "bd_server", move |stream, state| serve_connection(stream, state, ctrl_addr)).await
+}
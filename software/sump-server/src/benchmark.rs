@@ -0,0 +1,103 @@
+//! Register access benchmark
+//!
+//! `POST /api/ila/benchmark` times raw register reads and writes, local and
+//! serial-bus command round trips, and an uncached RLE readout, to show
+//! whether the wrapper RTL or the software path dominates. It runs as an
+//! exclusive operation but leaves the capture untouched.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Instant;
+
+use sump_driver::*;
+use sump_model::*;
+
+use crate::ila::IlaState;
+use crate::validate;
+
+/// Default accesses per measurement
+const DEFAULT_ITERATIONS: u32 = 1000;
+
+/// Upper bound on iterations, so one request can't hold the ILA for minutes
+const MAX_ITERATIONS: u32 = 100_000;
+
+/// Time `iterations` calls of `f`; `f` returns whether the access succeeded
+fn measure(iterations: u32, mut f: impl FnMut() -> bool) -> LatencyStats {
+    let mut samples = Vec::with_capacity(iterations as usize);
+    let mut failures = 0;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        if !f() {
+            failures += 1;
+        }
+        samples.push(start.elapsed().as_nanos() as u64);
+    }
+    samples.sort_unstable();
+
+    let count = samples.len();
+    if count == 0 {
+        return LatencyStats::default();
+    }
+    LatencyStats {
+        count: count as u32,
+        failures,
+        min_ns: samples[0],
+        mean_ns: samples.iter().sum::<u64>() / count as u64,
+        p99_ns: samples[(count * 99 / 100).min(count - 1)],
+        max_ns: samples[count - 1],
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BenchmarkQuery {
+    iterations: Option<u32>,
+    /// Samples to read in the readout measurement (default: the whole pod RAM)
+    samples: Option<u32>,
+}
+
+/// POST /api/ila/benchmark?iterations=N&samples=N - Measure register and readout throughput
+pub async fn post_benchmark(
+    State(state): State<Arc<IlaState>>,
+    Query(query): Query<BenchmarkQuery>,
+) -> Result<Json<BenchmarkReport>, Response> {
+    let iterations = query.iterations.unwrap_or(DEFAULT_ITERATIONS);
+    if iterations == 0 || iterations > MAX_ITERATIONS {
+        return Err((StatusCode::BAD_REQUEST, format!("iterations must be between 1 and {}", MAX_ITERATIONS)).into_response());
+    }
+
+    state.run_op("benchmark", move |s| -> Result<_, validate::Invalid> {
+        validate::pod(&s.ila, 0, 0)?;
+        let (ts_bits, _, ram_depth) = s.ila.get_pod_config(0, 0);
+        let samples = query.samples.unwrap_or(ram_depth);
+        validate::count("samples", samples, ram_depth)?;
+
+        let ila = &s.ila;
+        let read32 = measure(iterations, || ila.read_reg(REG_HW_INFO).is_some());
+        let write32 = measure(iterations, || ila.write_reg(REG_WDATA, 0));
+        let local_cmd = measure(iterations, || ila.exec_cmd(CMD_RD_STATUS, 0, 0).is_some());
+        let serial_cmd = measure(iterations, || ila.exec_cmd(CMD_RD_POD_COUNT, 0, 0).is_some());
+
+        let start = Instant::now();
+        let read = ila.read_rle_samples(0, 0, 0, samples, ts_bits).0.len();
+        let elapsed = start.elapsed();
+
+        Ok(BenchmarkReport {
+            backend: ila.backend(),
+            read32,
+            write32,
+            local_cmd,
+            serial_cmd,
+            readout_samples: read as u32,
+            readout_us: elapsed.as_micros() as u64,
+            samples_per_sec: read as f64 / elapsed.as_secs_f64().max(1e-9),
+        })
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map(Json)
+    .map_err(IntoResponse::into_response)
+}
@@ -0,0 +1,146 @@
+//! Circuit breaker for a wedged bus
+//!
+//! Once `SUMP_BREAKER_TIMEOUTS` commands in a row time out (default 3,
+//! 0 = off) the circuit opens: hardware endpoints answer 503 straight away
+//! with the failure, instead of each request spending the full DONE poll
+//! budget per command. A background task probes with a status read every
+//! `SUMP_BREAKER_PROBE_MS` and closes the circuit once one completes.
+//! `GET /api/ila/circuit` reports the state.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sump_driver::{clock::rfc3339, Ila, CMD_RD_STATUS};
+use sump_model::CircuitStatus;
+
+use crate::ila::IlaState;
+
+/// Default `SUMP_BREAKER_TIMEOUTS`
+pub const DEFAULT_THRESHOLD: u64 = 3;
+
+/// Default `SUMP_BREAKER_PROBE_MS`
+pub const DEFAULT_PROBE_MS: u64 = 1000;
+
+/// Circuit state shared by the middleware and the probe task
+pub struct Breaker {
+    status: Mutex<CircuitStatus>,
+    probe: Duration,
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_THRESHOLD, Duration::from_millis(DEFAULT_PROBE_MS))
+    }
+}
+
+impl Breaker {
+    /// Open after `threshold` consecutive command timeouts (0 = never),
+    /// probing every `probe` while open
+    pub fn new(threshold: u64, probe: Duration) -> Self {
+        let probe = probe.max(Duration::from_millis(1));
+        Self { status: Mutex::new(CircuitStatus { threshold, ..Default::default() }), probe }
+    }
+
+    pub fn status(&self) -> CircuitStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Why hardware requests are refused, while the circuit is open
+    fn open_reason(&self) -> Option<String> {
+        let status = self.status.lock().unwrap();
+        status.open.then(|| status.reason.clone().unwrap_or_default())
+    }
+
+    /// Open the circuit if the driver's consecutive timeouts reached the
+    /// threshold; reads counters only, so it never waits for the bus
+    pub fn observe(&self, ila: &Ila) {
+        let timeouts = ila.consecutive_timeouts();
+        let mut status = self.status.lock().unwrap();
+        status.consecutive_timeouts = timeouts;
+        if status.open || status.threshold == 0 || timeouts < status.threshold {
+            return;
+        }
+        let reason = match ila.last_command_failure() {
+            Some(failure) => format!("{} consecutive command timeouts, last {}", timeouts, failure),
+            None => format!("{} consecutive command timeouts", timeouts),
+        };
+        tracing::error!("Circuit open: {}", reason);
+        status.open = true;
+        status.reason = Some(reason);
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+        status.opened_at = Some(rfc3339(now_ms));
+        status.trips += 1;
+    }
+
+    /// Probe the bus while open; a completed status read closes the circuit
+    fn probe(&self, ila: &Ila) {
+        if !self.status.lock().unwrap().open {
+            return;
+        }
+        if ila.exec_cmd(CMD_RD_STATUS, 0, 0).is_none() {
+            tracing::debug!("Circuit probe failed");
+            return;
+        }
+        let mut status = self.status.lock().unwrap();
+        status.open = false;
+        status.reason = None;
+        status.opened_at = None;
+        status.consecutive_timeouts = ila.consecutive_timeouts();
+        tracing::info!("Circuit closed: ILA answering again");
+    }
+}
+
+/// Middleware answering 503 with the reason while the circuit is open
+pub async fn guard(State(state): State<Arc<IlaState>>, request: Request, next: Next) -> Response {
+    if let Some(reason) = state.breaker.open_reason() {
+        let retry_after = state.breaker.probe.as_secs().max(1).to_string();
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after)],
+            format!("ILA circuit open: {}; probing in the background", reason),
+        )
+            .into_response();
+    }
+    let response = next.run(request).await;
+    state.breaker.observe(&state.ila);
+    response
+}
+
+/// Watch for timeouts from any client and probe while open, until the
+/// server exits
+pub async fn run(state: Arc<IlaState>) {
+    if state.breaker.status().threshold == 0 {
+        return;
+    }
+    let mut ticker = tokio::time::interval(state.breaker.probe);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut shutdown = state.shutdown_signal();
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+        // Nothing to probe until the backend reconnects (see `degraded`)
+        if state.degraded.lock().unwrap().is_some() {
+            continue;
+        }
+        let state = state.clone();
+        let _ = tokio::task::spawn_blocking(move || {
+            state.breaker.observe(&state.ila);
+            state.breaker.probe(&state.ila);
+        })
+        .await;
+    }
+}
+
+/// GET /api/ila/circuit - Circuit breaker state
+pub async fn get_circuit(State(state): State<Arc<IlaState>>) -> Json<CircuitStatus> {
+    let status = state.breaker.status();
+    Json(CircuitStatus { consecutive_timeouts: state.ila.consecutive_timeouts(), ..status })
+}
@@ -0,0 +1,66 @@
+//! Capability discovery
+//!
+//! `GET /api/capabilities` lists what this server was built with and is
+//! configured for (backends, exporters, decoders, deep capture, the largest
+//! sample count), so the CLI and CI scripts can work across servers built
+//! with different feature flags. `/api/ui-config` embeds the same document.
+
+use axum::{extract::State, http::Extensions, response::Json, routing::get, Router};
+use std::sync::Arc;
+
+use sump_model::Capabilities;
+
+use crate::ila::IlaState;
+use crate::readonly::ReadOnlyListener;
+use crate::{decode, export};
+
+/// `SUMP_BACKEND` schemes understood by `open_backend`
+pub const BACKENDS: &[&str] = &["devmem", "tcp", "xvc", "uart", "replay"];
+
+/// Cargo features compiled in
+fn features() -> Vec<String> {
+    let mut features = Vec::new();
+    if cfg!(feature = "grpc") {
+        features.push("grpc".to_string());
+    }
+    if cfg!(feature = "plugins") {
+        features.push("plugins".to_string());
+    }
+    features
+}
+
+/// Capabilities as seen by a request with `extensions`
+pub async fn capabilities(state: &Arc<IlaState>, extensions: &Extensions) -> Capabilities {
+    let degraded = state.degraded.lock().unwrap().is_some();
+    let max_sample_count = if degraded {
+        None
+    } else {
+        state.run(|s| s.info().hubs.iter().flat_map(|hub| hub.pods.iter().map(|pod| pod.ram_depth)).max()).await
+    };
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features: features(),
+        backends: BACKENDS.iter().map(|b| b.to_string()).collect(),
+        backend: state.ila.backend(),
+        auth: "none".to_string(),
+        read_only: extensions.get::<ReadOnlyListener>().is_some(),
+        export_formats: export::all(state).map(|e| e.name().to_string()).collect(),
+        decoders: decode::all(state).map(|d| d.name().to_string()).collect(),
+        deep_capture: state.deep.is_some(),
+        ext_trigger: state.ext_trigger_gpio.is_some(),
+        console: state.console && extensions.get::<ReadOnlyListener>().is_none(),
+        max_sample_count,
+    }
+}
+
+/// GET /api/capabilities - Enabled features of this server
+async fn get_capabilities(State(state): State<Arc<IlaState>>, extensions: Extensions) -> Json<Capabilities> {
+    Json(capabilities(&state, &extensions).await)
+}
+
+/// Create the `/api/capabilities` router
+pub fn capabilities_router(state: Arc<IlaState>) -> Router {
+    Router::new()
+        .route("/", get(get_capabilities))
+        .with_state(state)
+}
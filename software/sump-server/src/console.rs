@@ -0,0 +1,202 @@
+//! Command console over WebSocket
+//!
+//! `GET /api/ila/console` upgrades to a WebSocket for wrapper RTL bring-up.
+//! Each text message is a `ConsoleRequest` (raw `exec_cmd`, wrapper and pod
+//! register access, pod RAM dumps) answered by `ConsoleReply` messages
+//! carrying its `id`; a RAM dump streams `DUMP_CHUNK` words per reply.
+//! Requests are checked like their REST counterparts (`validate`), each
+//! holds the ILA as operation `console` like a batch, and commands that
+//! drive the wrapper go to the audit log (`audit`).
+//!
+//! The console is off unless `SUMP_CONSOLE=1` and is never served on the
+//! read-only listener. There is no client authentication: keep the main
+//! listener on `SUMP_BIND=127.0.0.1` or a trusted network when enabling it.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, State,
+    },
+    http::{Extensions, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use sump_driver::failure_message;
+use sump_model::{ConsoleCommand, ConsoleReply, ConsoleRequest};
+
+use crate::audit;
+use crate::ila::IlaState;
+use crate::readonly::ReadOnlyListener;
+use crate::validate;
+
+/// RAM words per dump reply
+const DUMP_CHUNK: u32 = 256;
+
+/// GET /api/ila/console - WebSocket command console (`SUMP_CONSOLE=1`)
+pub async fn ws_console(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<IlaState>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    extensions: Extensions,
+) -> Response {
+    if extensions.get::<ReadOnlyListener>().is_some() {
+        return (StatusCode::FORBIDDEN, "The console is not available on the read-only listener").into_response();
+    }
+    if !state.console {
+        return (StatusCode::NOT_FOUND, "The console is disabled; enable it with SUMP_CONSOLE=1").into_response();
+    }
+    audit::record(client, "console", "connected");
+    ws.on_upgrade(move |socket| console_loop(socket, state, client))
+}
+
+fn reply(id: u64, result: Result<(Option<u32>, String), String>) -> ConsoleReply {
+    match result {
+        Ok((value, message)) => ConsoleReply { id, ok: true, message, value, done: true, ..Default::default() },
+        Err(message) => ConsoleReply { id, ok: false, message, done: true, ..Default::default() },
+    }
+}
+
+/// The audit log entry of a command that drives the wrapper
+fn audited(command: &ConsoleCommand) -> Option<String> {
+    match *command {
+        ConsoleCommand::ExecCmd { cmd, addr, wdata } => Some(audit::command(cmd, addr, wdata)),
+        ConsoleCommand::WritePodReg { hub, pod, reg, value } => {
+            Some(format!("write hub {} pod {} reg 0x{:02X} = 0x{:08X}", hub, pod, reg, value))
+        }
+        _ => None,
+    }
+}
+
+/// Run a single-reply command
+fn execute(s: &IlaState, command: &ConsoleCommand) -> Result<(Option<u32>, String), String> {
+    let invalid = |(_, message): validate::Invalid| message;
+    match *command {
+        ConsoleCommand::ExecCmd { cmd, addr, wdata } => match s.ila.exec_cmd(cmd, addr, wdata) {
+            Some(rdata) => Ok((Some(rdata), format!("Command 0x{:02X} complete", cmd))),
+            None => Err(failure_message(&format!("Command 0x{:02X}", cmd))),
+        },
+        ConsoleCommand::ReadReg { offset } => {
+            validate::offset(&s.ila, offset).map_err(invalid)?;
+            let value = s.ila.read_reg(offset).ok_or_else(|| format!("Read of register 0x{:X} failed", offset))?;
+            Ok((Some(value), format!("Register 0x{:X}", offset)))
+        }
+        ConsoleCommand::ReadPodReg { hub, pod, reg } => {
+            validate::visible_pod(s, hub, pod).map_err(invalid)?;
+            let value = s.ila.read_pod_reg(hub, pod, reg)
+                .ok_or_else(|| failure_message(&format!("Read of hub {} pod {} reg 0x{:02X}", hub, pod, reg)))?;
+            Ok((Some(value), format!("Hub {} pod {} reg 0x{:02X}", hub, pod, reg)))
+        }
+        ConsoleCommand::WritePodReg { hub, pod, reg, value } => {
+            validate::visible_pod(s, hub, pod).map_err(invalid)?;
+            if !s.ila.write_pod_reg(hub, pod, reg, value) {
+                return Err(failure_message(&format!("Write of hub {} pod {} reg 0x{:02X}", hub, pod, reg)));
+            }
+            Ok((None, format!("Hub {} pod {} reg 0x{:02X} written", hub, pod, reg)))
+        }
+        ConsoleCommand::DumpRam { .. } => unreachable!("streamed by dump_ram"),
+    }
+}
+
+/// Check a RAM dump request against the pod
+fn check_dump(s: &IlaState, hub: u8, pod: u8, start: u32, count: u32) -> Result<(), String> {
+    validate::visible_pod(s, hub, pod).map_err(|(_, message)| message)?;
+    let (_, _, ram_depth) = s.ila.get_pod_config(hub, pod);
+    validate::count("count", count, ram_depth).map_err(|(_, message)| message)?;
+    if start as u64 + count as u64 > ram_depth as u64 {
+        return Err(format!("start + count must not exceed the RAM depth of {}", ram_depth));
+    }
+    Ok(())
+}
+
+/// Stream a RAM window; each chunk claims the ILA separately, so a long
+/// dump doesn't lock out other clients. Returns false once the socket is gone.
+async fn dump_ram(socket: &mut WebSocket, state: &Arc<IlaState>, id: u64, command: ConsoleCommand) -> bool {
+    let ConsoleCommand::DumpRam { hub, pod, page, start, count } = command else {
+        unreachable!("called for DumpRam");
+    };
+    let checked = state.run_op("console", move |s| check_dump(s, hub, pod, start, count)).await;
+    if let Some(message) = match checked {
+        Ok(Ok(())) => None,
+        Ok(Err(message)) => Some(message),
+        Err(conflict) => Some(conflict.0.message),
+    } {
+        return send(socket, &reply(id, Err(message))).await;
+    }
+
+    let end = start + count;
+    let mut address = start;
+    while address < end {
+        let chunk = DUMP_CHUNK.min(end - address);
+        let words = state.run_op("console", move |s| {
+            (address..address + chunk).map(|a| s.ila.read_ram_word(hub, pod, page, a).ok_or(a)).collect()
+        }).await;
+        let message = match words {
+            Ok(Ok(words)) => {
+                let done = address + chunk == end;
+                let message = format!("Hub {} pod {} page {} words {}..{}", hub, pod, page, address, address + chunk);
+                let chunk_reply = ConsoleReply { id, ok: true, message, address: Some(address), words, done, ..Default::default() };
+                if !send(socket, &chunk_reply).await {
+                    return false;
+                }
+                address += chunk;
+                continue;
+            }
+            Ok(Err(failed)) => failure_message(&format!("RAM read at page {} address {}", page, failed)),
+            Err(conflict) => conflict.0.message,
+        };
+        return send(socket, &reply(id, Err(message))).await;
+    }
+    true
+}
+
+/// Send a reply; false once the socket is gone
+async fn send(socket: &mut WebSocket, reply: &ConsoleReply) -> bool {
+    let text = serde_json::to_string(reply).expect("ConsoleReply serializes");
+    socket.send(Message::Text(text)).await.is_ok()
+}
+
+async fn console_loop(mut socket: WebSocket, state: Arc<IlaState>, client: SocketAddr) {
+    let mut shutdown = state.shutdown_signal();
+    loop {
+        let text = tokio::select! {
+            _ = shutdown.changed() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        let request = match serde_json::from_str::<ConsoleRequest>(&text) {
+            Ok(request) => request,
+            Err(e) => {
+                if !send(&mut socket, &reply(0, Err(format!("Invalid console request: {}", e)))).await {
+                    break;
+                }
+                continue;
+            }
+        };
+        if let Some(action) = audited(&request.command) {
+            audit::record(client, "console", action);
+        }
+        let id = request.id;
+        let sent = match request.command {
+            command @ ConsoleCommand::DumpRam { .. } => dump_ram(&mut socket, &state, id, command).await,
+            command => {
+                let result = match state.run_op("console", move |s| execute(s, &command)).await {
+                    Ok(result) => result,
+                    Err(conflict) => Err(conflict.0.message),
+                };
+                send(&mut socket, &reply(id, result)).await
+            }
+        };
+        if !sent {
+            break;
+        }
+    }
+    audit::record(client, "console", "disconnected");
+}
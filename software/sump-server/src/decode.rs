@@ -0,0 +1,631 @@
+//! Protocol decoders
+//!
+//! Each decoder implements `Decoder` and is listed in `DECODERS` (or loaded
+//! from a plugin, see `plugins`). Its
+//! parameters are declared as `DecoderParam`s, so `GET /api/ila/decoders`
+//! doubles as the form description for a UI, and the request parameters are
+//! checked and resolved (signal names to bit fields, defaults filled in)
+//! before the decoder runs. `POST /api/ila/capture/:hub/:pod/decode/:decoder`
+//! with the parameters as a JSON object reads the whole capture of the pod
+//! and returns the annotations.
+//!
+//! Decoders see the capture as level changes in hub clock cycles: an RLE
+//! sample holds its value until the next one.
+//!
+//! `POST /api/ila/deep/decode/:decoder?hub=&pod=` decodes the stopped deep
+//! capture instead, resolving signals and bit times against the given pod
+//! (default 0.0). Its samples are unwrapped into a `spill` trace, so a full
+//! DDR buffer needn't fit in memory.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{Extensions, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use sump_model::{
+    Annotation, CaptureData, DecodeResult, DecoderInfo, DecoderParam, DecoderParamKind, FieldError, PodInfo,
+    ValidationErrors,
+};
+
+use crate::deep::DeepPodQuery;
+use crate::ila::IlaState;
+use crate::spill::Spilled;
+use crate::{digest, masks, validate};
+
+/// Bit field of the sample data
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    lo: u16,
+    hi: u16,
+}
+
+impl Field {
+    pub fn extract(self, data: u32) -> u32 {
+        let width = self.hi - self.lo + 1;
+        let mask = if width >= 32 { u32::MAX } else { (1u32 << width) - 1 };
+        (data >> self.lo) & mask
+    }
+}
+
+/// A signal of the pod by full name, by name without its `[hi:lo]` suffix,
+/// or one bit of it as `name[n]`
+pub fn resolve_signal(pod: &PodInfo, name: &str) -> Result<Field, String> {
+    let base = |s: &str| s.split_once('[').map_or(s, |(base, _)| base).to_string();
+    let single_bit = || {
+        let (sig, bit) = name.strip_suffix(']')?.rsplit_once('[')?;
+        let bit: u16 = bit.parse().ok()?;
+        pod.signals.iter()
+            .any(|s| base(&s.name) == sig && (s.bit_low..=s.bit_high).contains(&bit))
+            .then_some(Field { lo: bit, hi: bit })
+    };
+    let field = match pod.signals.iter().find(|s| s.name == name || base(&s.name) == name) {
+        Some(s) => Field { lo: s.bit_low, hi: s.bit_high },
+        None => single_bit().ok_or_else(|| format!("unknown signal '{}'", name))?,
+    };
+    if field.hi > 31 {
+        return Err(format!("signal '{}' lies beyond the 32 bits of sample data", name));
+    }
+    Ok(field)
+}
+
+/// Written samples of a capture in chronological order
+pub struct Trace {
+    cycles: Spilled<u64>,
+    data: Spilled<u32>,
+    pub hub_hz: u64,
+}
+
+impl Trace {
+    pub fn new(capture: &CaptureData, hub_hz: u64) -> Self {
+        Self {
+            cycles: Spilled::Memory(capture.elapsed_cycles()),
+            data: Spilled::Memory(capture.samples.iter().filter(|s| s.code != 0).map(|s| s.data).collect()),
+            hub_hz,
+        }
+    }
+
+    /// Samples collected elsewhere (see `deep::read_trace`)
+    pub fn from_parts(cycles: Spilled<u64>, data: Spilled<u32>, hub_hz: u64) -> Self {
+        Self { cycles, data, hub_hz }
+    }
+
+    /// `(cycle, value)` of a field at each sample
+    pub fn samples(&self, field: Field) -> impl Iterator<Item = (u64, u32)> + '_ {
+        self.cycles.iter().zip(self.data.iter()).map(move |(&t, &d)| (t, field.extract(d)))
+    }
+
+    /// Changes of a field, starting with its first value
+    pub fn edges(&self, field: Field) -> Vec<(u64, u32)> {
+        let mut edges: Vec<(u64, u32)> = Vec::new();
+        for (t, value) in self.samples(field) {
+            if edges.last().is_none_or(|&(_, last)| last != value) {
+                edges.push((t, value));
+            }
+        }
+        edges
+    }
+
+    /// Sample cycles and raw data, for plugins
+    #[cfg(feature = "plugins")]
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({ "hub_hz": self.hub_hz, "cycles": &*self.cycles, "data": &*self.data })
+    }
+
+    /// Cycle of the last sample
+    pub fn end(&self) -> u64 {
+        self.cycles.last().copied().unwrap_or(0)
+    }
+}
+
+/// Value of a field at cycle `t`, from its `edges`
+fn level(edges: &[(u64, u32)], t: u64) -> u32 {
+    match edges.partition_point(|&(c, _)| c <= t) {
+        0 => edges.first().map_or(0, |&(_, v)| v),
+        i => edges[i - 1].1,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ParamValue {
+    Signal(Field),
+    Integer(i64),
+    Boolean(bool),
+    Choice(String),
+}
+
+/// Checked decoder parameters; accessors panic only for names the decoder
+/// didn't declare
+pub struct Params(BTreeMap<String, ParamValue>);
+
+impl Params {
+    pub fn parse(declared: &[DecoderParam], given: &Map<String, Value>, pod: &PodInfo) -> Result<Self, Vec<FieldError>> {
+        let mut errors = Vec::new();
+        for name in given.keys().filter(|name| !declared.iter().any(|p| &p.name == *name)) {
+            errors.push(FieldError { field: name.clone(), message: "unknown parameter".into() });
+        }
+
+        let mut values = BTreeMap::new();
+        for param in declared {
+            let text = param.default.as_ref().map(|d| Value::String(d.clone()));
+            let Some(value) = given.get(&param.name).cloned().or(text) else {
+                if param.required {
+                    errors.push(FieldError { field: param.name.clone(), message: "required".into() });
+                }
+                continue;
+            };
+            match parse_value(param, &value, pod) {
+                Ok(v) => {
+                    values.insert(param.name.clone(), v);
+                }
+                Err(message) => errors.push(FieldError { field: param.name.clone(), message }),
+            }
+        }
+        if errors.is_empty() { Ok(Self(values)) } else { Err(errors) }
+    }
+
+    /// Signals as `{"lo": n, "hi": n}`, the rest as JSON scalars, for plugins
+    #[cfg(feature = "plugins")]
+    pub fn to_json(&self) -> Value {
+        let values = self.0.iter().map(|(name, value)| {
+            let value = match value {
+                ParamValue::Signal(f) => serde_json::json!({ "lo": f.lo, "hi": f.hi }),
+                ParamValue::Integer(v) => Value::from(*v),
+                ParamValue::Boolean(v) => Value::from(*v),
+                ParamValue::Choice(v) => Value::from(v.as_str()),
+            };
+            (name.clone(), value)
+        });
+        Value::Object(values.collect())
+    }
+
+    fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.0.get(name)
+    }
+
+    pub fn signal(&self, name: &str) -> Option<Field> {
+        match self.get(name)? {
+            ParamValue::Signal(f) => Some(*f),
+            other => panic!("parameter {} is {:?}, not a signal", name, other),
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> i64 {
+        match self.get(name) {
+            Some(ParamValue::Integer(v)) => *v,
+            other => panic!("parameter {} is {:?}, not an integer", name, other),
+        }
+    }
+
+    pub fn boolean(&self, name: &str) -> bool {
+        match self.get(name) {
+            Some(ParamValue::Boolean(v)) => *v,
+            other => panic!("parameter {} is {:?}, not a boolean", name, other),
+        }
+    }
+
+    pub fn choice(&self, name: &str) -> &str {
+        match self.get(name) {
+            Some(ParamValue::Choice(v)) => v,
+            other => panic!("parameter {} is {:?}, not a choice", name, other),
+        }
+    }
+}
+
+/// JSON values and their text form (defaults, form fields) are both
+/// accepted; signal names are taken verbatim, as RTL names may be padded
+fn parse_value(param: &DecoderParam, value: &Value, pod: &PodInfo) -> Result<ParamValue, String> {
+    let raw = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        other => return Err(format!("expected a string, number or boolean, got {}", other)),
+    };
+    let text = raw.trim().to_string();
+    match param.kind {
+        DecoderParamKind::Signal => resolve_signal(pod, &raw).map(ParamValue::Signal),
+        DecoderParamKind::Integer => {
+            text.parse().map(ParamValue::Integer).map_err(|_| format!("'{}' is not an integer", text))
+        }
+        DecoderParamKind::Boolean => {
+            text.parse().map(ParamValue::Boolean).map_err(|_| format!("'{}' is not true or false", text))
+        }
+        DecoderParamKind::Choice if param.choices.contains(&text) => Ok(ParamValue::Choice(text)),
+        DecoderParamKind::Choice => Err(format!("'{}' is not one of {}", text, param.choices.join(", "))),
+    }
+}
+
+fn param(name: &str, kind: DecoderParamKind, description: &str, default: Option<&str>) -> DecoderParam {
+    DecoderParam {
+        name: name.into(),
+        kind,
+        description: description.into(),
+        required: default.is_none(),
+        default: default.map(str::to_string),
+        choices: Vec::new(),
+    }
+}
+
+fn optional_signal(name: &str, description: &str) -> DecoderParam {
+    DecoderParam { required: false, ..param(name, DecoderParamKind::Signal, description, None) }
+}
+
+fn choice(name: &str, description: &str, choices: &[&str]) -> DecoderParam {
+    DecoderParam {
+        choices: choices.iter().map(|c| c.to_string()).collect(),
+        ..param(name, DecoderParamKind::Choice, description, Some(choices[0]))
+    }
+}
+
+fn annotation(start_cycle: u64, end_cycle: u64, label: String, value: Option<u32>, error: bool) -> Annotation {
+    Annotation { start_cycle, end_cycle, label, value, error }
+}
+
+pub trait Decoder: Sync {
+    /// Path segment and listing name
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    fn params(&self) -> Vec<DecoderParam>;
+    /// Decode with checked parameters; `Err` if they don't fit the capture
+    fn decode(&self, trace: &Trace, params: &Params) -> Result<Vec<Annotation>, String>;
+}
+
+struct Uart;
+
+impl Decoder for Uart {
+    fn name(&self) -> &'static str { "uart" }
+    fn description(&self) -> &'static str { "Asynchronous serial, idle high, LSB first" }
+
+    fn params(&self) -> Vec<DecoderParam> {
+        vec![
+            param("rx", DecoderParamKind::Signal, "Serial line", None),
+            param("baud", DecoderParamKind::Integer, "Bit rate", Some("115200")),
+            param("data_bits", DecoderParamKind::Integer, "Data bits per frame", Some("8")),
+            choice("parity", "Parity bit", &["none", "even", "odd"]),
+        ]
+    }
+
+    fn decode(&self, trace: &Trace, params: &Params) -> Result<Vec<Annotation>, String> {
+        let rx = trace.edges(params.signal("rx").expect("required"));
+        let (baud, data_bits) = (params.integer("baud"), params.integer("data_bits"));
+        if trace.hub_hz == 0 {
+            return Err("the hub doesn't report its clock, so bit times are unknown".into());
+        }
+        if baud <= 0 || baud as u64 > trace.hub_hz / 2 {
+            return Err(format!("baud must be between 1 and half the {} Hz hub clock", trace.hub_hz));
+        }
+        if !(5..=9).contains(&data_bits) {
+            return Err("data_bits must be between 5 and 9".into());
+        }
+        let parity = params.choice("parity");
+        let cycles_per_bit = trace.hub_hz as f64 / baud as f64;
+        let frame_bits = 1 + data_bits as u32 + (parity != "none") as u32;
+        let at = |start: u64, bits: f64| start + (bits * cycles_per_bit).round() as u64;
+
+        let mut annotations = Vec::new();
+        let mut resume = 0;
+        for pair in rx.windows(2) {
+            let ((_, before), (start, now)) = (pair[0], pair[1]);
+            if before == 0 || now != 0 || start < resume {
+                continue;
+            }
+            let stop = at(start, frame_bits as f64 + 0.5);
+            if stop > trace.end() {
+                break;
+            }
+            let bit = |n: u32| (level(&rx, at(start, n as f64 + 0.5)) != 0) as u32;
+            let value = (0..data_bits as u32).fold(0, |v, i| v | bit(1 + i) << i);
+            let parity_ok = match parity {
+                "even" => (value.count_ones() + bit(frame_bits - 1)) % 2 == 0,
+                "odd" => (value.count_ones() + bit(frame_bits - 1)) % 2 == 1,
+                _ => true,
+            };
+            let framing_ok = bit(frame_bits) == 1;
+            let mut label = format!("0x{:02X}", value);
+            if !framing_ok {
+                label.push_str(" framing error");
+            } else if !parity_ok {
+                label.push_str(" parity error");
+            }
+            let end = at(start, frame_bits as f64 + 1.0);
+            annotations.push(annotation(start, end, label, Some(value), !(framing_ok && parity_ok)));
+            resume = stop;
+        }
+        Ok(annotations)
+    }
+}
+
+struct Spi;
+
+impl Decoder for Spi {
+    fn name(&self) -> &'static str { "spi" }
+    fn description(&self) -> &'static str { "SPI words on MOSI and/or MISO, framed by an active-low chip select" }
+
+    fn params(&self) -> Vec<DecoderParam> {
+        vec![
+            param("sclk", DecoderParamKind::Signal, "Serial clock", None),
+            optional_signal("mosi", "Controller to peripheral data"),
+            optional_signal("miso", "Peripheral to controller data"),
+            optional_signal("cs", "Active-low chip select; words restart when it rises"),
+            choice("mode", "SPI mode (CPOL * 2 + CPHA)", &["0", "1", "2", "3"]),
+            param("word_bits", DecoderParamKind::Integer, "Bits per word", Some("8")),
+            param("msb_first", DecoderParamKind::Boolean, "Most significant bit first", Some("true")),
+        ]
+    }
+
+    fn decode(&self, trace: &Trace, params: &Params) -> Result<Vec<Annotation>, String> {
+        let (mosi, miso) = (params.signal("mosi"), params.signal("miso"));
+        if mosi.is_none() && miso.is_none() {
+            return Err("select mosi, miso or both".into());
+        }
+        let word_bits = params.integer("word_bits");
+        if !(1..=32).contains(&word_bits) {
+            return Err("word_bits must be between 1 and 32".into());
+        }
+        let mode: u32 = params.choice("mode").parse().expect("declared choice");
+        // CPOL == CPHA samples on the rising edge
+        let sample_level = ((mode >> 1) == (mode & 1)) as u32;
+        let msb_first = params.boolean("msb_first");
+        let cs = params.signal("cs").map(|f| trace.edges(f));
+        let (mosi, miso) = (mosi.map(|f| trace.edges(f)), miso.map(|f| trace.edges(f)));
+
+        let mut annotations = Vec::new();
+        let (mut bits, mut start, mut out, mut inp) = (0, 0, 0u32, 0u32);
+        let sclk = trace.edges(params.signal("sclk").expect("required"));
+        for &(t, clk) in sclk.iter().skip(1) {
+            if cs.as_ref().is_some_and(|cs| level(cs, t) != 0) {
+                bits = 0;
+                continue;
+            }
+            if (clk != 0) as u32 != sample_level {
+                continue;
+            }
+            if bits == 0 {
+                start = t;
+                (out, inp) = (0, 0);
+            }
+            let shift = |word: u32, line: &Option<Vec<(u64, u32)>>| {
+                let bit = line.as_ref().map_or(0, |l| (level(l, t) != 0) as u32);
+                if msb_first { word << 1 | bit } else { word | bit << bits }
+            };
+            (out, inp) = (shift(out, &mosi), shift(inp, &miso));
+            bits += 1;
+            if bits == word_bits {
+                let mut label = Vec::new();
+                if mosi.is_some() {
+                    label.push(format!("MOSI 0x{:02X}", out));
+                }
+                if miso.is_some() {
+                    label.push(format!("MISO 0x{:02X}", inp));
+                }
+                let value = if mosi.is_some() { out } else { inp };
+                annotations.push(annotation(start, t, label.join(" "), Some(value), false));
+                bits = 0;
+            }
+        }
+        Ok(annotations)
+    }
+}
+
+struct I2c;
+
+impl Decoder for I2c {
+    fn name(&self) -> &'static str { "i2c" }
+    fn description(&self) -> &'static str { "I2C start/stop conditions, 7-bit addresses and data bytes with ACK" }
+
+    fn params(&self) -> Vec<DecoderParam> {
+        vec![
+            param("scl", DecoderParamKind::Signal, "Clock line", None),
+            param("sda", DecoderParamKind::Signal, "Data line", None),
+        ]
+    }
+
+    fn decode(&self, trace: &Trace, params: &Params) -> Result<Vec<Annotation>, String> {
+        let (scl, sda) = (params.signal("scl").expect("required"), params.signal("sda").expect("required"));
+        let lines: Vec<(u64, bool, bool)> = trace.samples(scl).zip(trace.samples(sda))
+            .map(|((t, c), (_, d))| (t, c != 0, d != 0))
+            .collect();
+
+        let mut annotations = Vec::new();
+        let (mut in_frame, mut address_next) = (false, false);
+        let (mut bits, mut start) = (Vec::new(), 0);
+        for pair in lines.windows(2) {
+            let ((_, scl_was, sda_was), (t, scl, sda)) = (pair[0], pair[1]);
+            if scl_was && scl && sda_was && !sda {
+                let label = if in_frame { "Sr" } else { "START" };
+                annotations.push(annotation(t, t, label.into(), None, false));
+                (in_frame, address_next) = (true, true);
+                bits.clear();
+            } else if scl_was && scl && !sda_was && sda {
+                annotations.push(annotation(t, t, "STOP".into(), None, false));
+                in_frame = false;
+            } else if in_frame && !scl_was && scl {
+                if bits.is_empty() {
+                    start = t;
+                }
+                bits.push(sda);
+                if bits.len() == 9 {
+                    let byte = bits[..8].iter().fold(0u32, |v, &b| v << 1 | b as u32);
+                    let ack = if bits[8] { "NACK" } else { "ACK" };
+                    let (label, value) = if address_next {
+                        let rw = if byte & 1 != 0 { "R" } else { "W" };
+                        (format!("Addr 0x{:02X} {} {}", byte >> 1, rw, ack), byte >> 1)
+                    } else {
+                        (format!("0x{:02X} {}", byte, ack), byte)
+                    };
+                    annotations.push(annotation(start, t, label, Some(value), false));
+                    address_next = false;
+                    bits.clear();
+                }
+            }
+        }
+        Ok(annotations)
+    }
+}
+
+struct Axi;
+
+impl Decoder for Axi {
+    fn name(&self) -> &'static str { "axi" }
+    fn description(&self) -> &'static str {
+        "AXI / AXI-Stream channel handshake: spans with valid and ready high, and the data during them"
+    }
+
+    fn params(&self) -> Vec<DecoderParam> {
+        vec![
+            param("valid", DecoderParamKind::Signal, "VALID (or TVALID)", None),
+            param("ready", DecoderParamKind::Signal, "READY (or TREADY)", None),
+            optional_signal("data", "Payload (DATA, TDATA, ADDR, ...)"),
+        ]
+    }
+
+    fn decode(&self, trace: &Trace, params: &Params) -> Result<Vec<Annotation>, String> {
+        let (valid, ready) = (params.signal("valid").expect("required"), params.signal("ready").expect("required"));
+        let data = params.signal("data");
+        let mut annotations = Vec::new();
+        let mut pending: Option<(u64, Option<u32>)> = None;
+        for (&t, &d) in trace.cycles.iter().zip(trace.data.iter()) {
+            let beat = valid.extract(d) != 0 && ready.extract(d) != 0;
+            let value = data.map(|f| f.extract(d));
+            if let Some((begin, v)) = pending.filter(|&(_, v)| !beat || v != value) {
+                annotations.push(handshake(begin, t, v));
+                pending = None;
+            }
+            if beat && pending.is_none() {
+                pending = Some((t, value));
+            }
+        }
+        if let Some((begin, v)) = pending {
+            annotations.push(handshake(begin, trace.end(), v));
+        }
+        Ok(annotations)
+    }
+}
+
+fn handshake(start: u64, end: u64, value: Option<u32>) -> Annotation {
+    let label = match value {
+        Some(v) => format!("0x{:X} ({} cycles)", v, end - start),
+        None => format!("transfer ({} cycles)", end - start),
+    };
+    annotation(start, end, label, value, false)
+}
+
+/// Built-in decoders, in listing order
+pub static DECODERS: &[&dyn Decoder] = &[&Uart, &Spi, &I2c, &Axi];
+
+/// Built-in decoders, then those of plugins
+pub fn all(state: &IlaState) -> impl Iterator<Item = &dyn Decoder> {
+    DECODERS.iter().copied().chain(state.plugins.decoders.iter().map(|d| d.as_ref() as &dyn Decoder))
+}
+
+pub fn find<'a>(state: &'a IlaState, name: &str) -> Option<&'a dyn Decoder> {
+    all(state).find(|d| d.name() == name)
+}
+
+/// GET /api/ila/decoders - Decoders and their parameters
+pub async fn get_decoders(State(state): State<Arc<IlaState>>) -> Json<Vec<DecoderInfo>> {
+    Json(all(&state).map(|d| DecoderInfo {
+        name: d.name().into(),
+        description: d.description().into(),
+        params: d.params(),
+    }).collect())
+}
+
+enum DecodeError {
+    Request(validate::Invalid),
+    Params(Vec<FieldError>),
+}
+
+impl IntoResponse for DecodeError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Request(invalid) => invalid.into_response(),
+            Self::Params(errors) => (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response(),
+        }
+    }
+}
+
+/// POST /api/ila/capture/:hub/:pod/decode/:decoder - Decode the whole capture with the given parameters
+pub async fn post_decode(
+    State(state): State<Arc<IlaState>>,
+    Path((hub, pod, name)): Path<(u8, u8, String)>,
+    extensions: Extensions,
+    Json(given): Json<Map<String, Value>>,
+) -> Result<Json<DecodeResult>, Response> {
+    let partner = masks::partner(&extensions);
+    if find(&state, &name).is_none() {
+        let message = format!("Unknown decoder '{}' (see /api/ila/decoders)", name);
+        return Err((StatusCode::NOT_FOUND, message).into_response());
+    }
+
+    state.run_op("readout", move |s| {
+        let decoder = find(s, &name).expect("checked above");
+        validate::visible_pod(s, hub, pod).map_err(DecodeError::Request)?;
+        // Redacted signals can't be named, blanked ones decode as 0
+        let mask = masks::for_request(s, partner, hub, pod);
+        let mut info = s.ila.enumerate_pod(hub, pod);
+        if let Some(mask) = &mask {
+            mask.redact(&mut info);
+        }
+        let params = Params::parse(&decoder.params(), &given, &info).map_err(DecodeError::Params)?;
+        let mut capture = s.ila.read_capture_all(hub, pod);
+        if let Some(e) = &capture.readout_error {
+            return Err(DecodeError::Request((StatusCode::BAD_GATEWAY, e.message.clone())));
+        }
+        digest::record(s, &capture);
+        if let Some(mask) = &mask {
+            mask.blank(&mut capture);
+        }
+        let hub_clock_hz = s.ila.hub_clock_hz(hub);
+        let annotations = decoder.decode(&Trace::new(&capture, hub_clock_hz), &params)
+            .map_err(|message| DecodeError::Request((StatusCode::UNPROCESSABLE_ENTITY, message)))?;
+        Ok(DecodeResult { decoder: name, hub, pod, hub_clock_hz, annotations })
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map(Json)
+    .map_err(IntoResponse::into_response)
+}
+
+/// POST /api/ila/deep/decode/:decoder?hub=&pod= - Decode the stopped deep capture with the given parameters
+pub async fn post_deep_decode(
+    State(state): State<Arc<IlaState>>,
+    Path(name): Path<String>,
+    Query(query): Query<DeepPodQuery>,
+    extensions: Extensions,
+    Json(given): Json<Map<String, Value>>,
+) -> Result<Json<DecodeResult>, Response> {
+    masks::unmaskable(&state, masks::partner(&extensions), "Deep capture data").map_err(IntoResponse::into_response)?;
+    crate::deep::stopped(&state).await?;
+    if find(&state, &name).is_none() {
+        let message = format!("Unknown decoder '{}' (see /api/ila/decoders)", name);
+        return Err((StatusCode::NOT_FOUND, message).into_response());
+    }
+    let DeepPodQuery { hub, pod } = query;
+
+    // Only the pod layout and clock need the ILA; the decode runs without holding it
+    let decoder_name = name.clone();
+    let (params, hub_clock_hz) = state.run_op("readout", move |s| {
+        let decoder = find(s, &decoder_name).expect("checked above");
+        validate::visible_pod(s, hub, pod).map_err(DecodeError::Request)?;
+        let params = Params::parse(&decoder.params(), &given, &s.ila.enumerate_pod(hub, pod))
+            .map_err(DecodeError::Params)?;
+        Ok((params, s.ila.hub_clock_hz(hub)))
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map_err(|e: DecodeError| e.into_response())?;
+
+    state.run(move |s| {
+        let deep = s.deep.as_ref().expect("checked by stopped");
+        let trace = crate::deep::read_trace(deep, &s.spill, hub_clock_hz).map_err(|e| {
+            DecodeError::Request((StatusCode::BAD_GATEWAY, format!("Deep capture readout failed: {}", e)))
+        })?;
+        let annotations = find(s, &name).expect("checked above").decode(&trace, &params)
+            .map_err(|message| DecodeError::Request((StatusCode::UNPROCESSABLE_ENTITY, message)))?;
+        Ok::<_, DecodeError>(DecodeResult { decoder: name, hub, pod, hub_clock_hz, annotations })
+    }).await
+    .map(Json)
+    .map_err(IntoResponse::into_response)
+}
@@ -0,0 +1,251 @@
+//! Deep-capture (DDR sink) endpoints
+//!
+//! With `SUMP_DEEP_ADDR` set to the register window of a
+//! `sump3_deep_sink.sv` instance, captures far beyond pod BRAM depth are
+//! recorded into DDR and streamed back from `/api/ila/deep/data`. The sink
+//! is reached through local `/dev/mem`, whatever `SUMP_BACKEND` is.
+//! The data download honours `Range` (see `range`) so a large capture can
+//! resume after a dropped connection.
+//!
+//! The sink has no signal layout of its own; `/api/ila/deep/export/vcd` and
+//! `/api/ila/deep/decode/:decoder` (see `decode`) name the pod whose signals
+//! and hub clock its probes carry with `?hub=&pod=` (default 0.0). The VCD is
+//! written while the records stream out, so neither needs the capture in
+//! memory at once.
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, Extensions, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use std::fmt::Write;
+use std::io;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use sump_driver::deep::{DeepSink, RECORD_BYTES};
+use sump_export::vcd::VcdWriter;
+use sump_model::{CommandResult, DeepConfig, DeepStatus, ValidationErrors};
+
+use crate::decode::Trace;
+use crate::ila::IlaState;
+use crate::masks;
+use crate::range::{self, ByteRange, Requested};
+use crate::spill::{SpillConfig, SpillVec};
+use crate::validate;
+
+/// Chunks read ahead of the HTTP client
+const READ_AHEAD: usize = 4;
+
+fn not_configured() -> Response {
+    (StatusCode::NOT_FOUND, "No deep-capture sink configured (SUMP_DEEP_ADDR)").into_response()
+}
+
+/// Run `f` against the sink on the blocking pool, or 404 without one
+async fn with_sink<T, F>(state: &Arc<IlaState>, f: F) -> Result<T, Response>
+where
+    F: FnOnce(&DeepSink) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    if state.deep.is_none() {
+        return Err(not_configured());
+    }
+    Ok(state.run(move |s| f(s.deep.as_ref().unwrap())).await)
+}
+
+/// Sink state, or 409 while it's still recording
+pub async fn stopped(state: &Arc<IlaState>) -> Result<DeepStatus, Response> {
+    let status = with_sink(state, |deep| deep.status()).await?;
+    if status.running {
+        return Err((StatusCode::CONFLICT, "Deep capture still running; stop it or wait for the trigger").into_response());
+    }
+    Ok(status)
+}
+
+/// Pod whose signal layout and hub clock apply to the sink's probes
+#[derive(Debug, Deserialize)]
+pub struct DeepPodQuery {
+    #[serde(default)]
+    pub hub: u8,
+    #[serde(default)]
+    pub pod: u8,
+}
+
+/// Turns records into `(cycles since the first, data)`, unwrapping the 32-bit
+/// timestamps; records 2^32 or more cycles apart look closer than they are
+#[derive(Default)]
+struct Unwrap {
+    first: Option<u64>,
+    last: u32,
+    wraps: u64,
+}
+
+impl Unwrap {
+    fn record(&mut self, record: &[u8]) -> (u64, u32) {
+        let data = u32::from_le_bytes(record[0..4].try_into().unwrap());
+        let timestamp = u32::from_le_bytes(record[4..8].try_into().unwrap());
+        if self.first.is_some() && timestamp < self.last {
+            self.wraps += 1;
+        }
+        self.last = timestamp;
+        let t = self.wraps << 32 | timestamp as u64;
+        (t - *self.first.get_or_insert(t), data)
+    }
+}
+
+/// The stopped capture as a decoder `Trace`, spilled to disk past `spill`'s budget
+pub fn read_trace(deep: &DeepSink, spill: &SpillConfig, hub_hz: u64) -> io::Result<Trace> {
+    let (mut cycles, mut data) = (SpillVec::new(spill), SpillVec::new(spill));
+    let mut unwrap = Unwrap::default();
+    let mut spilled = Ok(());
+    deep.read_records(|chunk| {
+        for record in chunk.chunks_exact(RECORD_BYTES as usize) {
+            let (t, d) = unwrap.record(record);
+            if let Err(e) = cycles.push(t).and_then(|()| data.push(d)) {
+                spilled = Err(e);
+                return false;
+            }
+        }
+        true
+    })?;
+    spilled?;
+    Ok(Trace::from_parts(cycles.finish()?, data.finish()?, hub_hz))
+}
+
+/// GET /api/ila/deep - Deep-capture sink state
+pub async fn get_deep(State(state): State<Arc<IlaState>>) -> Result<Json<DeepStatus>, Response> {
+    with_sink(&state, |deep| deep.status()).await.map(Json)
+}
+
+/// PUT /api/ila/deep/config - Set the DDR buffer and post-trigger length (stops recording)
+pub async fn put_deep_config(
+    State(state): State<Arc<IlaState>>,
+    Json(config): Json<DeepConfig>,
+) -> Result<Json<DeepStatus>, Response> {
+    state.deep_generation.fetch_add(1, Ordering::Relaxed);
+    with_sink(&state, move |deep| deep.configure(&config).map(|()| deep.status())).await?
+        .map(Json)
+        .map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response())
+}
+
+/// POST /api/ila/deep/arm - Start recording into an empty buffer
+pub async fn post_deep_arm(State(state): State<Arc<IlaState>>) -> Result<Json<CommandResult>, Response> {
+    state.deep_generation.fetch_add(1, Ordering::Relaxed);
+    let status = with_sink(&state, |deep| {
+        deep.arm();
+        deep.status()
+    }).await?;
+    Ok(Json(CommandResult {
+        success: status.running,
+        message: if status.running { "Deep capture armed".into() } else { "Deep capture did not start".into() },
+    }))
+}
+
+/// POST /api/ila/deep/stop - Stop recording and keep the buffer
+pub async fn post_deep_stop(State(state): State<Arc<IlaState>>) -> Result<Json<CommandResult>, Response> {
+    with_sink(&state, |deep| deep.stop()).await?;
+    Ok(Json(CommandResult { success: true, message: "Deep capture stopped".into() }))
+}
+
+/// GET /api/ila/deep/data - Stream records oldest first: 8 bytes each,
+/// little-endian `{data: u32, timestamp: u32}`
+pub async fn get_deep_data(
+    State(state): State<Arc<IlaState>>,
+    request: HeaderMap,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    masks::unmaskable(&state, masks::partner(&extensions), "Deep capture data").map_err(IntoResponse::into_response)?;
+    let status = stopped(&state).await?;
+    let len = status.records * RECORD_BYTES as u64;
+    let etag = format!("\"deep-{}-{}\"", state.deep_generation.load(Ordering::Relaxed), status.records);
+    let part = match range::requested(&request, len, &etag) {
+        Requested::Whole => None,
+        Requested::Part(range) => Some(range),
+        Requested::Unsatisfiable => return Err(range::not_satisfiable(len)),
+    };
+    let ByteRange { start, end } = part.unwrap_or(ByteRange { start: 0, end: len });
+
+    let (tx, rx) = mpsc::channel::<std::io::Result<Vec<u8>>>(READ_AHEAD);
+    let reader = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let deep = reader.deep.as_ref().unwrap();
+        if let Err(e) = deep.read_range(start, end, |chunk| tx.blocking_send(Ok(chunk)).is_ok()) {
+            tracing::warn!("Deep capture readout failed: {}", e);
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    let mut response = Response::builder()
+        .status(if part.is_some() { StatusCode::PARTIAL_CONTENT } else { StatusCode::OK })
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, end - start)
+        .header(header::CONTENT_DISPOSITION, "attachment; filename=\"deep_capture.bin\"")
+        .header("x-sump-records", status.records);
+    if let Some(index) = status.trigger_record {
+        response = response.header("x-sump-trigger-record", index);
+    }
+    let mut response = response.body(Body::from_stream(stream)).unwrap();
+    range::add_headers(response.headers_mut(), &etag);
+    if let Some(part) = part {
+        range::add_part_headers(response.headers_mut(), part, len);
+    }
+    Ok(response)
+}
+
+/// GET /api/ila/deep/export/vcd?hub=&pod= - Stream the stopped capture as VCD with the pod's signals
+pub async fn get_deep_vcd(
+    State(state): State<Arc<IlaState>>,
+    Query(DeepPodQuery { hub, pod }): Query<DeepPodQuery>,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    masks::unmaskable(&state, masks::partner(&extensions), "Deep capture data").map_err(IntoResponse::into_response)?;
+    let status = stopped(&state).await?;
+    let (info, hub_hz) = state.run_op("readout", move |s| {
+        validate::visible_pod(s, hub, pod)?;
+        Ok((s.ila.enumerate_pod(hub, pod), s.ila.hub_clock_hz(hub)))
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map_err(|e: validate::Invalid| e.into_response())?;
+
+    let (tx, rx) = mpsc::channel::<io::Result<String>>(READ_AHEAD);
+    let reader = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let deep = reader.deep.as_ref().unwrap();
+        let mut writer = VcdWriter::new(&info, info.data_bits, hub_hz);
+        let mut vcd = format!("$version\n   SUMP3 deep capture - hub {} pod {}\n$end\n", hub, pod);
+        writer.definitions(&mut vcd, &info, hub);
+        let mut unwrap = Unwrap::default();
+        let mut index = 0;
+        let result = deep.read_records(|chunk| {
+            for record in chunk.chunks_exact(RECORD_BYTES as usize) {
+                let (cycles, data) = unwrap.record(record);
+                if status.trigger_record == Some(index) {
+                    let _ = writeln!(vcd, "$comment\n   trigger at record {}\n$end", index);
+                }
+                writer.sample(&mut vcd, cycles, data);
+                index += 1;
+            }
+            tx.blocking_send(Ok(std::mem::take(&mut vcd))).is_ok()
+        });
+        if let Err(e) = result {
+            tracing::warn!("Deep capture VCD export failed: {}", e);
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "text/x-vcd")
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"deep_hub{}_pod{}.vcd\"", hub, pod))
+        .header("x-sump-records", status.records)
+        .body(Body::from_stream(stream))
+        .unwrap())
+}
@@ -0,0 +1,81 @@
+//! Degraded startup
+//!
+//! When the register backend can't be opened, or the wrapper doesn't answer
+//! with the SUMP3 HW_ID, the server starts anyway instead of exiting: the
+//! frontend is served, `/api/ila` reports the reason in `error`, hardware
+//! endpoints answer 503 with it and `/readyz` stays 503. A background task
+//! reopens the backend every `RECONNECT_INTERVAL` and swaps it in once the
+//! wrapper answers; the startup INIT/ARM run then.
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sump_driver::{Backend, HW_ID_SUMP3, HW_INFO_ID, REG_HW_INFO};
+
+use crate::ila::IlaState;
+
+/// Delay between reconnect attempts
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A register backend and its window size, as opened from `SUMP_BACKEND`
+pub type Opened = (Box<dyn Backend>, usize);
+
+/// Check that a SUMP3 wrapper answers on `backend`
+pub fn verify(backend: &mut dyn Backend) -> Result<(), String> {
+    match backend.read32(REG_HW_INFO) {
+        None => Err("HW_INFO read failed".into()),
+        Some(hw_info) if HW_INFO_ID.get(hw_info) != HW_ID_SUMP3 => {
+            Err(format!("no SUMP3 wrapper answers (HW_INFO 0x{:08X})", hw_info))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// Retry `open` (which opens and `verify`s the backend) until it succeeds,
+/// swap the backend in, then run `connected`
+pub async fn reconnect<F, C>(state: Arc<IlaState>, open: F, connected: C)
+where
+    F: Fn() -> Result<Opened, String> + Send + Sync + 'static,
+    C: Future<Output = ()>,
+{
+    let open = Arc::new(open);
+    let mut shutdown = state.shutdown_signal();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(RECONNECT_INTERVAL) => {}
+            _ = shutdown.changed() => return,
+        }
+        let open = open.clone();
+        let opened = tokio::task::spawn_blocking(move || open()).await.expect("reconnect task panicked");
+
+        match opened {
+            Ok((backend, window)) => {
+                state.ila.set_backend(backend, window);
+                *state.degraded.lock().unwrap() = None;
+                tracing::info!("ILA reconnected: {}", state.ila.backend());
+                connected.await;
+                return;
+            }
+            Err(e) => {
+                tracing::debug!("Reconnect failed: {}", e);
+                *state.degraded.lock().unwrap() = Some(e);
+            }
+        }
+    }
+}
+
+/// Middleware answering 503 with the reason while there is no hardware
+pub async fn unavailable(State(state): State<Arc<IlaState>>, request: Request, next: Next) -> Response {
+    let degraded = state.degraded.lock().unwrap().clone();
+    match degraded {
+        Some(reason) => (StatusCode::SERVICE_UNAVAILABLE, format!("ILA unavailable: {}", reason)).into_response(),
+        None => next.run(request).await,
+    }
+}
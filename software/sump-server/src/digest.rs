@@ -0,0 +1,139 @@
+//! Capture digests
+//!
+//! Every completed capture read out through the server (capture endpoints,
+//! waveform URLs) is summarized into a `CaptureDigest`: per-bit activity,
+//! the trigger sample's data and a content hash, compared against the
+//! previous capture of the same pod. `GET /api/ila/digests` returns them as
+//! a timeline, so one odd capture among hundreds of continuous-mode runs
+//! stands out without opening each.
+
+use axum::{
+    extract::{Query, State},
+    http::Extensions,
+    response::Json,
+};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use sump_model::{CaptureData, CaptureDigest};
+
+use crate::ila::IlaState;
+use crate::masks;
+
+/// Digests kept, oldest dropped first
+const KEPT_DIGESTS: usize = 1000;
+
+/// Recorded digests, oldest first
+pub type Digests = VecDeque<CaptureDigest>;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |h, &b| (h ^ b as u64).wrapping_mul(0x100_0000_01B3))
+}
+
+/// Summarize a capture; `previous` is the last digest of the same pod
+fn digest(capture: &CaptureData, capture_id: u64, previous: Option<&CaptureDigest>) -> CaptureDigest {
+    let mut toggles = vec![0u32; 32];
+    let mut hash = 0xCBF2_9CE4_8422_2325;
+    let mut last: Option<u32> = None;
+    let mut samples = 0;
+    for sample in capture.samples.iter().filter(|s| s.code != 0) {
+        samples += 1;
+        hash = fnv1a(hash, &sample.timestamp.to_le_bytes());
+        hash = fnv1a(hash, &sample.data.to_le_bytes());
+        if let Some(last) = last {
+            let changed = last ^ sample.data;
+            for (bit, count) in toggles.iter_mut().enumerate() {
+                *count += (changed >> bit) & 1;
+            }
+        }
+        last = Some(sample.data);
+    }
+    let active_bits = toggles.iter().enumerate()
+        .filter(|(_, &n)| n > 0)
+        .fold(0u32, |mask, (bit, _)| mask | 1 << bit);
+    let hash = format!("{:016x}", hash);
+
+    CaptureDigest {
+        capture_id,
+        hub: capture.hub,
+        pod: capture.pod,
+        triggered_at_ms: capture.triggered_at_ms,
+        triggered_at: capture.triggered_at.clone(),
+        samples,
+        toggles,
+        active_bits,
+        activity_changed: previous.map_or(0, |p| p.active_bits ^ active_bits),
+        trigger_data: capture.samples.iter().find(|s| s.code == 2).map(|s| s.data),
+        repeat: previous.is_some_and(|p| p.hash == hash),
+        hash,
+        gaps: capture.gaps.len() as u32,
+    }
+}
+
+/// Record the digest of an acquired capture, and check it against the
+/// alert rules; a re-read of the same capture replaces its entry
+pub fn record(state: &IlaState, capture: &CaptureData) {
+    if !capture.status.acquired || capture.readout_error.is_some() {
+        return;
+    }
+    let capture_id = capture.sequence;
+    let mut digests = state.digests.lock().unwrap();
+    let same_pod = |d: &&CaptureDigest| (d.hub, d.pod) == (capture.hub, capture.pod);
+    digests.retain(|d| !(same_pod(&d) && d.capture_id == capture_id));
+    let entry = digest(capture, capture_id, digests.iter().rev().find(same_pod));
+    if entry.activity_changed != 0 {
+        tracing::info!(
+            "Capture {} of hub {} pod {}: activity changed on bits 0x{:08X}",
+            capture_id, capture.hub, capture.pod, entry.activity_changed
+        );
+    }
+    digests.push_back(entry);
+    while digests.len() > KEPT_DIGESTS {
+        digests.pop_front();
+    }
+    drop(digests);
+    crate::alerts::evaluate(state, capture);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestQuery {
+    hub: Option<u8>,
+    pod: Option<u8>,
+    /// Only captures with a larger id
+    after: Option<u64>,
+}
+
+/// GET /api/ila/digests?hub=H&pod=P&after=ID - Capture digest timeline, oldest first
+pub async fn get_digests(
+    State(state): State<Arc<IlaState>>,
+    Query(query): Query<DigestQuery>,
+    extensions: Extensions,
+) -> Json<Vec<CaptureDigest>> {
+    let settings = state.settings.lock().unwrap().clone();
+    let mut digests: Vec<CaptureDigest> = state.digests.lock().unwrap().iter()
+        .filter(|d| !crate::settings::is_hidden(&settings, d.hub, d.pod))
+        .filter(|d| query.hub.is_none_or(|hub| d.hub == hub))
+        .filter(|d| query.pod.is_none_or(|pod| d.pod == pod))
+        .filter(|d| query.after.is_none_or(|after| d.capture_id > after))
+        .cloned()
+        .collect();
+
+    if masks::partner(&extensions) {
+        let mut pods: Vec<(u8, u8)> = digests.iter()
+            .map(|d| (d.hub, d.pod))
+            .filter(|&(hub, pod)| masks::masked(&settings, hub, pod))
+            .collect();
+        pods.sort_unstable();
+        pods.dedup();
+        for (hub, pod) in pods {
+            let Some(mask) = state.run(move |s| masks::for_request(s, true, hub, pod)).await else {
+                continue;
+            };
+            for digest in digests.iter_mut().filter(|d| (d.hub, d.pod) == (hub, pod)) {
+                mask.blank_digest(digest);
+            }
+        }
+    }
+    Json(digests)
+}
@@ -0,0 +1,93 @@
+//! Capture exporters
+//!
+//! The built-in formats are the `sump_export::EXPORTERS`, shared with
+//! `sump-cli convert`; plugins add more (see `plugins`).
+//! `GET /api/ila/export-formats` lists them and
+//! `GET /api/ila/capture/:hub/:pod/export/:format` reads the whole capture
+//! of a pod and writes it in that format.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, Extensions, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use std::io;
+use std::sync::Arc;
+
+use sump_export::{Export, Exporter, EXPORTERS};
+use sump_model::ExportFormat;
+
+use crate::ila::IlaState;
+use crate::{digest, masks, validate};
+
+/// Built-in formats, then those of plugins
+pub fn all(state: &IlaState) -> impl Iterator<Item = &dyn Exporter> {
+    EXPORTERS.iter().copied().chain(state.plugins.exporters.iter().map(|e| e.as_ref() as &dyn Exporter))
+}
+
+pub fn find<'a>(state: &'a IlaState, name: &str) -> Option<&'a dyn Exporter> {
+    all(state).find(|e| e.name() == name)
+}
+
+/// GET /api/ila/export-formats - Formats for /capture/:hub/:pod/export/:format
+pub async fn get_export_formats(State(state): State<Arc<IlaState>>) -> Json<Vec<ExportFormat>> {
+    Json(all(&state).map(|e| ExportFormat {
+        name: e.name().into(),
+        mime_type: e.mime_type().into(),
+        extension: e.extension().into(),
+        description: e.description().into(),
+    }).collect())
+}
+
+/// GET /api/ila/capture/:hub/:pod/export/:format - The whole capture as a file
+pub async fn get_export(
+    State(state): State<Arc<IlaState>>,
+    Path((hub, pod, format)): Path<(u8, u8, String)>,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    let partner = masks::partner(&extensions);
+    let Some(exporter) = find(&state, &format) else {
+        let message = format!("Unknown export format '{}' (see /api/ila/export-formats)", format);
+        return Err((StatusCode::NOT_FOUND, message).into_response());
+    };
+    let (mime_type, extension) = (exporter.mime_type().to_string(), exporter.extension().to_string());
+
+    let (body, sequence) = state.run_op("readout", move |s| -> Result<_, validate::Invalid> {
+        let exporter = find(s, &format).expect("checked above");
+        validate::visible_pod(s, hub, pod)?;
+        let mut capture = s.ila.read_capture_all(hub, pod);
+        if let Some(e) = &capture.readout_error {
+            return Err((StatusCode::BAD_GATEWAY, e.message.clone()));
+        }
+        digest::record(s, &capture);
+        let (mut info, hub_name) = s.labeled_pod(hub, pod);
+        if let Some(mask) = masks::for_request(s, partner, hub, pod) {
+            mask.blank(&mut capture);
+            mask.redact(&mut info);
+        }
+        let export = Export {
+            capture: &capture,
+            pod: &info,
+            hub_name: &hub_name,
+            hub_hz: s.ila.hub_clock_hz(hub),
+            id: capture.sequence,
+        };
+        let mut body = Vec::new();
+        exporter.write(&export, &mut body).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidInput => (StatusCode::UNPROCESSABLE_ENTITY, format!("Hub {} pod {}: {}", hub, pod, e)),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+        Ok((body, capture.sequence))
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map_err(IntoResponse::into_response)?;
+
+    let filename = format!("capture{}_hub{}_pod{}.{}", sequence, hub, pod, extension);
+    Ok((
+        [
+            (header::CONTENT_TYPE, mime_type),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    ).into_response())
+}
@@ -0,0 +1,133 @@
+//! GPIO-driven external trigger
+//!
+//! With `SUMP_EXT_TRIG_GPIO` set, `POST /api/ila/ext-trigger` pulses a
+//! sysfs GPIO line wired to one of the wrapper's `ext_trig_in` pins. Select
+//! that pin with the trigger routing and use the `external` trigger type.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sump_model::CommandResult;
+
+use crate::ila::IlaState;
+
+const SYSFS_GPIO: &str = "/sys/class/gpio";
+
+/// Default pulse width
+pub const DEFAULT_PULSE_US: u64 = 100;
+
+/// Longest pulse a request may ask for
+const MAX_PULSE_US: u64 = 1_000_000;
+
+/// How long to wait for udev to make a freshly exported line writable
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct ExtTriggerGpio {
+    line: u32,
+    active_low: bool,
+    pulse: Duration,
+    value: Mutex<File>,
+}
+
+impl ExtTriggerGpio {
+    /// Export and claim `<line>[:active_low]` as an output at its inactive level
+    pub fn open(spec: &str, pulse: Duration) -> Result<Self, String> {
+        let (line, active_low) = match spec.split_once(':') {
+            Some((line, "active_low")) => (line, true),
+            Some((_, flag)) => return Err(format!("unknown GPIO flag '{}' (expected active_low)", flag)),
+            None => (spec, false),
+        };
+        let line: u32 = line.trim().parse().map_err(|_| format!("invalid GPIO line '{}'", line))?;
+
+        let dir = PathBuf::from(SYSFS_GPIO).join(format!("gpio{}", line));
+        if !dir.exists() {
+            std::fs::write(PathBuf::from(SYSFS_GPIO).join("export"), line.to_string())
+                .map_err(|e| format!("failed to export GPIO {}: {}", line, e))?;
+        }
+
+        // "low"/"high" sets output direction and the initial level atomically
+        let inactive = if active_low { "high" } else { "low" };
+        let deadline = Instant::now() + EXPORT_TIMEOUT;
+        loop {
+            match std::fs::write(dir.join("direction"), inactive) {
+                Ok(()) => break,
+                Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+                Err(e) => return Err(format!("failed to configure GPIO {} as output: {}", line, e)),
+            }
+        }
+        let value = OpenOptions::new()
+            .write(true)
+            .open(dir.join("value"))
+            .map_err(|e| format!("failed to open GPIO {} value: {}", line, e))?;
+
+        Ok(Self {
+            line,
+            active_low,
+            pulse,
+            value: Mutex::new(value),
+        })
+    }
+
+    /// Drive the line active for `width`, then back to inactive
+    pub fn pulse(&self, width: Duration) -> io::Result<()> {
+        let value = self.value.lock().unwrap();
+        let (active, inactive) = if self.active_low { (b"0", b"1") } else { (b"1", b"0") };
+        value.write_all_at(active, 0)?;
+        std::thread::sleep(width);
+        value.write_all_at(inactive, 0)
+    }
+
+    /// Pulse for the configured width (`SUMP_EXT_TRIG_PULSE_US`)
+    pub fn fire(&self) -> io::Result<()> {
+        self.pulse(self.pulse)
+    }
+
+    pub fn describe(&self) -> String {
+        format!("GPIO {}{}", self.line, if self.active_low { " (active low)" } else { "" })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PulseQuery {
+    pulse_us: Option<u64>,
+}
+
+/// POST /api/ila/ext-trigger?pulse_us=N - Pulse the external trigger GPIO
+pub async fn post_ext_trigger(
+    State(state): State<Arc<IlaState>>,
+    Query(query): Query<PulseQuery>,
+) -> Result<Json<CommandResult>, Response> {
+    let Some(gpio) = state.ext_trigger_gpio.as_ref() else {
+        return Err((StatusCode::NOT_FOUND, "No external trigger GPIO configured (SUMP_EXT_TRIG_GPIO)").into_response());
+    };
+    let width = match query.pulse_us {
+        Some(us) if us == 0 || us > MAX_PULSE_US => {
+            return Err((StatusCode::BAD_REQUEST, format!("pulse_us must be between 1 and {}", MAX_PULSE_US)).into_response());
+        }
+        Some(us) => Duration::from_micros(us),
+        None => gpio.pulse,
+    };
+    let name = gpio.describe();
+
+    let result = state.run(move |s| s.ext_trigger_gpio.as_ref().unwrap().pulse(width)).await;
+    Ok(Json(match result {
+        Ok(()) => {
+            tracing::info!("External trigger pulsed on {} for {} us", name, width.as_micros());
+            CommandResult { success: true, message: format!("Pulsed {} for {} us", name, width.as_micros()) }
+        }
+        Err(e) => {
+            tracing::warn!("External trigger pulse on {} failed: {}", name, e);
+            CommandResult { success: false, message: format!("Failed to drive {}: {}", name, e) }
+        }
+    }))
+}
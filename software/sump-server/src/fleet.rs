@@ -0,0 +1,218 @@
+//! Fleet proxy mode
+//!
+//! With `SUMP_FLEET` set, this server opens no local hardware and instead
+//! fronts several remote sump-server instances:
+//!
+//! - `GET /api/boards` - combined status/health of every board
+//! - `PUT /api/boards/correlation` - tag captures on every board with one id
+//! - `/api/boards/:name/ila/...` - forwarded to `<board url>/api/ila/...`
+//!
+//! The `/api/ila/watch` WebSocket is not forwarded; connect to the board directly.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{header, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Json, Response},
+    routing::{any, get, put},
+    Router,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinSet;
+
+use sump_model::{BoardStatus, CommandResult, Correlation, FleetStatus};
+
+/// Per-board deadline when building the combined status
+const POLL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Connect deadline for forwarded requests (readouts themselves may be slow)
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Board {
+    name: String,
+    url: String,
+    client: sump_client::Client,
+}
+
+pub struct Fleet {
+    boards: Vec<Board>,
+    http: reqwest::Client,
+}
+
+impl Fleet {
+    /// Parse `name=url,name=url,...`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let mut boards: Vec<Board> = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, url) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=url, got '{}'", entry))?;
+            if name.is_empty() || name.contains('/') {
+                return Err(format!("invalid board name '{}'", name));
+            }
+            if boards.iter().any(|b| b.name == name) {
+                return Err(format!("duplicate board name '{}'", name));
+            }
+            let client = sump_client::Client::with_http_client(url, http.clone());
+            boards.push(Board {
+                name: name.to_string(),
+                url: client.base_url().to_string(),
+                client,
+            });
+        }
+        if boards.is_empty() {
+            return Err("no boards configured".into());
+        }
+        Ok(Self { boards, http })
+    }
+
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+}
+
+async fn poll_board(name: String, url: String, client: sump_client::Client) -> BoardStatus {
+    let polled = tokio::time::timeout(POLL_TIMEOUT, async {
+        let status = client.status().await?;
+        let health = client.health().await?;
+        Ok::<_, sump_client::Error>((status, health))
+    })
+    .await;
+
+    let (status, health, error) = match polled {
+        Ok(Ok((status, health))) => (Some(status), Some(health), None),
+        Ok(Err(e)) => (None, None, Some(e.to_string())),
+        Err(_) => (None, None, Some(format!("no response within {} ms", POLL_TIMEOUT.as_millis()))),
+    };
+    BoardStatus {
+        name,
+        url,
+        reachable: error.is_none(),
+        error,
+        status,
+        health,
+    }
+}
+
+/// GET /api/boards - Status and health of every board, polled concurrently
+async fn get_boards(State(fleet): State<Arc<Fleet>>) -> Json<FleetStatus> {
+    let mut polls = JoinSet::new();
+    for (i, board) in fleet.boards.iter().enumerate() {
+        let poll = poll_board(board.name.clone(), board.url.clone(), board.client.clone());
+        polls.spawn(async move { (i, poll.await) });
+    }
+
+    let mut boards: Vec<Option<BoardStatus>> = vec![None; fleet.boards.len()];
+    while let Some(Ok((i, status))) = polls.join_next().await {
+        boards[i] = Some(status);
+    }
+    Json(FleetStatus {
+        boards: boards.into_iter().flatten().collect(),
+    })
+}
+
+/// PUT /api/boards/correlation - Set the same correlation tag and reference
+/// time on every board, so their trigger offsets share one origin
+async fn put_correlation(
+    State(fleet): State<Arc<Fleet>>,
+    Json(mut correlation): Json<Correlation>,
+) -> Json<CommandResult> {
+    correlation.reference_ms.get_or_insert_with(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+    });
+
+    let mut puts = JoinSet::new();
+    for board in &fleet.boards {
+        let (name, client, correlation) = (board.name.clone(), board.client.clone(), correlation.clone());
+        puts.spawn(async move {
+            let result = tokio::time::timeout(POLL_TIMEOUT, client.set_correlation(&correlation)).await;
+            match result {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(format!("{}: {}", name, e)),
+                Err(_) => Err(format!("{}: no response", name)),
+            }
+        });
+    }
+
+    let mut failed = Vec::new();
+    while let Some(Ok(result)) = puts.join_next().await {
+        if let Err(e) = result {
+            failed.push(e);
+        }
+    }
+    failed.sort();
+
+    let tagged = fleet.boards.len() - failed.len();
+    let mut message = format!("Tagged {}/{} boards with '{}'", tagged, fleet.boards.len(), correlation.id);
+    if !failed.is_empty() {
+        message = format!("{} ({})", message, failed.join("; "));
+    }
+    Json(CommandResult { success: failed.is_empty(), message })
+}
+
+/// ANY /api/boards/:name/ila/*path - Forward to the board's `/api/ila/*path`
+async fn forward(
+    State(fleet): State<Arc<Fleet>>,
+    Path(params): Path<HashMap<String, String>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let name = params.get("name").cloned().unwrap_or_default();
+    let Some(board) = fleet.boards.iter().find(|b| b.name == name) else {
+        return (StatusCode::NOT_FOUND, format!("Unknown board '{}'", name)).into_response();
+    };
+
+    let mut url = format!("{}/api/ila", board.url);
+    if let Some(path) = params.get("path") {
+        url.push('/');
+        url.push_str(path);
+    }
+    if let Some(query) = uri.query() {
+        url.push('?');
+        url.push_str(query);
+    }
+
+    let mut request = fleet.http.request(method, &url).body(body);
+    if let Some(content_type) = headers.get(header::CONTENT_TYPE) {
+        request = request.header(header::CONTENT_TYPE, content_type);
+    }
+
+    let upstream = match request.send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            tracing::warn!("Fleet: {} unreachable: {}", board.name, e);
+            return (StatusCode::BAD_GATEWAY, format!("Board '{}' unreachable: {}", board.name, e)).into_response();
+        }
+    };
+
+    let status = upstream.status();
+    let mut response = Response::builder().status(status);
+    for name in [header::CONTENT_TYPE, header::RETRY_AFTER] {
+        if let Some(value) = upstream.headers().get(&name) {
+            response = response.header(name, value);
+        }
+    }
+    match upstream.bytes().await {
+        Ok(bytes) => response.body(Body::from(bytes)).unwrap(),
+        Err(e) => (StatusCode::BAD_GATEWAY, format!("Board '{}': {}", board.name, e)).into_response(),
+    }
+}
+
+/// Create the `/api/boards` router
+pub fn fleet_router(fleet: Arc<Fleet>) -> Router {
+    Router::new()
+        .route("/", get(get_boards))
+        .route("/correlation", put(put_correlation))
+        .route("/:name/ila", any(forward))
+        .route("/:name/ila/*path", any(forward))
+        .with_state(fleet)
+}
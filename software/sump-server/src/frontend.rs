@@ -0,0 +1,182 @@
+//! Frontend bundles
+//!
+//! Besides the embedded Surfer bundle (`surfer`, feature `embed-frontend`)
+//! the server always has the built-in status dashboard (`dashboard`, one
+//! page, for field units), and `SUMP_FRONTENDS=name=/path/to/dist,...` adds
+//! bundles served from disk (a disk bundle named like a built-in one
+//! replaces it). `SUMP_FRONTEND` picks the bundle served at `/`: `surfer`
+//! by default, `dashboard` when Surfer isn't embedded.
+//!
+//! Every bundle is also served under `/ui/<name>/`; bundles other than the
+//! one at `/` must reference their files with relative URLs there (e.g.
+//! `trunk build --public-url ./`). Unknown paths get the bundle's
+//! `index.html` (SPA routing).
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "embed-frontend")]
+use rust_embed::Embed;
+
+/// Embedded Surfer WASM frontend files (built by trunk during cargo build,
+/// or the prebuilt `SUMP_SURFER_DIST`; see build.rs)
+#[cfg(feature = "embed-frontend")]
+#[derive(Embed)]
+#[folder = "$SUMP_SURFER_DIST_DIR/"]
+#[allow_missing = true]
+struct Assets;
+
+/// No frontend: built without the `embed-frontend` feature
+#[cfg(not(feature = "embed-frontend"))]
+struct Assets;
+
+#[cfg(not(feature = "embed-frontend"))]
+impl Assets {
+    fn get(_path: &str) -> Option<rust_embed::EmbeddedFile> {
+        None
+    }
+}
+
+/// Built-in status dashboard, served for every path of `dashboard`
+const DASHBOARD_PAGE: &str = include_str!("dashboard.html");
+
+/// Path prefix serving every bundle by name
+const PREFIX: &str = "/ui/";
+
+/// Where the files of a bundle come from
+enum Source {
+    /// `Assets`
+    Surfer,
+    /// `DASHBOARD_PAGE`
+    Dashboard,
+    /// A folder on disk
+    Disk(PathBuf),
+}
+
+impl Source {
+    /// File at `path` (relative, `/`-separated) in the bundle
+    fn get(&self, path: &str) -> Option<Cow<'static, [u8]>> {
+        match self {
+            Source::Surfer => Assets::get(path).map(|file| file.data),
+            Source::Dashboard => (path == "index.html").then_some(Cow::Borrowed(DASHBOARD_PAGE.as_bytes())),
+            Source::Disk(root) => {
+                let relative = Path::new(path);
+                if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+                    return None;
+                }
+                std::fs::read(root.join(relative)).ok().map(Cow::Owned)
+            }
+        }
+    }
+}
+
+/// The frontend bundles and the one served at `/`
+pub struct Frontends {
+    bundles: BTreeMap<String, Source>,
+    default: String,
+}
+
+/// Parse one `SUMP_FRONTENDS` entry
+fn parse_entry(entry: &str) -> Result<(String, PathBuf), String> {
+    let (name, path) = entry.split_once('=').ok_or_else(|| format!("'{}' is not name=path", entry))?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("invalid frontend name '{}'", name));
+    }
+    let path = PathBuf::from(path.trim());
+    if !path.join("index.html").is_file() {
+        return Err(format!("{} has no index.html", path.display()));
+    }
+    Ok((name.to_string(), path))
+}
+
+impl Frontends {
+    /// Built-in bundles, those of `SUMP_FRONTENDS` and the `SUMP_FRONTEND` default
+    pub fn from_env() -> Self {
+        let mut bundles = BTreeMap::new();
+        bundles.insert("dashboard".to_string(), Source::Dashboard);
+        if Assets::get("index.html").is_some() {
+            bundles.insert("surfer".to_string(), Source::Surfer);
+        }
+        if let Ok(spec) = std::env::var("SUMP_FRONTENDS") {
+            for entry in spec.split(',').filter(|entry| !entry.trim().is_empty()) {
+                match parse_entry(entry) {
+                    Ok((name, path)) => {
+                        bundles.insert(name, Source::Disk(path));
+                    }
+                    Err(e) => tracing::error!("SUMP_FRONTENDS: {}", e),
+                }
+            }
+        }
+
+        let builtin = if bundles.contains_key("surfer") { "surfer" } else { "dashboard" };
+        let default = match std::env::var("SUMP_FRONTEND") {
+            Ok(name) if bundles.contains_key(&name) => name,
+            Ok(name) => {
+                tracing::error!("SUMP_FRONTEND: no frontend '{}', serving '{}'", name, builtin);
+                builtin.to_string()
+            }
+            Err(_) => builtin.to_string(),
+        };
+        if !bundles.contains_key("surfer") {
+            tracing::warn!("Surfer frontend not embedded");
+        }
+        let names: Vec<&str> = bundles.keys().map(String::as_str).collect();
+        tracing::info!("Frontends: {} ('{}' at /, all under {}<name>/)", names.join(", "), default, PREFIX);
+        Self { bundles, default }
+    }
+
+    /// Response for `file` of bundle `name`
+    fn respond(&self, name: &str, file: &str) -> Response {
+        let Some(source) = self.bundles.get(name) else {
+            return (StatusCode::NOT_FOUND, format!("No frontend '{}'", name)).into_response();
+        };
+        let file = if file.is_empty() { "index.html" } else { file };
+        if let Some(data) = source.get(file) {
+            let mime = mime_guess::from_path(file).first_or_octet_stream();
+            return ([(header::CONTENT_TYPE, mime.as_ref())], data).into_response();
+        }
+        // For SPA routing, serve index.html for unknown paths
+        match source.get("index.html") {
+            Some(data) => ([(header::CONTENT_TYPE, "text/html; charset=utf-8")], data).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+}
+
+/// Serve `/ui/<name>/<file>` from bundle `name`, other paths from the default bundle
+async fn serve(State(frontends): State<Arc<Frontends>>, uri: Uri) -> Response {
+    let path = uri.path();
+    let (name, file) = match path.strip_prefix(PREFIX) {
+        Some(rest) => match rest.split_once('/') {
+            Some(split) => split,
+            None if rest.is_empty() => {
+                let names: Vec<&str> = frontends.bundles.keys().map(String::as_str).collect();
+                return (StatusCode::NOT_FOUND, format!("Frontends: {}", names.join(", "))).into_response();
+            }
+            // Relative URLs of the bundle need the trailing slash
+            None => return Redirect::permanent(&format!("{}{}/", PREFIX, rest)).into_response(),
+        },
+        None => (frontends.default.as_str(), path.trim_start_matches('/')),
+    };
+    let (name, file) = (name.to_string(), file.to_string());
+    // Disk bundles read files
+    tokio::task::spawn_blocking(move || frontends.respond(&name, &file))
+        .await
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Create the fallback router serving the frontends
+pub fn frontend_router(frontends: Arc<Frontends>) -> Router {
+    Router::new()
+        .fallback(serve)
+        .with_state(frontends)
+}
@@ -0,0 +1,453 @@
+//! gRPC control interface (feature `grpc`)
+//!
+//! Serves the `sump.v1.Ila` service of `proto/sump.proto` on
+//! `SUMP_GRPC_PORT`: enumeration, status, arm, trigger configuration and
+//! capture readout as a server-streaming RPC. Operations share the REST
+//! API's exclusion (`ops`), so a busy ILA answers `ABORTED` instead of 409.
+
+use axum::http::StatusCode;
+use futures_util::stream::{self, Stream};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use sump_driver::*;
+use sump_model::{ReadoutWindow, ValidationErrors};
+
+use crate::ila::IlaState;
+use crate::ops::Conflict;
+use crate::validate;
+
+/// Samples per `SampleBatch` when the request leaves `batch_size` at 0
+const DEFAULT_BATCH: usize = 1024;
+
+/// Samples on each side of the trigger when a window omits `span`
+const DEFAULT_SPAN: u32 = 100;
+
+/// Messages of `proto/sump.proto`, written out so no protoc is needed
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Empty {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SignalInfo {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(uint32, tag = "2")]
+        pub bit_high: u32,
+        #[prost(uint32, tag = "3")]
+        pub bit_low: u32,
+        #[prost(string, tag = "4")]
+        pub signal_type: String,
+        #[prost(bool, tag = "5")]
+        pub triggerable: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PodInfo {
+        #[prost(uint32, tag = "1")]
+        pub index: u32,
+        #[prost(string, tag = "2")]
+        pub name: String,
+        #[prost(uint32, tag = "3")]
+        pub ram_depth: u32,
+        #[prost(uint32, tag = "4")]
+        pub data_bits: u32,
+        #[prost(uint32, tag = "5")]
+        pub ts_bits: u32,
+        #[prost(uint32, tag = "6")]
+        pub triggerable: u32,
+        #[prost(bool, tag = "7")]
+        pub rle_disable: bool,
+        #[prost(message, repeated, tag = "8")]
+        pub signals: Vec<SignalInfo>,
+        #[prost(string, tag = "9")]
+        pub display_name: String,
+        #[prost(string, tag = "10")]
+        pub description: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HubInfo {
+        #[prost(uint32, tag = "1")]
+        pub index: u32,
+        #[prost(string, tag = "2")]
+        pub name: String,
+        #[prost(uint64, tag = "3")]
+        pub freq_hz: u64,
+        #[prost(message, repeated, tag = "4")]
+        pub pods: Vec<PodInfo>,
+        #[prost(string, tag = "5")]
+        pub display_name: String,
+        #[prost(string, tag = "6")]
+        pub description: String,
+        #[prost(uint32, tag = "7")]
+        pub instance: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct IlaInfo {
+        #[prost(bool, tag = "1")]
+        pub connected: bool,
+        #[prost(string, tag = "2")]
+        pub hw_id: String,
+        #[prost(uint32, tag = "3")]
+        pub revision: u32,
+        #[prost(bool, tag = "4")]
+        pub is_armed: bool,
+        #[prost(bool, tag = "5")]
+        pub is_awake: bool,
+        #[prost(string, tag = "6")]
+        pub base_addr: String,
+        #[prost(message, repeated, tag = "7")]
+        pub hubs: Vec<HubInfo>,
+        #[prost(message, repeated, tag = "8")]
+        pub groups: Vec<PodGroup>,
+        #[prost(string, tag = "9")]
+        pub design_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PodGroup {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub description: String,
+        #[prost(string, repeated, tag = "3")]
+        pub pods: Vec<String>,
+        #[prost(string, repeated, tag = "4")]
+        pub signals: Vec<String>,
+        #[prost(message, repeated, tag = "5")]
+        pub groups: Vec<PodGroup>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CaptureStatus {
+        #[prost(bool, tag = "1")]
+        pub armed: bool,
+        #[prost(bool, tag = "2")]
+        pub pre_trigger: bool,
+        #[prost(bool, tag = "3")]
+        pub triggered: bool,
+        #[prost(bool, tag = "4")]
+        pub acquired: bool,
+        #[prost(bool, tag = "5")]
+        pub init_in_progress: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CommandResult {
+        #[prost(bool, tag = "1")]
+        pub success: bool,
+        #[prost(string, tag = "2")]
+        pub message: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PodTrigger {
+        #[prost(uint32, tag = "1")]
+        pub hub: u32,
+        #[prost(uint32, tag = "2")]
+        pub pod: u32,
+        #[prost(uint32, tag = "3")]
+        pub bits: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TriggerConfig {
+        #[prost(string, tag = "1")]
+        pub trigger_type: String,
+        #[prost(uint32, tag = "2")]
+        pub trigger_bits: u32,
+        #[prost(uint32, optional, tag = "3")]
+        pub post_trigger: Option<u32>,
+        #[prost(string, repeated, tag = "4")]
+        pub trigger_signals: Vec<String>,
+        #[prost(message, repeated, tag = "5")]
+        pub pods: Vec<PodTrigger>,
+        #[prost(uint32, optional, tag = "6")]
+        pub tick_divisor: Option<u32>,
+        #[prost(double, optional, tag = "7")]
+        pub timeout_s: Option<f64>,
+        #[prost(string, tag = "8")]
+        pub on_timeout: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CaptureRequest {
+        #[prost(uint32, tag = "1")]
+        pub hub: u32,
+        #[prost(uint32, tag = "2")]
+        pub pod: u32,
+        #[prost(string, tag = "3")]
+        pub window: String,
+        #[prost(uint32, optional, tag = "4")]
+        pub span: Option<u32>,
+        #[prost(uint32, tag = "5")]
+        pub batch_size: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RleSample {
+        #[prost(uint32, tag = "1")]
+        pub address: u32,
+        #[prost(uint32, tag = "2")]
+        pub code: u32,
+        #[prost(uint32, tag = "3")]
+        pub timestamp: u32,
+        #[prost(uint32, tag = "4")]
+        pub data: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SampleBatch {
+        #[prost(uint32, tag = "1")]
+        pub hub: u32,
+        #[prost(uint32, tag = "2")]
+        pub pod: u32,
+        #[prost(uint32, tag = "3")]
+        pub ts_bits: u32,
+        #[prost(uint32, tag = "4")]
+        pub data_bits: u32,
+        #[prost(uint32, tag = "5")]
+        pub offset: u32,
+        #[prost(message, repeated, tag = "6")]
+        pub samples: Vec<RleSample>,
+        #[prost(uint32, tag = "7")]
+        pub total: u32,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/sump.v1.Ila.rs"));
+}
+
+use proto::ila_server::{Ila as IlaService, IlaServer};
+
+/// REST status codes of shared validation, as gRPC codes
+fn status(code: StatusCode, message: String) -> Status {
+    match code {
+        StatusCode::NOT_FOUND => Status::not_found(message),
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Status::invalid_argument(message),
+        StatusCode::CONFLICT => Status::failed_precondition(message),
+        StatusCode::BAD_GATEWAY => Status::unavailable(message),
+        _ => Status::internal(message),
+    }
+}
+
+fn conflict(conflict: Conflict) -> Status {
+    Status::aborted(conflict.0.message)
+}
+
+fn invalid(errors: ValidationErrors) -> Status {
+    let fields: Vec<String> = errors.errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+    Status::invalid_argument(fields.join("; "))
+}
+
+fn capture_status(s: sump_model::CaptureStatus) -> proto::CaptureStatus {
+    proto::CaptureStatus {
+        armed: s.armed,
+        pre_trigger: s.pre_trigger,
+        triggered: s.triggered,
+        acquired: s.acquired,
+        init_in_progress: s.init_in_progress,
+    }
+}
+
+fn pod_group(group: sump_model::PodGroup) -> proto::PodGroup {
+    proto::PodGroup {
+        name: group.name,
+        description: group.description,
+        pods: group.pods,
+        signals: group.signals,
+        groups: group.groups.into_iter().map(pod_group).collect(),
+    }
+}
+
+fn ila_info(info: sump_model::IlaInfo) -> proto::IlaInfo {
+    proto::IlaInfo {
+        connected: info.connected,
+        hw_id: info.hw_id,
+        revision: info.revision as u32,
+        is_armed: info.is_armed,
+        is_awake: info.is_awake,
+        base_addr: info.base_addr,
+        hubs: info.hubs.into_iter().map(|hub| proto::HubInfo {
+            index: hub.index as u32,
+            name: hub.name,
+            instance: hub.instance,
+            freq_hz: hub.freq_hz,
+            pods: hub.pods.into_iter().map(|pod| proto::PodInfo {
+                index: pod.index as u32,
+                name: pod.name,
+                ram_depth: pod.ram_depth,
+                data_bits: pod.data_bits as u32,
+                ts_bits: pod.ts_bits as u32,
+                triggerable: pod.triggerable,
+                rle_disable: pod.rle_disable,
+                signals: pod.signals.into_iter().map(|sig| proto::SignalInfo {
+                    name: sig.name,
+                    bit_high: sig.bit_high as u32,
+                    bit_low: sig.bit_low as u32,
+                    signal_type: sig.signal_type,
+                    triggerable: sig.triggerable,
+                }).collect(),
+                display_name: pod.display_name.unwrap_or_default(),
+                description: pod.description.unwrap_or_default(),
+            }).collect(),
+            display_name: hub.display_name.unwrap_or_default(),
+            description: hub.description.unwrap_or_default(),
+        }).collect(),
+        groups: info.groups.into_iter().map(pod_group).collect(),
+        design_id: info.design_id.unwrap_or_default(),
+    }
+}
+
+fn trigger_config(config: proto::TriggerConfig) -> Result<sump_model::TriggerConfig, String> {
+    let index = |value: u32, what: &str| u8::try_from(value).map_err(|_| format!("{} {} out of range", what, value));
+    let pods = config.pods.into_iter()
+        .map(|p| Ok(sump_model::PodTrigger { hub: index(p.hub, "hub")?, pod: index(p.pod, "pod")?, bits: p.bits }))
+        .collect::<Result<_, String>>()?;
+    let on_timeout = match config.on_timeout.as_str() {
+        "" => None,
+        action => Some(serde_json::from_value(serde_json::Value::from(action))
+            .map_err(|_| format!("unknown on_timeout '{}'", action))?),
+    };
+    Ok(sump_model::TriggerConfig {
+        trigger_type: config.trigger_type,
+        trigger_bits: config.trigger_bits,
+        post_trigger: config.post_trigger.unwrap_or(sump_model::TriggerConfig::default().post_trigger),
+        trigger_signals: config.trigger_signals,
+        pods,
+        tick_divisor: config.tick_divisor,
+        timeout_s: config.timeout_s,
+        on_timeout,
+    })
+}
+
+struct Service {
+    state: Arc<IlaState>,
+}
+
+type SampleStream = Pin<Box<dyn Stream<Item = Result<proto::SampleBatch, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl IlaService for Service {
+    async fn get_info(&self, _: Request<proto::Empty>) -> Result<Response<proto::IlaInfo>, Status> {
+        let info = self.state.run(|s| s.info()).await;
+        Ok(Response::new(ila_info(info)))
+    }
+
+    async fn get_status(&self, _: Request<proto::Empty>) -> Result<Response<proto::CaptureStatus>, Status> {
+        let status = self.state.run(|s| s.ila.capture_status()).await;
+        Ok(Response::new(capture_status(status)))
+    }
+
+    async fn arm(&self, _: Request<proto::Empty>) -> Result<Response<proto::CommandResult>, Status> {
+        let result = self.state.run_op("arm", |s| crate::ila::command_result(&s.ila, CMD_ARM, "Armed", "Arm"))
+            .await
+            .map_err(conflict)?;
+        Ok(Response::new(proto::CommandResult { success: result.success, message: result.message }))
+    }
+
+    async fn configure_trigger(
+        &self,
+        request: Request<proto::TriggerConfig>,
+    ) -> Result<Response<proto::CommandResult>, Status> {
+        let config = trigger_config(request.into_inner()).map_err(Status::invalid_argument)?;
+        let requested = config.clone();
+        let result = self.state.run_op("trigger", move |s| s.configure_trigger(&requested)).await
+            .map_err(conflict)?
+            .map_err(|errors| invalid(ValidationErrors { errors }))?;
+
+        let reply = match result {
+            Ok(bits) => proto::CommandResult {
+                success: true,
+                message: format!("Configured: type={}, bits=0x{:08X}, post={}", config.trigger_type, bits, config.post_trigger),
+            },
+            Err(message) => proto::CommandResult { success: false, message },
+        };
+        if reply.success {
+            crate::arm_timeout::start(&self.state, config.timeout_s, config.on_timeout, Some(config.clone()));
+        }
+        Ok(Response::new(reply))
+    }
+
+    type StreamCaptureStream = SampleStream;
+
+    async fn stream_capture(
+        &self,
+        request: Request<proto::CaptureRequest>,
+    ) -> Result<Response<SampleStream>, Status> {
+        let request = request.into_inner();
+        let (hub, pod) = match (u8::try_from(request.hub), u8::try_from(request.pod)) {
+            (Ok(hub), Ok(pod)) => (hub, pod),
+            _ => return Err(Status::not_found(format!("Hub {} pod {} not found", request.hub, request.pod))),
+        };
+        let window = match request.window.as_str() {
+            "" => None,
+            "pre" => Some(ReadoutWindow::Pre),
+            "post" => Some(ReadoutWindow::Post),
+            "around" => Some(ReadoutWindow::Around),
+            other => return Err(Status::invalid_argument(format!("Unknown window '{}' (pre, post or around)", other))),
+        };
+
+        let capture = self.state.run_op("readout", move |s| -> Result<_, validate::Invalid> {
+            validate::visible_pod(s, hub, pod)?;
+            let capture = match window {
+                None => s.ila.read_capture_all(hub, pod),
+                Some(window) => {
+                    let (_, _, ram_depth) = s.ila.get_pod_config(hub, pod);
+                    let span = request.span.unwrap_or(DEFAULT_SPAN.min(ram_depth));
+                    validate::count("span", span, ram_depth)?;
+                    s.ila.read_capture_window(hub, pod, window, span).ok_or_else(|| {
+                        (StatusCode::CONFLICT, format!("No trigger sample in hub {} pod {} RAM", hub, pod))
+                    })?
+                }
+            };
+            if let Some(e) = &capture.readout_error {
+                return Err((StatusCode::BAD_GATEWAY, format!("Readout failed at 0x{:X}: {}", e.address, e.message)));
+            }
+            crate::digest::record(s, &capture);
+            Ok(capture)
+        }).await
+        .map_err(conflict)?
+        .map_err(|(code, message)| status(code, message))?;
+
+        let batch_size = if request.batch_size == 0 { DEFAULT_BATCH } else { request.batch_size as usize };
+        let total = capture.samples.len() as u32;
+        let header = (capture.hub as u32, capture.pod as u32, capture.ts_bits as u32, capture.data_bits as u32);
+        let batches: Vec<_> = capture.samples.chunks(batch_size).enumerate().map(|(i, chunk)| {
+            proto::SampleBatch {
+                hub: header.0,
+                pod: header.1,
+                ts_bits: header.2,
+                data_bits: header.3,
+                offset: (i * batch_size) as u32,
+                samples: chunk.iter().map(|s| proto::RleSample {
+                    address: s.address,
+                    code: s.code as u32,
+                    timestamp: s.timestamp,
+                    data: s.data,
+                }).collect(),
+                total,
+            }
+        }).collect();
+        Ok(Response::new(Box::pin(stream::iter(batches.into_iter().map(Ok)))))
+    }
+}
+
+/// Serve the gRPC interface until shutdown
+pub async fn run(state: Arc<IlaState>, addr: SocketAddr) {
+    tracing::info!("gRPC sump.v1.Ila on {}", addr);
+    let mut shutdown = state.shutdown_signal();
+    let service = IlaServer::new(Service { state });
+    let result = tonic::transport::Server::builder()
+        .add_service(service)
+        .serve_with_shutdown(addr, async move {
+            let _ = shutdown.changed().await;
+        })
+        .await;
+    if let Err(e) = result {
+        tracing::error!("gRPC: failed to serve on {}: {}", addr, e);
+    }
+}
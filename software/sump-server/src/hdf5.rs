@@ -0,0 +1,144 @@
+//! HDF5 copies of archived captures (feature `hdf5`)
+//!
+//! With `SUMP_ARCHIVE_HDF5=1` each archived capture is also written as
+//! `<id>.h5` next to its dumps, for analysis tools that read HDF5. Each pod
+//! is a group `/hub<H>/pod<P>` holding its written samples, oldest first:
+//! - `cycles` (uint64): hub clock cycles since the first sample
+//! - `data` (uint32): the pod's low 32 data bits
+//! - `code` (uint8): RLE code, 1 pre-trigger, 2 trigger, 3 post-trigger
+//!
+//! Pod groups carry `name`, `hub_hz` (0 if the hub doesn't report its
+//! clock), `data_bits`, `ts_bits`, `signals` (the enumerated signals as
+//! JSON) and, if the capture triggered, `trigger_index` into the datasets.
+//! The root carries `capture_id` and, if one was applied, `trigger_config`
+//! (the last `TriggerConfig` as JSON).
+//!
+//! Written through the system libhdf5 (`hdf5-metno-sys`); the archive
+//! writes one capture at a time, so a non-threadsafe build is fine.
+
+use std::ffi::{c_void, CString};
+use std::io;
+use std::path::Path;
+
+use hdf5_metno_sys::h5::{herr_t, hsize_t, H5open};
+use hdf5_metno_sys::h5i::hid_t;
+use hdf5_metno_sys::h5p::H5P_DEFAULT;
+use hdf5_metno_sys::{h5a, h5d, h5f, h5g, h5s, h5t};
+use sump_model::{CaptureData, SignalInfo, TriggerConfig};
+
+/// One pod of an HDF5 capture
+pub struct PodCapture {
+    pub capture: CaptureData,
+    pub name: String,
+    pub signals: Vec<SignalInfo>,
+    pub hub_hz: u64,
+}
+
+/// An HDF5 object, closed when dropped
+struct Handle(hid_t, unsafe extern "C" fn(hid_t) -> herr_t);
+
+impl Handle {
+    fn new(id: hid_t, close: unsafe extern "C" fn(hid_t) -> herr_t, what: &str) -> io::Result<Self> {
+        if id < 0 {
+            return Err(io::Error::other(format!("HDF5: cannot create {}", what)));
+        }
+        Ok(Self(id, close))
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe { (self.1)(self.0) };
+    }
+}
+
+fn check(status: herr_t, what: &str) -> io::Result<()> {
+    if status < 0 {
+        return Err(io::Error::other(format!("HDF5: cannot write {}", what)));
+    }
+    Ok(())
+}
+
+fn c_name(name: &str) -> io::Result<CString> {
+    CString::new(name).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("HDF5: NUL in {:?}", name)))
+}
+
+fn group(loc: &Handle, name: &str) -> io::Result<Handle> {
+    let c = c_name(name)?;
+    let id = unsafe { h5g::H5Gcreate2(loc.0, c.as_ptr(), H5P_DEFAULT, H5P_DEFAULT, H5P_DEFAULT) };
+    Handle::new(id, h5g::H5Gclose, name)
+}
+
+/// A 1-d dataset of `values`, stored as the native type `ty`
+fn dataset<T>(loc: &Handle, name: &str, ty: hid_t, values: &[T]) -> io::Result<()> {
+    let c = c_name(name)?;
+    let dims = [values.len() as hsize_t];
+    let space = Handle::new(unsafe { h5s::H5Screate_simple(1, dims.as_ptr(), std::ptr::null()) }, h5s::H5Sclose, name)?;
+    let id = unsafe { h5d::H5Dcreate2(loc.0, c.as_ptr(), ty, space.0, H5P_DEFAULT, H5P_DEFAULT, H5P_DEFAULT) };
+    let set = Handle::new(id, h5d::H5Dclose, name)?;
+    let buf = values.as_ptr() as *const c_void;
+    check(unsafe { h5d::H5Dwrite(set.0, ty, h5s::H5S_ALL, h5s::H5S_ALL, H5P_DEFAULT, buf) }, name)
+}
+
+/// A scalar attribute of type `ty` read from `value`
+fn attribute(loc: &Handle, name: &str, ty: hid_t, value: *const c_void) -> io::Result<()> {
+    let c = c_name(name)?;
+    let space = Handle::new(unsafe { h5s::H5Screate(h5s::H5S_class_t::H5S_SCALAR) }, h5s::H5Sclose, name)?;
+    let attr = Handle::new(unsafe { h5a::H5Acreate2(loc.0, c.as_ptr(), ty, space.0, H5P_DEFAULT, H5P_DEFAULT) }, h5a::H5Aclose, name)?;
+    check(unsafe { h5a::H5Awrite(attr.0, ty, value) }, name)
+}
+
+fn attribute_u64(loc: &Handle, name: &str, value: u64) -> io::Result<()> {
+    attribute(loc, name, *h5t::H5T_NATIVE_UINT64, &value as *const u64 as *const c_void)
+}
+
+/// A fixed-length, NUL-terminated string attribute
+fn attribute_str(loc: &Handle, name: &str, value: &str) -> io::Result<()> {
+    let value = c_name(value)?;
+    let ty = Handle::new(unsafe { h5t::H5Tcopy(*h5t::H5T_C_S1) }, h5t::H5Tclose, name)?;
+    check(unsafe { h5t::H5Tset_size(ty.0, value.as_bytes_with_nul().len()) }, name)?;
+    check(unsafe { h5t::H5Tset_strpad(ty.0, h5t::H5T_str_t::H5T_STR_NULLTERM) }, name)?;
+    attribute(loc, name, ty.0, value.as_ptr() as *const c_void)
+}
+
+fn write_pod(hub: &Handle, p: &PodCapture) -> io::Result<()> {
+    let group = group(hub, &format!("pod{}", p.capture.pod))?;
+    let written: Vec<_> = p.capture.samples.iter().filter(|s| s.code != 0).collect();
+    let data: Vec<u32> = written.iter().map(|s| s.data).collect();
+    let code: Vec<u8> = written.iter().map(|s| s.code).collect();
+    dataset(&group, "cycles", *h5t::H5T_NATIVE_UINT64, &p.capture.elapsed_cycles())?;
+    dataset(&group, "data", *h5t::H5T_NATIVE_UINT32, &data)?;
+    dataset(&group, "code", *h5t::H5T_NATIVE_UINT8, &code)?;
+    attribute_str(&group, "name", &p.name)?;
+    attribute_u64(&group, "hub_hz", p.hub_hz)?;
+    attribute_u64(&group, "data_bits", p.capture.data_bits as u64)?;
+    attribute_u64(&group, "ts_bits", p.capture.ts_bits as u64)?;
+    attribute_str(&group, "signals", &serde_json::to_string(&p.signals)?)?;
+    if let Some(i) = code.iter().position(|&c| c == 2) {
+        attribute_u64(&group, "trigger_index", i as u64)?;
+    }
+    Ok(())
+}
+
+/// Write capture `id` of `pods` to `path`, replacing any file there
+pub fn write(path: &Path, id: u64, trigger: Option<&TriggerConfig>, pods: &[PodCapture]) -> io::Result<()> {
+    let name = path.display().to_string();
+    let c_path = c_name(&name)?;
+    check(unsafe { H5open() }, "library")?;
+    let created = unsafe { h5f::H5Fcreate(c_path.as_ptr(), h5f::H5F_ACC_TRUNC, H5P_DEFAULT, H5P_DEFAULT) };
+    let file = Handle::new(created, h5f::H5Fclose, &name)?;
+    attribute_u64(&file, "capture_id", id)?;
+    if let Some(trigger) = trigger {
+        attribute_str(&file, "trigger_config", &serde_json::to_string(trigger)?)?;
+    }
+    let mut hubs: Vec<(u8, Handle)> = Vec::new();
+    for p in pods {
+        let hub = p.capture.hub;
+        if !hubs.iter().any(|(h, _)| *h == hub) {
+            hubs.push((hub, group(&file, &format!("hub{}", hub))?));
+        }
+        let (_, group) = hubs.iter().find(|(h, _)| *h == hub).unwrap();
+        write_pod(group, p)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,298 @@
+//! Rolling history mode
+//!
+//! Like an oscilloscope's "stop" button: the ILA stays armed with a trigger
+//! that doesn't fire and one post-trigger sample, so pod RAM keeps rolling
+//! over the latest samples. `POST /api/ila/history/snapshot` freezes it,
+//! reads the pod out, keeps the capture and re-arms. With `external`, the
+//! trigger is the routed external input instead: a hardware event (or the
+//! `SUMP_EXT_TRIG_GPIO` pulse, used for requested snapshots when present)
+//! freezes the buffer with the trigger marked. Otherwise a requested
+//! snapshot stops the core with IDLE, and the RAM is read in address order.
+//!
+//! The run holds the ILA as operation `history` until stopped. With
+//! `analysis`, each kept snapshot is analyzed and the report listed with it
+//! (see `analysis`).
+
+use axum::{
+    extract::{Path, State},
+    http::{Extensions, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use sump_driver::clock::rfc3339;
+use sump_driver::*;
+use sump_model::{
+    CaptureData, CommandResult, FieldError, HistoryConfig, HistorySnapshot, HistoryStatus, ValidationErrors,
+};
+
+use crate::analysis::Pipeline;
+use crate::digest;
+use crate::ila::IlaState;
+use crate::masks;
+use crate::validate;
+
+/// Poll interval for snapshot and stop requests
+const POLL: Duration = Duration::from_millis(10);
+
+/// Capture status poll interval while waiting for the external input
+const EXTERNAL_POLL: Duration = Duration::from_millis(100);
+
+/// Post-trigger samples; the rest of pod RAM is history
+const POST_TRIGGER: u32 = 1;
+
+/// Most snapshots `keep` may ask for
+const MAX_KEEP: u32 = 64;
+
+/// Longest wait for a requested snapshot
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Rolling history progress and the kept captures, oldest first
+#[derive(Default)]
+pub struct History {
+    status: HistoryStatus,
+    captures: VecDeque<CaptureData>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// Program the rolling trigger, then INIT and ARM
+fn arm(state: &IlaState, config: &HistoryConfig) -> Result<(), String> {
+    let trig_type = if config.external { TRIG_EXT_RISING } else { TRIG_OR_RISING };
+    state.ila.configure_trigger(trig_type, 0, POST_TRIGGER, &[]).map_err(with_cause)?;
+    if state.ila.exec_cmd(CMD_INIT, 0, 0).is_none() {
+        return Err(failure_message("Init"));
+    }
+    state.ila.wait_init()?;
+    if state.ila.exec_cmd(CMD_ARM, 0, 0).is_none() {
+        return Err(failure_message("Arm"));
+    }
+    Ok(())
+}
+
+/// Stop the rolling buffer for a requested snapshot
+fn freeze(state: &IlaState, config: &HistoryConfig) -> Result<(), String> {
+    if let (true, Some(gpio)) = (config.external, &state.ext_trigger_gpio) {
+        match gpio.fire() {
+            Ok(()) => {
+                let deadline = Instant::now() + Duration::from_secs(1);
+                while Instant::now() < deadline {
+                    if state.ila.capture_status().acquired {
+                        return Ok(());
+                    }
+                    std::thread::sleep(POLL);
+                }
+                tracing::warn!("History: {} pulse didn't trigger; stopping with IDLE", gpio.describe());
+            }
+            Err(e) => tracing::warn!("History: {} pulse failed ({}); stopping with IDLE", gpio.describe(), e),
+        }
+    }
+    match state.ila.exec_cmd(CMD_IDLE, 0, 0) {
+        Some(_) => Ok(()),
+        None => Err(failure_message("Idle")),
+    }
+}
+
+/// Keep a snapshot, dropping the oldest beyond `keep`
+fn store(state: &IlaState, config: &HistoryConfig, source: &str, capture: CaptureData, pipeline: Option<&Pipeline>) {
+    let id = state.ila.sequence();
+    let taken_at_ms = now_ms();
+    let report = pipeline.map(|p| p.run(state, &capture, state.ila.hub_clock_hz(config.hub)));
+    let snapshot = HistorySnapshot {
+        id,
+        taken_at_ms,
+        taken_at: rfc3339(taken_at_ms),
+        source: source.into(),
+        samples: capture.samples.len() as u32,
+        report,
+    };
+    tracing::info!("History: snapshot {} ({}, {} samples)", id, source, snapshot.samples);
+    if let Some(findings) = snapshot.report.as_ref().map(|r| r.findings).filter(|&n| n > 0) {
+        tracing::warn!("History: snapshot {} analysis has {} findings", id, findings);
+    }
+    let mut history = state.history.lock().unwrap();
+    history.status.snapshots.push(snapshot);
+    history.captures.push_back(capture);
+    while history.captures.len() > config.keep as usize {
+        history.status.snapshots.remove(0);
+        history.captures.pop_front();
+    }
+}
+
+/// Arm, then snapshot on request (or external trigger) and re-arm until stopped
+fn run(state: &IlaState, config: &HistoryConfig, pipeline: Option<Pipeline>) {
+    let shutdown = state.shutdown_signal();
+    let stopped = || state.history_stop.load(Ordering::Relaxed) || *shutdown.borrow();
+
+    let message = loop {
+        if let Err(e) = arm(state, config) {
+            break e;
+        }
+        let mut last_poll = Instant::now();
+        let source = loop {
+            if stopped() {
+                break None;
+            }
+            if state.history_snapshot.swap(false, Ordering::Relaxed) {
+                break Some("request");
+            }
+            if config.external && last_poll.elapsed() >= EXTERNAL_POLL {
+                last_poll = Instant::now();
+                if state.ila.capture_status().acquired {
+                    break Some("external");
+                }
+            }
+            std::thread::sleep(POLL);
+        };
+        let Some(source) = source else {
+            break "Stopped".into();
+        };
+        if source == "request" {
+            if let Err(e) = freeze(state, config) {
+                break e;
+            }
+        }
+        let capture = state.ila.read_capture_all(config.hub, config.pod);
+        if let Some(e) = &capture.readout_error {
+            break format!("Readout failed at 0x{:X}: {}", e.address, e.message);
+        }
+        digest::record(state, &capture);
+        store(state, config, source, capture, pipeline.as_ref());
+    };
+
+    if state.ila.exec_cmd(CMD_IDLE, 0, 0).is_none() {
+        tracing::warn!("History: failed to disarm after the run");
+    }
+    tracing::info!("History: {}", message);
+    let mut history = state.history.lock().unwrap();
+    history.status.running = false;
+    history.status.message = message;
+}
+
+fn check(config: &HistoryConfig) -> Result<(), Vec<FieldError>> {
+    if config.keep == 0 || config.keep > MAX_KEEP {
+        return Err(vec![FieldError { field: "keep".into(), message: format!("must be between 1 and {}", MAX_KEEP) }]);
+    }
+    Ok(())
+}
+
+/// GET /api/ila/history - Rolling history progress and kept snapshots
+pub async fn get_history(State(state): State<Arc<IlaState>>, extensions: Extensions) -> Json<HistoryStatus> {
+    let mut status = state.history.lock().unwrap().status.clone();
+    // Analysis reports cover every signal of the pod
+    if let Some(config) = status.config.as_ref().filter(|_| masks::partner(&extensions)) {
+        if masks::masked(&state.settings.lock().unwrap(), config.hub, config.pod) {
+            for snapshot in &mut status.snapshots {
+                snapshot.report = None;
+            }
+        }
+    }
+    Json(status)
+}
+
+/// POST /api/ila/history - Keep the ILA armed as a rolling buffer
+pub async fn post_history(
+    State(state): State<Arc<IlaState>>,
+    Json(config): Json<HistoryConfig>,
+) -> Result<Json<HistoryStatus>, Response> {
+    check(&config).map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response())?;
+    let guard = state.claim_op("history").await.map_err(IntoResponse::into_response)?;
+    let (hub, pod, analysis) = (config.hub, config.pod, config.analysis.clone());
+    state.run(move |s| validate::visible_pod(s, hub, pod)).await.map_err(IntoResponse::into_response)?;
+    let pipeline = match analysis {
+        Some(analysis) => Some(
+            state.run(move |s| Pipeline::new(s, &analysis, &s.ila.enumerate_pod(hub, pod))).await.map_err(|errors| {
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response()
+            })?,
+        ),
+        None => None,
+    };
+
+    state.history_stop.store(false, Ordering::Relaxed);
+    state.history_snapshot.store(false, Ordering::Relaxed);
+    let status = HistoryStatus {
+        config: Some(config.clone()),
+        running: true,
+        snapshots: Vec::new(),
+        message: "Rolling".into(),
+    };
+    *state.history.lock().unwrap() = History { status: status.clone(), captures: VecDeque::new() };
+    tracing::info!(
+        "History: rolling on hub {} pod {}{}, keeping {}",
+        config.hub, config.pod, if config.external { ", external input snapshots" } else { "" }, config.keep
+    );
+
+    let runner = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let _guard = guard;
+        run(&runner, &config, pipeline);
+    });
+    Ok(Json(status))
+}
+
+/// POST /api/ila/history/snapshot - Freeze the rolling buffer, read it out and re-arm
+pub async fn post_history_snapshot(
+    State(state): State<Arc<IlaState>>,
+) -> Result<Json<CaptureData>, (StatusCode, String)> {
+    let before = {
+        let history = state.history.lock().unwrap();
+        if !history.status.running {
+            return Err((StatusCode::CONFLICT, "Rolling history is not running".into()));
+        }
+        history.status.snapshots.last().map(|s| s.id)
+    };
+    state.history_snapshot.store(true, Ordering::Relaxed);
+
+    let deadline = Instant::now() + SNAPSHOT_TIMEOUT;
+    loop {
+        tokio::time::sleep(POLL).await;
+        {
+            let history = state.history.lock().unwrap();
+            if history.status.snapshots.last().map(|s| s.id) != before {
+                if let Some(capture) = history.captures.back() {
+                    return Ok(Json(capture.clone()));
+                }
+            }
+            if !history.status.running {
+                return Err((StatusCode::CONFLICT, history.status.message.clone()));
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err((StatusCode::GATEWAY_TIMEOUT, format!("No snapshot within {} s", SNAPSHOT_TIMEOUT.as_secs())));
+        }
+    }
+}
+
+/// POST /api/ila/history/stop - Stop rolling; the ILA is left idle
+pub async fn post_history_stop(State(state): State<Arc<IlaState>>) -> Json<CommandResult> {
+    let running = state.history.lock().unwrap().status.running;
+    state.history_stop.store(true, Ordering::Relaxed);
+    Json(CommandResult {
+        success: running,
+        message: if running { "Rolling history stopping".into() } else { "Rolling history is not running".into() },
+    })
+}
+
+/// GET /api/ila/history/:id - A kept snapshot
+pub async fn get_history_snapshot(
+    State(state): State<Arc<IlaState>>,
+    Path(id): Path<u64>,
+    extensions: Extensions,
+) -> Result<Json<CaptureData>, (StatusCode, String)> {
+    let mut capture = {
+        let history = state.history.lock().unwrap();
+        let index = history.status.snapshots.iter().position(|s| s.id == id)
+            .ok_or((StatusCode::NOT_FOUND, format!("Snapshot {} is not kept", id)))?;
+        history.captures[index].clone()
+    };
+    let (partner, hub, pod) = (masks::partner(&extensions), capture.hub, capture.pod);
+    if let Some(mask) = state.run(move |s| masks::for_request(s, partner, hub, pod)).await {
+        mask.blank(&mut capture);
+    }
+    Ok(Json(capture))
+}
@@ -0,0 +1,221 @@
+//! Capture event hooks
+//!
+//! `SUMP_HOOKS` names a JSON file of local commands to run when a capture
+//! triggers, completes or is archived:
+//!
+//! ```json
+//! [{"event": "archive", "command": ["/usr/local/bin/copy-capture", "/mnt/nfs"], "timeout_s": 30}]
+//! ```
+//!
+//! Each command gets the event (`trigger`, `capture` or `archive`) and the
+//! capture id appended as arguments, and `SUMP_EVENT`, `SUMP_CAPTURE_ID` and
+//! `SUMP_URL` (this server, e.g. to fetch `$SUMP_URL/waveforms/latest.vcd`)
+//! in its environment. An `archive` hook runs once per archived pod and
+//! also gets the compressed file's path, as a third argument and in
+//! `SUMP_ARCHIVE_FILE` (see `archive`). A command is killed after
+//! `timeout_s` (default 30, at most a day); its exit status and output go
+//! to the server log. Hooks come only from the local file, never from the
+//! API.
+//!
+//! Trigger and completion are detected by polling the capture status while
+//! no operation holds the ILA, so each fires at most once per ARM.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use sump_model::ArchiveEntry;
+
+use crate::ila::IlaState;
+
+/// Status poll interval while hooks are configured
+const POLL: Duration = Duration::from_millis(200);
+
+/// Hook output kept in the log, per stream
+const MAX_OUTPUT: usize = 4096;
+
+/// Longest accepted `timeout_s` (one day)
+const MAX_TIMEOUT_S: f64 = 24.0 * 3600.0;
+
+fn default_timeout_s() -> f64 {
+    30.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// The trigger fired
+    Trigger,
+    /// Post-trigger samples are stored; the capture can be read out
+    Capture,
+    /// A pod's capture is stored in the archive
+    Archive,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Trigger => "trigger",
+            Self::Capture => "capture",
+            Self::Archive => "archive",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    event: HookEvent,
+    /// Program and its leading arguments
+    command: Vec<String>,
+    #[serde(default = "default_timeout_s")]
+    timeout_s: f64,
+}
+
+/// Read and check the hooks file
+pub fn load(path: &Path) -> Result<Vec<Hook>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let hooks: Vec<Hook> = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    for (i, hook) in hooks.iter().enumerate() {
+        if hook.command.first().is_none_or(|program| program.is_empty()) {
+            return Err(format!("hook {}: empty command", i));
+        }
+        if !hook.timeout_s.is_finite() || hook.timeout_s <= 0.0 {
+            return Err(format!("hook {}: timeout_s must be a positive number of seconds", i));
+        }
+        if hook.timeout_s > MAX_TIMEOUT_S {
+            return Err(format!("hook {}: timeout_s must be at most {} s", i, MAX_TIMEOUT_S));
+        }
+    }
+    Ok(hooks)
+}
+
+/// The next archived entry; never, without an archive
+async fn next_archived(archived: &mut Option<broadcast::Receiver<ArchiveEntry>>) -> ArchiveEntry {
+    if let Some(receiver) = archived {
+        loop {
+            match receiver.recv().await {
+                Ok(entry) => return entry,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Hooks: {} archived captures missed", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+    std::future::pending().await
+}
+
+/// Watch for capture events and run the matching hooks until shutdown
+pub async fn run(state: Arc<IlaState>, hooks: Vec<Hook>, url: String) {
+    let hooks: Arc<[Hook]> = hooks.into();
+    let mut ticker = tokio::time::interval(POLL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut shutdown = state.shutdown_signal();
+    let archive_hooks = hooks.iter().any(|h| h.event == HookEvent::Archive);
+    if archive_hooks && state.archive.is_none() {
+        tracing::warn!("Archive hooks never run: SUMP_ARCHIVE_DIR is not set");
+    }
+    let mut archived = state.archive.as_ref().filter(|_| archive_hooks).map(|archive| archive.subscribe());
+    // Capture id, and whether its trigger and completion have fired
+    let mut seen = (state.ila.sequence(), true, true);
+
+    let fire = |event: HookEvent, id: u64, file: Option<PathBuf>| {
+        for (index, _) in hooks.iter().enumerate().filter(|(_, h)| h.event == event) {
+            tokio::spawn(execute(hooks.clone(), index, id, file.clone(), url.clone()));
+        }
+    };
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            entry = next_archived(&mut archived) => {
+                let file = state.archive.as_ref().map(|archive| archive.path(&entry));
+                fire(HookEvent::Archive, entry.id, file);
+                continue;
+            }
+            _ = shutdown.changed() => return,
+        }
+        if state.operation.lock().unwrap().is_some() {
+            continue;
+        }
+        let (id, status) = state.run(|s| (s.ila.sequence(), s.ila.capture_status())).await;
+        if id != seen.0 {
+            seen = (id, false, false);
+        }
+        if status.triggered && !seen.1 {
+            seen.1 = true;
+            fire(HookEvent::Trigger, id, None);
+        }
+        if status.acquired && !seen.2 {
+            seen.2 = true;
+            fire(HookEvent::Capture, id, None);
+        }
+    }
+}
+
+/// Lossy text of a hook's output stream, trimmed to `MAX_OUTPUT`
+fn output_text(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_OUTPUT)]);
+    let truncated = if bytes.len() > MAX_OUTPUT { " [truncated]" } else { "" };
+    format!("{}{}", text.trim_end(), truncated)
+}
+
+async fn execute(hooks: Arc<[Hook]>, index: usize, id: u64, file: Option<PathBuf>, url: String) {
+    let hook = &hooks[index];
+    let event = hook.event.name();
+    let mut command = tokio::process::Command::new(&hook.command[0]);
+    command.args(&hook.command[1..])
+        .arg(event)
+        .arg(id.to_string())
+        .env("SUMP_EVENT", event)
+        .env("SUMP_CAPTURE_ID", id.to_string())
+        .env("SUMP_URL", &url);
+    if let Some(file) = &file {
+        command.arg(file).env("SUMP_ARCHIVE_FILE", file);
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Own process group, so a timeout also kills what the hook started
+        .process_group(0)
+        .kill_on_drop(true);
+
+    let started = Instant::now();
+    let timeout = Duration::from_secs_f64(hook.timeout_s);
+    let program = &hook.command[0];
+    let child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            tracing::error!("Hook {} ({} {}): failed to start: {}", program, event, id, e);
+            return;
+        }
+    };
+    let pid = child.id();
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Err(_) => {
+            if let Some(pid) = pid {
+                // SAFETY: signals only the hook's own process group
+                unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) };
+            }
+            tracing::warn!("Hook {} ({} {}): killed after {} s", program, event, id, hook.timeout_s);
+        }
+        Ok(Err(e)) => tracing::error!("Hook {} ({} {}): {}", program, event, id, e),
+        Ok(Ok(output)) => {
+            let elapsed = started.elapsed().as_millis();
+            if output.status.success() {
+                tracing::info!("Hook {} ({} {}): {} in {} ms", program, event, id, output.status, elapsed);
+            } else {
+                tracing::warn!("Hook {} ({} {}): {} in {} ms", program, event, id, output.status, elapsed);
+            }
+            for (stream, bytes) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+                if !bytes.is_empty() {
+                    tracing::info!("Hook {} ({} {}) {}: {}", program, event, id, stream, output_text(bytes));
+                }
+            }
+        }
+    }
+}
@@ -1,641 +1,519 @@
 //! SUMP3 ILA API endpoints
 //!
 //! Provides REST API for the open-source SUMP3 Integrated Logic Analyzer.
-//! Uses polling-based register access via /dev/mem (no IRQ/kernel driver needed).
+//! Register access and enumeration live in the `sump-driver` crate.
 
 use axum::{
-    extract::{Path, State},
-    response::Json,
-    routing::{get, post},
+    body::Body,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, Extensions, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post, put},
     Router,
 };
-use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use parking_lot::Mutex;
-
-use crate::devmem::DevMem;
-
-const ILA_SIZE: usize = 0x100;
-
-// Register offsets (from sump3_axi_wrapper.sv)
-const REG_CMD: usize        = 0x00;
-const REG_ADDR: usize       = 0x04;
-const REG_WDATA: usize      = 0x08;
-const REG_CTRL: usize       = 0x0C;
-const REG_STATUS: usize     = 0x10;
-const REG_RDATA: usize      = 0x14;
-const REG_HW_INFO: usize    = 0x1C;
-const REG_CAP_STATUS: usize = 0x20;
-
-// Command codes - State commands
-const CMD_ARM: u32          = 0x01;
-const CMD_RESET: u32        = 0x02;
-const CMD_INIT: u32         = 0x03;
-
-// Command codes - Local reads
-const CMD_RD_HW_ID: u32         = 0x10;
-const CMD_RD_STATUS: u32        = 0x12;
-
-// Command codes - Local writes
-const CMD_WR_TRIG_TYPE: u32     = 0x23;
-const CMD_WR_TRIG_DIG_FIELD: u32= 0x24;
-const CMD_WR_DIG_POST_TRIG: u32 = 0x2A;
-
-// Command codes - Serial bus reads (external CMD codes from sump3_axi_wrapper.sv)
-const CMD_RD_HUB_FREQ: u32      = 0x30;
-const CMD_RD_POD_COUNT: u32     = 0x31;
-const CMD_RD_POD_REG: u32       = 0x32;
-const CMD_RD_HUB_INSTANCE: u32  = 0x35;
-const CMD_RD_HUB_NAME_0_3: u32  = 0x36;
-const CMD_RD_HUB_NAME_4_7: u32  = 0x37;
-const CMD_RD_HUB_NAME_8_11: u32 = 0x38;
-
-// Command codes - Serial bus writes
-const CMD_WR_POD_REG: u32       = 0x40;
-
-// Pod register addresses
-const POD_REG_HW_CFG: u8        = 0x00;
-const POD_REG_TRIG_CFG: u8      = 0x03;
-const POD_REG_TRIG_EN: u8       = 0x04;
-const POD_REG_RAM_PTR: u8       = 0x08;
-const POD_REG_RAM_DATA: u8      = 0x09;
-const POD_REG_RAM_CFG: u8       = 0x0A;
-const POD_REG_TRIGGERABLE: u8   = 0x0E;
-const POD_REG_NAME_0_3: u8      = 0x1D;
-const POD_REG_NAME_4_7: u8      = 0x1E;
-const POD_REG_NAME_8_11: u8     = 0x1F;
-
-// Control bits
-const CTRL_START: u32 = 0x01;
-
-// Trigger types
-const TRIG_OR_RISING: u32       = 0x02;
-const TRIG_OR_FALLING: u32      = 0x03;
-const TRIG_EXT_RISING: u32      = 0x06;
-
-/// Shared state containing the ILA memory map
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+use sump_driver::deep::DeepSink;
+use sump_driver::*;
+use sump_model::*;
+
+use crate::alerts::Alerts;
+use crate::archive::Archive;
+use crate::arm_timeout::PendingTimeout;
+use crate::audit;
+use crate::breaker::Breaker;
+use crate::digest::{self, Digests};
+use crate::ext_trigger::ExtTriggerGpio;
+use crate::history::History;
+use crate::masks;
+use crate::ops::Conflict;
+use crate::persist;
+use crate::plugins::Plugins;
+use crate::ratelimit::{with_limits, Limiter};
+use crate::spill::SpillConfig;
+use crate::status::CachedStatus;
+use crate::timeout::{with_timeout, Timeouts};
+use crate::validate;
+use crate::waveform::Waveforms;
+
+/// Shared state containing the ILA driver handle
 pub struct IlaState {
-    mem: Mutex<DevMem>,
-    base_addr: usize,
+    pub(crate) ila: Ila,
+    /// Why there is no hardware, while reconnecting (see `degraded`)
+    pub(crate) degraded: Mutex<Option<String>>,
+    pub(crate) started: Instant,
+    pub(crate) watchdog: Mutex<WatchdogStatus>,
+    /// Directory for persisted configuration (see `persist`)
+    pub(crate) state_dir: PathBuf,
+    pub(crate) settings: Mutex<Settings>,
+    /// Logical operation currently holding the ILA (see `ops`)
+    pub(crate) operation: Mutex<Option<&'static str>>,
+    /// Capture status from the background poller (see `status`)
+    pub(crate) status_cache: Mutex<CachedStatus>,
+    /// Sequenced trigger progress (see `sequence`)
+    pub(crate) sequence: Mutex<SequenceStatus>,
+    /// Asks a running sequenced trigger to stop
+    pub(crate) sequence_stop: AtomicBool,
+    /// Rolling history progress and snapshots (see `history`)
+    pub(crate) history: Mutex<History>,
+    /// Asks the rolling history run for a snapshot
+    pub(crate) history_snapshot: AtomicBool,
+    /// Asks the rolling history run to stop
+    pub(crate) history_stop: AtomicBool,
+    /// Startup INIT progress and its last message (see `init_on_boot`)
+    pub(crate) boot_init: Mutex<(BootInit, String)>,
+    /// Timeout of the current capture (see `arm_timeout`)
+    pub(crate) arm_timeout: Mutex<PendingTimeout>,
+    /// Captures rendered for `/waveforms` (see `waveform`)
+    pub(crate) waveforms: Mutex<Waveforms>,
+    /// Summaries of captures read out (see `digest`)
+    pub(crate) digests: Mutex<Digests>,
+    /// DDR deep-capture sink, if configured
+    pub(crate) deep: Option<DeepSink>,
+    /// Memory budget of deep-capture decodes (see `spill`)
+    pub(crate) spill: SpillConfig,
+    /// Changes whenever the deep buffer is re-armed or reconfigured; seeded
+    /// from the clock so it also changes across restarts (see `deep`)
+    pub(crate) deep_generation: AtomicU64,
+    /// GPIO wired to an external trigger input, if configured
+    pub(crate) ext_trigger_gpio: Option<ExtTriggerGpio>,
+    /// Decoders and exporters loaded from `SUMP_PLUGIN_DIR` (see `plugins`)
+    pub(crate) plugins: Plugins,
+    /// Served by `/api/ui-config` (see `ui_config`)
+    pub(crate) ui_config: UiConfig,
+    /// Rules checked on each capture and their events (see `alerts`)
+    pub(crate) alerts: Alerts,
+    /// `/api/ila/console` accepts connections (see `console`)
+    pub(crate) console: bool,
+    /// Fails hardware requests fast on a wedged bus (see `breaker`)
+    pub(crate) breaker: Breaker,
+    /// Completed captures kept on disk, if configured (see `archive`)
+    pub(crate) archive: Option<Archive>,
+    /// Set once on SIGTERM/Ctrl+C; long-lived tasks subscribe to stop early
+    shutdown: watch::Sender<bool>,
 }
 
 impl IlaState {
-    pub fn new(base_addr: usize) -> Result<Self, std::io::Error> {
-        let mem = DevMem::new(base_addr, ILA_SIZE)?;
-        tracing::info!(
-            "SUMP3 ILA mapped at 0x{:08X}, size {} bytes",
-            base_addr,
-            ILA_SIZE
-        );
-        Ok(Self { 
-            mem: Mutex::new(mem),
-            base_addr,
-        })
-    }
-    
-    /// Execute a command and wait for completion (polling)
-    fn exec_cmd(&self, cmd: u32, addr: u32, wdata: u32) -> Option<u32> {
-        let mem = self.mem.lock();
-        
-        // Write command parameters
-        mem.write32(REG_CMD, cmd);
-        mem.write32(REG_ADDR, addr);
-        mem.write32(REG_WDATA, wdata);
-        
-        // Set START bit to begin execution
-        mem.write32(REG_CTRL, CTRL_START);
-        
-        // Poll for completion (DONE bit)
-        for _ in 0..100000 {
-            let status = mem.read32(REG_STATUS)?;
-            let done = (status & 0x02) != 0;
-            let error = (status & 0x04) != 0;
-            
-            if done {
-                if error {
-                    tracing::warn!("ILA command 0x{:02X} error", cmd);
-                    return None;
-                }
-                return mem.read32(REG_RDATA);
-            }
-            std::hint::spin_loop();
+    pub fn new(ila: Ila, state_dir: PathBuf) -> Self {
+        Self {
+            ila,
+            degraded: Mutex::new(None),
+            started: Instant::now(),
+            watchdog: Mutex::new(WatchdogStatus {
+                mode: "off".into(),
+                healthy: true,
+                ..Default::default()
+            }),
+            shutdown: watch::Sender::new(false),
+            operation: Mutex::new(None),
+            status_cache: Mutex::new(None),
+            sequence: Mutex::new(SequenceStatus::default()),
+            sequence_stop: AtomicBool::new(false),
+            history: Mutex::new(History::default()),
+            history_snapshot: AtomicBool::new(false),
+            history_stop: AtomicBool::new(false),
+            boot_init: Mutex::new((BootInit::Disabled, "Startup INIT not requested".into())),
+            arm_timeout: Mutex::new(None),
+            waveforms: Mutex::new(Waveforms::new()),
+            digests: Mutex::new(Digests::new()),
+            ext_trigger_gpio: None,
+            deep: None,
+            spill: SpillConfig::default(),
+            deep_generation: AtomicU64::new(
+                SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+            ),
+            plugins: Plugins::default(),
+            ui_config: UiConfig::default(),
+            alerts: Alerts::default(),
+            console: false,
+            breaker: Breaker::default(),
+            archive: None,
+            settings: Mutex::new(persist::load_json(&state_dir, persist::SETTINGS_FILE).unwrap_or_default()),
+            state_dir,
         }
-        tracing::warn!("ILA command 0x{:02X} timeout", cmd);
-        None
     }
-    
-    /// Read a pod register
-    fn read_pod_reg(&self, hub: u8, pod: u8, reg: u8) -> Option<u32> {
-        let addr = ((hub as u32) << 16) | ((pod as u32) << 8) | (reg as u32);
-        self.exec_cmd(CMD_RD_POD_REG, addr, 0)
+
+    pub fn with_ext_trigger_gpio(mut self, gpio: ExtTriggerGpio) -> Self {
+        self.ext_trigger_gpio = Some(gpio);
+        self
     }
-    
-    /// Write a pod register
-    fn write_pod_reg(&self, hub: u8, pod: u8, reg: u8, value: u32) -> bool {
-        let addr = ((hub as u32) << 16) | ((pod as u32) << 8) | (reg as u32);
-        self.exec_cmd(CMD_WR_POD_REG, addr, value).is_some()
+
+    /// Start without hardware (see `degraded`)
+    pub fn with_degraded(mut self, reason: String) -> Self {
+        self.degraded = Mutex::new(Some(reason));
+        self
     }
-    
-    /// Read hub name (12 ASCII chars)
-    fn read_hub_name(&self, hub: u8) -> String {
-        let addr = (hub as u32) << 16;
-        let mut name = Vec::with_capacity(12);
-        
-        for cmd in [CMD_RD_HUB_NAME_0_3, CMD_RD_HUB_NAME_4_7, CMD_RD_HUB_NAME_8_11] {
-            if let Some(data) = self.exec_cmd(cmd, addr, 0) {
-                name.push(((data >> 24) & 0xFF) as u8);
-                name.push(((data >> 16) & 0xFF) as u8);
-                name.push(((data >> 8) & 0xFF) as u8);
-                name.push((data & 0xFF) as u8);
-            }
+
+    pub fn with_ui_config(mut self, ui_config: UiConfig) -> Self {
+        self.ui_config = ui_config;
+        self
+    }
+
+    pub fn with_alerts(mut self, alerts: Alerts) -> Self {
+        self.alerts = alerts;
+        self
+    }
+
+    /// Enable the command console WebSocket
+    pub fn with_console(mut self) -> Self {
+        self.console = true;
+        self
+    }
+
+    pub fn with_breaker(mut self, breaker: Breaker) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    pub fn with_archive(mut self, archive: Archive) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    pub fn with_deep_sink(mut self, deep: DeepSink) -> Self {
+        self.deep = Some(deep);
+        self
+    }
+
+    pub fn with_spill(mut self, spill: SpillConfig) -> Self {
+        self.spill = spill;
+        self
+    }
+
+    #[cfg(feature = "plugins")]
+    pub fn with_plugins(mut self, plugins: Plugins) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Program the trigger on pod (0,0), then INIT and ARM
+    pub fn apply_trigger(&self, config: &TriggerConfig) -> Result<u32, String> {
+        let trig_bits = self.ila.program_trigger(config).map_err(with_cause)?;
+
+        if self.ila.exec_cmd(CMD_INIT, 0, 0).is_none() {
+            return Err(failure_message("Init"));
+        }
+        self.ila.wait_init()?;
+
+        if self.ila.exec_cmd(CMD_ARM, 0, 0).is_none() {
+            return Err(failure_message("Arm"));
         }
-        
-        String::from_utf8_lossy(&name).trim().to_string()
+        Ok(trig_bits)
     }
-    
-    /// Read pod name (12 ASCII chars)
-    fn read_pod_name(&self, hub: u8, pod: u8) -> String {
-        let mut name = Vec::with_capacity(12);
-        
-        for reg in [POD_REG_NAME_0_3, POD_REG_NAME_4_7, POD_REG_NAME_8_11] {
-            if let Some(data) = self.read_pod_reg(hub, pod, reg) {
-                name.push(((data >> 24) & 0xFF) as u8);
-                name.push(((data >> 16) & 0xFF) as u8);
-                name.push(((data >> 8) & 0xFF) as u8);
-                name.push((data & 0xFF) as u8);
-            }
+
+    /// Replace the trigger with an immediate one and ARM, so the capture
+    /// completes with whatever the bus shows (see `snapshot`, `arm_timeout`)
+    pub fn arm_immediate(&self, post_trigger: u32) -> Result<(), String> {
+        self.ila.configure_trigger(TRIG_IMMEDIATE, 0, post_trigger, &[]).map_err(with_cause)?;
+        match self.ila.exec_cmd(CMD_ARM, 0, 0) {
+            Some(_) => Ok(()),
+            None => Err(failure_message("Arm")),
         }
-        
-        String::from_utf8_lossy(&name).trim().to_string()
     }
-    
-    /// Read RLE sample from pod RAM (with configurable timestamp bits)
-    fn read_rle_sample(&self, hub: u8, pod: u8, addr: u32, ts_bits: u8) -> Option<RleSample> {
-        // Set RAM pointer to page 0, address
-        self.write_pod_reg(hub, pod, POD_REG_RAM_PTR, addr);
-        
-        // Read low 32 bits (data)
-        let data = self.read_pod_reg(hub, pod, POD_REG_RAM_DATA)?;
-        
-        // Read high bits from page 1
-        self.write_pod_reg(hub, pod, POD_REG_RAM_PTR, (1 << 20) | addr);
-        let hi = self.read_pod_reg(hub, pod, POD_REG_RAM_DATA)?;
-        
-        // Decode based on timestamp width
-        let ts_mask = (1u32 << ts_bits) - 1;
-        let code = ((hi >> ts_bits) & 0x3) as u8;
-        let timestamp = (hi & ts_mask) as u32;
-        
-        Some(RleSample {
-            address: addr,
-            code,
-            timestamp,
-            data,
-        })
+
+    /// Hub/pod enumeration with the configured display names and groups
+    pub fn info(&self) -> IlaInfo {
+        let mut info = self.ila.info();
+        crate::settings::annotate_info(&self.settings.lock().unwrap(), &mut info);
+        info
     }
-    
-    /// Get pod configuration (timestamp bits, data bits, etc.)
-    fn get_pod_config(&self, hub: u8, pod: u8) -> (u8, u16, u32) {
-        let ram_cfg = self.read_pod_reg(hub, pod, POD_REG_RAM_CFG).unwrap_or(0);
-        let depth_bits = (ram_cfg & 0xFF) as u8;
-        let data_bits = ((ram_cfg >> 8) & 0xFFFF) as u16;
-        let ts_bits = ((ram_cfg >> 24) & 0xFF) as u8;
-        let ram_depth = 1u32 << depth_bits;
-        (ts_bits, data_bits, ram_depth)
+
+    /// One pod's enumeration with its configured label, and the hub's name
+    pub fn labeled_pod(&self, hub: u8, pod: u8) -> (PodInfo, String) {
+        let mut info = self.ila.enumerate_pod(hub, pod);
+        let settings = self.settings.lock().unwrap().clone();
+        crate::settings::label_pod(&settings, hub, &mut info);
+        let hub_name = match settings.hub_labels.get(&hub.to_string()) {
+            Some(label) => label.display_name.clone(),
+            None => self.ila.read_hub_name(hub).trim().to_string(),
+        };
+        (info, hub_name)
     }
-}
 
-// ============================================================================
-// Signal generation helpers
-// ============================================================================
+    /// Resolve signal names, validate against the topology, then program and
+    /// arm; the outer error lists rejected fields, the inner one a hardware
+    /// failure. A configuration that armed is saved for `SUMP_ARM_ON_BOOT`;
+    /// the caller starts its `timeout_s` (see `arm_timeout::start`).
+    pub fn configure_trigger(&self, config: &TriggerConfig) -> Result<Result<u32, String>, Vec<FieldError>> {
+        crate::arm_timeout::check(config.timeout_s, config.on_timeout)?;
+        let applied = self.ila.resolve_trigger_signals(config)?;
+        self.ila.validate_trigger(&applied)?;
+        let result = self.apply_trigger(&applied);
+        if result.is_ok() {
+            if let Err(e) = persist::save_json(&self.state_dir, persist::LAST_TRIGGER_FILE, config) {
+                tracing::warn!("Failed to save trigger configuration: {}", e);
+            }
+        }
+        Ok(result)
+    }
 
-/// Generate signal list based on norom_view_* flags
-fn generate_norom_signals(
-    pod_name: &str,
-    data_bits: u16,
-    view_dwords: bool,
-    view_words: bool,
-    view_bytes: bool,
-    view_bits: bool,
-    rle_disable: bool,
-) -> (String, Vec<SignalInfo>) {
-    let mut signals = Vec::new();
-    let pod_name_trimmed = pod_name.trim();
-    
-    // Determine view mode based on flags
-    let view_mode = if view_dwords {
-        "dwords"
-    } else if view_words {
-        "words"
-    } else if view_bytes {
-        "bytes"
-    } else if view_bits {
-        "bits"
-    } else {
-        "dwords" // default
-    };
-    
-    let signal_type = if rle_disable { "analog" } else { "vector" };
-    
-    // Special case: ADC I/Q pod with known layout
-    if pod_name_trimmed.contains("adc") && pod_name_trimmed.contains("iq") && data_bits >= 25 {
-        signals.push(SignalInfo {
-            name: "adc_i[11:0]".to_string(),
-            bit_high: 11,
-            bit_low: 0,
-            signal_type: "analog".to_string(),
-        });
-        signals.push(SignalInfo {
-            name: "adc_q[11:0]".to_string(),
-            bit_high: 23,
-            bit_low: 12,
-            signal_type: "analog".to_string(),
-        });
-        signals.push(SignalInfo {
-            name: "adc_valid".to_string(),
-            bit_high: 24,
-            bit_low: 24,
-            signal_type: "bit".to_string(),
-        });
-        return ("iq".to_string(), signals);
+    /// Resolve and validate like `configure_trigger`, but only list the
+    /// commands it would issue
+    pub fn plan_trigger(&self, config: &TriggerConfig) -> Result<TriggerPlan, Vec<FieldError>> {
+        crate::arm_timeout::check(config.timeout_s, config.on_timeout)?;
+        let applied = self.ila.resolve_trigger_signals(config)?;
+        self.ila.validate_trigger(&applied)?;
+        let (trigger_bits, mut commands) = trigger_plan(&applied);
+        commands.push(wrapper_command(CMD_INIT, 0, 0));
+        commands.push(wrapper_command(CMD_ARM, 0, 0));
+        Ok(TriggerPlan { trigger_bits, commands })
     }
-    
-    match view_mode {
-        "dwords" => {
-            let num_dwords = (data_bits + 31) / 32;
-            for i in 0..num_dwords {
-                let bit_low = i * 32;
-                let bit_high = std::cmp::min((i + 1) * 32 - 1, data_bits - 1);
-                signals.push(SignalInfo {
-                    name: if num_dwords == 1 {
-                        format!("{}[{}:0]", pod_name_trimmed, bit_high)
-                    } else {
-                        format!("{}_d{}[{}:{}]", pod_name_trimmed, i, bit_high, bit_low)
-                    },
-                    bit_high,
-                    bit_low,
-                    signal_type: signal_type.to_string(),
-                });
+
+    /// INIT, then poll until the pod RAMs are clear without holding a
+    /// blocking worker; a success completes a pending or failed startup INIT
+    pub async fn init(self: &Arc<Self>) -> Result<CommandResult, Conflict> {
+        let guard = self.claim_op("init").await?;
+        let deadline = Instant::now() + INIT_TIMEOUT;
+        let mut result = self.run(|s| command_result(&s.ila, CMD_INIT, "Init complete", "Init")).await;
+        while result.success && self.run(|s| s.ila.capture_status()).await.init_in_progress {
+            if Instant::now() >= deadline {
+                result = CommandResult { success: false, message: init_timeout_message() };
+                break;
             }
+            tokio::time::sleep(INIT_POLL).await;
         }
-        "words" => {
-            let num_words = (data_bits + 15) / 16;
-            for i in 0..num_words {
-                let bit_low = i * 16;
-                let bit_high = std::cmp::min((i + 1) * 16 - 1, data_bits - 1);
-                signals.push(SignalInfo {
-                    name: if num_words == 1 {
-                        format!("{}[{}:0]", pod_name_trimmed, bit_high)
-                    } else {
-                        format!("{}_w{}[{}:{}]", pod_name_trimmed, i, bit_high, bit_low)
-                    },
-                    bit_high,
-                    bit_low,
-                    signal_type: signal_type.to_string(),
-                });
-            }
+        drop(guard);
+        let mut boot_init = self.boot_init.lock().unwrap();
+        if result.success && boot_init.0 != BootInit::Disabled {
+            *boot_init = (BootInit::Done, "Pod RAM initialized".into());
         }
-        "bytes" => {
-            let num_bytes = (data_bits + 7) / 8;
-            for i in 0..num_bytes {
-                let bit_low = i * 8;
-                let bit_high = std::cmp::min((i + 1) * 8 - 1, data_bits - 1);
-                signals.push(SignalInfo {
-                    name: format!("{}_b{}[{}:{}]", pod_name_trimmed, i, bit_high, bit_low),
-                    bit_high,
-                    bit_low,
-                    signal_type: "vector".to_string(),
-                });
+        Ok(result)
+    }
+
+    /// INIT once the hardware enumerates (`SUMP_INIT_ON_BOOT`), so the
+    /// first capture after boot doesn't read stale RAM
+    pub async fn init_on_boot(self: &Arc<Self>) {
+        *self.boot_init.lock().unwrap() = (BootInit::Pending, "Startup INIT pending".into());
+        let info = self.run(|s| s.ila.info()).await;
+        let failure = if !info.connected || info.hub_count == 0 {
+            "SUMP3 hardware not detected; POST /api/ila/init once it is".to_string()
+        } else {
+            match self.init().await {
+                Ok(result) if result.success => {
+                    tracing::info!("Init on boot: pod RAM initialized");
+                    return;
+                }
+                Ok(result) => result.message,
+                Err(conflict) => conflict.0.message,
             }
+        };
+        tracing::warn!("Init on boot: {}", failure);
+        *self.boot_init.lock().unwrap() = (BootInit::Failed, failure);
+    }
+
+    /// Restore the last applied trigger configuration and arm, if the
+    /// hardware answers with a valid HW_INFO
+    pub fn arm_on_boot(&self) {
+        let info = self.ila.info();
+        if !info.connected || info.hub_count == 0 {
+            tracing::warn!("Arm on boot: SUMP3 hardware not detected, not arming");
+            return;
         }
-        "bits" => {
-            for i in 0..data_bits {
-                signals.push(SignalInfo {
-                    name: format!("{}[{}]", pod_name_trimmed, i),
-                    bit_high: i,
-                    bit_low: i,
-                    signal_type: "bit".to_string(),
-                });
+        let Some(config) = persist::load_json::<TriggerConfig>(&self.state_dir, persist::LAST_TRIGGER_FILE) else {
+            tracing::warn!("Arm on boot: no saved trigger configuration in {}", self.state_dir.display());
+            return;
+        };
+        let checked = self.ila.resolve_trigger_signals(&config)
+            .and_then(|resolved| self.ila.validate_trigger(&resolved).map(|()| resolved));
+        let config = match checked {
+            Ok(resolved) => resolved,
+            Err(errors) => {
+                for e in errors {
+                    tracing::error!("Arm on boot: saved trigger invalid for this hardware: {}: {}", e.field, e.message);
+                }
+                return;
             }
+        };
+        match self.apply_trigger(&config) {
+            Ok(bits) => tracing::info!(
+                "Arm on boot: restored type={}, bits=0x{:08X}, post={} and armed",
+                config.trigger_type, bits, config.post_trigger
+            ),
+            Err(e) => tracing::error!("Arm on boot failed: {}", e),
+        }
+    }
+
+    /// Run a hardware operation on the blocking pool, so request timeouts
+    /// still fire while a bus transaction is wedged
+    pub async fn run<T, F>(self: &Arc<Self>, f: F) -> T
+    where
+        F: FnOnce(&IlaState) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let state = self.clone();
+        tokio::task::spawn_blocking(move || f(&state))
+            .await
+            .expect("hardware task panicked")
+    }
+
+    /// Signal background tasks and WebSocket sessions to stop
+    pub fn begin_shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Receiver that resolves `changed()` when shutdown begins
+    pub fn shutdown_signal(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+
+    /// Leave the hardware disarmed; called after in-flight requests drained
+    pub fn quiesce(&self) {
+        if self.ila.exec_cmd(CMD_IDLE, 0, 0).is_some() {
+            tracing::info!("ILA disarmed");
+        } else {
+            tracing::warn!("Failed to disarm ILA on shutdown");
         }
-        _ => {}
     }
-    
-    (view_mode.to_string(), signals)
 }
 
 // ============================================================================
-// Data structures
+// API handlers
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-pub struct IlaInfo {
-    pub connected: bool,
-    pub hw_id: String,
-    pub revision: u8,
-    pub hub_count: u8,
-    pub is_armed: bool,
-    pub is_awake: bool,
-    pub base_addr: String,
-    pub hubs: Vec<HubInfo>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct HubInfo {
-    pub index: u8,
-    pub name: String,
-    pub freq_mhz: u32,
-    pub pod_count: u8,
-    pub pods: Vec<PodInfo>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct PodInfo {
-    pub index: u8,
-    pub name: String,
-    pub hw_rev: u8,
-    pub ram_depth: u32,
-    pub data_bits: u16,
-    pub ts_bits: u8,
-    pub triggerable: u32,
-    pub rle_disable: bool,
-    pub view_rom_en: bool,
-    pub view_mode: String,
-    pub signals: Vec<SignalInfo>,
-}
-
-#[derive(Debug, Serialize, Clone)]
-pub struct SignalInfo {
-    pub name: String,
-    pub bit_high: u16,
-    pub bit_low: u16,
-    pub signal_type: String,
-}
-
-#[derive(Debug, Serialize, Clone)]
-pub struct RleSample {
-    pub address: u32,
-    pub code: u8,
-    pub timestamp: u32,
-    pub data: u32,
-}
-
-#[derive(Debug, Serialize)]
-pub struct CaptureStatus {
-    pub armed: bool,
-    pub pre_trigger: bool,
-    pub triggered: bool,
-    pub acquired: bool,
-    pub init_in_progress: bool,
-}
-
-#[derive(Debug, Serialize)]
-pub struct CaptureData {
-    pub hub: u8,
-    pub pod: u8,
-    pub ts_bits: u8,
-    pub data_bits: u16,
-    pub status: CaptureStatus,
-    pub samples: Vec<RleSample>,
-    pub sample_count: u32,
+/// GET /api/ila - Get ILA info with full hub/pod enumeration
+async fn get_info(State(state): State<Arc<IlaState>>, extensions: Extensions) -> Json<IlaInfo> {
+    let mut info = state.run(|s| s.info()).await;
+    if masks::partner(&extensions) {
+        masks::redact_info(&state.settings.lock().unwrap(), &mut info);
+    }
+    info.error = state.degraded.lock().unwrap().clone();
+    Json(info)
 }
 
 #[derive(Debug, Deserialize)]
-pub struct TriggerConfig {
+struct ResetQuery {
     #[serde(default)]
-    pub trigger_type: String,
-    #[serde(default)]
-    pub trigger_bits: u32,
-    #[serde(default = "default_post_trigger")]
-    pub post_trigger: u32,
+    force: bool,
 }
 
-fn default_post_trigger() -> u32 { 64 }
+/// Issue a state command; a failure message carries the wrapper's error cause
+pub(crate) fn command_result(ila: &Ila, cmd: u32, done: &str, what: &str) -> CommandResult {
+    match ila.exec_cmd(cmd, 0, 0) {
+        Some(_) => CommandResult { success: true, message: done.into() },
+        None => CommandResult { success: false, message: failure_message(what) },
+    }
+}
 
-#[derive(Debug, Serialize)]
-pub struct RegisterValue {
-    pub offset: usize,
-    pub value: Option<u32>,
+/// POST /api/ila/reset?force=true - Reset ILA (409 while armed unless forced)
+async fn post_reset(
+    State(state): State<Arc<IlaState>>,
+    Query(query): Query<ResetQuery>,
+) -> Result<Json<CommandResult>, Conflict> {
+    let result = state.run_op("reset", move |s| {
+        if !query.force {
+            let status = s.ila.capture_status();
+            if status.armed && !status.acquired {
+                return Err(status);
+            }
+        }
+        Ok(command_result(&s.ila, CMD_RESET, "Reset complete", "Reset"))
+    }).await?
+    .map_err(|status| Conflict::new("ILA is armed; use ?force=true to reset anyway", None, status))?;
+    Ok(Json(result))
 }
 
-#[derive(Debug, Serialize)]
-pub struct CommandResult {
-    pub success: bool,
-    pub message: String,
+/// POST /api/ila/init - Initialize RAM
+async fn post_init(State(state): State<Arc<IlaState>>) -> Result<Json<CommandResult>, Conflict> {
+    state.init().await.map(Json)
 }
 
-// ============================================================================
-// API handlers
-// ============================================================================
+#[derive(Debug, Deserialize)]
+struct ArmQuery {
+    timeout_s: Option<f64>,
+    on_timeout: Option<TimeoutAction>,
+}
 
-/// GET /api/ila - Get ILA info with full hub/pod enumeration
-async fn get_info(State(state): State<Arc<IlaState>>) -> Json<IlaInfo> {
-    let mem = state.mem.lock();
-    
-    let hw_info = mem.read32(REG_HW_INFO).unwrap_or(0);
-    drop(mem);
-    
-    let id = (hw_info >> 16) & 0xFFFF;
-    let hub_count = ((hw_info >> 8) & 0xFF) as u8;
-    let revision = (hw_info & 0xFF) as u8;
-    
-    let connected = id == 0x5303;
-    let hw_id = format!("{}{}", 
-        char::from_u32((id >> 8) & 0xFF).unwrap_or('?'),
-        char::from_u32(id & 0xFF).unwrap_or('?')
-    );
-    
-    let mem = state.mem.lock();
-    let cap_status = mem.read32(REG_CAP_STATUS).unwrap_or(0);
-    drop(mem);
-    
-    let is_armed = (cap_status & 0x01) != 0;
-    let is_awake = (cap_status & 0x02) != 0;
-    
-    // Enumerate hubs and pods
-    let mut hubs = Vec::new();
-    if connected {
-        for hub_idx in 0..hub_count {
-            let addr = (hub_idx as u32) << 16;
-            
-            let name = state.read_hub_name(hub_idx);
-            let freq = state.exec_cmd(CMD_RD_HUB_FREQ, addr, 0).unwrap_or(0);
-            let freq_mhz = (freq >> 20) & 0xFFF;
-            let pod_count = state.exec_cmd(CMD_RD_POD_COUNT, addr, 0)
-                .map(|v| (v & 0xFF) as u8)
-                .unwrap_or(0);
-            
-            let mut pods = Vec::new();
-            for pod_idx in 0..pod_count {
-                let pod_name = state.read_pod_name(hub_idx, pod_idx);
-                
-                let hw_cfg = state.read_pod_reg(hub_idx, pod_idx, POD_REG_HW_CFG).unwrap_or(0);
-                let hw_rev = ((hw_cfg >> 24) & 0xFF) as u8;
-                
-                let norom_view_dwords = (hw_cfg & 0x0800) != 0;
-                let norom_view_words = (hw_cfg & 0x0400) != 0;
-                let norom_view_bytes = (hw_cfg & 0x0200) != 0;
-                let norom_view_bits = (hw_cfg & 0x0100) != 0;
-                let rle_disable = (hw_cfg & 0x04) != 0;
-                let view_rom_en = (hw_cfg & 0x02) != 0;
-                
-                let ram_cfg = state.read_pod_reg(hub_idx, pod_idx, POD_REG_RAM_CFG).unwrap_or(0);
-                let depth_bits = (ram_cfg & 0xFF) as u8;
-                let data_bits = ((ram_cfg >> 8) & 0xFFFF) as u16;
-                let ts_bits = ((ram_cfg >> 24) & 0xFF) as u8;
-                let ram_depth = 1u32 << depth_bits;
-                
-                let triggerable = state.read_pod_reg(hub_idx, pod_idx, POD_REG_TRIGGERABLE).unwrap_or(0);
-                
-                let (view_mode, signals) = if view_rom_en {
-                    ("custom".to_string(), Vec::new())
-                } else {
-                    generate_norom_signals(&pod_name, data_bits, 
-                        norom_view_dwords, norom_view_words, norom_view_bytes, norom_view_bits,
-                        rle_disable)
-                };
-                
-                pods.push(PodInfo {
-                    index: pod_idx,
-                    name: pod_name,
-                    hw_rev,
-                    ram_depth,
-                    data_bits,
-                    ts_bits,
-                    triggerable,
-                    rle_disable,
-                    view_rom_en,
-                    view_mode,
-                    signals,
-                });
-            }
-            
-            hubs.push(HubInfo {
-                index: hub_idx,
-                name,
-                freq_mhz,
-                pod_count,
-                pods,
-            });
-        }
+/// POST /api/ila/arm?timeout_s=N&on_timeout=ACTION - Arm for capture
+async fn post_arm(
+    State(state): State<Arc<IlaState>>,
+    Query(query): Query<ArmQuery>,
+) -> Result<Json<CommandResult>, Response> {
+    crate::arm_timeout::check(query.timeout_s, query.on_timeout)
+        .map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response())?;
+    let result = state.run_op("arm", |s| command_result(&s.ila, CMD_ARM, "Armed", "Arm")).await
+        .map_err(IntoResponse::into_response)?;
+    if result.success {
+        crate::arm_timeout::start(&state, query.timeout_s, query.on_timeout, None);
     }
-    
-    Json(IlaInfo {
-        connected,
-        hw_id,
-        revision,
-        hub_count,
-        is_armed,
-        is_awake,
-        base_addr: format!("0x{:08X}", state.base_addr),
-        hubs,
-    })
-}
-
-/// GET /api/ila/status - Get capture status
-async fn get_capture_status(State(state): State<Arc<IlaState>>) -> Json<CaptureStatus> {
-    let status = state.exec_cmd(CMD_RD_STATUS, 0, 0).unwrap_or(0);
-    
-    Json(CaptureStatus {
-        armed: (status & 0x01) != 0,
-        pre_trigger: (status & 0x02) != 0,
-        triggered: (status & 0x04) != 0,
-        acquired: (status & 0x08) != 0,
-        init_in_progress: (status & 0x10) != 0,
-    })
-}
-
-/// POST /api/ila/reset - Reset ILA
-async fn post_reset(State(state): State<Arc<IlaState>>) -> Json<CommandResult> {
-    let success = state.exec_cmd(CMD_RESET, 0, 0).is_some();
-    Json(CommandResult {
-        success,
-        message: if success { "Reset complete".into() } else { "Reset failed".into() },
-    })
+    Ok(Json(result))
 }
 
-/// POST /api/ila/init - Initialize RAM
-async fn post_init(State(state): State<Arc<IlaState>>) -> Json<CommandResult> {
-    let success = state.exec_cmd(CMD_INIT, 0, 0).is_some();
-    std::thread::sleep(std::time::Duration::from_millis(100));
-    Json(CommandResult {
-        success,
-        message: if success { "Init complete".into() } else { "Init failed".into() },
-    })
+/// POST /api/ila/disarm - Stop waiting for the trigger (IDLE); pod RAM keeps
+/// the samples taken so far
+async fn post_disarm(State(state): State<Arc<IlaState>>) -> Result<Json<CommandResult>, Conflict> {
+    state.run_op("arm", |s| command_result(&s.ila, CMD_IDLE, "Disarmed", "Disarm")).await.map(Json)
 }
 
-/// POST /api/ila/arm - Arm for capture
-async fn post_arm(State(state): State<Arc<IlaState>>) -> Json<CommandResult> {
-    let success = state.exec_cmd(CMD_ARM, 0, 0).is_some();
-    Json(CommandResult {
-        success,
-        message: if success { "Armed".into() } else { "Arm failed".into() },
-    })
+#[derive(Debug, Deserialize)]
+struct DryRunQuery {
+    #[serde(default)]
+    dry_run: bool,
 }
 
-/// POST /api/ila/trigger - Configure trigger and arm
+/// POST /api/ila/trigger?dry_run=true - Configure trigger and arm; a dry run
+/// only validates and lists the commands
 async fn post_configure_trigger(
     State(state): State<Arc<IlaState>>,
+    Query(query): Query<DryRunQuery>,
     Json(config): Json<TriggerConfig>,
-) -> Json<CommandResult> {
-    if state.exec_cmd(CMD_RESET, 0, 0).is_none() {
-        return Json(CommandResult { success: false, message: "Reset failed".into() });
+) -> Result<Response, Response> {
+    if query.dry_run {
+        let plan = state.run(move |s| s.plan_trigger(&config)).await
+            .map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response())?;
+        return Ok(Json(plan).into_response());
     }
-    
-    let trig_type = match config.trigger_type.as_str() {
-        "or_falling" => TRIG_OR_FALLING,
-        "external" => TRIG_EXT_RISING,
-        _ => TRIG_OR_RISING,
+    let requested = config.clone();
+    let result = state.run_op("trigger", move |s| s.configure_trigger(&requested)).await
+        .map_err(IntoResponse::into_response)?
+        .map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response())?;
+
+    let trig_bits = match result {
+        Ok(bits) => bits,
+        Err(message) => return Ok(Json(CommandResult { success: false, message }).into_response()),
     };
-    
-    if state.exec_cmd(CMD_WR_TRIG_TYPE, 0, trig_type).is_none() {
-        return Json(CommandResult { success: false, message: "Failed to set trigger type".into() });
-    }
-    
-    let trig_bits = if config.trigger_bits == 0 { 0x00000001 } else { config.trigger_bits };
-    if state.exec_cmd(CMD_WR_TRIG_DIG_FIELD, 0, trig_bits).is_none() {
-        return Json(CommandResult { success: false, message: "Failed to set trigger field".into() });
-    }
-    
-    if state.exec_cmd(CMD_WR_DIG_POST_TRIG, 0, config.post_trigger).is_none() {
-        return Json(CommandResult { success: false, message: "Failed to set post-trigger".into() });
-    }
-    
-    let pod_trig_cfg = (trig_type & 0x07) | 0x20;
-    state.write_pod_reg(0, 0, POD_REG_TRIG_CFG, pod_trig_cfg);
-    state.write_pod_reg(0, 0, POD_REG_TRIG_EN, trig_bits);
-    
-    if state.exec_cmd(CMD_INIT, 0, 0).is_none() {
-        return Json(CommandResult { success: false, message: "Init failed".into() });
-    }
-    // Small delay for INIT to complete (was 200ms, reduced to 10ms)
-    std::thread::sleep(std::time::Duration::from_millis(10));
-    
-    if state.exec_cmd(CMD_ARM, 0, 0).is_none() {
-        return Json(CommandResult { success: false, message: "Arm failed".into() });
-    }
-    
-    Json(CommandResult {
+    crate::arm_timeout::start(&state, config.timeout_s, config.on_timeout, Some(config.clone()));
+
+    Ok(Json(CommandResult {
         success: true,
         message: format!("Configured: type={}, bits=0x{:08X}, post={}", 
             config.trigger_type, trig_bits, config.post_trigger),
-    })
+    }).into_response())
 }
 
-/// GET /api/ila/capture/:count - Get captured samples from hub 0, pod 0 (default)
+/// Samples on each side of the trigger when a window omits `span`
+const DEFAULT_WINDOW_SPAN: u32 = 100;
+
+/// Optional readout window; `count` is ignored when one is selected
+#[derive(Debug, Deserialize)]
+struct WindowQuery {
+    window: Option<ReadoutWindow>,
+    span: Option<u32>,
+}
+
+/// GET /api/ila/capture/:count?window=pre|post|around&span=N - Get captured samples from hub 0, pod 0 (default)
 async fn get_capture(
     State(state): State<Arc<IlaState>>,
     Path(count): Path<u32>,
-) -> Json<CaptureData> {
-    get_capture_from_pod(state, 0, 0, count).await
+    Query(query): Query<WindowQuery>,
+    extensions: Extensions,
+) -> Result<Json<CaptureData>, Response> {
+    get_capture_from_pod(state, 0, 0, count, query, masks::partner(&extensions)).await
 }
 
-/// GET /api/ila/capture/:hub/:pod/:count - Get captured samples from specific hub/pod
+/// GET /api/ila/capture/:hub/:pod/:count?window=pre|post|around&span=N - Get captured samples from specific hub/pod
 async fn get_capture_hub_pod(
     State(state): State<Arc<IlaState>>,
     Path((hub, pod, count)): Path<(u8, u8, u32)>,
-) -> Json<CaptureData> {
-    get_capture_from_pod(state, hub, pod, count).await
+    Query(query): Query<WindowQuery>,
+    extensions: Extensions,
+) -> Result<Json<CaptureData>, Response> {
+    get_capture_from_pod(state, hub, pod, count, query, masks::partner(&extensions)).await
 }
 
 /// Internal function to capture from a specific hub/pod
@@ -644,64 +522,294 @@ async fn get_capture_from_pod(
     hub: u8,
     pod: u8,
     count: u32,
-) -> Json<CaptureData> {
-    let status_val = state.exec_cmd(CMD_RD_STATUS, 0, 0).unwrap_or(0);
-    let status = CaptureStatus {
-        armed: (status_val & 0x01) != 0,
-        pre_trigger: (status_val & 0x02) != 0,
-        triggered: (status_val & 0x04) != 0,
-        acquired: (status_val & 0x08) != 0,
-        init_in_progress: (status_val & 0x10) != 0,
-    };
-    
-    let (ts_bits, data_bits, ram_depth) = state.get_pod_config(hub, pod);
-    
-    let sample_count = count.min(ram_depth).min(2048);
-    let mut samples = Vec::with_capacity(sample_count as usize);
-    
-    for i in 0..sample_count {
-        if let Some(sample) = state.read_rle_sample(hub, pod, i, ts_bits) {
-            samples.push(sample);
+    query: WindowQuery,
+    partner: bool,
+) -> Result<Json<CaptureData>, Response> {
+    state.run_op("readout", move |s| -> Result<_, validate::Invalid> {
+        validate::visible_pod(s, hub, pod)?;
+        let (_, _, ram_depth) = s.ila.get_pod_config(hub, pod);
+        let mut capture = match query.window {
+            None => {
+                validate::count("count", count, ram_depth)?;
+                s.ila.read_capture(hub, pod, count)
+            }
+            Some(window) => {
+                let span = query.span.unwrap_or(DEFAULT_WINDOW_SPAN.min(ram_depth));
+                validate::count("span", span, ram_depth)?;
+                s.ila.read_capture_window(hub, pod, window, span).ok_or_else(|| {
+                    (StatusCode::CONFLICT, format!("No trigger sample in hub {} pod {} RAM", hub, pod))
+                })?
+            }
+        };
+        digest::record(s, &capture);
+        if let Some(mask) = masks::for_request(s, partner, hub, pod) {
+            mask.blank(&mut capture);
+        }
+        Ok(capture)
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map(Json)
+    .map_err(IntoResponse::into_response)
+}
+
+/// GET /api/ila/capture/:hub/:pod/raw - Dump pod RAM pages verbatim (see `RawRamHeader`)
+async fn get_capture_raw(
+    State(state): State<Arc<IlaState>>,
+    Path((hub, pod)): Path<(u8, u8)>,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    let partner = masks::partner(&extensions);
+    let (header, words) = state.run_op("readout", move |s| -> Result<_, validate::Invalid> {
+        validate::visible_pod(s, hub, pod)?;
+        let (header, mut words) = s.ila.dump_ram(hub, pod)
+            .map_err(|message| (StatusCode::INTERNAL_SERVER_ERROR, with_cause(&message)))?;
+        if let Some(mask) = masks::for_request(s, partner, hub, pod) {
+            mask.blank_dump(&header, &mut words);
         }
+        Ok((header, words))
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map_err(IntoResponse::into_response)?;
+
+    Ok(raw_dump_response(&header, &words))
+}
+
+/// Encode a raw RAM dump (see `RawRamHeader`)
+pub(crate) fn raw_dump(header: &RawRamHeader, words: &[u32]) -> Vec<u8> {
+    let json = serde_json::to_vec(header).unwrap();
+    let mut body = Vec::with_capacity(RAW_DUMP_MAGIC.len() + 4 + json.len() + words.len() * 4);
+    body.extend_from_slice(RAW_DUMP_MAGIC);
+    body.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    body.extend_from_slice(&json);
+    for word in words {
+        body.extend_from_slice(&word.to_le_bytes());
     }
-    
-    Json(CaptureData {
-        hub,
-        pod,
-        ts_bits,
-        data_bits,
-        status,
-        samples,
-        sample_count,
-    })
+    body
+}
+
+/// A raw RAM dump as a `.sumpraw` download
+pub(crate) fn raw_dump_response(header: &RawRamHeader, words: &[u32]) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"capture{}_hub{}_pod{}.sumpraw\"", header.sequence, header.hub, header.pod),
+        )
+        .body(Body::from(raw_dump(header, words)))
+        .unwrap()
+}
+
+#[derive(Debug, Deserialize)]
+struct FillQuery {
+    stride: Option<u32>,
+}
+
+/// GET /api/ila/fill/:hub/:pod?stride=N - Pod RAM fill level (stride=1 scans every address)
+async fn get_ram_fill(
+    State(state): State<Arc<IlaState>>,
+    Path((hub, pod)): Path<(u8, u8)>,
+    Query(query): Query<FillQuery>,
+) -> Result<Json<RamFill>, Response> {
+    state.run_op("readout", move |s| {
+        validate::visible_pod(s, hub, pod)?;
+        if let Some(stride) = query.stride {
+            let (_, _, ram_depth) = s.ila.get_pod_config(hub, pod);
+            validate::count("stride", stride, ram_depth)?;
+        }
+        s.ila.ram_fill(hub, pod, query.stride).ok_or_else(|| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            with_cause(&format!("RAM read failed: hub {} pod {}", hub, pod)),
+        ))
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map(Json)
+    .map_err(IntoResponse::into_response)
 }
 
 /// GET /api/ila/reg/:offset - Read raw register
 async fn get_register(
     State(state): State<Arc<IlaState>>,
     Path(offset): Path<usize>,
-) -> Json<RegisterValue> {
-    let value = if offset < ILA_SIZE {
-        let mem = state.mem.lock();
-        mem.read32(offset)
-    } else {
-        None
-    };
-    
-    Json(RegisterValue { offset, value })
+    extensions: Extensions,
+) -> Result<Json<RegisterValue>, validate::Invalid> {
+    // Pod data reads through the register window can't be masked
+    masks::unmaskable(&state, masks::partner(&extensions), "Raw register access")?;
+    validate::offset(&state.ila, offset)?;
+    let value = state.run(move |s| s.ila.read_reg(offset)).await;
+    Ok(Json(RegisterValue { offset, value }))
+}
+
+/// GET /api/ila/ext-trigger/routing - External trigger input/output routing
+async fn get_ext_trigger_routing(State(state): State<Arc<IlaState>>) -> Result<Json<ExtTriggerRouting>, Response> {
+    state.run(|s| s.ila.ext_trigger_routing()).await
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Wrapper has no external trigger routing").into_response())
+}
+
+/// PUT /api/ila/ext-trigger/routing - Select the trigger input and enable trigger output
+async fn put_ext_trigger_routing(
+    State(state): State<Arc<IlaState>>,
+    Json(routing): Json<ExtTriggerRouting>,
+) -> Result<Json<ExtTriggerRouting>, Response> {
+    let applied = state.run_op("trigger", move |s| s.ila.set_ext_trigger_routing(&routing)).await
+        .map_err(IntoResponse::into_response)?
+        .map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response())?;
+    tracing::info!("External trigger routing: source={}, output={}", applied.source, applied.output_enable);
+    Ok(Json(applied))
+}
+
+/// GET /api/ila/user-ctrl - Last value written to the core user_ctrl outputs
+async fn get_user_ctrl(State(state): State<Arc<IlaState>>) -> Json<UserBits> {
+    Json(UserBits { value: state.ila.user_ctrl() })
+}
+
+/// PUT /api/ila/user-ctrl - Set core user_ctrl bits (e.g. capture mux selects)
+async fn put_user_ctrl(
+    State(state): State<Arc<IlaState>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Json(write): Json<UserBitsWrite>,
+) -> Result<Json<UserBits>, Response> {
+    audit::record(client, "rest", format_args!("user_ctrl = 0x{:08X} mask 0x{:08X}", write.value, write.mask));
+    state.run_op("user_ctrl", move |s| s.ila.write_user_ctrl(write.value, write.mask).ok_or_else(|| failure_message("user_ctrl write"))).await
+        .map_err(IntoResponse::into_response)?
+        .map(|value| Json(UserBits { value: Some(value) }))
+        .map_err(|message| (StatusCode::INTERNAL_SERVER_ERROR, message).into_response())
+}
+
+/// GET /api/ila/user-stim/:hub/:pod - Pod user control (stimulus) bits
+async fn get_user_stim(
+    State(state): State<Arc<IlaState>>,
+    Path((hub, pod)): Path<(u8, u8)>,
+) -> Result<Json<UserBits>, validate::Invalid> {
+    state.run(move |s| {
+        validate::visible_pod(s, hub, pod)?;
+        Ok(Json(UserBits { value: s.ila.read_user_stim(hub, pod) }))
+    }).await
+}
+
+/// PUT /api/ila/user-stim/:hub/:pod - Drive pod stimulus bits into the design
+async fn put_user_stim(
+    State(state): State<Arc<IlaState>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Path((hub, pod)): Path<(u8, u8)>,
+    Json(write): Json<UserBitsWrite>,
+) -> Result<Json<UserBits>, Response> {
+    audit::record(client, "rest", format_args!("hub {} pod {} user_stim = 0x{:08X} mask 0x{:08X}", hub, pod, write.value, write.mask));
+    state.run_op("user_stim", move |s| -> Result<_, validate::Invalid> {
+        validate::visible_pod(s, hub, pod)?;
+        let value = s.ila.write_user_stim(hub, pod, write.value, write.mask).ok_or_else(|| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            failure_message(&format!("user_stim write to hub {} pod {}", hub, pod)),
+        ))?;
+        Ok(Json(UserBits { value: Some(value) }))
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map_err(IntoResponse::into_response)
+}
+
+/// GET /api/ila/correlation - Current capture correlation tag (null if none)
+async fn get_correlation(State(state): State<Arc<IlaState>>) -> Json<Option<Correlation>> {
+    Json(state.ila.correlation())
+}
+
+/// PUT /api/ila/correlation - Tag subsequent captures with a shared id
+async fn put_correlation(
+    State(state): State<Arc<IlaState>>,
+    Json(mut correlation): Json<Correlation>,
+) -> Result<Json<Correlation>, validate::Invalid> {
+    if correlation.id.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Correlation id must not be empty".into()));
+    }
+    correlation.reference_ms.get_or_insert_with(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+    });
+    tracing::info!("Capture correlation set to '{}'", correlation.id);
+    state.ila.set_correlation(Some(correlation.clone()));
+    Ok(Json(correlation))
+}
+
+/// DELETE /api/ila/correlation - Stop tagging captures
+async fn delete_correlation(State(state): State<Arc<IlaState>>) -> StatusCode {
+    state.ila.set_correlation(None);
+    StatusCode::NO_CONTENT
 }
 
 /// Create the ILA API router
-pub fn ila_router(state: Arc<IlaState>) -> Router {
-    Router::new()
-        .route("/", get(get_info))
-        .route("/status", get(get_capture_status))
+pub fn ila_router(state: Arc<IlaState>, timeouts: &Timeouts, limiter: Arc<Limiter>) -> Router {
+    let commands = Router::new()
+        .route("/status", get(crate::status::get_capture_status))
         .route("/reset", post(post_reset))
         .route("/init", post(post_init))
         .route("/arm", post(post_arm))
+        .route("/disarm", post(post_disarm))
         .route("/trigger", post(post_configure_trigger))
+        .route("/sequence", get(crate::sequence::get_sequence).post(crate::sequence::post_sequence))
+        .route("/sequence/stop", post(crate::sequence::post_sequence_stop))
+        .route("/history", get(crate::history::get_history).post(crate::history::post_history))
+        .route("/history/snapshot", post(crate::history::post_history_snapshot))
+        .route("/history/stop", post(crate::history::post_history_stop))
+        .route("/history/:id", get(crate::history::get_history_snapshot))
+        .route("/ext-trigger", post(crate::ext_trigger::post_ext_trigger))
+        .route("/ext-trigger/routing", get(get_ext_trigger_routing).put(put_ext_trigger_routing))
+        .route("/batch", post(crate::batch::post_batch))
+        .route("/selftest", post(crate::selftest::post_selftest))
+        .route("/deep", get(crate::deep::get_deep))
+        .route("/deep/config", put(crate::deep::put_deep_config))
+        .route("/deep/arm", post(crate::deep::post_deep_arm))
+        .route("/deep/stop", post(crate::deep::post_deep_stop))
+        .route("/user-ctrl", get(get_user_ctrl).put(put_user_ctrl))
+        .route("/user-stim/:hub/:pod", get(get_user_stim).put(put_user_stim))
+        .route("/reg/:offset", get(get_register));
+
+    // Pod RAM readouts issue thousands of serial-bus commands
+    let readout = Router::new()
+        .route("/capture/:hub/:pod/raw", get(get_capture_raw))
+        .route("/capture/:hub/:pod/npz", get(crate::npy::get_capture_npz))
+        .route("/capture/:hub/:pod/npy", get(crate::npy::get_capture_npy))
         .route("/capture/:hub/:pod/:count", get(get_capture_hub_pod))
         .route("/capture/:count", get(get_capture))
-        .route("/reg/:offset", get(get_register))
+        .route("/fill/:hub/:pod", get(get_ram_fill))
+        .route("/deep/data", get(crate::deep::get_deep_data))
+        .route("/deep/export/vcd", get(crate::deep::get_deep_vcd))
+        .route("/deep/decode/:decoder", post(crate::decode::post_deep_decode))
+        .route("/capture/:hub/:pod/export/:format", get(crate::export::get_export))
+        .route("/capture/:hub/:pod/decode/:decoder", post(crate::decode::post_decode))
+        .route("/snapshot", post(crate::snapshot::post_snapshot))
+        .route("/benchmark", post(crate::benchmark::post_benchmark));
+
+    // Counters, watchdog state, the correlation tag and the archive don't
+    // touch the hardware
+    let monitoring = Router::new()
+        .route("/stats", get(crate::stats::get_stats))
+        .route("/export-formats", get(crate::export::get_export_formats))
+        .route("/decoders", get(crate::decode::get_decoders))
+        .route("/arm-timeout", get(crate::arm_timeout::get_arm_timeout))
+        .route("/health", get(crate::watchdog::get_health))
+        .route("/circuit", get(crate::breaker::get_circuit))
+        .route("/digests", get(crate::digest::get_digests))
+        .route("/alerts", get(crate::alerts::get_alerts))
+        .route("/archive", get(crate::archive::get_archive))
+        .route("/archive/:id/:hub/:pod", get(crate::archive::get_archived_capture))
+        .route("/archive/:id/:hub/:pod/raw", get(crate::archive::get_archived_raw))
+        .route("/correlation", get(get_correlation).put(put_correlation).delete(delete_correlation));
+
+    // Enumeration also reports why there is no hardware (see `degraded`)
+    let info = Router::new().route("/", get(get_info));
+
+    let hardware = with_timeout(commands, timeouts.request)
+        .merge(with_timeout(readout, timeouts.readout))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), crate::degraded::unavailable))
+        .merge(with_timeout(info, timeouts.request))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), crate::breaker::guard));
+
+    // A single register read for high-frequency pollers, exempt from the limits
+    let raw_status = Router::new().route("/status/raw", get(crate::status::get_raw_status));
+
+    with_limits(hardware, limiter)
+        .merge(with_timeout(raw_status, timeouts.request))
+        .merge(monitoring)
+        .route("/watch", get(crate::watch::ws_watch))
+        .route("/console", get(crate::console::ws_console))
         .with_state(state)
 }
@@ -0,0 +1,347 @@
+//! Log outputs
+//!
+//! Besides stdout, log lines can go to a file that rotates by size and/or
+//! time (`SUMP_LOG_FILE`, `SUMP_LOG_MAX_MB`, `SUMP_LOG_ROTATE`,
+//! `SUMP_LOG_KEEP`) and to the system log (`SUMP_LOG_SYSLOG`), and stdout
+//! can be turned off (`SUMP_LOG_STDOUT=0`) on boards whose init scripts
+//! redirect it to tmpfs. The filter starts from `RUST_LOG` and can be
+//! changed at runtime with `PUT /api/log`.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry,
+};
+
+use sump_model::LogConfig;
+
+use crate::validate::Invalid;
+
+/// Filter without `RUST_LOG`
+const DEFAULT_FILTER: &str = "sump_server=info,tower_http=info";
+
+/// Default `SUMP_LOG_MAX_MB`
+const DEFAULT_MAX_MB: u64 = 10;
+
+/// Default `SUMP_LOG_KEEP`
+const DEFAULT_KEEP: usize = 5;
+
+/// When a `RotatingFile` starts a new file
+#[derive(Debug, Clone, Copy)]
+pub struct Rotation {
+    /// Size in bytes past which the file rotates (0 = no limit)
+    pub max_bytes: u64,
+    /// Period in seconds, aligned to UTC, after which the file rotates
+    pub period: Option<u64>,
+    /// Rotated files kept as `<file>.1` (newest) to `<file>.<keep>`
+    pub keep: usize,
+}
+
+/// Log file appended to, rotated per its `Rotation`
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    rotation: Rotation,
+    /// Period number the current file was opened in
+    opened: u64,
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    name.into()
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, rotation: Rotation) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        let mut log = Self { path: path.into(), file, size, rotation, opened: 0 };
+        log.opened = log.period_now();
+        Ok(log)
+    }
+
+    fn period_now(&self) -> u64 {
+        self.rotation.period.map_or(0, |period| unix_secs() / period)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..self.rotation.keep).rev() {
+            let from = rotated(&self.path, n);
+            if from.exists() {
+                std::fs::rename(&from, rotated(&self.path, n + 1))?;
+            }
+        }
+        if self.rotation.keep > 0 {
+            std::fs::rename(&self.path, rotated(&self.path, 1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.size = 0;
+        self.opened = self.period_now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let full = self.rotation.max_bytes > 0 && self.size > 0 && self.size + buf.len() as u64 > self.rotation.max_bytes;
+        if full || self.period_now() != self.opened {
+            if let Err(e) = self.rotate() {
+                // Keep appending to the current file rather than lose lines
+                eprintln!("Failed to rotate {}: {}", self.path.display(), e);
+                self.opened = self.period_now();
+            }
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// System log protocol of `SUMP_LOG_SYSLOG`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogProtocol {
+    /// RFC 3164 datagrams to `/dev/log`
+    Syslog,
+    /// systemd-journald native protocol
+    Journald,
+}
+
+impl SyslogProtocol {
+    fn socket(self) -> &'static str {
+        match self {
+            Self::Syslog => "/dev/log",
+            Self::Journald => "/run/systemd/journal/socket",
+        }
+    }
+}
+
+/// Log writer sending each line as one datagram to the system log
+pub struct Syslog {
+    socket: UnixDatagram,
+    protocol: SyslogProtocol,
+}
+
+impl Syslog {
+    pub fn connect(protocol: SyslogProtocol) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(protocol.socket())?;
+        Ok(Self { socket, protocol })
+    }
+
+    fn send(&self, severity: u8, message: &str) {
+        let packet = match self.protocol {
+            // Facility daemon (3)
+            SyslogProtocol::Syslog => {
+                format!("<{}>sump-server[{}]: {}", 3 * 8 + severity, std::process::id(), message).into_bytes()
+            }
+            SyslogProtocol::Journald => {
+                let mut packet = format!("PRIORITY={}\nSYSLOG_IDENTIFIER=sump-server\nMESSAGE\n", severity).into_bytes();
+                packet.extend_from_slice(&(message.len() as u64).to_le_bytes());
+                packet.extend_from_slice(message.as_bytes());
+                packet.push(b'\n');
+                packet
+            }
+        };
+        // Nothing to report a lost line to but the log itself
+        let _ = self.socket.send(&packet);
+    }
+}
+
+/// One log line bound for `Syslog`, at a syslog severity
+pub struct SyslogLine<'a> {
+    syslog: &'a Syslog,
+    severity: u8,
+}
+
+impl Write for SyslogLine<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.syslog.send(self.severity, String::from_utf8_lossy(buf).trim_end());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Syslog {
+    type Writer = SyslogLine<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SyslogLine { syslog: self, severity: 6 }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        let severity = match *meta.level() {
+            tracing::Level::ERROR => 3,
+            tracing::Level::WARN => 4,
+            tracing::Level::INFO => 6,
+            _ => 7,
+        };
+        SyslogLine { syslog: self, severity }
+    }
+}
+
+/// The installed log filter, changed by `PUT /api/log`
+pub struct LogControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    filter: Mutex<String>,
+    sinks: Vec<String>,
+}
+
+impl LogControl {
+    pub fn config(&self) -> LogConfig {
+        LogConfig { filter: self.filter.lock().unwrap().clone(), sinks: self.sinks.clone() }
+    }
+
+    /// Replace the filter with `RUST_LOG`-style directives
+    pub fn set_filter(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.handle.reload(filter).map_err(|e| e.to_string())?;
+        *self.filter.lock().unwrap() = directives.to_string();
+        Ok(())
+    }
+}
+
+/// Parse an unsigned environment variable, with a default
+fn env_number<T: std::str::FromStr>(name: &str, default: T, problems: &mut Vec<String>) -> T {
+    match std::env::var(name) {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            problems.push(format!("Invalid {} '{}'", name, value));
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Install the log subscriber with the outputs the environment selects
+pub fn init() -> Arc<LogControl> {
+    let mut problems = Vec::new();
+    let directives = std::env::var("RUST_LOG").ok().filter(|d| !d.is_empty() && EnvFilter::try_new(d).is_ok());
+    let directives = directives.unwrap_or_else(|| DEFAULT_FILTER.to_string());
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(&directives));
+    let mut sinks = Vec::new();
+
+    let stdout = std::env::var("SUMP_LOG_STDOUT").map_or(true, |v| v != "0" && v != "false");
+    if stdout {
+        sinks.push("stdout".to_string());
+    }
+
+    let file = std::env::var("SUMP_LOG_FILE").ok().filter(|path| !path.is_empty()).and_then(|path| {
+        let period = match std::env::var("SUMP_LOG_ROTATE").as_deref() {
+            Ok("hourly") => Some(3600),
+            Ok("daily") => Some(86400),
+            Ok("never") | Err(_) => None,
+            Ok(other) => {
+                problems.push(format!("Invalid SUMP_LOG_ROTATE '{}' (expected 'hourly', 'daily' or 'never')", other));
+                None
+            }
+        };
+        let rotation = Rotation {
+            max_bytes: env_number("SUMP_LOG_MAX_MB", DEFAULT_MAX_MB, &mut problems) << 20,
+            period,
+            keep: env_number("SUMP_LOG_KEEP", DEFAULT_KEEP, &mut problems),
+        };
+        match RotatingFile::open(Path::new(&path), rotation) {
+            Ok(file) => {
+                sinks.push(format!("file:{}", path));
+                Some(file)
+            }
+            Err(e) => {
+                problems.push(format!("Failed to open log file {}: {}", path, e));
+                None
+            }
+        }
+    });
+
+    let syslog = std::env::var("SUMP_LOG_SYSLOG").ok().and_then(|spec| {
+        let protocol = match spec.as_str() {
+            "syslog" | "1" => SyslogProtocol::Syslog,
+            "journald" => SyslogProtocol::Journald,
+            _ => {
+                problems.push(format!("Invalid SUMP_LOG_SYSLOG '{}' (expected 'syslog' or 'journald')", spec));
+                return None;
+            }
+        };
+        match Syslog::connect(protocol) {
+            Ok(syslog) => {
+                sinks.push(spec);
+                Some(syslog)
+            }
+            Err(e) => {
+                problems.push(format!("Failed to connect to {}: {}", protocol.socket(), e));
+                None
+            }
+        }
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stdout.then(|| tracing_subscriber::fmt::layer().with_target(false)))
+        .with(file.map(|file| {
+            tracing_subscriber::fmt::layer().with_target(false).with_ansi(false).with_writer(Mutex::new(file))
+        }))
+        .with(syslog.map(|syslog| {
+            // The system log adds its own timestamp and severity
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_ansi(false)
+                .without_time()
+                .with_level(false)
+                .with_writer(syslog)
+        }))
+        .init();
+
+    if std::env::var("RUST_LOG").is_ok_and(|d| !d.is_empty() && d != directives) {
+        problems.push(format!("Invalid RUST_LOG, using '{}'", directives));
+    }
+    for problem in problems {
+        tracing::error!("{}", problem);
+    }
+    Arc::new(LogControl { handle, filter: Mutex::new(directives), sinks })
+}
+
+/// GET /api/log - Current log filter and outputs
+async fn get_log(State(control): State<Arc<LogControl>>) -> Json<LogConfig> {
+    Json(control.config())
+}
+
+/// PUT /api/log - Change the log filter
+async fn put_log(
+    State(control): State<Arc<LogControl>>,
+    Json(config): Json<LogConfig>,
+) -> Result<Json<LogConfig>, Invalid> {
+    control
+        .set_filter(&config.filter)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid log filter '{}': {}", config.filter, e)))?;
+    tracing::info!("Log filter set to '{}'", config.filter);
+    Ok(Json(control.config()))
+}
+
+/// Create the `/api/log` router; it touches no hardware
+pub fn log_router(control: Arc<LogControl>) -> Router {
+    Router::new()
+        .route("/", get(get_log).put(put_log))
+        .with_state(control)
+}
@@ -1,7 +1,9 @@
 //! SUMP3 ILA Web Server
 //!
 //! Standalone HTTP server for the SUMP3 Integrated Logic Analyzer.
-//! Provides a REST API and serves the embedded Surfer WASM frontend.
+//! Provides a REST API and serves the embedded Surfer WASM frontend. The
+//! last completed capture is at `/waveforms/latest.vcd`; open it in Surfer
+//! with `/?load_url=/waveforms/latest.vcd`.
 //!
 //! ## Build-time Configuration
 //! - `SUMP_PORT`: HTTP server port (default: 8082)
@@ -10,27 +12,169 @@
 //!
 //! ## Runtime Configuration
 //! - `PORT`: Override server port at runtime
+//! - `SUMP_BIND`: Address the server listens on (default: 0.0.0.0); e.g.
+//!   `127.0.0.1` to keep the full API local to the board
+//! - `SUMP_READONLY_ADDR`: Also serve the API read-only on this `host:port`
+//!   (GET requests and decodes only; see `readonly`). The bd_server, text and
+//!   gRPC listeners have no read-only mode and stay on `SUMP_BIND`; they
+//!   refuse to start while it is all interfaces
 //! - `SUMP_AXI_ADDR`: Override AXI address at runtime
+//! - `SUMP_ILA_SIZE`: Register window size in bytes, hex or decimal (default:
+//!   0x100, or the window a `sump-agent` reports), for wrapper variants with
+//!   registers above 0x100; `/api/ila/reg` accepts offsets below it
+//! - `SUMP_DESIGN_ID`: FPGA design identification reported in `/api/ila` and
+//!   every capture: a bitstream id, or `reg:0xOFFSET` to read a design/version
+//!   register of the wrapper
+//! - `SUMP_BACKEND`: Register backend: `devmem` (default),
+//!   `tcp://host:port` to run off-target against a `sump-agent`, or
+//!   `xvc://host:port[?ir=0x02&ir_len=6&tck_ns=100]` for JTAG via an XVC server, or
+//!   `uart:///dev/ttyUSB0[?baud=921600&ctrl=0x98]` for MesaBus over a UART, or
+//!   `replay:///path/transcript.txt[?realtime=1]` to answer from a recorded
+//!   register transcript instead of hardware
+//! - `SUMP_RECORD`: Record every register access of the backend, with its
+//!   timing, to this transcript file (replaced on each connect); record with
+//!   `SUMP_WATCHDOG=off` and `SUMP_STATUS_POLL_MS=0` for a replayable one
+//! - `SUMP_WATCHDOG`: Stuck-ILA watchdog mode: `off`, `report` (default) or `recover`
+//! - `SUMP_WATCHDOG_INTERVAL_MS`: Watchdog probe interval (default: 1000)
+//! - `SUMP_BREAKER_TIMEOUTS`: Consecutive command timeouts that make hardware
+//!   endpoints fail fast with 503 (default: 3, `0` disables the breaker)
+//! - `SUMP_BREAKER_PROBE_MS`: Probe interval while the breaker is open (default: 1000)
+//! - `SUMP_STATUS_POLL_MS`: Background capture status poll interval served by
+//!   `GET /api/ila/status` (default: 250, 0 = read on every request)
+//! - `SUMP_CMD_RETRIES`: Retries of a failed read command (default: 2, 0 = off)
+//! - `SUMP_CMD_RETRY_BACKOFF_US`: Delay before the first retry, doubled after
+//!   each one (default: 200)
+//! - `SUMP_CMD_RETRY_WRITES`: Set to `1` to also retry writes and state commands
+//! - `SUMP_REQUEST_TIMEOUT_MS`: Deadline for register/state requests (default: 10000)
+//! - `SUMP_READOUT_TIMEOUT_MS`: Deadline for pod RAM readouts (default: 60000)
+//! - `SUMP_RATE_LIMIT`: Hardware requests per second per client IP (default: 20, 0 = off)
+//! - `SUMP_RATE_BURST`: Back-to-back requests allowed per client (default: 2x the rate)
+//! - `SUMP_MAX_CONCURRENT`: Hardware requests in flight (default: 4, 0 = off);
+//!   requests over either limit get 429 with `Retry-After`
+//! - `SUMP_BD_PORT`: Enable the bd_server protocol for sump3.py on this TCP
+//!   port (upstream default: 21567), on the `SUMP_BIND` address; it has no
+//!   authentication and drives the core like the full API
+//! - `SUMP_BD_CTRL_ADDR`: Core control register address seen by sump3.py (default: 0x98)
+//! - `SUMP_STATE_DIR`: Directory for persisted state (default: /var/lib/sump-server)
+//! - `SUMP_ARCHIVE_DIR`: Keep every completed capture of the visible pods in
+//!   this directory, zstd-compressed, listed by `GET /api/ila/archive` (see
+//!   `archive`)
+//! - `SUMP_ARCHIVE_KEEP`: Archived pod captures kept before the oldest are
+//!   deleted (default: 256)
+//! - `SUMP_ARCHIVE_ANALYSIS`: JSON file of per-pod analysis pipelines run on
+//!   each archived capture, reported in the archive listing (see `archive`)
+//! - `SUMP_ARCHIVE_HDF5`: Set to `1` to also write each archived capture as
+//!   `<id>.h5` (requires the `hdf5` cargo feature; see `hdf5`)
+//! - `SUMP_ARM_ON_BOOT`: Set to `1` to restore the last trigger configuration
+//!   and arm as soon as the hardware is detected
+//! - `SUMP_INIT_ON_BOOT`: Set to `1` to run INIT (pod RAM init) once at startup
+//!   after the hardware enumerates; `GET /readyz` answers 503 until it's done
+//! - `SUMP_EXT_TRIG_GPIO`: sysfs GPIO line (`<n>` or `<n>:active_low`) wired to an
+//!   external trigger input, pulsed by `POST /api/ila/ext-trigger`
+//! - `SUMP_EXT_TRIG_PULSE_US`: Default pulse width (default: 100)
+//! - `SUMP_DEEP_ADDR`: Register address (hex) of a `sump3_deep_sink` for
+//!   DDR-backed deep captures under `/api/ila/deep`
+//! - `SUMP_SPILL_MB`: Memory a deep-capture decode holds before spilling
+//!   samples to disk (default: 64; see `spill`)
+//! - `SUMP_SPILL_DIR`: Directory for spill files (default: the system temp dir)
+//! - `SUMP_POD_READOUT`: Pods whose RAM is mirrored into the AXI address space,
+//!   `hub.pod=0xADDR,...`; read with block copies instead of the serial bus
+//! - `SUMP_TEXT_PORT`: Serve the line-based text control protocol (`ARM`,
+//!   `STAT?`, `TRIG ...`, `CAP? ...`) on this TCP port of the `SUMP_BIND`
+//!   address, without authentication
+//! - `SUMP_GRPC_PORT`: Serve the gRPC interface of `proto/sump.proto` on this
+//!   port of the `SUMP_BIND` address, without authentication (requires the
+//!   `grpc` cargo feature)
+//! - `SUMP_PLUGIN_DIR`: Load `*.wasm` decoder and exporter plugins from this
+//!   directory (requires the `plugins` cargo feature; see `plugins`)
+//! - `SUMP_HOOKS`: JSON file of local commands to run when a capture triggers
+//!   or completes (see `hooks`)
+//! - `SUMP_ALERTS`: JSON file of alert rules checked on each capture, with
+//!   webhooks and an MQTT topic to notify (see `alerts`)
+//! - `SUMP_CONSOLE`: Set to `1` to serve the raw command console WebSocket
+//!   `/api/ila/console` (see `console`); it has no authentication, so only
+//!   on a trusted network
+//! - `SUMP_FRONTEND`: Frontend served at `/`: `surfer` (default), `dashboard`
+//!   (built-in status page) or a `SUMP_FRONTENDS` name; all are also under
+//!   `/ui/<name>/` (see `frontend`)
+//! - `SUMP_FRONTENDS`: Extra frontend bundles served from disk,
+//!   `name=/path/to/dist,...`
+//! - `SUMP_UI_CONFIG`: JSON file of embedded frontend settings (theme, default
+//!   pod, signal groups) served by `GET /api/ui-config` (see `ui_config`)
+//! - `RUST_LOG`: Initial log filter (default: `sump_server=info,tower_http=info`);
+//!   `PUT /api/log` changes it at runtime
+//! - `SUMP_LOG_STDOUT`: Set to `0` to stop logging to stdout
+//! - `SUMP_LOG_FILE`: Also log to this file, rotated to `<file>.1`... by
+//!   `SUMP_LOG_MAX_MB` (default: 10, 0 = no size limit) and `SUMP_LOG_ROTATE`
+//!   (`hourly`, `daily` or `never`, the default), keeping `SUMP_LOG_KEEP`
+//!   rotated files (default: 5; see `logging`)
+//! - `SUMP_LOG_SYSLOG`: Also log to `syslog` (`/dev/log`) or `journald`
+//! - `SUMP_FLEET`: Fleet proxy mode, `name=url,name=url,...`: open no local
+//!   hardware and front the listed sump-server instances under `/api/boards`
+//!
+//! ## Signals
+//! - `SIGUSR1`/`SIGUSR2`: Snapshot every visible pod now and keep it under
+//!   `/waveforms` and in the archive, if configured (see `snapshot`)
 
-mod devmem;
+mod alerts;
+mod analysis;
+mod archive;
+mod arm_timeout;
+mod audit;
+mod batch;
+mod benchmark;
+mod bd_server;
+mod breaker;
+mod capabilities;
+mod console;
+mod decode;
+mod deep;
+mod degraded;
+mod digest;
+mod export;
+mod ext_trigger;
+mod fleet;
+mod frontend;
+mod history;
+mod hooks;
+mod logging;
+mod masks;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "hdf5")]
+mod hdf5;
+mod npy;
+mod numbering;
 mod ila;
+mod ops;
+mod persist;
+mod plugins;
+mod range;
+mod ratelimit;
+mod readonly;
+mod readiness;
+mod schema;
+mod selftest;
+mod sequence;
+mod settings;
+mod snapshot;
+mod spill;
+mod stats;
+mod status;
+mod tcp;
+mod text_control;
+mod timeout;
+mod ui_config;
+mod validate;
+mod watch;
+mod watchdog;
+mod waveform;
 
-use axum::{
-    body::Body,
-    http::{header, StatusCode, Uri},
-    response::{IntoResponse, Response},
-    Router,
-};
-use rust_embed::Embed;
-use std::net::SocketAddr;
+use axum::Router;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-
-/// Embedded Surfer WASM frontend files (built by trunk during cargo build)
-#[derive(Embed)]
-#[folder = "../../surfer/surfer/dist/"]
-struct Assets;
 
 /// Default port (set at compile time via build.rs)
 const DEFAULT_PORT: u16 = {
@@ -56,52 +200,33 @@ const DEFAULT_AXI_ADDR: &str = match option_env!("SUMP_DEFAULT_AXI_ADDR") {
     None => "0x43C20000",
 };
 
-/// Serve embedded static files
-async fn serve_static(uri: Uri) -> impl IntoResponse {
-    let path = uri.path().trim_start_matches('/');
-    
-    // Default to index.html for root or missing files (SPA routing)
-    let path = if path.is_empty() { "index.html" } else { path };
-    
-    match Assets::get(path) {
-        Some(content) => {
-            let mime = mime_guess::from_path(path).first_or_octet_stream();
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, mime.as_ref())
-                .body(Body::from(content.data.into_owned()))
-                .unwrap()
-        }
-        None => {
-            // For SPA routing, serve index.html for unknown paths
-            match Assets::get("index.html") {
-                Some(content) => Response::builder()
-                    .status(StatusCode::OK)
-                    .header(header::CONTENT_TYPE, "text/html")
-                    .body(Body::from(content.data.into_owned()))
-                    .unwrap(),
-                None => Response::builder()
-                    .status(StatusCode::NOT_FOUND)
-                    .body(Body::from("Not Found"))
-                    .unwrap(),
-            }
-        }
-    }
-}
-
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "sump_server=info,tower_http=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer().with_target(false))
-        .init();
+    // Initialize logging (see `logging`)
+    let log_control = logging::init();
 
     tracing::info!("SUMP3 ILA Server starting...");
     tracing::info!("Build defaults: port={}, axi_addr={}", DEFAULT_PORT, DEFAULT_AXI_ADDR);
+    let frontends = Arc::new(frontend::Frontends::from_env());
+
+    // Parse port from environment or use compile-time default
+    let port: u16 = std::env::var("PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_PORT);
+    let bind_ip: IpAddr = match std::env::var("SUMP_BIND") {
+        Ok(ip) => ip.parse().unwrap_or_else(|e| {
+            tracing::error!("Invalid SUMP_BIND '{}': {}", ip, e);
+            std::process::exit(1);
+        }),
+        Err(_) => IpAddr::from([0, 0, 0, 0]),
+    };
+    let addr = SocketAddr::new(bind_ip, port);
+
+    if let Ok(spec) = std::env::var("SUMP_FLEET") {
+        run_fleet(&spec, addr, log_control, frontends).await;
+        return;
+    }
 
     // Parse AXI address (runtime override or build-time default)
     let axi_addr_str = std::env::var("SUMP_AXI_ADDR")
@@ -116,15 +241,277 @@ async fn main() {
     
     tracing::info!("Using AXI address: 0x{:08X}", axi_addr);
 
-    // Initialize ILA state
-    let ila_state = match ila::IlaState::new(axi_addr) {
-        Ok(state) => Arc::new(state),
-        Err(e) => {
-            tracing::error!("Failed to initialize ILA at 0x{:08X}: {}", axi_addr, e);
-            tracing::error!("Make sure you have permission to access /dev/mem (run as root)");
+    let window = std::env::var("SUMP_ILA_SIZE").ok().map(|size| {
+        parse_window_size(&size).unwrap_or_else(|e| {
+            tracing::error!("Invalid SUMP_ILA_SIZE '{}': {}", size, e);
             std::process::exit(1);
+        })
+    });
+
+    let state_dir = std::env::var("SUMP_STATE_DIR")
+        .unwrap_or_else(|_| persist::DEFAULT_STATE_DIR.to_string());
+
+    // Open the register backend; without it, start degraded and keep retrying
+    let backend = std::env::var("SUMP_BACKEND").unwrap_or_else(|_| "devmem".to_string());
+    let record = std::env::var("SUMP_RECORD").ok().filter(|path| !path.is_empty());
+    let open = move || -> Result<degraded::Opened, String> {
+        let (mut opened, size) = open_backend(&backend, axi_addr, window)?;
+        if let Some(path) = &record {
+            let recording = sump_driver::record::RecordingBackend::create(opened, path)
+                .map_err(|e| format!("Failed to create register transcript {}: {}", path, e))?;
+            opened = Box::new(recording);
+        }
+        degraded::verify(opened.as_mut())?;
+        Ok((opened, size))
+    };
+    let (ila, unavailable) = match open() {
+        Ok((opened, size)) => (sump_driver::Ila::with_backend(opened, axi_addr).with_window_size(size), None),
+        Err(e) => {
+            tracing::error!("{}; starting without hardware, retrying in the background", e);
+            let placeholder = sump_driver::backend::Disconnected(e.clone());
+            (sump_driver::Ila::with_backend(Box::new(placeholder), axi_addr), Some(e))
         }
     };
+    tracing::info!("Register backend: {}", ila.backend());
+    if let Ok(spec) = std::env::var("SUMP_DESIGN_ID") {
+        match sump_driver::DesignId::parse(&spec) {
+            Ok(design_id) => ila.set_design_id(Some(design_id)),
+            Err(e) => tracing::error!("SUMP_DESIGN_ID: {}", e),
+        }
+    }
+
+    let defaults = sump_driver::RetryPolicy::default();
+    ila.set_retry_policy(sump_driver::RetryPolicy {
+        retries: std::env::var("SUMP_CMD_RETRIES").ok().and_then(|n| n.parse().ok()).unwrap_or(defaults.retries),
+        backoff: std::env::var("SUMP_CMD_RETRY_BACKOFF_US").ok().and_then(|us| us.parse().ok())
+            .map_or(defaults.backoff, std::time::Duration::from_micros),
+        retry_writes: std::env::var("SUMP_CMD_RETRY_WRITES").is_ok_and(|v| v == "1" || v == "true"),
+    });
+
+    if let Ok(spec) = std::env::var("SUMP_POD_READOUT") {
+        match sump_driver::readout::parse_readout_map(&spec) {
+            Ok(pods) => {
+                for (hub, pod, addr) in pods {
+                    match ila.map_pod_ram(hub, pod, addr) {
+                        Ok(()) => tracing::info!("Hub {} pod {}: RAM read through mirror at 0x{:08X}", hub, pod, addr),
+                        Err(e) => tracing::error!("Hub {} pod {}: cannot map RAM mirror: {}", hub, pod, e),
+                    }
+                }
+            }
+            Err(e) => tracing::error!("SUMP_POD_READOUT: {}", e),
+        }
+    }
+
+    numbering::restore(&ila, state_dir.clone().into());
+
+    // Initialize ILA state
+    let mut ila_state = ila::IlaState::new(ila, state_dir.into());
+    if let Some(reason) = unavailable.clone() {
+        ila_state = ila_state.with_degraded(reason);
+    }
+    if let Ok(spec) = std::env::var("SUMP_EXT_TRIG_GPIO") {
+        let pulse = std::time::Duration::from_micros(
+            std::env::var("SUMP_EXT_TRIG_PULSE_US")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(ext_trigger::DEFAULT_PULSE_US),
+        );
+        match ext_trigger::ExtTriggerGpio::open(&spec, pulse) {
+            Ok(gpio) => {
+                tracing::info!("External trigger on {}", gpio.describe());
+                ila_state = ila_state.with_ext_trigger_gpio(gpio);
+            }
+            Err(e) => tracing::error!("SUMP_EXT_TRIG_GPIO: {}", e),
+        }
+    }
+    if let Ok(addr) = std::env::var("SUMP_DEEP_ADDR") {
+        let addr = usize::from_str_radix(addr.trim_start_matches("0x").trim_start_matches("0X"), 16);
+        match addr.map_err(|e| e.to_string())
+            .and_then(|addr| sump_driver::deep::DeepSink::open(addr).map_err(|e| e.to_string()))
+        {
+            Ok(deep) => {
+                tracing::info!("Deep-capture sink at 0x{:08X}", deep.regs_addr());
+                ila_state = ila_state.with_deep_sink(deep);
+            }
+            Err(e) => tracing::error!("SUMP_DEEP_ADDR: {}", e),
+        }
+    }
+    let defaults = spill::SpillConfig::default();
+    ila_state = ila_state.with_spill(spill::SpillConfig {
+        dir: std::env::var_os("SUMP_SPILL_DIR").map_or(defaults.dir, PathBuf::from),
+        threshold: std::env::var("SUMP_SPILL_MB").ok().and_then(|mb| mb.parse::<usize>().ok())
+            .map_or(defaults.threshold, |mb| mb << 20),
+    });
+    if let Ok(dir) = std::env::var("SUMP_PLUGIN_DIR") {
+        #[cfg(feature = "plugins")]
+        {
+            ila_state = ila_state.with_plugins(plugins::load(std::path::Path::new(&dir)));
+        }
+        #[cfg(not(feature = "plugins"))]
+        tracing::warn!("SUMP_PLUGIN_DIR={} ignored: built without the `plugins` feature", dir);
+    }
+    if let Ok(path) = std::env::var("SUMP_UI_CONFIG") {
+        match ui_config::load(std::path::Path::new(&path)) {
+            Ok(config) => ila_state = ila_state.with_ui_config(config),
+            Err(e) => tracing::error!("SUMP_UI_CONFIG {}: {}", path, e),
+        }
+    }
+    let mut alert_deliveries = None;
+    if let Ok(path) = std::env::var("SUMP_ALERTS") {
+        match alerts::load(std::path::Path::new(&path)) {
+            Ok(config) => {
+                tracing::info!("{} alert rules from {}", config.rule_count(), path);
+                let (alerts, events) = alerts::Alerts::new(&config);
+                ila_state = ila_state.with_alerts(alerts);
+                alert_deliveries = Some((config, events));
+            }
+            Err(e) => tracing::error!("SUMP_ALERTS {}: {}", path, e),
+        }
+    }
+    ila_state = ila_state.with_breaker(breaker::Breaker::new(
+        std::env::var("SUMP_BREAKER_TIMEOUTS").ok().and_then(|n| n.parse().ok()).unwrap_or(breaker::DEFAULT_THRESHOLD),
+        env_millis("SUMP_BREAKER_PROBE_MS", breaker::DEFAULT_PROBE_MS),
+    ));
+    if let Ok(dir) = std::env::var("SUMP_ARCHIVE_DIR") {
+        let keep = std::env::var("SUMP_ARCHIVE_KEEP").ok().and_then(|n| n.parse().ok()).unwrap_or(archive::DEFAULT_KEEP);
+        let analyses = match std::env::var("SUMP_ARCHIVE_ANALYSIS") {
+            Ok(path) => archive::load_analysis(std::path::Path::new(&path)).unwrap_or_else(|e| {
+                tracing::error!("SUMP_ARCHIVE_ANALYSIS {}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        let hdf5 = std::env::var("SUMP_ARCHIVE_HDF5").is_ok_and(|v| v == "1" || v == "true");
+        #[cfg(not(feature = "hdf5"))]
+        if hdf5 {
+            tracing::warn!("SUMP_ARCHIVE_HDF5 ignored: built without the `hdf5` feature");
+        }
+        match archive::Archive::open(PathBuf::from(&dir), keep) {
+            Ok(archive) => {
+                let archive = archive.with_analyses(analyses).with_hdf5(hdf5 && cfg!(feature = "hdf5"));
+                tracing::info!("Archiving captures to {}", archive.describe());
+                ila_state = ila_state.with_archive(archive);
+            }
+            Err(e) => tracing::error!("SUMP_ARCHIVE_DIR {}: {}", dir, e),
+        }
+    }
+    if std::env::var("SUMP_CONSOLE").is_ok_and(|v| v == "1" || v == "true") {
+        tracing::warn!("Command console enabled at /api/ila/console");
+        ila_state = ila_state.with_console();
+    }
+    let ila_state = Arc::new(ila_state);
+    if let Some((config, events)) = alert_deliveries {
+        tokio::spawn(alerts::deliver(config, events));
+        tokio::spawn(alerts::watch(ila_state.clone()));
+    }
+
+    let arm_on_boot = std::env::var("SUMP_ARM_ON_BOOT").is_ok_and(|v| v == "1" || v == "true");
+    let init_on_boot = std::env::var("SUMP_INIT_ON_BOOT").is_ok_and(|v| v == "1" || v == "true");
+    let boot = boot(ila_state.clone(), init_on_boot, arm_on_boot);
+    if unavailable.is_some() {
+        tokio::spawn(degraded::reconnect(ila_state.clone(), open, boot));
+    } else {
+        // In the background so /readyz answers while INIT runs
+        tokio::spawn(boot);
+    }
+
+    // Start the hardware watchdog
+    let watchdog_mode = match std::env::var("SUMP_WATCHDOG") {
+        Ok(s) => watchdog::WatchdogMode::parse(&s).unwrap_or_else(|| {
+            tracing::warn!("Unknown SUMP_WATCHDOG mode '{}', using 'report'", s);
+            watchdog::WatchdogMode::Report
+        }),
+        Err(_) => watchdog::WatchdogMode::Report,
+    };
+    tokio::spawn(watchdog::run(
+        ila_state.clone(),
+        watchdog_mode,
+        env_millis("SUMP_WATCHDOG_INTERVAL_MS", 1000),
+    ));
+
+    // Fail fast while the bus is wedged
+    tokio::spawn(breaker::run(ila_state.clone()));
+
+    // Local commands on capture events
+    if let Ok(path) = std::env::var("SUMP_HOOKS") {
+        match hooks::load(std::path::Path::new(&path)) {
+            Ok(hooks) => {
+                let local = match bind_ip {
+                    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::from([127, 0, 0, 1]),
+                    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::from(std::net::Ipv6Addr::LOCALHOST),
+                    ip => ip,
+                };
+                tracing::info!("{} capture hooks from {}", hooks.len(), path);
+                tokio::spawn(hooks::run(ila_state.clone(), hooks, format!("http://{}", SocketAddr::new(local, port))));
+            }
+            Err(e) => tracing::error!("SUMP_HOOKS {}: {}", path, e),
+        }
+    }
+
+    // Completed captures kept on disk
+    if ila_state.archive.is_some() {
+        tokio::spawn(archive::run(ila_state.clone()));
+    }
+
+    // Snapshots requested by other processes on the board
+    tokio::spawn(snapshot::on_signals(ila_state.clone()));
+
+    // Cached status for UI polling
+    tokio::spawn(status::run(
+        ila_state.clone(),
+        env_millis("SUMP_STATUS_POLL_MS", status::DEFAULT_POLL_MS),
+    ));
+
+    // Optional bd_server listener for the upstream sump3.py GUI
+    if let Some(port) = std::env::var("SUMP_BD_PORT").ok().and_then(|p| p.parse().ok()) {
+        let ctrl_addr = std::env::var("SUMP_BD_CTRL_ADDR")
+            .ok()
+            .and_then(|a| u32::from_str_radix(a.trim_start_matches("0x").trim_start_matches("0X"), 16).ok())
+            .unwrap_or(sump_driver::local_bus::DEFAULT_CTRL_ADDR);
+        if let Some(addr) = protocol_addr("SUMP_BD_PORT", bind_ip, port) {
+            tokio::spawn(bd_server::run(ila_state.clone(), addr, ctrl_addr));
+        }
+    }
+
+    // Optional line-based control for test equipment without HTTP
+    if let Some(port) = std::env::var("SUMP_TEXT_PORT").ok().and_then(|p| p.parse().ok()) {
+        if let Some(addr) = protocol_addr("SUMP_TEXT_PORT", bind_ip, port) {
+            tokio::spawn(text_control::run(ila_state.clone(), addr));
+        }
+    }
+
+    // Optional gRPC interface
+    if let Some(port) = std::env::var("SUMP_GRPC_PORT").ok().and_then(|p| p.parse::<u16>().ok()) {
+        #[cfg(feature = "grpc")]
+        if let Some(addr) = protocol_addr("SUMP_GRPC_PORT", bind_ip, port) {
+            tokio::spawn(grpc::run(ila_state.clone(), addr));
+        }
+        #[cfg(not(feature = "grpc"))]
+        tracing::warn!("SUMP_GRPC_PORT={} ignored: built without the `grpc` feature", port);
+    }
+
+    // Request deadlines (504 when a hardware transaction hangs)
+    let timeouts = timeout::Timeouts {
+        request: env_millis("SUMP_REQUEST_TIMEOUT_MS", timeout::DEFAULT_REQUEST_TIMEOUT_MS),
+        readout: env_millis("SUMP_READOUT_TIMEOUT_MS", timeout::DEFAULT_READOUT_TIMEOUT_MS),
+    };
+
+    // Per-client rate and global concurrency limits (429 when exceeded)
+    let rate_per_s = std::env::var("SUMP_RATE_LIMIT")
+        .ok()
+        .and_then(|r| r.parse().ok())
+        .unwrap_or(ratelimit::DEFAULT_RATE_PER_S);
+    let limiter = Arc::new(ratelimit::Limiter::new(ratelimit::Limits {
+        rate_per_s,
+        burst: std::env::var("SUMP_RATE_BURST")
+            .ok()
+            .and_then(|b| b.parse().ok())
+            .unwrap_or(rate_per_s * 2.0),
+        max_concurrent: std::env::var("SUMP_MAX_CONCURRENT")
+            .ok()
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(ratelimit::DEFAULT_MAX_CONCURRENT),
+    }));
 
     // CORS configuration for development (allows any origin)
     // Useful when running surfer locally against a remote sump-server
@@ -135,38 +522,233 @@ async fn main() {
 
     // Build the application router
     let app = Router::new()
-        .nest("/api/ila", ila::ila_router(ila_state))
-        // Serve embedded static files as fallback
-        .fallback(serve_static)
+        .nest("/api/ila", ila::ila_router(ila_state.clone(), &timeouts, limiter))
+        .nest("/waveforms", waveform::waveform_router(ila_state.clone(), timeouts.readout))
+        .nest("/api/settings", settings::settings_router(ila_state.clone()))
+        .nest("/api/ui-config", ui_config::ui_config_router(ila_state.clone()))
+        .nest("/api/capabilities", capabilities::capabilities_router(ila_state.clone()))
+        .nest("/api/schema", schema::schema_router())
+        .nest("/api/log", logging::log_router(log_control))
+        .merge(stats::metrics_router(ila_state.clone()))
+        .merge(readiness::readiness_router(ila_state.clone()))
+        // Serve the frontends as fallback (see `frontend`)
+        .fallback_service(frontend::frontend_router(frontends))
         .layer(cors);
 
-    // Parse port from environment or use compile-time default
-    let port: u16 = std::env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(DEFAULT_PORT);
+    // Optional read-only listener (see `readonly`), stopped with the main one
+    if let Ok(spec) = std::env::var("SUMP_READONLY_ADDR") {
+        match spec.parse::<SocketAddr>() {
+            Ok(readonly_addr) => {
+                let listener = bind(readonly_addr).await;
+                let readonly_app = app.clone().layer(axum::middleware::from_fn(readonly::read_only));
+                let mut stopped = ila_state.shutdown_signal();
+                tokio::spawn(async move {
+                    axum::serve(listener, readonly_app.into_make_service_with_connect_info::<SocketAddr>())
+                        .with_graceful_shutdown(async move {
+                            let _ = stopped.wait_for(|stop| *stop).await;
+                        })
+                        .await
+                        .unwrap();
+                });
+            }
+            Err(e) => tracing::error!("Invalid SUMP_READONLY_ADDR '{}': {}", spec, e),
+        }
+    }
+
+    let listener = bind(addr).await;
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    // Run server with graceful shutdown: stop background tasks and let
+    // in-flight readouts and trigger programming finish before disarming
+    let shutdown_state = ila_state.clone();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            shutdown_state.begin_shutdown();
+        })
+        .await
+        .unwrap();
+
+    ila_state.quiesce();
+    tracing::info!("Server shutdown complete");
+}
+
+/// Bind an HTTP listener, exiting on failure
+async fn bind(addr: SocketAddr) -> tokio::net::TcpListener {
     tracing::info!("Listening on http://{}", addr);
 
-    // Create listener
-    let listener = match tokio::net::TcpListener::bind(addr).await {
+    match tokio::net::TcpListener::bind(addr).await {
         Ok(l) => l,
         Err(e) => {
             tracing::error!("Failed to bind to {}: {}", addr, e);
             std::process::exit(1);
         }
+    }
+}
+
+/// Address of a bd_server, text or gRPC listener on `port`: the main
+/// listener's. These protocols have no read-only mode, so with a read-only
+/// split (`SUMP_READONLY_ADDR`) they only start on a specific `SUMP_BIND`
+/// address, never on all interfaces next to the partner one.
+fn protocol_addr(variable: &str, bind_ip: IpAddr, port: u16) -> Option<SocketAddr> {
+    if bind_ip.is_unspecified() && std::env::var_os("SUMP_READONLY_ADDR").is_some() {
+        tracing::error!(
+            "{}={} ignored: with SUMP_READONLY_ADDR it would expose full control on all interfaces; set SUMP_BIND to the admin address",
+            variable, port
+        );
+        return None;
+    }
+    Some(SocketAddr::new(bind_ip, port))
+}
+
+/// Serve the fleet proxy instead of local hardware (`SUMP_FLEET`)
+async fn run_fleet(
+    spec: &str,
+    addr: SocketAddr,
+    log_control: Arc<logging::LogControl>,
+    frontends: Arc<frontend::Frontends>,
+) {
+    let fleet = match fleet::Fleet::parse(spec) {
+        Ok(fleet) => Arc::new(fleet),
+        Err(e) => {
+            tracing::error!("Invalid SUMP_FLEET: {}", e);
+            std::process::exit(1);
+        }
     };
+    tracing::info!("Fleet proxy mode: {} boards", fleet.len());
 
-    // Run server with graceful shutdown
+    let app = Router::new()
+        .nest("/api/boards", fleet::fleet_router(fleet))
+        .nest("/api/schema", schema::schema_router())
+        .nest("/api/log", logging::log_router(log_control))
+        .fallback_service(frontend::frontend_router(frontends))
+        .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any));
+
+    let listener = bind(addr).await;
     axum::serve(listener, app)
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
-
     tracing::info!("Server shutdown complete");
 }
 
+/// Parse `SUMP_ILA_SIZE`: hex or decimal bytes, 32-bit aligned, at least `ILA_SIZE`
+fn parse_window_size(size: &str) -> Result<usize, String> {
+    let parsed = match size.strip_prefix("0x").or_else(|| size.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => size.parse(),
+    };
+    let size = parsed.map_err(|e| e.to_string())?;
+    if size < sump_driver::ILA_SIZE || !size.is_multiple_of(4) {
+        return Err(format!("expected a multiple of 4 of at least 0x{:X}", sump_driver::ILA_SIZE));
+    }
+    Ok(size)
+}
+
+/// RTL revision check, then startup INIT and ARM (`SUMP_INIT_ON_BOOT`,
+/// `SUMP_ARM_ON_BOOT`), once the hardware is there
+async fn boot(state: Arc<ila::IlaState>, init: bool, arm: bool) {
+    let info = state.run(|s| s.ila.info()).await;
+    if info.connected {
+        tracing::info!("SUMP3 wrapper RTL revision {}", info.revision);
+    }
+    for warning in &info.rtl_warnings {
+        tracing::warn!("{}", warning);
+    }
+    if let Some(reason) = &info.unreliable {
+        tracing::error!("Enumeration incomplete: {}", reason);
+    }
+    if init {
+        state.init_on_boot().await;
+    }
+    if arm {
+        state.run(|s| s.arm_on_boot()).await;
+    }
+}
+
+/// Open the register backend selected by `SUMP_BACKEND`, and its window
+/// size: `window` (`SUMP_ILA_SIZE`) when given
+fn open_backend(backend: &str, axi_addr: usize, window: Option<usize>) -> Result<degraded::Opened, String> {
+    use sump_driver::{
+        bridge::TcpBackend,
+        devmem::DevMem,
+        local_bus::{LocalBusBackend, DEFAULT_CTRL_ADDR},
+        record::ReplayBackend,
+        uart::{MesaUart, DEFAULT_BAUD},
+        xvc::{XvcBackend, XvcConfig},
+        ILA_SIZE,
+    };
+
+    if backend == "devmem" {
+        let size = window.unwrap_or(ILA_SIZE);
+        let mem = DevMem::new(axi_addr, size).map_err(|e| {
+            format!(
+                "Failed to map ILA at 0x{:08X}: {} (access to /dev/mem requires root)",
+                axi_addr, e
+            )
+        })?;
+        return Ok((Box::new(mem), size));
+    }
+    if let Some(agent) = backend.strip_prefix("tcp://") {
+        let tcp = TcpBackend::connect(agent)
+            .map_err(|e| format!("Failed to connect to sump-agent at {}: {}", agent, e))?;
+        // The agent maps the window; past its size every access is rejected
+        let size = window.unwrap_or(tcp.size());
+        if size > tcp.size() {
+            tracing::warn!("SUMP_ILA_SIZE 0x{:X} exceeds the 0x{:X}-byte window of sump-agent {}", size, tcp.size(), agent);
+        }
+        return Ok((Box::new(tcp), size));
+    }
+    if let Some(spec) = backend.strip_prefix("xvc://") {
+        let (server, query) = spec.split_once('?').unwrap_or((spec, ""));
+        let config = XvcConfig::parse_query(query)
+            .map_err(|e| format!("Invalid SUMP_BACKEND options: {}", e))?;
+        let xvc = XvcBackend::connect(server, config)
+            .map_err(|e| format!("Failed to connect to XVC server at {}: {}", server, e))?;
+        return Ok((Box::new(xvc), window.unwrap_or(ILA_SIZE)));
+    }
+    if let Some(spec) = backend.strip_prefix("uart://") {
+        let (tty, query) = spec.split_once('?').unwrap_or((spec, ""));
+        let (mut baud, mut ctrl_addr) = (DEFAULT_BAUD, DEFAULT_CTRL_ADDR);
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let parsed = match pair.split_once('=') {
+                Some(("baud", v)) => v.parse().ok().map(|v| baud = v),
+                Some(("ctrl", v)) => u32::from_str_radix(v.trim_start_matches("0x"), 16).ok().map(|v| ctrl_addr = v),
+                _ => None,
+            };
+            if parsed.is_none() {
+                return Err(format!("Invalid SUMP_BACKEND option '{}'", pair));
+            }
+        }
+        let uart = MesaUart::open(tty, baud)
+            .map_err(|e| format!("Failed to open MesaBus UART {}: {}", tty, e))?;
+        return Ok((Box::new(LocalBusBackend::new(uart, ctrl_addr)), window.unwrap_or(ILA_SIZE)));
+    }
+    if let Some(spec) = backend.strip_prefix("replay://") {
+        let (path, query) = spec.split_once('?').unwrap_or((spec, ""));
+        let realtime = match query {
+            "" | "realtime=0" => false,
+            "realtime=1" => true,
+            _ => return Err(format!("Invalid SUMP_BACKEND options '{}' (expected 'realtime=1')", query)),
+        };
+        let replay = ReplayBackend::open(path, realtime)
+            .map_err(|e| format!("Failed to load register transcript {}: {}", path, e))?;
+        return Ok((Box::new(replay), window.unwrap_or(ILA_SIZE)));
+    }
+    Err(format!(
+        "Unknown SUMP_BACKEND '{}' (expected 'devmem', 'tcp://', 'xvc://', 'uart://' or 'replay://')",
+        backend
+    ))
+}
+
+/// Parse a millisecond duration from the environment
+fn env_millis(name: &str, default_ms: u64) -> std::time::Duration {
+    let ms = std::env::var(name)
+        .ok()
+        .and_then(|ms| ms.parse().ok())
+        .unwrap_or(default_ms);
+    std::time::Duration::from_millis(ms)
+}
+
 /// Wait for shutdown signal (Ctrl+C or SIGTERM)
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -0,0 +1,162 @@
+//! Signal masks for partners
+//!
+//! `Settings.signal_masks` withholds signals, or every bit of a pod, from
+//! clients of the read-only listener (`SUMP_READONLY_ADDR`), the listener a
+//! shared board exposes to partners; the main listener is unaffected. A
+//! masked signal reads as 0 in every sample of captures, exports, decodes,
+//! raw dumps and digests; `redact` also leaves its name out of the
+//! enumeration, groups and aliases. Unlike `hidden_pods` the pod itself
+//! stays usable.
+//!
+//! What can't be masked is refused on the read-only listener while any mask
+//! is configured (`unmaskable`): kept waveforms of masked pods, deep capture
+//! data, the register watch and raw register reads.
+
+use axum::http::{Extensions, StatusCode};
+
+use sump_model::{CaptureData, CaptureDigest, IlaInfo, PodGroup, PodInfo, RawRamHeader, Settings, SignalMask};
+
+use crate::ila::IlaState;
+use crate::readonly::ReadOnlyListener;
+use crate::validate;
+
+/// Whether a request came in on the read-only listener, where masks apply
+pub fn partner(extensions: &Extensions) -> bool {
+    extensions.get::<ReadOnlyListener>().is_some()
+}
+
+/// Data bits and signal names of one pod withheld from partners
+#[derive(Debug, Clone, Default)]
+pub struct PodMask {
+    pub bits: u32,
+    redacted: Vec<String>,
+}
+
+impl PodMask {
+    /// Clear the masked bits of every sample
+    pub fn blank(&self, capture: &mut CaptureData) {
+        for sample in &mut capture.samples {
+            sample.data &= !self.bits;
+        }
+    }
+
+    /// Clear the masked bits of a raw dump's page 0; page 1 only holds
+    /// codes and timestamps
+    pub fn blank_dump(&self, header: &RawRamHeader, words: &mut [u32]) {
+        let depth = (header.ram_depth as usize).min(words.len());
+        for word in &mut words[..depth] {
+            *word &= !self.bits;
+        }
+    }
+
+    /// Leave redacted signals out of an enumeration
+    pub fn redact(&self, pod: &mut PodInfo) {
+        pod.signals.retain(|signal| !self.redacted.contains(&signal.name));
+    }
+
+    /// Drop the masked bits' activity; the hash and repeat flag cover
+    /// every bit, so they go too
+    pub fn blank_digest(&self, digest: &mut CaptureDigest) {
+        for (bit, toggles) in digest.toggles.iter_mut().enumerate() {
+            if self.bits & (1 << bit) != 0 {
+                *toggles = 0;
+            }
+        }
+        digest.active_bits &= !self.bits;
+        digest.activity_changed &= !self.bits;
+        digest.trigger_data = digest.trigger_data.map(|data| data & !self.bits);
+        digest.hash.clear();
+        digest.repeat = false;
+    }
+}
+
+/// Whether `signal_masks` names the pod
+pub fn masked(settings: &Settings, hub: u8, pod: u8) -> bool {
+    let key = format!("{}.{}", hub, pod);
+    settings.signal_masks.iter().any(|mask| mask.pod == key)
+}
+
+/// The pod's mask from its enumeration, if `signal_masks` names it
+pub fn pod_mask(settings: &Settings, hub: u8, pod: &PodInfo) -> Option<PodMask> {
+    let key = format!("{}.{}", hub, pod.index);
+    let mut result: Option<PodMask> = None;
+    for mask in settings.signal_masks.iter().filter(|mask| mask.pod == key) {
+        let result = result.get_or_insert_with(PodMask::default);
+        let whole_pod = mask.signals.is_empty();
+        if whole_pod {
+            result.bits = u32::MAX;
+        }
+        for signal in pod.signals.iter().filter(|s| whole_pod || mask.signals.contains(&s.name)) {
+            result.bits |= signal.mask();
+            if mask.redact {
+                result.redacted.push(signal.name.clone());
+            }
+        }
+    }
+    result
+}
+
+/// The mask for a request: none on the main listener or for pods without
+/// masks, so unmasked requests cost no enumeration
+pub fn for_request(s: &IlaState, partner: bool, hub: u8, pod: u8) -> Option<PodMask> {
+    if !partner {
+        return None;
+    }
+    let settings = s.settings.lock().unwrap().clone();
+    if !masked(&settings, hub, pod) {
+        return None;
+    }
+    pod_mask(&settings, hub, &s.ila.enumerate_pod(hub, pod))
+}
+
+/// Whether a group member (`"<hub>.<pod>/<signal>"`) is redacted
+fn redacted_member(masks: &[SignalMask], member: &str) -> bool {
+    let Some((pod, name)) = member.split_once('/') else {
+        return false;
+    };
+    masks.iter().any(|mask| mask.redact && mask.pod == pod && (mask.signals.is_empty() || mask.signals.iter().any(|s| s == name)))
+}
+
+fn redact_group(masks: &[SignalMask], group: &mut PodGroup) {
+    group.signals.retain(|member| !redacted_member(masks, member));
+    for child in &mut group.groups {
+        redact_group(masks, child);
+    }
+}
+
+/// Redact an enumeration for a partner
+pub fn redact_info(settings: &Settings, info: &mut IlaInfo) {
+    for hub in &mut info.hubs {
+        for pod in &mut hub.pods {
+            if let Some(mask) = pod_mask(settings, hub.index, pod) {
+                mask.redact(pod);
+            }
+        }
+    }
+    for group in &mut info.groups {
+        redact_group(&settings.signal_masks, group);
+    }
+}
+
+/// Settings as a partner sees them: no masks, and no redacted names in
+/// groups or aliases
+pub fn redact_settings(settings: &mut Settings) {
+    let masks = std::mem::take(&mut settings.signal_masks);
+    for group in &mut settings.groups {
+        redact_group(&masks, group);
+    }
+    let redacted: Vec<&String> = masks.iter()
+        .filter(|mask| mask.redact)
+        .flat_map(|mask| &mask.signals)
+        .collect();
+    settings.signal_aliases.retain(|name, _| !redacted.contains(&name));
+}
+
+/// 403 for a partner while any mask is configured; for endpoints whose data
+/// can't be masked
+pub fn unmaskable(s: &IlaState, partner: bool, what: &str) -> Result<(), validate::Invalid> {
+    if partner && !s.settings.lock().unwrap().signal_masks.is_empty() {
+        return Err((StatusCode::FORBIDDEN, format!("{} is not available on the read-only listener while signal masks are configured", what)));
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! NumPy export of analog fields
+//!
+//! `GET /api/ila/capture/:hub/:pod/npz` returns every `analog` signal of a
+//! pod as `<signal>.npy` arrays in an uncompressed `.npz`, together with
+//! `cycles.npy` (hub clock cycles since the first sample) and
+//! `sample_rate_hz.npy` (the hub clock, a 0-d float64). RLE pods record on
+//! change, so samples are not evenly spaced: use `cycles / sample_rate_hz`
+//! as the time axis. `GET .../npy?signal=NAME` returns one array, with the
+//! clock in the `x-sump-sample-rate-hz` header.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, Extensions, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use sump_export::npy::{analog_signals, npy, npz};
+use sump_model::{CaptureData, SignalInfo};
+
+use crate::digest;
+use crate::ila::IlaState;
+use crate::masks;
+use crate::validate;
+
+/// Read the whole capture with the pod's analog signals and hub clock
+async fn read_analog(
+    state: &Arc<IlaState>,
+    hub: u8,
+    pod: u8,
+    partner: bool,
+) -> Result<(CaptureData, Vec<SignalInfo>, u64), Response> {
+    state.run_op("readout", move |s| -> Result<_, validate::Invalid> {
+        validate::visible_pod(s, hub, pod)?;
+        let mask = masks::for_request(s, partner, hub, pod);
+        let mut info = s.ila.enumerate_pod(hub, pod);
+        if let Some(mask) = &mask {
+            mask.redact(&mut info);
+        }
+        let analog = analog_signals(&info);
+        if analog.is_empty() {
+            return Err((StatusCode::NOT_FOUND, format!("Hub {} pod {} has no analog fields in its low 32 bits", hub, pod)));
+        }
+        let mut capture = s.ila.read_capture_all(hub, pod);
+        if let Some(e) = &capture.readout_error {
+            return Err((StatusCode::BAD_GATEWAY, e.message.clone()));
+        }
+        digest::record(s, &capture);
+        if let Some(mask) = &mask {
+            mask.blank(&mut capture);
+        }
+        Ok((capture, analog, s.ila.hub_clock_hz(hub)))
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map_err(IntoResponse::into_response)
+}
+
+fn attachment(content_type: &'static str, filename: String, sample_rate_hz: u64, body: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            (header::HeaderName::from_static("x-sump-sample-rate-hz"), sample_rate_hz.to_string()),
+        ],
+        body,
+    ).into_response()
+}
+
+/// GET /api/ila/capture/:hub/:pod/npz - Analog fields, cycles and sample rate as .npz
+pub async fn get_capture_npz(
+    State(state): State<Arc<IlaState>>,
+    Path((hub, pod)): Path<(u8, u8)>,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    let (capture, analog, sample_rate_hz) = read_analog(&state, hub, pod, masks::partner(&extensions)).await?;
+
+    let filename = format!("capture{}_hub{}_pod{}_analog.npz", capture.sequence, hub, pod);
+    Ok(attachment("application/zip", filename, sample_rate_hz, npz(&capture, &analog, sample_rate_hz)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignalQuery {
+    signal: String,
+}
+
+/// GET /api/ila/capture/:hub/:pod/npy?signal=NAME - One analog field as .npy
+pub async fn get_capture_npy(
+    State(state): State<Arc<IlaState>>,
+    Path((hub, pod)): Path<(u8, u8)>,
+    Query(query): Query<SignalQuery>,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    let (capture, analog, sample_rate_hz) = read_analog(&state, hub, pod, masks::partner(&extensions)).await?;
+    let Some(signal) = analog.iter().find(|sig| sig.name == query.signal) else {
+        let names: Vec<&str> = analog.iter().map(|sig| sig.name.as_str()).collect();
+        let message = format!("No analog field '{}' (hub {} pod {} has {})", query.signal, hub, pod, names.join(", "));
+        return Err((StatusCode::NOT_FOUND, message).into_response());
+    };
+    let filename = format!("capture{}_{}.npy", capture.sequence, signal.name);
+    Ok(attachment("application/octet-stream", filename, sample_rate_hz, npy(&capture, signal)))
+}
@@ -0,0 +1,43 @@
+//! Capture sequence numbers across restarts
+//!
+//! Captures are numbered by `Ila::sequence`, which counts ARMs from a base.
+//! The base comes from `capture_sequence.json` in the state directory, so
+//! numbering continues after a restart. Numbers are reserved `BLOCK` at a
+//! time to spare the flash a write per ARM: a crash skips the rest of the
+//! block, but a number is never reused.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use sump_driver::Ila;
+
+use crate::persist;
+
+/// Sequence numbers reserved per write
+const BLOCK: u64 = 100;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Reserved {
+    /// Highest sequence number a capture may have used
+    reserved: u64,
+}
+
+/// Continue numbering from the saved reservation, and extend it as ARMs use it up
+pub fn restore(ila: &Ila, dir: PathBuf) {
+    let base = persist::load_json::<Reserved>(&dir, persist::CAPTURE_SEQUENCE_FILE).unwrap_or_default().reserved;
+    ila.set_sequence_base(base);
+    tracing::info!("Capture sequence continues after {}", base);
+
+    let reserved = AtomicU64::new(base);
+    ila.on_arm(move |sequence| {
+        if sequence <= reserved.load(Ordering::Relaxed) {
+            return;
+        }
+        let next = Reserved { reserved: sequence + BLOCK - 1 };
+        match persist::save_json(&dir, persist::CAPTURE_SEQUENCE_FILE, &next) {
+            Ok(()) => reserved.store(next.reserved, Ordering::Relaxed),
+            Err(e) => tracing::error!("Cannot save the capture sequence in {}: {}", dir.display(), e),
+        }
+    });
+}
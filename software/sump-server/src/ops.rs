@@ -0,0 +1,88 @@
+//! Logical operation exclusion
+//!
+//! The driver mutex serializes individual commands, but operations such as
+//! trigger programming or a RAM readout are sequences of commands that must
+//! not interleave. Each such operation holds the ILA for its whole duration;
+//! a second one is rejected with 409 Conflict and the current capture status
+//! instead of waiting or interleaving.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use std::sync::Arc;
+
+use sump_model::{CaptureStatus, OperationConflict};
+
+use crate::ila::IlaState;
+
+/// Held while a logical operation owns the ILA; released on drop
+pub struct OpGuard {
+    state: Arc<IlaState>,
+}
+
+impl Drop for OpGuard {
+    fn drop(&mut self) {
+        *self.state.operation.lock().unwrap() = None;
+        // The operation may have changed the capture state
+        *self.state.status_cache.lock().unwrap() = None;
+    }
+}
+
+/// 409 Conflict response
+pub struct Conflict(pub OperationConflict);
+
+impl Conflict {
+    pub fn new(message: impl Into<String>, operation: Option<&str>, status: CaptureStatus) -> Self {
+        Self(OperationConflict {
+            message: message.into(),
+            operation: operation.map(str::to_string),
+            status,
+        })
+    }
+}
+
+impl IntoResponse for Conflict {
+    fn into_response(self) -> Response {
+        (StatusCode::CONFLICT, Json(self.0)).into_response()
+    }
+}
+
+impl IlaState {
+    /// Claim the ILA for operation `name`; `Err` names the operation in progress
+    pub fn begin_op(self: &Arc<Self>, name: &'static str) -> Result<OpGuard, &'static str> {
+        let mut operation = self.operation.lock().unwrap();
+        if let Some(busy) = *operation {
+            return Err(busy);
+        }
+        *operation = Some(name);
+        Ok(OpGuard { state: self.clone() })
+    }
+
+    /// Run `f` on the blocking pool as operation `name`.
+    ///
+    /// The guard moves into the blocking task, so the ILA stays claimed until
+    /// the hardware sequence finishes even if the request times out.
+    pub async fn run_op<T, F>(self: &Arc<Self>, name: &'static str, f: F) -> Result<T, Conflict>
+    where
+        F: FnOnce(&IlaState) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let guard = self.claim_op(name).await?;
+        Ok(self.run(move |s| {
+            let _guard = guard;
+            f(s)
+        }).await)
+    }
+
+    /// `begin_op`, answering a busy ILA with the 409 response
+    pub async fn claim_op(self: &Arc<Self>, name: &'static str) -> Result<OpGuard, Conflict> {
+        match self.begin_op(name) {
+            Ok(guard) => Ok(guard),
+            Err(busy) => {
+                let status = self.run(|s| s.ila.capture_status()).await;
+                Err(Conflict::new(format!("ILA busy: {} in progress", busy), Some(busy), status))
+            }
+        }
+    }
+}
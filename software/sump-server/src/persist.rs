@@ -0,0 +1,42 @@
+//! On-disk server state
+//!
+//! Small JSON files kept in the state directory (`SUMP_STATE_DIR`,
+//! default `/var/lib/sump-server`) so configuration survives restarts.
+//! Captures are kept apart, in `SUMP_ARCHIVE_DIR` (see `archive`).
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::io;
+use std::path::Path;
+
+/// Default state directory
+pub const DEFAULT_STATE_DIR: &str = "/var/lib/sump-server";
+
+/// Last successfully applied trigger configuration
+pub const LAST_TRIGGER_FILE: &str = "last_trigger.json";
+
+/// User settings served by `/api/settings`
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Capture sequence numbers reserved so far (see `numbering`)
+pub const CAPTURE_SEQUENCE_FILE: &str = "capture_sequence.json";
+
+/// Read `dir/name`; `None` if missing or unparsable
+pub fn load_json<T: DeserializeOwned>(dir: &Path, name: &str) -> Option<T> {
+    let path = dir.join(name);
+    let bytes = std::fs::read(&path).ok()?;
+    match serde_json::from_slice(&bytes) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!("Ignoring malformed {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Write `dir/name` atomically (temp file + rename), creating `dir` if needed
+pub fn save_json<T: Serialize>(dir: &Path, name: &str, value: &T) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!(".{}.tmp", name));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(value)?)?;
+    std::fs::rename(&tmp, dir.join(name))
+}
@@ -0,0 +1,260 @@
+//! User-provided decoder and exporter plugins
+//!
+//! With the `plugins` cargo feature, `SUMP_PLUGIN_DIR` is scanned for
+//! `*.wasm` modules at startup. Each one is a decoder or an exporter, listed
+//! after the built-ins by `/decoders` and `/export-formats`. Plugins get no
+//! imports at all (no WASI, no hardware, no network), a fresh instance per
+//! call, a fuel budget and a memory cap.
+//!
+//! A module exports `memory`, `sump_alloc(len: i32) -> i32` and:
+//!
+//! - `sump_describe() -> i64` - JSON `{"kind": "decoder", "name",
+//!   "description", "params": [DecoderParam]}` or `{"kind": "exporter",
+//!   "name", "description", "mime_type", "extension"}`
+//! - `sump_decode(ptr: i32, len: i32) -> i64` - for decoders, given JSON
+//!   `{"params": {...}, "trace": {"hub_hz", "cycles": [...], "data": [...]}}`
+//!   (signal parameters as `{"lo", "hi"}` bit ranges); returns a JSON array
+//!   of `Annotation`
+//! - `sump_export(ptr: i32, len: i32) -> i64` - for exporters, given JSON
+//!   `{"capture": CaptureData, "pod": PodInfo, "hub_name", "hub_hz", "id"}`;
+//!   returns the file contents
+//!
+//! Inputs are written to a buffer from `sump_alloc`. Results are returned as
+//! `ptr << 32 | len` into `memory`; their first byte is 0 for success
+//! followed by the payload, anything else for an error followed by a UTF-8
+//! message (reported as 422).
+
+use sump_export::Exporter;
+
+use crate::decode::Decoder;
+
+#[cfg(feature = "plugins")]
+pub use wasm::load;
+
+/// Loaded plugins (see `decode::all`, `export::all`)
+#[derive(Default)]
+pub struct Plugins {
+    pub decoders: Vec<Box<dyn Decoder + Send>>,
+    pub exporters: Vec<Box<dyn Exporter + Send>>,
+}
+
+#[cfg(feature = "plugins")]
+mod wasm {
+    use serde::Deserialize;
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+    use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+    use sump_model::{Annotation, DecoderParam};
+
+    use super::Plugins;
+    use crate::decode::{self, Decoder, Params, Trace};
+    use sump_export::{Export, Exporter, EXPORTERS};
+
+    /// Instructions (roughly) a single call may execute
+    const FUEL: u64 = 1_000_000_000;
+
+    /// Linear memory a single call may grow to
+    const MAX_MEMORY: usize = 256 << 20;
+
+    #[derive(Deserialize)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    enum Description {
+        Decoder {
+            name: String,
+            description: String,
+            #[serde(default)]
+            params: Vec<DecoderParam>,
+        },
+        Exporter {
+            name: String,
+            description: String,
+            mime_type: String,
+            extension: String,
+        },
+    }
+
+    enum CallError {
+        /// Error reported by the plugin
+        Plugin(String),
+        /// Trap, exhausted fuel or a broken ABI
+        Fault(String),
+    }
+
+    /// A compiled plugin module
+    struct Plugin {
+        path: PathBuf,
+        engine: Engine,
+        module: Module,
+    }
+
+    impl Plugin {
+        /// Call `export` in a fresh instance with `input`; the payload of its reply
+        fn call(&self, export: &str, input: Option<&[u8]>) -> Result<Vec<u8>, CallError> {
+            let fault = |e: wasmtime::Error| CallError::Fault(format!("{}: {}", self.path.display(), e.root_cause()));
+            let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build();
+            let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(FUEL).map_err(fault)?;
+            let instance = Instance::new(&mut store, &self.module, &[]).map_err(fault)?;
+            let memory = instance.get_memory(&mut store, "memory")
+                .ok_or_else(|| CallError::Fault(format!("{}: no exported memory", self.path.display())))?;
+
+            let packed = match input {
+                Some(input) => {
+                    let alloc = instance.get_typed_func::<i32, i32>(&mut store, "sump_alloc").map_err(fault)?;
+                    let len = i32::try_from(input.len())
+                        .map_err(|_| CallError::Fault(format!("{}: input too large", self.path.display())))?;
+                    let ptr = alloc.call(&mut store, len).map_err(fault)?;
+                    memory.write(&mut store, ptr as u32 as usize, input).map_err(|e| fault(e.into()))?;
+                    let func = instance.get_typed_func::<(i32, i32), i64>(&mut store, export).map_err(fault)?;
+                    func.call(&mut store, (ptr, len)).map_err(fault)?
+                }
+                None => {
+                    let func = instance.get_typed_func::<(), i64>(&mut store, export).map_err(fault)?;
+                    func.call(&mut store, ()).map_err(fault)?
+                }
+            };
+
+            let (ptr, len) = ((packed as u64 >> 32) as usize, packed as u32 as usize);
+            let mut reply = vec![0; len];
+            memory.read(&store, ptr, &mut reply).map_err(|e| fault(e.into()))?;
+            match reply.split_first() {
+                Some((0, payload)) => Ok(payload.to_vec()),
+                Some((_, message)) => Err(CallError::Plugin(String::from_utf8_lossy(message).into_owned())),
+                None => Err(CallError::Fault(format!("{}: empty reply from {}", self.path.display(), export))),
+            }
+        }
+    }
+
+    struct WasmDecoder {
+        plugin: Plugin,
+        name: String,
+        description: String,
+        params: Vec<DecoderParam>,
+    }
+
+    impl Decoder for WasmDecoder {
+        fn name(&self) -> &str { &self.name }
+        fn description(&self) -> &str { &self.description }
+        fn params(&self) -> Vec<DecoderParam> { self.params.clone() }
+
+        fn decode(&self, trace: &Trace, params: &Params) -> Result<Vec<Annotation>, String> {
+            let input = serde_json::json!({ "params": params.to_json(), "trace": trace.to_json() });
+            let reply = self.plugin.call("sump_decode", Some(input.to_string().as_bytes()));
+            let reply = reply.map_err(|e| match e {
+                CallError::Plugin(message) => message,
+                CallError::Fault(message) => {
+                    tracing::error!("Decoder plugin {}", message);
+                    format!("decoder plugin failed: {}", message)
+                }
+            })?;
+            serde_json::from_slice(&reply).map_err(|e| format!("decoder plugin returned invalid annotations: {}", e))
+        }
+    }
+
+    struct WasmExporter {
+        plugin: Plugin,
+        name: String,
+        description: String,
+        mime_type: String,
+        extension: String,
+    }
+
+    impl Exporter for WasmExporter {
+        fn name(&self) -> &str { &self.name }
+        fn mime_type(&self) -> &str { &self.mime_type }
+        fn extension(&self) -> &str { &self.extension }
+        fn description(&self) -> &str { &self.description }
+
+        fn write(&self, e: &Export, out: &mut dyn Write) -> io::Result<()> {
+            let input = serde_json::json!({
+                "capture": e.capture,
+                "pod": e.pod,
+                "hub_name": e.hub_name,
+                "hub_hz": e.hub_hz,
+                "id": e.id,
+            });
+            match self.plugin.call("sump_export", Some(input.to_string().as_bytes())) {
+                Ok(file) => out.write_all(&file),
+                Err(CallError::Plugin(message)) => Err(io::Error::new(io::ErrorKind::InvalidInput, message)),
+                Err(CallError::Fault(message)) => {
+                    tracing::error!("Exporter plugin {}", message);
+                    Err(io::Error::other(format!("exporter plugin failed: {}", message)))
+                }
+            }
+        }
+    }
+
+    /// Compile a module and ask what it implements
+    fn open(engine: &Engine, path: &Path) -> Result<(Plugin, Description), String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let module = Module::new(engine, bytes).map_err(|e| format!("{}: {}", path.display(), e.root_cause()))?;
+        let plugin = Plugin { path: path.into(), engine: engine.clone(), module };
+        let reply = plugin.call("sump_describe", None).map_err(|e| match e {
+            CallError::Plugin(message) => format!("{}: {}", path.display(), message),
+            CallError::Fault(message) => message,
+        })?;
+        let description = serde_json::from_slice(&reply)
+            .map_err(|e| format!("{}: invalid description: {}", path.display(), e))?;
+        Ok((plugin, description))
+    }
+
+    /// Load the `*.wasm` plugins of `dir`, in file name order; a plugin
+    /// whose name is already taken is skipped
+    pub fn load(dir: &Path) -> Plugins {
+        let mut plugins = Plugins::default();
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = match Engine::new(&config) {
+            Ok(engine) => engine,
+            Err(e) => {
+                tracing::error!("Plugins: cannot create the WebAssembly engine: {}", e);
+                return plugins;
+            }
+        };
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "wasm"))
+                .collect(),
+            Err(e) => {
+                tracing::error!("SUMP_PLUGIN_DIR {}: {}", dir.display(), e);
+                return plugins;
+            }
+        };
+        paths.sort();
+
+        for path in paths {
+            let (plugin, description) = match open(&engine, &path) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    tracing::error!("Plugin {}", e);
+                    continue;
+                }
+            };
+            match description {
+                Description::Decoder { name, description, params } => {
+                    let taken = decode::DECODERS.iter().any(|d| d.name() == name)
+                        || plugins.decoders.iter().any(|d| d.name() == name);
+                    if taken {
+                        tracing::warn!("Plugin {}: decoder '{}' already exists, skipped", path.display(), name);
+                        continue;
+                    }
+                    tracing::info!("Plugin {}: decoder '{}'", path.display(), name);
+                    plugins.decoders.push(Box::new(WasmDecoder { plugin, name, description, params }));
+                }
+                Description::Exporter { name, description, mime_type, extension } => {
+                    let taken = EXPORTERS.iter().any(|e| e.name() == name)
+                        || plugins.exporters.iter().any(|e| e.name() == name);
+                    if taken {
+                        tracing::warn!("Plugin {}: export format '{}' already exists, skipped", path.display(), name);
+                        continue;
+                    }
+                    tracing::info!("Plugin {}: export format '{}'", path.display(), name);
+                    plugins.exporters.push(Box::new(WasmExporter { plugin, name, description, mime_type, extension }));
+                }
+            }
+        }
+        plugins
+    }
+}
@@ -0,0 +1,131 @@
+//! HTTP byte ranges for resumable downloads
+//!
+//! Downloads whose bytes don't change while they're kept (`/waveforms/:id.vcd`,
+//! a stopped deep capture) send an `ETag` and `Accept-Ranges: bytes`, and
+//! answer a single `Range: bytes=...` with 206. A stale `If-Range` gets the
+//! whole body again; multiple ranges are ignored, as RFC 9110 allows.
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// Bytes `start..end` of a body
+#[derive(Debug, Clone, Copy)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+pub enum Requested {
+    Whole,
+    Part(ByteRange),
+    /// 416: the range starts beyond the body
+    Unsatisfiable,
+}
+
+/// The range asked for by `headers`, for a body of `len` bytes tagged `etag`
+pub fn requested(headers: &HeaderMap, len: u64, etag: &str) -> Requested {
+    let Some(range) = headers.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return Requested::Whole;
+    };
+    if headers.get(header::IF_RANGE).is_some_and(|v| v.as_bytes() != etag.as_bytes()) {
+        return Requested::Whole;
+    }
+    let Some(spec) = range.trim().strip_prefix("bytes=") else {
+        return Requested::Whole;
+    };
+    if spec.contains(',') {
+        return Requested::Whole;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return Requested::Whole;
+    };
+    let (first, last) = (first.trim(), last.trim());
+
+    if first.is_empty() {
+        // Suffix: the last N bytes
+        return match last.parse::<u64>() {
+            Ok(0) => Requested::Unsatisfiable,
+            Ok(n) if len > 0 => Requested::Part(ByteRange { start: len.saturating_sub(n), end: len }),
+            Ok(_) => Requested::Unsatisfiable,
+            Err(_) => Requested::Whole,
+        };
+    }
+    let Ok(start) = first.parse::<u64>() else {
+        return Requested::Whole;
+    };
+    let end = match last {
+        "" => len,
+        last => match last.parse::<u64>() {
+            Ok(last) if last >= start => last.saturating_add(1).min(len),
+            _ => return Requested::Whole,
+        },
+    };
+    if start >= len {
+        return Requested::Unsatisfiable;
+    }
+    Requested::Part(ByteRange { start, end })
+}
+
+/// Headers every ranged download carries
+pub fn add_headers(headers: &mut HeaderMap, etag: &str) {
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(etag) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, etag);
+    }
+}
+
+/// 206 headers for `range` of a `len`-byte body
+pub fn add_part_headers(headers: &mut HeaderMap, range: ByteRange, len: u64) {
+    let content_range = format!("bytes {}-{}/{}", range.start, range.end - 1, len);
+    headers.insert(header::CONTENT_RANGE, HeaderValue::from_str(&content_range).unwrap());
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(range.end - range.start));
+}
+
+pub fn not_satisfiable(len: u64) -> Response {
+    (
+        StatusCode::RANGE_NOT_SATISFIABLE,
+        [(header::CONTENT_RANGE, format!("bytes */{}", len))],
+        format!("Range not satisfiable; the body is {} bytes", len),
+    ).into_response()
+}
+
+/// A body held in memory, whole or the requested part of it
+pub fn respond(request: &HeaderMap, etag: &str, mut response: Response, body: &[u8]) -> Response {
+    let len = body.len() as u64;
+    match requested(request, len, etag) {
+        Requested::Whole => {
+            add_headers(response.headers_mut(), etag);
+            *response.body_mut() = Body::from(body.to_vec());
+            response
+        }
+        Requested::Part(range) => {
+            add_headers(response.headers_mut(), etag);
+            add_part_headers(response.headers_mut(), range, len);
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            *response.body_mut() = Body::from(body[range.start as usize..range.end as usize].to_vec());
+            response
+        }
+        Requested::Unsatisfiable => not_satisfiable(len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(spec: &str, len: u64) -> Requested {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_str(spec).unwrap());
+        requested(&headers, len, "\"etag\"")
+    }
+
+    #[test]
+    fn last_byte_past_the_end_is_clamped() {
+        assert!(matches!(range("bytes=5-99", 10), Requested::Part(ByteRange { start: 5, end: 10 })));
+        assert!(matches!(range("bytes=5-18446744073709551615", 10), Requested::Part(ByteRange { start: 5, end: 10 })));
+        assert!(matches!(range("bytes=10-18446744073709551615", 10), Requested::Unsatisfiable));
+    }
+}
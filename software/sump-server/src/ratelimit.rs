@@ -0,0 +1,133 @@
+//! Rate and concurrency limits
+//!
+//! Guards the endpoints that generate register traffic: a token bucket per
+//! client IP plus a global cap on requests in flight. Rejected requests get
+//! 429 Too Many Requests with `Retry-After`, so a fast-polling dashboard
+//! can't starve captures of serial-bus bandwidth.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Default sustained requests per second per client
+pub const DEFAULT_RATE_PER_S: f64 = 20.0;
+
+/// Default requests in flight across all clients
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+/// Buckets idle this long are dropped when the table is pruned
+const IDLE_BUCKET: Duration = Duration::from_secs(60);
+
+/// Table size that triggers pruning
+const PRUNE_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Sustained requests per second per client (0 disables)
+    pub rate_per_s: f64,
+    /// Requests a client may issue back-to-back
+    pub burst: f64,
+    /// Requests in flight across all clients (0 disables)
+    pub max_concurrent: usize,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct Limiter {
+    limits: Limits,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl Limiter {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+            in_flight: Arc::new(Semaphore::new(limits.max_concurrent.max(1))),
+        }
+    }
+
+    /// Take a token for `ip`, or return how long until one is available
+    fn take(&self, ip: IpAddr) -> Result<(), Duration> {
+        let Limits { rate_per_s, burst, .. } = self.limits;
+        if rate_per_s <= 0.0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, b| now.duration_since(b.updated) < IDLE_BUCKET);
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket { tokens: burst, updated: now });
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * rate_per_s;
+        bucket.tokens = (bucket.tokens + refill).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate_per_s))
+        }
+    }
+}
+
+fn too_many(retry_after: Duration, message: String) -> Response {
+    let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, secs.to_string())],
+        message,
+    )
+        .into_response()
+}
+
+async fn limit(State(limiter): State<Arc<Limiter>>, req: Request, next: Next) -> Response {
+    let ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    if let Some(ip) = ip {
+        if let Err(wait) = limiter.take(ip) {
+            tracing::debug!("Rate limit exceeded by {}", ip);
+            return too_many(wait, format!(
+                "Rate limit of {} requests/s exceeded",
+                limiter.limits.rate_per_s
+            ));
+        }
+    }
+
+    if limiter.limits.max_concurrent == 0 {
+        return next.run(req).await;
+    }
+    let Ok(_permit) = limiter.in_flight.clone().try_acquire_owned() else {
+        return too_many(Duration::from_secs(1), format!(
+            "Too many concurrent hardware requests (limit {})",
+            limiter.limits.max_concurrent
+        ));
+    };
+    next.run(req).await
+}
+
+/// Apply the limits to every route of `router`
+pub fn with_limits<S>(router: Router<S>, limiter: Arc<Limiter>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(middleware::from_fn_with_state(limiter, limit))
+}
@@ -0,0 +1,41 @@
+//! Readiness probe
+//!
+//! `GET /readyz` answers 200 once the server can take captures: the startup
+//! INIT (`SUMP_INIT_ON_BOOT`) finished, if requested, the hardware is
+//! connected (see `degraded`) and the watchdog sees it. Otherwise 503 with the reason. It reads cached state only,
+//! so probes never touch the hardware.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+use sump_model::{BootInit, Readiness};
+
+use crate::ila::IlaState;
+
+/// GET /readyz - 200 when ready for captures, 503 otherwise
+async fn get_readyz(State(state): State<Arc<IlaState>>) -> (StatusCode, Json<Readiness>) {
+    let (boot_init, init_message) = state.boot_init.lock().unwrap().clone();
+    let degraded = state.degraded.lock().unwrap().clone();
+    let hardware_healthy = degraded.is_none() && state.watchdog.lock().unwrap().healthy;
+    let message = match (degraded, boot_init) {
+        (Some(reason), _) => format!("No hardware: {}", reason),
+        (None, BootInit::Pending | BootInit::Failed) => init_message,
+        _ if !hardware_healthy => "Watchdog reports the hardware unhealthy (see /api/ila/health)".into(),
+        _ => "Ready".into(),
+    };
+    let ready = hardware_healthy && matches!(boot_init, BootInit::Disabled | BootInit::Done);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(Readiness { ready, boot_init, hardware_healthy, message }))
+}
+
+pub fn readiness_router(state: Arc<IlaState>) -> Router {
+    Router::new()
+        .route("/readyz", get(get_readyz))
+        .with_state(state)
+}
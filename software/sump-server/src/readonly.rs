@@ -0,0 +1,58 @@
+//! Read-only listener
+//!
+//! `SUMP_READONLY_ADDR` serves the same routes on a second address, minus
+//! anything that changes the ILA or server state: only GET/HEAD/OPTIONS and
+//! the side-effect-free POSTs in `READ_ONLY_POSTS` get through, minus the
+//! WebSocket GETs of `WRITE_GETS`; the rest answer 403. A typical board keeps the full API on `SUMP_BIND=127.0.0.1`
+//! and exposes only this listener to the lab network. `Settings.signal_masks`
+//! further withholds signals from it (see `masks`). The bd_server, text and
+//! gRPC protocols have no read-only equivalent: they only listen on the
+//! main address, and not at all while that is every interface.
+
+use axum::{
+    extract::Request,
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// POST paths that only read: `(prefix, segment)` where the path starts
+/// with `prefix` and contains `segment`
+const READ_ONLY_POSTS: &[(&str, &str)] = &[
+    // Protocol decode of a capture
+    ("/api/ila/capture/", "/decode/"),
+    ("/api/ila/deep/decode/", ""),
+];
+
+/// GET paths that can change the ILA: WebSockets taking commands
+const WRITE_GETS: &[&str] = &[
+    // Raw command console
+    "/api/ila/console",
+];
+
+/// Request extension marking requests that came in on the read-only listener
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOnlyListener;
+
+/// Whether the read-only listener lets `method path` through
+pub fn allowed(method: &Method, path: &str) -> bool {
+    match *method {
+        Method::GET | Method::HEAD => !WRITE_GETS.contains(&path.trim_end_matches('/')),
+        Method::OPTIONS => true,
+        Method::POST => READ_ONLY_POSTS.iter().any(|(prefix, segment)| path.starts_with(prefix) && path.contains(segment)),
+        _ => false,
+    }
+}
+
+/// Middleware rejecting requests `allowed` refuses
+pub async fn read_only(mut request: Request, next: Next) -> Response {
+    if allowed(request.method(), request.uri().path()) {
+        request.extensions_mut().insert(ReadOnlyListener);
+        return next.run(request).await;
+    }
+    let message = format!(
+        "{} {} is not available on the read-only listener",
+        request.method(), request.uri().path()
+    );
+    (StatusCode::FORBIDDEN, message).into_response()
+}
@@ -0,0 +1,34 @@
+//! JSON Schema publication
+//!
+//! `GET /api/schema` lists the API types and `GET /api/schema/:type`
+//! returns one type's JSON Schema, generated from the `sump-model` serde
+//! structs so code generators in other languages track the Rust types.
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use sump_model::schema::{self, RootSchema, TYPES};
+
+/// GET /api/schema - Names of the types with a schema
+async fn list_types() -> Json<&'static [&'static str]> {
+    Json(TYPES)
+}
+
+/// GET /api/schema/:type - JSON Schema of one type (`TriggerConfig`, `CaptureData`, ...)
+async fn get_schema(Path(name): Path<String>) -> Result<Json<RootSchema>, (StatusCode, String)> {
+    let name = name.strip_suffix(".json").unwrap_or(&name);
+    schema::schema(name).map(Json).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("No schema for '{}'; see GET /api/schema for the type names", name))
+    })
+}
+
+/// Create the `/api/schema` router; it touches no hardware
+pub fn schema_router() -> Router {
+    Router::new()
+        .route("/", get(list_types))
+        .route("/:type", get(get_schema))
+}
@@ -0,0 +1,184 @@
+//! One-button plumbing check
+//!
+//! `POST /api/ila/selftest` walks the whole command path: wrapper ID,
+//! enumeration, a pod register round trip over the serial bus, INIT, an
+//! immediate-trigger capture and a short readout. The capture is destroyed,
+//! so like reset it is refused while armed unless forced. The saved trigger
+//! configuration is programmed back afterwards, left disarmed.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sump_driver::*;
+use sump_model::*;
+
+use crate::ila::IlaState;
+use crate::ops::Conflict;
+use crate::persist;
+
+/// Longest wait for INIT to finish or the immediate trigger to acquire
+const STEP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Samples read back in the readout step
+const READOUT_SAMPLES: u32 = 16;
+
+type StepResult = Result<String, String>;
+
+struct Runner {
+    steps: Vec<SelfTestStep>,
+}
+
+impl Runner {
+    /// Run `f` as step `name` unless an earlier step failed
+    fn step(&mut self, name: &str, f: impl FnOnce() -> StepResult) {
+        if self.steps.last().is_some_and(|s| !s.passed) {
+            return;
+        }
+        let start = Instant::now();
+        let (passed, detail) = match f() {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        self.steps.push(SelfTestStep {
+            name: name.into(),
+            passed,
+            detail,
+            duration_us: start.elapsed().as_micros() as u64,
+        });
+    }
+}
+
+/// Poll the capture status until `done` holds
+fn wait_status(ila: &Ila, done: impl Fn(&CaptureStatus) -> bool) -> Result<CaptureStatus, CaptureStatus> {
+    let deadline = Instant::now() + STEP_TIMEOUT;
+    loop {
+        let status = ila.capture_status();
+        if done(&status) {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            return Err(status);
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn run(state: &IlaState) -> SelfTestReport {
+    let ila = &state.ila;
+    let mut runner = Runner { steps: Vec::new() };
+
+    runner.step("hw_id", || {
+        let hw_info = ila.read_reg(REG_HW_INFO).ok_or("HW_INFO read failed")?;
+        match HW_INFO_ID.get(hw_info) {
+            HW_ID_SUMP3 => Ok(format!("HW_INFO 0x{:08X}", hw_info)),
+            id => Err(format!("unexpected ID 0x{:04X} (HW_INFO 0x{:08X})", id, hw_info)),
+        }
+    });
+
+    runner.step("enumerate", || {
+        let hub_count = ila.hub_count();
+        if hub_count == 0 {
+            return Err("no hubs".into());
+        }
+        let pods = (0..hub_count)
+            .map(|hub| ila.pod_count(hub).ok_or(format!("hub {} pod count read failed", hub)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if pods[0] == 0 {
+            return Err("hub 0 has no pods".into());
+        }
+        Ok(format!("{} hubs, pods per hub {:?}", hub_count, pods))
+    });
+
+    // RAM_PTR only steers readout, which this test redoes anyway
+    runner.step("pod_register", || {
+        let (_, _, ram_depth) = ila.get_pod_config(0, 0);
+        let pattern = (1 << 20) | (0x155 & (ram_depth - 1));
+        if !ila.write_pod_reg(0, 0, POD_REG_RAM_PTR, pattern) {
+            return Err("RAM_PTR write failed".into());
+        }
+        match ila.read_pod_reg(0, 0, POD_REG_RAM_PTR) {
+            Some(v) if v == pattern => Ok(format!("hub 0 pod 0 RAM_PTR 0x{:08X} read back", pattern)),
+            Some(v) => Err(format!("wrote 0x{:08X} to hub 0 pod 0 RAM_PTR, read 0x{:08X}", pattern, v)),
+            None => Err("RAM_PTR read failed".into()),
+        }
+    });
+
+    runner.step("init", || {
+        ila.exec_cmd(CMD_INIT, 0, 0).ok_or("INIT failed")?;
+        wait_status(ila, |s| !s.init_in_progress)
+            .map(|_| "RAM cleared".into())
+            .map_err(|_| "INIT still in progress".into())
+    });
+
+    runner.step("arm", || {
+        let (_, _, ram_depth) = ila.get_pod_config(0, 0);
+        ila.configure_trigger(TRIG_IMMEDIATE, 0, ram_depth / 2, &[])?;
+        ila.exec_cmd(CMD_ARM, 0, 0).ok_or("ARM failed")?;
+        Ok("armed with an immediate trigger".into())
+    });
+
+    runner.step("trigger", || {
+        wait_status(ila, |s| s.triggered && s.acquired)
+            .map(|_| "triggered and acquired".into())
+            .map_err(|s| format!("not acquired (armed={}, triggered={})", s.armed, s.triggered))
+    });
+
+    runner.step("readout", || {
+        let capture = ila.read_capture(0, 0, READOUT_SAMPLES);
+        let valid = capture.samples.iter().filter(|s| s.code != 0).count();
+        if capture.samples.len() < READOUT_SAMPLES as usize {
+            return Err(format!("read {} of {} samples", capture.samples.len(), READOUT_SAMPLES));
+        }
+        if valid == 0 {
+            return Err("all samples read as unwritten (RLE code 0)".into());
+        }
+        Ok(format!("{} samples, {} written", capture.samples.len(), valid))
+    });
+
+    let saved = persist::load_json::<TriggerConfig>(&state.state_dir, persist::LAST_TRIGGER_FILE);
+    if let Some(config) = saved.and_then(|config| ila.resolve_trigger_signals(&config).ok()) {
+        if let Err(e) = ila.program_trigger(&config) {
+            tracing::warn!("Self-test: failed to restore trigger configuration: {}", e);
+        }
+    }
+
+    SelfTestReport {
+        passed: runner.steps.iter().all(|s| s.passed),
+        steps: runner.steps,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelfTestQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+/// POST /api/ila/selftest?force=true - Run the self-test (409 while armed unless forced)
+pub async fn post_selftest(
+    State(state): State<Arc<IlaState>>,
+    Query(query): Query<SelfTestQuery>,
+) -> Result<Json<SelfTestReport>, Conflict> {
+    let report = state.run_op("selftest", move |s| {
+        if !query.force {
+            let status = s.ila.capture_status();
+            if status.armed && !status.acquired {
+                return Err(status);
+            }
+        }
+        Ok(run(s))
+    }).await?
+    .map_err(|status| Conflict::new("ILA is armed; use ?force=true to run the self-test anyway", None, status))?;
+
+    let failed = report.steps.iter().find(|s| !s.passed);
+    match failed {
+        None => tracing::info!("Self-test passed"),
+        Some(step) => tracing::warn!("Self-test failed at {}: {}", step.name, step.detail),
+    }
+    Ok(Json(report))
+}
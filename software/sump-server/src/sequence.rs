@@ -0,0 +1,172 @@
+//! Sequenced (A-then-B) trigger
+//!
+//! SUMP3 triggers on a single condition (with Nth/delay qualifiers), so an
+//! A-then-B sequence is emulated: condition A is the hardware trigger, and
+//! after each acquisition the post-trigger samples of pod (0,0) are checked
+//! for condition B within `within` cycles. Captures that don't match are
+//! discarded by re-arming. The run holds the ILA as operation `sequence`
+//! until it matches, gives up or is stopped.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sump_model::{
+    CaptureData, CommandResult, FieldError, ReadoutWindow, SequenceConfig, SequenceStatus, TriggerConfig,
+    ValidationErrors,
+};
+
+use crate::ila::IlaState;
+
+/// Capture status poll interval while waiting for an acquisition
+const POLL: Duration = Duration::from_millis(10);
+
+/// Whether condition B shows up within `within` cycles of the trigger
+/// sample: `None` if the post-trigger samples end before that
+fn b_seen(capture: &CaptureData, b_bits: u32, within: u32) -> Option<bool> {
+    let ts_mask = if capture.ts_bits >= 32 { u32::MAX } else { (1u32 << capture.ts_bits) - 1 };
+    let mut samples = capture.samples.iter();
+    let trigger = samples.next()?;
+    if trigger.data & b_bits != 0 {
+        return Some(true);
+    }
+    // RLE samples mark changes; accumulate deltas so timestamp wraps between samples are harmless
+    let (mut prev, mut offset) = (trigger.timestamp, 0u64);
+    for sample in samples.take_while(|s| s.code == 3) {
+        offset += (sample.timestamp.wrapping_sub(prev) & ts_mask) as u64;
+        prev = sample.timestamp;
+        if offset > within as u64 {
+            return Some(false);
+        }
+        if sample.data & b_bits != 0 {
+            return Some(true);
+        }
+    }
+    None
+}
+
+fn update(state: &IlaState, f: impl FnOnce(&mut SequenceStatus)) {
+    f(&mut state.sequence.lock().unwrap());
+}
+
+/// Arm, wait and evaluate until a capture matches or the run ends
+fn run(state: &IlaState, config: &SequenceConfig, trigger: &TriggerConfig, b_bits: u32) {
+    let shutdown = state.shutdown_signal();
+    let stopped = || state.sequence_stop.load(Ordering::Relaxed) || *shutdown.borrow();
+    let wanted = if config.b_absent { Some(false) } else { Some(true) };
+
+    let mut attempt = 0;
+    let (matched, message) = loop {
+        if config.max_attempts != 0 && attempt >= config.max_attempts {
+            break (false, format!("No matching capture in {} attempts", attempt));
+        }
+        if let Err(message) = state.apply_trigger(trigger) {
+            break (false, message);
+        }
+        while !state.ila.capture_status().acquired {
+            if stopped() {
+                break;
+            }
+            std::thread::sleep(POLL);
+        }
+        if stopped() {
+            break (false, format!("Stopped after {} attempts", attempt));
+        }
+
+        attempt += 1;
+        update(state, |s| s.attempts = attempt);
+        let Some(capture) = state.ila.read_capture_window(0, 0, ReadoutWindow::Post, trigger.post_trigger) else {
+            tracing::warn!("Sequence attempt {}: no trigger sample in pod RAM", attempt);
+            continue;
+        };
+        if let Some(e) = capture.readout_error {
+            break (false, format!("Readout failed at 0x{:X}: {}", e.address, e.message));
+        }
+        match b_seen(&capture, b_bits, config.within) {
+            seen if seen == wanted => break (true, format!("Matched on attempt {}", attempt)),
+            None => tracing::debug!(
+                "Sequence attempt {}: post-trigger samples end within {} cycles; raise post_trigger",
+                attempt, config.within
+            ),
+            Some(_) => {}
+        }
+    };
+
+    if matched {
+        tracing::info!("Sequenced trigger: {}", message);
+    } else {
+        tracing::warn!("Sequenced trigger: {}", message);
+    }
+    update(state, |s| {
+        s.running = false;
+        s.matched = matched;
+        s.message = message;
+    });
+}
+
+/// Resolve condition A and B against pod (0,0)
+fn check(state: &IlaState, config: &SequenceConfig) -> Result<(TriggerConfig, u32), Vec<FieldError>> {
+    let trigger = state.ila.resolve_trigger_signals(&config.trigger)?;
+    state.ila.validate_trigger(&trigger)?;
+    let b_bits = config.b_bits | state.ila.signal_bits(&config.b_signals, "b_signals")?;
+
+    let mut errors = Vec::new();
+    if b_bits == 0 {
+        errors.push(FieldError { field: "b_bits".into(), message: "condition B selects no bits".into() });
+    }
+    if config.within == 0 {
+        errors.push(FieldError { field: "within".into(), message: "must be at least 1 cycle".into() });
+    }
+    if errors.is_empty() { Ok((trigger, b_bits)) } else { Err(errors) }
+}
+
+/// GET /api/ila/sequence - Sequenced trigger progress
+pub async fn get_sequence(State(state): State<Arc<IlaState>>) -> Json<SequenceStatus> {
+    Json(state.sequence.lock().unwrap().clone())
+}
+
+/// POST /api/ila/sequence - Re-arm until condition A is followed by B (or, with b_absent, isn't)
+pub async fn post_sequence(
+    State(state): State<Arc<IlaState>>,
+    Json(config): Json<SequenceConfig>,
+) -> Result<Json<SequenceStatus>, Response> {
+    let guard = state.claim_op("sequence").await.map_err(IntoResponse::into_response)?;
+    let checked = config.clone();
+    let (trigger, b_bits) = state.run(move |s| check(s, &checked)).await
+        .map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response())?;
+
+    state.sequence_stop.store(false, Ordering::Relaxed);
+    let status = SequenceStatus {
+        config: Some(config.clone()),
+        running: true,
+        message: "Waiting for condition A".into(),
+        ..Default::default()
+    };
+    *state.sequence.lock().unwrap() = status.clone();
+    tracing::info!(
+        "Sequenced trigger: A bits=0x{:08X}, B bits=0x{:08X} {} within {} cycles",
+        trigger.trigger_bits, b_bits, if config.b_absent { "absent" } else { "present" }, config.within
+    );
+
+    let runner = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let _guard = guard;
+        run(&runner, &config, &trigger, b_bits);
+    });
+    Ok(Json(status))
+}
+
+/// POST /api/ila/sequence/stop - Stop re-arming; the ILA stays armed for condition A
+pub async fn post_sequence_stop(State(state): State<Arc<IlaState>>) -> Json<CommandResult> {
+    let running = state.sequence.lock().unwrap().running;
+    state.sequence_stop.store(true, Ordering::Relaxed);
+    Json(CommandResult {
+        success: running,
+        message: if running { "Sequenced trigger stopping".into() } else { "No sequenced trigger running".into() },
+    })
+}
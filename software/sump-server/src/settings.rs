@@ -0,0 +1,156 @@
+//! Persistent settings
+//!
+//! `GET /api/settings` returns the stored `Settings`; `PUT /api/settings`
+//! replaces them and writes `settings.json` in the state directory. Hub and
+//! pod labels and the group hierarchy are applied to enumeration responses
+//! and exports here, and hidden pods left out. Hiding is a presentation
+//! filter: raw register access (`/reg`, batch `cmd`) still reaches them.
+//! Signal masks only apply on the read-only listener (see `masks`).
+
+use axum::{
+    extract::State,
+    http::{Extensions, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+use sump_model::{FieldError, IlaInfo, PodGroup, PodInfo, Settings, ValidationErrors};
+
+use crate::ila::IlaState;
+use crate::masks;
+use crate::persist;
+
+/// Set `display_name`/`description` of a pod from `pod_labels`
+pub fn label_pod(settings: &Settings, hub: u8, pod: &mut PodInfo) {
+    if let Some(label) = settings.pod_labels.get(&format!("{}.{}", hub, pod.index)) {
+        pod.display_name = Some(label.display_name.clone());
+        pod.description = Some(label.description.clone()).filter(|d| !d.is_empty());
+    }
+}
+
+/// Deepest accepted group nesting
+const MAX_GROUP_DEPTH: usize = 8;
+
+/// Whether `Settings.hidden_pods` lists the pod
+pub fn is_hidden(settings: &Settings, hub: u8, pod: u8) -> bool {
+    let key = format!("{}.{}", hub, pod);
+    settings.hidden_pods.contains(&key)
+}
+
+/// A group without members of hidden pods
+fn visible_group(settings: &Settings, group: &PodGroup) -> PodGroup {
+    let hidden = |member: &str| settings.hidden_pods.iter().any(|pod| member.split('/').next() == Some(pod));
+    PodGroup {
+        pods: group.pods.iter().filter(|pod| !hidden(pod)).cloned().collect(),
+        signals: group.signals.iter().filter(|signal| !hidden(signal)).cloned().collect(),
+        groups: group.groups.iter().map(|child| visible_group(settings, child)).collect(),
+        ..group.clone()
+    }
+}
+
+/// Apply hub and pod labels and the group hierarchy to an enumeration, and
+/// drop hidden pods
+pub fn annotate_info(settings: &Settings, info: &mut IlaInfo) {
+    info.groups = settings.groups.iter().map(|group| visible_group(settings, group)).collect();
+    for hub in &mut info.hubs {
+        let index = hub.index;
+        hub.pods.retain(|pod| !is_hidden(settings, index, pod.index));
+        if let Some(label) = settings.hub_labels.get(&hub.index.to_string()) {
+            hub.display_name = Some(label.display_name.clone());
+            hub.description = Some(label.description.clone()).filter(|d| !d.is_empty());
+        }
+        for pod in &mut hub.pods {
+            label_pod(settings, hub.index, pod);
+        }
+    }
+}
+
+fn is_pod_key(key: &str) -> bool {
+    key.split_once('.').is_some_and(|(hub, pod)| hub.parse::<u8>().is_ok() && pod.parse::<u8>().is_ok())
+}
+
+/// Check a group and its subgroups; `path` names it in messages
+fn check_group(group: &PodGroup, path: &str, depth: usize, errors: &mut Vec<FieldError>) {
+    let mut error = |message: String| errors.push(FieldError { field: format!("groups{}", path), message });
+    if group.name.trim().is_empty() {
+        error("group name is empty".into());
+    }
+    if depth > MAX_GROUP_DEPTH {
+        error(format!("groups nest deeper than {} levels", MAX_GROUP_DEPTH));
+        return;
+    }
+    for pod in group.pods.iter().filter(|pod| !is_pod_key(pod)) {
+        error(format!("pod '{}' is not '<hub>.<pod>'", pod));
+    }
+    for signal in &group.signals {
+        if !signal.split_once('/').is_some_and(|(pod, name)| is_pod_key(pod) && !name.is_empty()) {
+            error(format!("signal '{}' is not '<hub>.<pod>/<signal>'", signal));
+        }
+    }
+    for (i, child) in group.groups.iter().enumerate() {
+        check_group(child, &format!("{}.groups[{}]", path, i), depth + 1, errors);
+    }
+}
+
+/// Label keys must name a hub (`"0"`) or pod (`"0.1"`), as must hidden and
+/// masked pods; group members a pod or `"<hub>.<pod>/<signal>"`
+fn check_settings(settings: &Settings) -> Vec<FieldError> {
+    let hubs = settings.hub_labels.keys()
+        .filter(|key| key.parse::<u8>().is_err())
+        .map(|key| FieldError { field: "hub_labels".into(), message: format!("key '{}' is not a hub index", key) });
+    let pods = settings.pod_labels.keys()
+        .filter(|key| !is_pod_key(key))
+        .map(|key| FieldError { field: "pod_labels".into(), message: format!("key '{}' is not '<hub>.<pod>'", key) });
+    let hidden = settings.hidden_pods.iter()
+        .filter(|key| !is_pod_key(key))
+        .map(|key| FieldError { field: "hidden_pods".into(), message: format!("'{}' is not '<hub>.<pod>'", key) });
+    let masks = settings.signal_masks.iter().enumerate().flat_map(|(i, mask)| {
+        let field = format!("signal_masks[{}]", i);
+        let pod = (!is_pod_key(&mask.pod))
+            .then(|| FieldError { field: field.clone(), message: format!("pod '{}' is not '<hub>.<pod>'", mask.pod) });
+        let empty = mask.signals.iter().any(|name| name.trim().is_empty())
+            .then(|| FieldError { field, message: "signal name is empty".into() });
+        pod.into_iter().chain(empty)
+    });
+    let mut errors: Vec<FieldError> = hubs.chain(pods).chain(hidden).chain(masks).collect();
+    for (i, group) in settings.groups.iter().enumerate() {
+        check_group(group, &format!("[{}]", i), 1, &mut errors);
+    }
+    errors
+}
+
+/// GET /api/settings - Current settings, without signal masks on the
+/// read-only listener
+async fn get_settings(State(state): State<Arc<IlaState>>, extensions: Extensions) -> Json<Settings> {
+    let mut settings = state.settings.lock().unwrap().clone();
+    if masks::partner(&extensions) {
+        masks::redact_settings(&mut settings);
+    }
+    Json(settings)
+}
+
+/// PUT /api/settings - Replace and persist settings
+async fn put_settings(
+    State(state): State<Arc<IlaState>>,
+    Json(settings): Json<Settings>,
+) -> Result<Json<Settings>, Response> {
+    let errors = check_settings(&settings);
+    if !errors.is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(ValidationErrors { errors })).into_response());
+    }
+    persist::save_json(&state.state_dir, persist::SETTINGS_FILE, &settings).map_err(|e| {
+        tracing::error!("Failed to save settings: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save settings: {}", e)).into_response()
+    })?;
+    *state.settings.lock().unwrap() = settings.clone();
+    Ok(Json(settings))
+}
+
+/// Create the `/api/settings` router
+pub fn settings_router(state: Arc<IlaState>) -> Router {
+    Router::new()
+        .route("/", get(get_settings).put(put_settings))
+        .with_state(state)
+}
@@ -0,0 +1,133 @@
+//! Untriggered snapshot
+//!
+//! `POST /api/ila/snapshot` arms with an immediate trigger, waits for the
+//! acquisition and reads the pod out in one call, as operation `snapshot`.
+//! The capture stays in pod RAM, so `/waveforms/latest.vcd` and the other
+//! readout endpoints see it too. The immediate trigger replaces the
+//! configured one until the next trigger request.
+//!
+//! SIGUSR1 or SIGUSR2 does the same for every visible pod at once, so other
+//! processes on the board (a fault watchdog, say) can grab the bus state
+//! without HTTP. Each pod's capture is kept as `/waveforms/:id.vcd` (see
+//! `waveform`) and the ids are logged; capture hooks fire and the capture
+//! is archived (see `archive`) as for any ARM.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sump_model::CaptureData;
+
+use crate::digest;
+use crate::ila::IlaState;
+use crate::validate;
+
+/// Longest wait for the immediate trigger to fill pod RAM
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotQuery {
+    #[serde(default)]
+    hub: u8,
+    #[serde(default)]
+    pod: u8,
+    /// Newest samples to read; the whole capture when absent
+    count: Option<u32>,
+}
+
+/// Arm with an immediate trigger and wait until pod RAM is full
+fn acquire_now(s: &IlaState, post_trigger: u32) -> Result<(), validate::Invalid> {
+    s.arm_immediate(post_trigger).map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+    let deadline = Instant::now() + ACQUIRE_TIMEOUT;
+    loop {
+        let status = s.ila.capture_status();
+        if status.acquired {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err((StatusCode::GATEWAY_TIMEOUT, format!(
+                "No acquisition within {} s (armed={}, triggered={})",
+                ACQUIRE_TIMEOUT.as_secs(), status.armed, status.triggered
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// POST /api/ila/snapshot?hub=H&pod=P&count=N - Capture the bus now and read it out
+pub async fn post_snapshot(
+    State(state): State<Arc<IlaState>>,
+    Query(SnapshotQuery { hub, pod, count }): Query<SnapshotQuery>,
+) -> Result<Json<CaptureData>, Response> {
+    state.run_op("snapshot", move |s| -> Result<_, validate::Invalid> {
+        validate::visible_pod(s, hub, pod)?;
+        let (_, _, ram_depth) = s.ila.get_pod_config(hub, pod);
+        if let Some(count) = count {
+            validate::count("count", count, ram_depth)?;
+        }
+        acquire_now(s, ram_depth / 2)?;
+
+        let capture = match count {
+            Some(count) => s.ila.read_capture(hub, pod, count),
+            None => s.ila.read_capture_all(hub, pod),
+        };
+        if let Some(e) = &capture.readout_error {
+            return Err((StatusCode::BAD_GATEWAY, e.message.clone()));
+        }
+        digest::record(s, &capture);
+        tracing::info!("Snapshot of hub {} pod {}: {} samples", hub, pod, capture.samples.len());
+        Ok(capture)
+    }).await
+    .map_err(IntoResponse::into_response)?
+    .map(Json)
+    .map_err(IntoResponse::into_response)
+}
+
+/// Snapshot every visible pod into the kept waveforms
+fn snapshot_all(s: &IlaState) -> Result<u64, validate::Invalid> {
+    let pods: Vec<(u8, u8, u32)> = s.info().hubs.iter()
+        .flat_map(|hub| hub.pods.iter().map(|pod| (hub.index, pod.index, pod.ram_depth)))
+        .collect();
+    let Some(depth) = pods.iter().map(|&(_, _, depth)| depth).min() else {
+        return Err((StatusCode::NOT_FOUND, "No visible pods".into()));
+    };
+    acquire_now(s, depth / 2)?;
+    let id = s.ila.sequence();
+    for &(hub, pod, _) in &pods {
+        if let Err((_, e)) = crate::waveform::keep_latest(s, hub, pod) {
+            tracing::error!("Signal snapshot {}: hub {} pod {}: {}", id, hub, pod, e);
+        }
+    }
+    Ok(id)
+}
+
+/// Take a snapshot on each SIGUSR1/SIGUSR2 until shutdown
+pub async fn on_signals(state: Arc<IlaState>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (mut usr1, mut usr2) = match (signal(SignalKind::user_defined1()), signal(SignalKind::user_defined2())) {
+        (Ok(usr1), Ok(usr2)) => (usr1, usr2),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Cannot install SIGUSR1/SIGUSR2 handlers: {}", e);
+            return;
+        }
+    };
+    let mut shutdown = state.shutdown_signal();
+    loop {
+        let name = tokio::select! {
+            _ = usr1.recv() => "SIGUSR1",
+            _ = usr2.recv() => "SIGUSR2",
+            _ = shutdown.changed() => return,
+        };
+        match state.run_op("snapshot", snapshot_all).await {
+            Ok(Ok(id)) => tracing::info!("{}: snapshot {} kept under /waveforms/{}.vcd", name, id, id),
+            Ok(Err((_, e))) => tracing::error!("{}: snapshot failed: {}", name, e),
+            Err(conflict) => tracing::warn!("{}: snapshot skipped: {}", name, conflict.0.message),
+        }
+    }
+}
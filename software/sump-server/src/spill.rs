@@ -0,0 +1,135 @@
+//! Disk spill for large intermediate data
+//!
+//! Decoding a deep capture unwraps its records into per-sample cycles and
+//! data, which for a DDR buffer of hundreds of MB would not fit next to it
+//! in the 512 MB of a Zynq board. A `SpillVec` keeps the first
+//! `SUMP_SPILL_MB` in memory; past that it moves to an unlinked temp file in
+//! `SUMP_SPILL_DIR`, and the finished `Spilled` slice is read back through a
+//! file mapping the kernel can page in and out as needed.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::ops::Deref;
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Default in-memory budget of each `SpillVec`
+pub const DEFAULT_SPILL_BYTES: usize = 64 << 20;
+
+/// Names temp files apart within the process
+static NEXT_FILE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct SpillConfig {
+    /// Where spill files are created (and unlinked right away)
+    pub dir: PathBuf,
+    /// Bytes held in memory before spilling
+    pub threshold: usize,
+}
+
+impl Default for SpillConfig {
+    fn default() -> Self {
+        Self { dir: std::env::temp_dir(), threshold: DEFAULT_SPILL_BYTES }
+    }
+}
+
+/// Plain values whose bytes can be written out and mapped back
+pub trait Plain: Copy + Send + Sync + 'static {}
+
+impl Plain for u32 {}
+impl Plain for u64 {}
+
+fn bytes<T: Plain>(values: &[T]) -> &[u8] {
+    // SAFETY: `Plain` types have no padding and every byte is initialized
+    unsafe { std::slice::from_raw_parts(values.as_ptr().cast(), std::mem::size_of_val(values)) }
+}
+
+/// Append-only vector that moves to a temp file past its threshold
+pub struct SpillVec<T: Plain> {
+    memory: Vec<T>,
+    file: Option<BufWriter<File>>,
+    len: usize,
+    config: SpillConfig,
+}
+
+impl<T: Plain> SpillVec<T> {
+    pub fn new(config: &SpillConfig) -> Self {
+        Self { memory: Vec::new(), file: None, len: 0, config: config.clone() }
+    }
+
+    pub fn push(&mut self, value: T) -> io::Result<()> {
+        self.len += 1;
+        if let Some(file) = &mut self.file {
+            return file.write_all(bytes(&[value]));
+        }
+        self.memory.push(value);
+        if std::mem::size_of_val(self.memory.as_slice()) >= self.config.threshold {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        let path = self.config.dir.join(format!(
+            "sump-spill-{}-{}", std::process::id(), NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        // Unlinked at once: the space is freed when the mapping goes, even after a crash
+        std::fs::remove_file(&path)?;
+        let mut file = BufWriter::new(file);
+        file.write_all(bytes(&self.memory))?;
+        tracing::debug!("Spilled {} bytes to {}", std::mem::size_of_val(self.memory.as_slice()), self.config.dir.display());
+        self.memory = Vec::new();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// The values pushed, in memory or mapped from the spill file
+    pub fn finish(self) -> io::Result<Spilled<T>> {
+        let Some(file) = self.file else {
+            return Ok(Spilled::Memory(self.memory));
+        };
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        let size = self.len * std::mem::size_of::<T>();
+        // SAFETY: a fresh read-only private mapping of a file only we hold
+        let ptr = unsafe {
+            libc::mmap(std::ptr::null_mut(), size, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Spilled::Mapped { ptr: ptr.cast(), len: self.len })
+    }
+}
+
+/// Finished `SpillVec`, dereferencing to its values
+pub enum Spilled<T: Plain> {
+    Memory(Vec<T>),
+    Mapped { ptr: *const T, len: usize },
+}
+
+// SAFETY: the mapping is read-only and owned by this value
+unsafe impl<T: Plain> Send for Spilled<T> {}
+unsafe impl<T: Plain> Sync for Spilled<T> {}
+
+impl<T: Plain> Deref for Spilled<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Memory(values) => values,
+            // SAFETY: `len` values were written before mapping, and mmap is page aligned
+            Self::Mapped { ptr, len } => unsafe { std::slice::from_raw_parts(*ptr, *len) },
+        }
+    }
+}
+
+impl<T: Plain> Drop for Spilled<T> {
+    fn drop(&mut self) {
+        if let Self::Mapped { ptr, len } = *self {
+            // SAFETY: unmaps exactly the mapping made in `finish`
+            unsafe { libc::munmap(ptr as *mut libc::c_void, len * std::mem::size_of::<T>()) };
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Statistics counters
+//!
+//! `GET /api/ila/stats` returns the counters as JSON; `GET /metrics` renders
+//! the same values in the Prometheus text exposition format, including the
+//! per-command latency histograms recorded by the driver.
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Json},
+    routing::get,
+    Router,
+};
+use std::fmt::Write;
+use std::sync::Arc;
+
+use sump_model::IlaStats;
+
+use crate::ila::IlaState;
+
+fn collect(state: &IlaState) -> IlaStats {
+    IlaStats {
+        uptime_s: state.started.elapsed().as_secs(),
+        recoveries: state.watchdog.lock().unwrap().recoveries,
+        circuit_trips: state.breaker.status().trips,
+        ..state.ila.stats()
+    }
+}
+
+/// GET /api/ila/stats - Counters since server start
+pub async fn get_stats(State(state): State<Arc<IlaState>>) -> Json<IlaStats> {
+    Json(collect(&state))
+}
+
+/// GET /metrics - Prometheus text format
+async fn get_metrics(State(state): State<Arc<IlaState>>) -> impl IntoResponse {
+    let stats = collect(&state);
+    let metrics: [(&str, &str, &str, u64); 12] = [
+        ("sump_uptime_seconds", "gauge", "Seconds since server start", stats.uptime_s),
+        ("sump_commands_total", "counter", "Wrapper commands issued", stats.commands),
+        ("sump_command_errors_total", "counter", "Commands completed with the error bit set", stats.command_errors),
+        ("sump_command_timeouts_total", "counter", "Commands that never signalled DONE", stats.command_timeouts),
+        ("sump_command_retries_total", "counter", "Failed commands retried", stats.command_retries),
+        ("sump_arms_total", "counter", "Successful ARM commands", stats.arms),
+        ("sump_triggers_total", "counter", "Trigger events seen by status reads", stats.triggers),
+        ("sump_readout_cache_hits_total", "counter", "Readouts served from the capture cache", stats.readout_cache_hits),
+        ("sump_reconnects_total", "counter", "Hardware reconnects", stats.reconnects),
+        ("sump_watchdog_recoveries_total", "counter", "Watchdog resets that restored the ILA", stats.recoveries),
+        ("sump_circuit_trips_total", "counter", "Times the circuit breaker opened", stats.circuit_trips),
+        ("sump_circuit_open", "gauge", "Hardware requests fail fast (1) or go through (0)", state.breaker.status().open as u64),
+    ];
+
+    let mut body = String::new();
+    for (name, kind, help, value) in metrics {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} {}", name, kind);
+        let _ = writeln!(body, "{} {}", name, value);
+    }
+
+    let name = "sump_command_duration_seconds";
+    let _ = writeln!(body, "# HELP {} Wrapper command execution time by command", name);
+    let _ = writeln!(body, "# TYPE {} histogram", name);
+    for latency in &stats.command_latency {
+        let labels = format!("cmd=\"{}\",code=\"0x{:02X}\"", latency.name, latency.cmd);
+        for bucket in &latency.buckets {
+            let le = bucket.le_us as f64 / 1e6;
+            let _ = writeln!(body, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, le, bucket.count);
+        }
+        let _ = writeln!(body, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, latency.count);
+        let _ = writeln!(body, "{}_sum{{{}}} {}", name, labels, latency.sum_us / 1e6);
+        let _ = writeln!(body, "{}_count{{{}}} {}", name, labels, latency.count);
+    }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// Create the top-level `/metrics` router
+pub fn metrics_router(state: Arc<IlaState>) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .with_state(state)
+}
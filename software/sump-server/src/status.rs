@@ -0,0 +1,81 @@
+//! Cached capture status
+//!
+//! A background task reads the capture status every `SUMP_STATUS_POLL_MS`
+//! and `GET /api/ila/status` answers from that copy, so any number of UI
+//! pollers cost one status command per interval on the serial bus. The copy
+//! is dropped whenever a logical operation (arm, reset, readout, ...) ends,
+//! so the next request after a state change reads the hardware.
+//!
+//! `GET /api/ila/status/raw` is the cheap path for high-frequency pollers
+//! that only need "armed or not": one read of the wrapper's `CAP_STATUS`
+//! register, no serial-bus command, no cache and no rate limit.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sump_driver::REG_CAP_STATUS;
+use sump_model::{CaptureStatus, RawStatus, StatusSnapshot};
+
+use crate::ila::IlaState;
+
+/// Default poll interval
+pub const DEFAULT_POLL_MS: u64 = 250;
+
+/// Last polled status and when it was read
+pub type CachedStatus = Option<(CaptureStatus, Instant)>;
+
+/// Poll the capture status until shutdown; a zero interval disables polling
+pub async fn run(state: Arc<IlaState>, interval: Duration) {
+    if interval.is_zero() {
+        return;
+    }
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut shutdown = state.shutdown_signal();
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+        // The operation's own end invalidates the cache; don't add bus load meanwhile
+        if state.operation.lock().unwrap().is_some() {
+            continue;
+        }
+        let status = state.run(|s| s.ila.capture_status()).await;
+        *state.status_cache.lock().unwrap() = Some((status, Instant::now()));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusQuery {
+    #[serde(default)]
+    fresh: bool,
+}
+
+/// GET /api/ila/status?fresh=true - Capture status, from the poller's cache unless fresh
+pub async fn get_capture_status(
+    State(state): State<Arc<IlaState>>,
+    Query(query): Query<StatusQuery>,
+) -> Json<StatusSnapshot> {
+    let cached = state.status_cache.lock().unwrap().clone();
+    if let Some((status, read_at)) = cached.filter(|_| !query.fresh) {
+        let age_ms = read_at.elapsed().as_millis() as u64;
+        return Json(StatusSnapshot { status, age_ms });
+    }
+    let status = state.run(|s| s.ila.capture_status()).await;
+    Json(StatusSnapshot { status, age_ms: 0 })
+}
+
+/// GET /api/ila/status/raw - Armed/awake bits of the CAP_STATUS register, one AXI read
+pub async fn get_raw_status(State(state): State<Arc<IlaState>>) -> Result<Json<RawStatus>, (StatusCode, &'static str)> {
+    state.run(|s| s.ila.read_reg(REG_CAP_STATUS)).await
+        .map(|raw| Json(RawStatus::from_bits(raw)))
+        .ok_or((StatusCode::BAD_GATEWAY, "CAP_STATUS read failed"))
+}
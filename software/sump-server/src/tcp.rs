@@ -0,0 +1,57 @@
+//! Raw TCP listeners
+//!
+//! The accept loop shared by the socket protocols (`bd_server`,
+//! `text_control`): each connection runs on its own task until the client
+//! disconnects or the server shuts down.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::ila::IlaState;
+
+/// Accept connections on `addr` until shutdown, serving each with `serve`;
+/// `name` prefixes the log lines
+pub async fn listen<F, Fut>(state: Arc<IlaState>, addr: SocketAddr, name: &'static str, serve: F)
+where
+    F: Fn(TcpStream, Arc<IlaState>) -> Fut,
+    Fut: Future<Output = io::Result<()>> + Send + 'static,
+{
+    let listener = match TcpListener::bind(addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::error!("{}: failed to bind to {}: {}", name, addr, e);
+            return;
+        }
+    };
+    tracing::info!("{} protocol on tcp://{}", name, addr);
+
+    let mut shutdown = state.shutdown_signal();
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::warn!("{}: accept failed: {}", name, e);
+                    continue;
+                }
+            },
+            _ = shutdown.changed() => return,
+        };
+        tracing::info!("{}: client {} connected", name, peer);
+
+        let connection = serve(stream, state.clone());
+        let mut shutdown = state.shutdown_signal();
+        tokio::spawn(async move {
+            tokio::select! {
+                result = connection => match result {
+                    Ok(()) => tracing::info!("{}: client {} disconnected", name, peer),
+                    Err(e) => tracing::warn!("{}: client {}: {}", name, peer, e),
+                },
+                _ = shutdown.changed() => {}
+            }
+        });
+    }
+}
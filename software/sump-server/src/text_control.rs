@@ -0,0 +1,220 @@
+//! Line-based text control protocol
+//!
+//! For test equipment that can open a socket but not speak HTTP. Each
+//! command is one line; each reply is a line starting with `OK` or `ERR`
+//! (`ERR BUSY` when another operation holds the ILA), followed by data lines
+//! only for `CAP?`. Commands are case-insensitive:
+//!
+//! - `*IDN?` - `OK SUMP3,<hw_id>,<revision>,<hubs>`
+//! - `STAT?` - `OK ARMED=<0|1>,PRE=<0|1>,TRIG=<0|1>,ACQ=<0|1>`
+//! - `ARM [<timeout_s> [<on_timeout>]]` - arm, as `POST /api/ila/arm`
+//! - `INIT` - clear pod RAM; replies once the INIT has completed
+//! - `TRIG <type> <bits|signal,signal,...> [<post> [<timeout_s> [<on_timeout>]]]` -
+//!   configure the trigger on pod (0,0) and arm, as `POST /api/ila/trigger`;
+//!   bits are decimal or `0x` hex
+//! - `CAP? [<hub> <pod>] <count>` - `OK <n>`, then `n` lines of
+//!   `<address>,<code>,<timestamp>,<data>` in hex, oldest first
+//!
+//! Enabled by `SUMP_TEXT_PORT`; commands go through the same operation
+//! exclusion and trigger validation as the REST handlers.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use sump_driver::*;
+use sump_model::{CommandResult, TimeoutAction, TriggerConfig};
+
+use crate::digest;
+use crate::ila::{command_result, IlaState};
+use crate::ops::Conflict;
+use crate::tcp;
+use crate::validate;
+
+/// Longest accepted command line
+const MAX_LINE: usize = 4096;
+
+/// Error reply; control characters (e.g. from RTL signal names) would break
+/// the line framing
+fn err(message: &str) -> String {
+    let message: String = message.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    format!("ERR {}", message)
+}
+
+fn busy(conflict: Conflict) -> String {
+    err(&format!("BUSY {}", conflict.0.message))
+}
+
+fn command_reply(result: Result<CommandResult, Conflict>) -> String {
+    match result {
+        Ok(r) if r.success => format!("OK {}", r.message),
+        Ok(r) => err(&r.message),
+        Err(conflict) => busy(conflict),
+    }
+}
+
+fn number(token: &str) -> Option<u32> {
+    match token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => token.parse().ok(),
+    }
+}
+
+fn flag(value: bool) -> u8 {
+    value as u8
+}
+
+/// `[<timeout_s> [<on_timeout>]]`, checked as for `POST /api/ila/arm`
+fn parse_timeout(args: &[&str]) -> Result<(Option<f64>, Option<TimeoutAction>), String> {
+    let timeout_s = match args.first() {
+        Some(t) => Some(t.parse::<f64>().map_err(|_| format!("bad timeout '{}'", t))?),
+        None => None,
+    };
+    let on_timeout = match args.get(1) {
+        Some(a) => Some(serde_json::from_value(serde_json::Value::String(a.to_ascii_lowercase()))
+            .map_err(|_| format!("bad timeout action '{}' (expected disarm, force_trigger or rearm)", a))?),
+        None => None,
+    };
+    if args.len() > 2 {
+        return Err(format!("unexpected argument '{}'", args[2]));
+    }
+    crate::arm_timeout::check(timeout_s, on_timeout)
+        .map_err(|errors| errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect::<Vec<_>>().join("; "))?;
+    Ok((timeout_s, on_timeout))
+}
+
+/// `TRIG <type> <bits|signals> [<post> [<timeout_s> [<on_timeout>]]]`
+fn parse_trigger(args: &[&str]) -> Result<TriggerConfig, String> {
+    let (&trigger_type, rest) = args.split_first().ok_or("usage: TRIG <type> <bits|signals> [<post> [<timeout_s> [<on_timeout>]]]")?;
+    let (&bits, rest) = rest.split_first().ok_or("missing trigger bits or signal names")?;
+    let mut config = TriggerConfig { trigger_type: trigger_type.to_ascii_lowercase(), ..Default::default() };
+    match number(bits) {
+        Some(bits) => config.trigger_bits = bits,
+        None => config.trigger_signals = bits.split(',').map(str::to_string).collect(),
+    }
+    if let Some((post, rest)) = rest.split_first() {
+        config.post_trigger = number(post).ok_or_else(|| format!("bad post-trigger count '{}'", post))?;
+        (config.timeout_s, config.on_timeout) = parse_timeout(rest)?;
+    }
+    Ok(config)
+}
+
+/// `CAP? [<hub> <pod>] <count>`
+fn parse_capture(args: &[&str]) -> Result<(u8, u8, u32), String> {
+    let numbers: Vec<u32> = args.iter()
+        .map(|a| number(a).ok_or_else(|| format!("bad number '{}'", a)))
+        .collect::<Result<_, _>>()?;
+    let index = |v: u32| u8::try_from(v).map_err(|_| format!("index {} out of range", v));
+    match numbers[..] {
+        [count] => Ok((0, 0, count)),
+        [hub, pod, count] => Ok((index(hub)?, index(pod)?, count)),
+        _ => Err("usage: CAP? [<hub> <pod>] <count>".into()),
+    }
+}
+
+/// Execute one command line and return the reply (without the final newline)
+async fn execute(state: &Arc<IlaState>, line: &str) -> String {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let Some((command, args)) = tokens.split_first() else {
+        return err("empty command");
+    };
+    match command.to_ascii_uppercase().as_str() {
+        "*IDN?" => {
+            let info = state.run(|s| s.ila.info()).await;
+            format!("OK SUMP3,{},{},{}", info.hw_id, info.revision, info.hub_count)
+        }
+        "STAT?" => {
+            let s = state.run(|s| s.ila.capture_status()).await;
+            format!("OK ARMED={},PRE={},TRIG={},ACQ={}", flag(s.armed), flag(s.pre_trigger), flag(s.triggered), flag(s.acquired))
+        }
+        "ARM" => {
+            let (timeout_s, on_timeout) = match parse_timeout(args) {
+                Ok(timeout) => timeout,
+                Err(e) => return err(&e),
+            };
+            let result = state.run_op("arm", |s| command_result(&s.ila, CMD_ARM, "Armed", "Arm")).await;
+            if matches!(&result, Ok(r) if r.success) {
+                crate::arm_timeout::start(state, timeout_s, on_timeout, None);
+            }
+            command_reply(result)
+        }
+        "INIT" => command_reply(state.init().await),
+        "TRIG" => {
+            let config = match parse_trigger(args) {
+                Ok(config) => config,
+                Err(e) => return err(&e),
+            };
+            let requested = config.clone();
+            match state.run_op("trigger", move |s| s.configure_trigger(&requested)).await {
+                Ok(Ok(Ok(bits))) => {
+                    crate::arm_timeout::start(state, config.timeout_s, config.on_timeout, Some(config.clone()));
+                    format!("OK bits=0x{:08X},post={}", bits, config.post_trigger)
+                }
+                Ok(Ok(Err(message))) => err(&message),
+                Ok(Err(errors)) => {
+                    let fields: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+                    err(&fields.join("; "))
+                }
+                Err(conflict) => busy(conflict),
+            }
+        }
+        "CAP?" => {
+            let (hub, pod, count) = match parse_capture(args) {
+                Ok(request) => request,
+                Err(e) => return err(&e),
+            };
+            let capture = state.run_op("readout", move |s| -> Result<_, validate::Invalid> {
+                validate::visible_pod(s, hub, pod)?;
+                let (_, _, ram_depth) = s.ila.get_pod_config(hub, pod);
+                validate::count("count", count, ram_depth)?;
+                let capture = s.ila.read_capture(hub, pod, count);
+                digest::record(s, &capture);
+                Ok(capture)
+            }).await;
+            match capture {
+                Ok(Ok(capture)) => match capture.readout_error {
+                    Some(e) => err(&format!("readout failed at 0x{:X}: {}", e.address, e.message)),
+                    None => {
+                        let mut reply = format!("OK {}", capture.samples.len());
+                        for s in &capture.samples {
+                            reply.push_str(&format!("\n{:X},{},{:X},{:X}", s.address, s.code, s.timestamp, s.data));
+                        }
+                        reply
+                    }
+                },
+                Ok(Err((_, message))) => err(&message),
+                Err(conflict) => busy(conflict),
+            }
+        }
+        other => err(&format!("unknown command '{}'", other)),
+    }
+}
+
+async fn serve_connection(stream: TcpStream, state: Arc<IlaState>) -> io::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if (&mut reader).take(MAX_LINE as u64 + 1).read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        if line.len() > MAX_LINE {
+            write.write_all(b"ERR line too long\n").await?;
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut reply = execute(&state, line.trim()).await;
+        reply.push('\n');
+        write.write_all(reply.as_bytes()).await?;
+    }
+}
+
+/// Accept text protocol connections until shutdown
+pub async fn run(state: Arc<IlaState>, addr: SocketAddr) {
+    tcp::listen(state, addr, "Text control", serve_connection).await
+}
@@ -0,0 +1,53 @@
+//! Request timeouts
+//!
+//! Hardware handlers run on the blocking pool (`IlaState::run`), so a wedged
+//! bus transaction can't stall the server. These layers bound how long a
+//! client waits for one: past the deadline the request fails with
+//! 504 Gateway Timeout while the transaction finishes in the background.
+
+use axum::{
+    error_handling::HandleErrorLayer,
+    http::StatusCode,
+    BoxError, Router,
+};
+use std::time::Duration;
+use tower::{timeout::error::Elapsed, timeout::TimeoutLayer, ServiceBuilder};
+
+/// Default deadline for register and state commands
+pub const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 10_000;
+
+/// Default deadline for pod RAM readouts
+pub const DEFAULT_READOUT_TIMEOUT_MS: u64 = 60_000;
+
+/// Per-class request deadlines
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    pub request: Duration,
+    pub readout: Duration,
+}
+
+/// Apply a deadline to every route of `router`
+pub fn with_timeout<S>(router: Router<S>, deadline: Duration) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(move |err: BoxError| async move {
+                timeout_error(err, deadline)
+            }))
+            .layer(TimeoutLayer::new(deadline)),
+    )
+}
+
+fn timeout_error(err: BoxError, deadline: Duration) -> (StatusCode, String) {
+    if err.is::<Elapsed>() {
+        tracing::warn!("Request timed out after {} ms", deadline.as_millis());
+        (
+            StatusCode::GATEWAY_TIMEOUT,
+            format!("Hardware operation timed out after {} ms", deadline.as_millis()),
+        )
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Unhandled error: {}", err))
+    }
+}
@@ -0,0 +1,52 @@
+//! Embedded frontend configuration
+//!
+//! `SUMP_UI_CONFIG` names a JSON `UiConfig` file (theme, pod selected at
+//! startup, waveform to open, signal groups) that `GET /api/ui-config`
+//! serves to the embedded Surfer when it starts, so a deployment can
+//! customize the viewer without rebuilding the WASM bundle:
+//!
+//! ```json
+//! {"theme": "dark+", "default_pod": "0.1", "load_url": "/waveforms/latest.vcd",
+//!  "signal_groups": [{"name": "bus", "signals": ["0.1/valid", "0.1/ready"]}]}
+//! ```
+//!
+//! The response adds the server's `capabilities` (see `capabilities`).
+
+use axum::{extract::State, http::Extensions, response::Json, routing::get, Router};
+use std::path::Path;
+use std::sync::Arc;
+
+use sump_model::UiConfig;
+
+use crate::capabilities::capabilities;
+use crate::ila::IlaState;
+
+/// Whether `pod` reads `"<hub>.<pod>"`
+fn is_pod_key(pod: &str) -> bool {
+    pod.split_once('.').is_some_and(|(hub, pod)| hub.parse::<u8>().is_ok() && pod.parse::<u8>().is_ok())
+}
+
+/// Read and check the frontend configuration file
+pub fn load(path: &Path) -> Result<UiConfig, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let config: UiConfig = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    if let Some(pod) = config.default_pod.as_deref().filter(|pod| !is_pod_key(pod)) {
+        return Err(format!("default_pod '{}' is not \"<hub>.<pod>\"", pod));
+    }
+    Ok(config)
+}
+
+/// GET /api/ui-config - Frontend configuration and server capabilities
+async fn get_ui_config(State(state): State<Arc<IlaState>>, extensions: Extensions) -> Json<UiConfig> {
+    Json(UiConfig {
+        capabilities: capabilities(&state, &extensions).await,
+        ..state.ui_config.clone()
+    })
+}
+
+/// Create the `/api/ui-config` router; it touches no hardware
+pub fn ui_config_router(state: Arc<IlaState>) -> Router {
+    Router::new()
+        .route("/", get(get_ui_config))
+        .with_state(state)
+}
@@ -0,0 +1,57 @@
+//! Request parameter validation
+//!
+//! Checks hub/pod indices against the live topology, register offsets
+//! against the wrapper window, and sample counts against pod RAM depth, so
+//! out-of-range requests fail with 400/404 instead of returning zeroed data.
+
+use axum::http::StatusCode;
+
+use sump_driver::Ila;
+
+use crate::ila::IlaState;
+
+/// Rejected parameter: status code and message
+pub type Invalid = (StatusCode, String);
+
+/// The hub and pod must exist (404 otherwise)
+pub fn pod(ila: &Ila, hub: u8, pod: u8) -> Result<(), Invalid> {
+    let hub_count = ila.hub_count();
+    if hub >= hub_count {
+        return Err((StatusCode::NOT_FOUND, format!("Hub {} not found ({} hubs)", hub, hub_count)));
+    }
+    let pod_count = ila.pod_count(hub).ok_or_else(|| {
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read pod count of hub {}", hub))
+    })?;
+    if pod >= pod_count {
+        return Err((StatusCode::NOT_FOUND, format!("Pod {} not found on hub {} ({} pods)", pod, hub, pod_count)));
+    }
+    Ok(())
+}
+
+/// Like `pod`, but a pod in `Settings.hidden_pods` is not found either
+pub fn visible_pod(state: &IlaState, hub: u8, pod: u8) -> Result<(), Invalid> {
+    if crate::settings::is_hidden(&state.settings.lock().unwrap(), hub, pod) {
+        return Err((StatusCode::NOT_FOUND, format!("Pod {} not found on hub {}", pod, hub)));
+    }
+    self::pod(&state.ila, hub, pod)
+}
+
+/// Wrapper register offset: 32-bit aligned (400) and inside the register
+/// window (404)
+pub fn offset(ila: &Ila, offset: usize) -> Result<(), Invalid> {
+    if !offset.is_multiple_of(4) {
+        return Err((StatusCode::BAD_REQUEST, format!("Offset 0x{:X} is not 32-bit aligned", offset)));
+    }
+    if offset >= ila.window_size() {
+        return Err((StatusCode::NOT_FOUND, format!("Offset 0x{:X} is outside the 0x{:X}-byte register window", offset, ila.window_size())));
+    }
+    Ok(())
+}
+
+/// Sample count or scan stride: between 1 and the pod RAM depth (400)
+pub fn count(name: &str, value: u32, ram_depth: u32) -> Result<(), Invalid> {
+    if value == 0 || value > ram_depth {
+        return Err((StatusCode::BAD_REQUEST, format!("{} must be between 1 and the RAM depth of {} (got {})", name, ram_depth, value)));
+    }
+    Ok(())
+}
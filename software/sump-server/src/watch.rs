@@ -0,0 +1,138 @@
+//! Register watch subscriptions over WebSocket
+//!
+//! `GET /api/ila/watch` upgrades to a WebSocket. The client sends a
+//! `WatchRequest` as a JSON text message; the server then samples the
+//! requested registers every `interval_ms` and pushes `WatchUpdate` messages
+//! until the socket closes. Sending another request replaces the subscription.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::Extensions,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sump_driver::CMD_RD_STATUS;
+use sump_model::*;
+
+use crate::ila::IlaState;
+use crate::masks;
+use crate::validate;
+
+/// Fastest allowed sampling interval; every pod register read is a serial-bus command
+const MIN_INTERVAL_MS: u64 = 10;
+
+/// Maximum number of registers per subscription
+const MAX_TARGETS: usize = 64;
+
+/// GET /api/ila/watch - WebSocket register watch
+pub async fn ws_watch(ws: WebSocketUpgrade, State(state): State<Arc<IlaState>>, extensions: Extensions) -> Response {
+    // Pod registers can't be masked
+    if let Err(refused) = masks::unmaskable(&state, masks::partner(&extensions), "The register watch") {
+        return refused.into_response();
+    }
+    ws.on_upgrade(move |socket| watch_loop(socket, state))
+}
+
+fn read_target(state: &IlaState, target: &WatchTarget) -> Option<u32> {
+    match *target {
+        WatchTarget::Reg { offset } => state.ila.read_reg(offset),
+        WatchTarget::PodReg { hub, pod, reg } => state.ila.read_pod_reg(hub, pod, reg),
+        WatchTarget::Status => state.ila.exec_cmd(CMD_RD_STATUS, 0, 0),
+    }
+}
+
+/// Reject oversized subscriptions, misaligned or out-of-window offsets and
+/// registers of hidden pods
+fn check_request(state: &IlaState, req: &WatchRequest) -> Result<(), String> {
+    if req.targets.len() > MAX_TARGETS {
+        return Err(format!("Too many targets ({} > {})", req.targets.len(), MAX_TARGETS));
+    }
+    for target in &req.targets {
+        match *target {
+            WatchTarget::Reg { offset } => validate::offset(&state.ila, offset).map_err(|(_, msg)| msg)?,
+            WatchTarget::PodReg { hub, pod, .. } if crate::settings::is_hidden(&state.settings.lock().unwrap(), hub, pod) => {
+                return Err(format!("Pod {} not found on hub {}", pod, hub));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn error_message(msg: String) -> Message {
+    Message::Text(serde_json::json!({ "error": msg }).to_string())
+}
+
+async fn watch_loop(mut socket: WebSocket, state: Arc<IlaState>) {
+    let mut request: Option<WatchRequest> = None;
+    let mut last: Vec<Option<u32>> = Vec::new();
+    let mut started = Instant::now();
+    let mut ticker = tokio::time::interval(Duration::from_millis(MIN_INTERVAL_MS));
+    let mut shutdown = state.shutdown_signal();
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<WatchRequest>(&text) {
+                    Ok(req) => {
+                        if let Err(msg) = check_request(&state, &req) {
+                            if socket.send(error_message(msg)).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        }
+                        let interval = req.interval_ms.max(MIN_INTERVAL_MS);
+                        tracing::debug!("Watch: {} targets every {} ms", req.targets.len(), interval);
+                        ticker = tokio::time::interval(Duration::from_millis(interval));
+                        last.clear();
+                        started = Instant::now();
+                        request = Some(req);
+                    }
+                    Err(e) => {
+                        if socket.send(error_message(format!("Invalid watch request: {}", e))).await.is_err() {
+                            break;
+                        }
+                    }
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = ticker.tick(), if request.is_some() => {
+                let Some(req) = request.as_ref() else { continue };
+                let targets = req.targets.clone();
+                let values: Vec<Option<u32>> = state.run(move |s| {
+                    targets.iter().map(|t| read_target(s, t)).collect()
+                }).await;
+
+                let update = WatchUpdate {
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                    values: req.targets.iter()
+                        .zip(&values)
+                        .enumerate()
+                        .filter(|(i, (_, v))| !req.on_change || last.get(*i) != Some(*v))
+                        .map(|(_, (target, value))| WatchValue { target: target.clone(), value: *value })
+                        .collect(),
+                };
+                last = values;
+
+                if update.values.is_empty() {
+                    continue;
+                }
+                let text = serde_json::to_string(&update).unwrap();
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    tracing::debug!("Watch connection closed");
+}
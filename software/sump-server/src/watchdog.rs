@@ -0,0 +1,185 @@
+//! Hardware watchdog
+//!
+//! Background task that probes the ILA every interval with a local status
+//! read and a hub 0 serial-bus read. A probe also fails if any other command
+//! timed out since the previous one. After `FAILURE_THRESHOLD` consecutive
+//! failures the ILA is reported unhealthy and, in `recover` mode, reset with
+//! `CMD_RESET` and re-enumerated.
+
+use axum::{extract::State, response::Json};
+use std::sync::Arc;
+use std::time::Duration;
+
+use sump_driver::*;
+use sump_model::WatchdogStatus;
+
+use crate::ila::IlaState;
+
+/// Consecutive failed probes before the ILA is considered stuck
+const FAILURE_THRESHOLD: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogMode {
+    Off,
+    /// Log and expose the failure, leave the hardware alone
+    Report,
+    /// Reset and re-enumerate once the failure threshold is reached
+    Recover,
+}
+
+impl WatchdogMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(Self::Off),
+            "report" => Some(Self::Report),
+            "recover" => Some(Self::Recover),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Report => "report",
+            Self::Recover => "recover",
+        }
+    }
+}
+
+struct Watchdog {
+    mode: WatchdogMode,
+    last_timeouts: u64,
+}
+
+impl Watchdog {
+    fn probe(&mut self, ila: &Ila) -> Result<(), String> {
+        let result = if ila.exec_cmd(CMD_RD_STATUS, 0, 0).is_none() {
+            Err("status read failed".to_string())
+        } else if ila.exec_cmd(CMD_RD_POD_COUNT, 0, 0).is_none() {
+            Err("hub 0 serial-bus read failed".to_string())
+        } else {
+            Ok(())
+        };
+
+        let timeouts = ila.stats().command_timeouts;
+        let new_timeouts = timeouts - self.last_timeouts;
+        self.last_timeouts = timeouts;
+        match result {
+            Ok(()) if new_timeouts > 0 => Err(format!("{} command timeouts since last probe", new_timeouts)),
+            other => other,
+        }
+    }
+
+    fn recover(&mut self, ila: &Ila) -> Result<(), String> {
+        if ila.exec_cmd(CMD_RESET, 0, 0).is_none() {
+            return Err("reset failed".into());
+        }
+        let info = ila.info();
+        if info.hub_count == 0 {
+            return Err(format!("no hubs after reset (hw_id {})", info.hw_id));
+        }
+        self.last_timeouts = ila.stats().command_timeouts;
+        Ok(())
+    }
+
+    /// One probe/recover cycle; runs on a blocking thread
+    fn check(&mut self, state: &Arc<IlaState>) {
+        // Nothing to probe until the backend reconnects (see `degraded`)
+        if state.degraded.lock().unwrap().is_some() {
+            return;
+        }
+        let result = self.probe(&state.ila);
+
+        let failures = {
+            let mut status = state.watchdog.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    if !status.healthy {
+                        tracing::info!("Watchdog: ILA responding again");
+                    }
+                    status.healthy = true;
+                    status.consecutive_failures = 0;
+                    return;
+                }
+                Err(e) => {
+                    status.consecutive_failures += 1;
+                    if status.consecutive_failures >= FAILURE_THRESHOLD && status.healthy {
+                        tracing::error!(
+                            "Watchdog: ILA stuck after {} failed probes: {}",
+                            status.consecutive_failures, e
+                        );
+                        status.healthy = false;
+                    } else {
+                        tracing::debug!("Watchdog: probe failed: {}", e);
+                    }
+                    status.last_error = Some(e);
+                    status.consecutive_failures
+                }
+            }
+        };
+
+        if self.mode != WatchdogMode::Recover || failures < FAILURE_THRESHOLD {
+            return;
+        }
+
+        let Ok(_guard) = state.begin_op("watchdog recovery") else {
+            tracing::debug!("Watchdog: ILA busy, deferring recovery");
+            return;
+        };
+        tracing::warn!("Watchdog: resetting ILA");
+        let result = self.recover(&state.ila);
+        let mut status = state.watchdog.lock().unwrap();
+        match result {
+            Ok(()) => {
+                tracing::info!("Watchdog: ILA recovered");
+                status.healthy = true;
+                status.consecutive_failures = 0;
+                status.recoveries += 1;
+            }
+            Err(e) => {
+                tracing::error!("Watchdog: recovery failed: {}", e);
+                status.last_error = Some(e);
+            }
+        }
+    }
+}
+
+/// Run the watchdog until the server exits
+pub async fn run(state: Arc<IlaState>, mode: WatchdogMode, interval: Duration) {
+    state.watchdog.lock().unwrap().mode = mode.as_str().into();
+    if mode == WatchdogMode::Off {
+        return;
+    }
+    tracing::info!("Watchdog: {} mode, probing every {} ms", mode.as_str(), interval.as_millis());
+
+    let mut watchdog = Watchdog {
+        mode,
+        last_timeouts: state.ila.stats().command_timeouts,
+    };
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut shutdown = state.shutdown_signal();
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+        let state = state.clone();
+        watchdog = match tokio::task::spawn_blocking(move || {
+            watchdog.check(&state);
+            watchdog
+        }).await {
+            Ok(watchdog) => watchdog,
+            Err(e) => {
+                tracing::error!("Watchdog task failed: {}", e);
+                return;
+            }
+        };
+    }
+}
+
+/// GET /api/ila/health - Watchdog state
+pub async fn get_health(State(state): State<Arc<IlaState>>) -> Json<WatchdogStatus> {
+    Json(state.watchdog.lock().unwrap().clone())
+}
@@ -0,0 +1,242 @@
+//! Captures as VCD waveform URLs
+//!
+//! `GET /waveforms/latest.vcd` renders the completed capture of a pod (0,0
+//! unless `?hub=&pod=` say otherwise) and `GET /waveforms/:id.vcd` returns
+//! an earlier one. The id is the capture sequence number, which continues
+//! across restarts, so a new capture gets a new URL and the latest one is
+//! rendered once. The embedded Surfer
+//! opens them with `/?load_url=/waveforms/latest.vcd`. Both honour `Range`
+//! so an interrupted download can resume.
+//!
+//! `GET /waveforms/merged.vcd?pods=0.0,1.0` puts the completed captures of
+//! several pods (all visible ones by default) in one VCD. Pods of hubs with
+//! different clocks are converted to picoseconds and aligned on their
+//! trigger samples; the header notes each hub's clock.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, Extensions, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sump_export::vcd::{render_merged_vcd, render_vcd, MergedPod};
+
+use crate::ila::IlaState;
+use crate::masks::{self, PodMask};
+use crate::range;
+use crate::timeout::with_timeout;
+use crate::validate;
+
+/// Rendered captures kept for `/waveforms/:id.vcd`
+const KEPT_WAVEFORMS: usize = 8;
+
+pub struct Waveform {
+    pub id: u64,
+    pub hub: u8,
+    pub pod: u8,
+    pub vcd: Arc<str>,
+}
+
+/// Recently rendered waveforms, newest last
+pub type Waveforms = VecDeque<Waveform>;
+
+/// The VCD, or the part `Range` asks for (see `range`)
+fn vcd_response(request: &HeaderMap, waveform: &Waveform, cache_control: &'static str) -> Response {
+    let response = (
+        [
+            (header::CONTENT_TYPE, "text/x-vcd".to_string()),
+            (header::CACHE_CONTROL, cache_control.to_string()),
+            (header::HeaderName::from_static("x-sump-capture-id"), waveform.id.to_string()),
+        ],
+    ).into_response();
+    let etag = format!("\"vcd-{}-{}-{}\"", waveform.id, waveform.hub, waveform.pod);
+    range::respond(request, &etag, response, waveform.vcd.as_bytes())
+}
+
+/// Render the completed capture of a pod into the kept waveforms, unless it
+/// already is; its capture id and VCD
+pub fn keep_latest(s: &IlaState, hub: u8, pod: u8) -> Result<(u64, Arc<str>), validate::Invalid> {
+    let id = completed(s, hub, pod)?;
+    let kept = s.waveforms.lock().unwrap().iter()
+        .find(|w| (w.id, w.hub, w.pod) == (id, hub, pod))
+        .map(|w| w.vcd.clone());
+    if let Some(vcd) = kept {
+        return Ok((id, vcd));
+    }
+
+    let capture = s.ila.read_capture_all(hub, pod);
+    if let Some(e) = &capture.readout_error {
+        return Err((StatusCode::BAD_GATEWAY, e.message.clone()));
+    }
+    crate::digest::record(s, &capture);
+    let (info, hub_name) = s.labeled_pod(hub, pod);
+    let vcd: Arc<str> = render_vcd(&capture, &info, &hub_name, s.ila.hub_clock_hz(hub), id).into();
+
+    let mut waveforms = s.waveforms.lock().unwrap();
+    waveforms.push_back(Waveform { id, hub, pod, vcd: vcd.clone() });
+    while waveforms.len() > KEPT_WAVEFORMS {
+        waveforms.pop_front();
+    }
+    tracing::info!("Rendered capture {} of hub {} pod {} as VCD ({} samples)", id, hub, pod, capture.samples.len());
+    Ok((id, vcd))
+}
+
+/// The id of the pod's completed capture
+fn completed(s: &IlaState, hub: u8, pod: u8) -> Result<u64, validate::Invalid> {
+    validate::visible_pod(s, hub, pod)?;
+    if !s.ila.capture_status().acquired {
+        return Err((StatusCode::CONFLICT, "No completed capture; arm and wait for the trigger".into()));
+    }
+    Ok(s.ila.sequence())
+}
+
+/// Render the completed capture of a pod with `mask` applied, without
+/// keeping it (see `masks`)
+fn masked_latest(s: &IlaState, hub: u8, pod: u8, mask: &PodMask) -> Result<(u64, Arc<str>), validate::Invalid> {
+    let id = completed(s, hub, pod)?;
+    let mut capture = s.ila.read_capture_all(hub, pod);
+    if let Some(e) = &capture.readout_error {
+        return Err((StatusCode::BAD_GATEWAY, e.message.clone()));
+    }
+    crate::digest::record(s, &capture);
+    let (mut info, hub_name) = s.labeled_pod(hub, pod);
+    mask.blank(&mut capture);
+    mask.redact(&mut info);
+    Ok((id, render_vcd(&capture, &info, &hub_name, s.ila.hub_clock_hz(hub), id).into()))
+}
+
+/// Read the completed captures of `pods` ("H.P,...", all visible pods by
+/// default) and render them as one VCD; its capture id and VCD
+fn merged(s: &IlaState, pods: Option<&str>, partner: bool) -> Result<(u64, String), validate::Invalid> {
+    let pods: Vec<(u8, u8)> = match pods {
+        Some(list) => list.split(',').map(|key| {
+            key.trim().split_once('.')
+                .and_then(|(hub, pod)| Some((hub.parse().ok()?, pod.parse().ok()?)))
+                .ok_or((StatusCode::BAD_REQUEST, format!("Invalid pod '{}' (expected <hub>.<pod>)", key)))
+        }).collect::<Result<_, _>>()?,
+        None => s.info().hubs.iter().flat_map(|hub| hub.pods.iter().map(|pod| (hub.index, pod.index))).collect(),
+    };
+    let mut pods = pods;
+    pods.sort_unstable();
+    pods.dedup();
+    if pods.is_empty() {
+        return Err((StatusCode::NOT_FOUND, "No visible pods".into()));
+    }
+    for &(hub, pod) in &pods {
+        validate::visible_pod(s, hub, pod)?;
+    }
+    if !s.ila.capture_status().acquired {
+        return Err((StatusCode::CONFLICT, "No completed capture; arm and wait for the trigger".into()));
+    }
+
+    let mut read = Vec::with_capacity(pods.len());
+    for &(hub, pod) in &pods {
+        let mut capture = s.ila.read_capture_all(hub, pod);
+        if let Some(e) = &capture.readout_error {
+            return Err((StatusCode::BAD_GATEWAY, format!("Hub {} pod {}: {}", hub, pod, e.message)));
+        }
+        crate::digest::record(s, &capture);
+        let (mut info, hub_name) = s.labeled_pod(hub, pod);
+        if let Some(mask) = masks::for_request(s, partner, hub, pod) {
+            mask.blank(&mut capture);
+            mask.redact(&mut info);
+        }
+        read.push((capture, info, hub_name, s.ila.hub_clock_hz(hub)));
+    }
+    let merged: Vec<MergedPod> = read.iter()
+        .map(|(capture, pod, hub_name, hub_hz)| MergedPod { capture, pod, hub_name, hub_hz: *hub_hz })
+        .collect();
+    let id = s.ila.sequence();
+    Ok((id, render_merged_vcd(&merged, id)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergedQuery {
+    #[serde(default)]
+    pods: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PodQuery {
+    #[serde(default)]
+    hub: u8,
+    #[serde(default)]
+    pod: u8,
+}
+
+/// GET /waveforms/latest.vcd?hub=H&pod=P - Completed capture as VCD
+async fn get_latest(
+    State(state): State<Arc<IlaState>>,
+    Query(PodQuery { hub, pod }): Query<PodQuery>,
+    request: HeaderMap,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    let partner = masks::partner(&extensions);
+    let rendered = state.run_op("readout", move |s| match masks::for_request(s, partner, hub, pod) {
+        Some(mask) => masked_latest(s, hub, pod, &mask),
+        None => keep_latest(s, hub, pod),
+    }).await
+        .map_err(IntoResponse::into_response)?
+        .map_err(IntoResponse::into_response)?;
+
+    let (id, vcd) = rendered;
+    Ok(vcd_response(&request, &Waveform { id, hub, pod, vcd }, "no-store"))
+}
+
+/// GET /waveforms/merged.vcd?pods=H.P,... - Completed captures of several
+/// pods (default: all visible) on one timebase
+async fn get_merged(
+    State(state): State<Arc<IlaState>>,
+    Query(MergedQuery { pods }): Query<MergedQuery>,
+    extensions: Extensions,
+) -> Result<Response, Response> {
+    let partner = masks::partner(&extensions);
+    let (id, vcd) = state.run_op("readout", move |s| merged(s, pods.as_deref(), partner)).await
+        .map_err(IntoResponse::into_response)?
+        .map_err(IntoResponse::into_response)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/x-vcd".to_string()),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+            (header::HeaderName::from_static("x-sump-capture-id"), id.to_string()),
+        ],
+        vcd,
+    ).into_response())
+}
+
+/// GET /waveforms/:id.vcd?hub=H&pod=P - A recently rendered capture
+async fn get_by_id(
+    State(state): State<Arc<IlaState>>,
+    Path(file): Path<String>,
+    Query(PodQuery { hub, pod }): Query<PodQuery>,
+    request: HeaderMap,
+    extensions: Extensions,
+) -> Result<Response, (StatusCode, String)> {
+    // Kept waveforms are rendered unmasked
+    if masks::partner(&extensions) && masks::masked(&state.settings.lock().unwrap(), hub, pod) {
+        let message = format!("Kept waveforms of hub {} pod {} are not available on the read-only listener; use /waveforms/latest.vcd", hub, pod);
+        return Err((StatusCode::FORBIDDEN, message));
+    }
+    let id: u64 = file.strip_suffix(".vcd")
+        .and_then(|id| id.parse().ok())
+        .ok_or((StatusCode::NOT_FOUND, format!("No waveform '{}' (expected <id>.vcd)", file)))?;
+    let waveforms = state.waveforms.lock().unwrap();
+    let waveform = waveforms.iter()
+        .find(|w| (w.id, w.hub, w.pod) == (id, hub, pod))
+        .ok_or((StatusCode::NOT_FOUND, format!("Capture {} of hub {} pod {} is not kept", id, hub, pod)))?;
+    Ok(vcd_response(&request, waveform, "max-age=31536000, immutable"))
+}
+
+pub fn waveform_router(state: Arc<IlaState>, readout_timeout: Duration) -> Router {
+    let routes = Router::new()
+        .route("/latest.vcd", get(get_latest))
+        .route("/merged.vcd", get(get_merged))
+        .route("/:file", get(get_by_id));
+    with_timeout(routes, readout_timeout).with_state(state)
+}